@@ -10,7 +10,10 @@ use crate::{
     error::ContractError,
     execute::register_amp_lp_token,
     ownership::{claim_ownership, drop_ownership_proposal, propose_new_owner},
-    queries::{query_config, query_exchange_rates, query_state, query_user_info},
+    queries::{
+        query_apr, query_config, query_exchange_rate_twap, query_exchange_rates, query_state,
+        query_user_info,
+    },
     state::{Config, DepositProfitDelay, CONFIG, OWNERSHIP_PROPOSAL},
 };
 
@@ -274,6 +277,12 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             start_after,
             limit,
         } => to_json_binary(&query_exchange_rates(deps, env, start_after, limit)?),
+        QueryMsg::ExchangeRateTwap {
+            window_s,
+        } => to_json_binary(&query_exchange_rate_twap(deps, env, window_s)?),
+        QueryMsg::Apr {
+            windows,
+        } => to_json_binary(&query_apr(deps, env, windows)?),
     }
 }
 /// ## Description
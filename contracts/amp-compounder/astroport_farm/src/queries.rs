@@ -8,6 +8,9 @@ use eris::{
         ConfigResponse, ExchangeRatesResponse, StateResponse, UserInfo, UserInfoResponse,
     },
     constants::DAY,
+    helpers::exchange_rate::{
+        compute_apr, compute_twap, load_exchange_rates, AprResponse, ExchangeRateTwapResponse,
+    },
     voting_escrow::{DEFAULT_LIMIT, MAX_LIMIT},
 };
 
@@ -136,3 +139,41 @@ pub fn query_exchange_rates(
         apr,
     })
 }
+
+/// ## Description
+/// Returns the time-weighted average exchange rate over the last `window_s` seconds
+pub fn query_exchange_rate_twap(
+    deps: Deps,
+    env: Env,
+    window_s: u64,
+) -> StdResult<ExchangeRateTwapResponse> {
+    let now = env.block.time.seconds();
+    let exchange_rates = load_exchange_rates(
+        deps.storage,
+        &EXCHANGE_HISTORY,
+        now.saturating_sub(window_s),
+        |time, rate| (time, rate),
+    )?;
+
+    compute_twap(&exchange_rates, now, window_s)
+}
+
+/// ## Description
+/// Returns the APR of the exchange rate over each of the `windows`
+pub fn query_apr(deps: Deps, env: Env, windows: Vec<u64>) -> StdResult<AprResponse> {
+    let now = env.block.time.seconds();
+    let max_window = windows.iter().max().copied().unwrap_or_default();
+    let exchange_rates = load_exchange_rates(
+        deps.storage,
+        &EXCHANGE_HISTORY,
+        now.saturating_sub(max_window),
+        |time, rate| (time, rate),
+    )?;
+
+    Ok(AprResponse {
+        aprs: windows
+            .into_iter()
+            .map(|window_s| compute_apr(&exchange_rates, now, window_s))
+            .collect(),
+    })
+}
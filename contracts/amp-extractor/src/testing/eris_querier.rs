@@ -15,12 +15,14 @@ impl ErisQuerier {
         match &query {
             QueryMsg::State {} => Ok(to_json_binary(&StateResponse {
                 total_ustake: Uint128::zero(),
-                total_uluna: Uint128::zero(),
+                total_utoken: Uint128::zero(),
                 exchange_rate: self.exchange_rate,
                 unlocked_coins: vec![],
                 unbonding: Uint128::zero(),
                 available: Uint128::zero(),
-                tvl_uluna: Uint128::zero(),
+                tvl_utoken: Uint128::zero(),
+                instant_unbond_buffer: Uint128::zero(),
+                instant_unbond_utilization: Decimal::zero(),
             })
             .into())
             .into(),
@@ -115,6 +115,15 @@ fn _vote() -> StdResult<()> {
                 vote_operator: Some(helper.base.prop_gauges.get_address_string()),
                 epoch_period: None,
                 unbond_period: None,
+                instant_unbond: None,
+                validator_scoring: None,
+                unbond_receipt_nft: None,
+                fee_receivers: None,
+                referral_fee: None,
+                guardian: None,
+                timelock_delay_s: None,
+                keeper_tips: None,
+                prop_gauges: None,
             },
         )
         .unwrap();
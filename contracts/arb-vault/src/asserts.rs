@@ -1,8 +1,10 @@
+use std::str::FromStr;
+
 use crate::error::ContractError;
 use crate::extensions::BalancesEx;
 
-use cosmwasm_std::{Decimal, Uint128};
-use eris::arb_vault::{BalancesDetails, ValidatedConfig};
+use cosmwasm_std::{Decimal, StdError, Uint128};
+use eris::arb_vault::{BalancesDetails, ValidatedConfig, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE};
 
 //----------------------------------------------------------------------------------------
 //  ASSERTS
@@ -13,8 +15,9 @@ pub fn assert_max_amount(
     balances: &BalancesDetails,
     wanted_profit: &Decimal,
     wanted_amount: &Uint128,
+    utilization_factor: Decimal,
 ) -> Result<(), ContractError> {
-    let takeable = balances.calc_takeable_for_profit(config, wanted_profit)?;
+    let takeable = balances.calc_takeable_for_profit(config, wanted_profit, utilization_factor)?;
     if takeable.lt(wanted_amount) {
         return Err(ContractError::NotEnoughFundsTakeable {});
     }
@@ -38,3 +41,35 @@ pub fn assert_has_funds(funds_amount: &Uint128) -> Result<(), ContractError> {
 
     Ok(())
 }
+
+pub fn assert_max_spread(
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+) -> Result<(), ContractError> {
+    let default_spread = Decimal::from_str(DEFAULT_SLIPPAGE)?;
+    let max_allowed_spread = Decimal::from_str(MAX_ALLOWED_SLIPPAGE)?;
+
+    let max_spread = max_spread.unwrap_or(default_spread);
+    if max_spread.gt(&max_allowed_spread) {
+        return Err(ContractError::AllowedSpreadAssertion {});
+    }
+
+    if let Some(belief_price) = belief_price {
+        let expected_return = offer_amount
+            * belief_price.inv().ok_or_else(|| StdError::generic_err("Invalid belief_price"))?;
+        let spread_amount = expected_return.saturating_sub(return_amount);
+
+        if return_amount < expected_return
+            && Decimal::from_ratio(spread_amount, expected_return) > max_spread
+        {
+            return Err(ContractError::MaxSpreadAssertion {});
+        }
+    } else if Decimal::from_ratio(spread_amount, return_amount + spread_amount) > max_spread {
+        return Err(ContractError::MaxSpreadAssertion {});
+    }
+
+    Ok(())
+}
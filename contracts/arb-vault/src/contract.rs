@@ -1,21 +1,27 @@
 use crate::constants::{CONTRACT_NAME, CONTRACT_VERSION, INSTANTIATE_TOKEN_REPLY_ID};
 use crate::domain;
 use crate::domain::callback::handle_callback;
-use crate::domain::config::execute_update_config;
+use crate::domain::config::{
+    cancel_timelocked_action, execute_timelocked_action, execute_update_config,
+};
 use crate::domain::execute::{
-    execute_arbitrage, execute_deposit, execute_unbond_liquidity, execute_withdraw_liquidity,
-    execute_withdraw_unbonded, execute_withdraw_unbonding_immediate, receive_cw20,
+    execute_arbitrage, execute_arbitrage_batch, execute_crank, execute_deposit,
+    execute_unbond_liquidity, execute_withdraw_liquidity, execute_withdraw_unbonded,
+    execute_withdraw_unbonding_immediate, receive_cw20,
 };
 use crate::domain::ownership::{claim_ownership, drop_ownership_proposal, propose_new_owner};
 use crate::error::{ContractError, ContractResult, CustomResult};
 use crate::query::{
-    query_config, query_exchange_rates, query_pair, query_state, query_takeable,
-    query_unbond_requests, query_user_info,
+    query_apr, query_config, query_exchange_rate_twap, query_exchange_rates, query_exposure,
+    query_next_actions, query_pair, query_pending_actions, query_reverse_simulation,
+    query_simulation, query_state, query_takeable, query_unbond_requests, query_user_info,
 };
+use crate::state::{LegacyConfig, State};
 use cosmwasm_std::{
     entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
 };
 use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Item;
 
 use eris::arb_vault::InstantiateMsg;
 use eris::arb_vault::{ExecuteMsg, MigrateMsg, QueryMsg};
@@ -55,6 +61,10 @@ pub fn execute(
             result_token,
             wanted_profit,
         } => execute_arbitrage(deps, env, info, msg, result_token, wanted_profit),
+        ExecuteMsg::ExecuteArbitrageBatch {
+            legs,
+            min_total_profit,
+        } => execute_arbitrage_batch(deps, env, info, legs, min_total_profit),
 
         ExecuteMsg::WithdrawFromLiquidStaking {
             names,
@@ -64,6 +74,9 @@ pub fn execute(
             names,
         } => execute_unbond_liquidity(deps, env, info, names),
 
+        // Allowed by anyone
+        ExecuteMsg::Crank {} => execute_crank(deps, env),
+
         // User actions
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::Deposit {
@@ -79,14 +92,30 @@ pub fn execute(
 
         ExecuteMsg::Swap {
             offer_asset,
+            ask_asset_info,
             belief_price,
             max_spread,
             to,
-        } => execute_deposit(deps, env, info, offer_asset, to),
+        } => domain::swap::execute_swap_native(
+            deps,
+            env,
+            info,
+            offer_asset,
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+        ),
         // Allowed by Owner
         ExecuteMsg::UpdateConfig {
             ..
         } => execute_update_config(deps, env, info, msg),
+        ExecuteMsg::ExecuteTimelockedAction {
+            id,
+        } => execute_timelocked_action(deps, env, id),
+        ExecuteMsg::CancelTimelockedAction {
+            id,
+        } => cancel_timelocked_action(deps, info, id),
 
         ExecuteMsg::ProposeNewOwner {
             owner,
@@ -111,6 +140,8 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> CustomResult<Binary> {
         QueryMsg::Takeable {
             wanted_profit,
         } => to_json_binary(&query_takeable(deps, env, wanted_profit)?)?,
+        QueryMsg::Exposure {} => to_json_binary(&query_exposure(deps, env)?)?,
+        QueryMsg::NextActions {} => to_json_binary(&query_next_actions(deps, env)?)?,
 
         QueryMsg::UnbondRequests {
             address,
@@ -122,7 +153,25 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> CustomResult<Binary> {
             start_after_d,
             limit,
         } => to_json_binary(&query_exchange_rates(deps, env, start_after_d, limit)?)?,
+        QueryMsg::ExchangeRateTwap {
+            window_s,
+        } => to_json_binary(&query_exchange_rate_twap(deps, env, window_s)?)?,
+        QueryMsg::Apr {
+            windows,
+        } => to_json_binary(&query_apr(deps, env, windows)?)?,
+        QueryMsg::PendingActions {
+            start_after,
+            limit,
+        } => to_json_binary(&query_pending_actions(deps, start_after, limit)?)?,
         QueryMsg::Pair {} => to_json_binary(&query_pair(deps, env)?)?,
+        QueryMsg::Simulation {
+            offer_asset,
+            ask_asset_info,
+        } => to_json_binary(&query_simulation(deps, env, offer_asset, ask_asset_info)?)?,
+        QueryMsg::ReverseSimulation {
+            offer_asset_info,
+            ask_asset,
+        } => to_json_binary(&query_reverse_simulation(deps, env, offer_asset_info, ask_asset)?)?,
     };
     Ok(res)
 }
@@ -131,6 +180,13 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> CustomResult<Binary> {
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let contract_version = get_contract_version(deps.storage)?;
 
+    // configs stored before every LSD type carried its `cw20_or_denom` can't be loaded anymore
+    let state = State::default();
+    if state.config.load(deps.storage).is_err() {
+        let legacy: LegacyConfig = Item::new("config").load(deps.storage)?;
+        state.config.save(deps.storage, &legacy.into_config())?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(Response::new()
@@ -1,13 +1,20 @@
 use std::ops::Div;
 
 use astroport::asset::{native_asset, AssetInfo, AssetInfoExt};
-use cosmwasm_std::{attr, Decimal, DepsMut, Env, MessageInfo, Response};
-use eris::arb_vault::{CallbackMsg, ExchangeHistory};
+use cosmwasm_std::{
+    attr, Attribute, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, QuerierWrapper, Response,
+    Storage, Uint128,
+};
+use eris::arb_vault::{
+    BalancesDetails, CallbackMsg, ClaimBalance, ExchangeHistory, ValidatedConfig,
+    ValidatedFeeConfig,
+};
 use eris::constants::DAY;
 use eris::CustomResponse;
 
-use crate::error::{ContractError, ContractResult};
+use crate::error::{ContractError, ContractResult, CustomResult};
 use crate::extensions::{BalancesEx, ConfigEx};
+use crate::lsds::lsdwrapper::LsdWrapper;
 use crate::state::State;
 
 pub fn handle_callback(
@@ -25,6 +32,10 @@ pub fn handle_callback(
             result_token,
             wanted_profit,
         } => execute_assert_result(deps, env, result_token, wanted_profit),
+        CallbackMsg::AssertBatchResult {
+            result_tokens,
+            min_total_profit,
+        } => execute_assert_batch_result(deps, env, result_tokens, min_total_profit),
     }
 }
 
@@ -48,6 +59,7 @@ pub fn execute_assert_result(
     let result_token_string = result_token.to_string();
     let active_lsd_adapter = lsds.get_adapter_by_asset(result_token)?;
     let active_lsd_balance = new_balances.get_by_name(&active_lsd_adapter.name)?;
+    let old_active_balance = old_balance.active_balance.ok_or(ContractError::NotExecuting {})?;
 
     let old_value = old_balance.tvl_utoken;
     let new_value = new_balances.tvl_utoken;
@@ -63,7 +75,7 @@ pub fn execute_assert_result(
 
     let received_x_amount = active_lsd_balance
         .xbalance
-        .checked_sub(old_balance.active_balance.xbalance)
+        .checked_sub(old_active_balance.xbalance)
         .map_err(|e| ContractError::CalculationError("profit_by_asset".into(), e.to_string()))?;
     let profit_by_xasset = received_x_amount * active_lsd_balance.xfactor - used_balance;
 
@@ -74,7 +86,7 @@ pub fn execute_assert_result(
         return Err(ContractError::ProfitBalancesDoesNotMatch {
             profit,
             profit_by_xasset,
-            old_balance: old_balance.active_balance.xbalance,
+            old_balance: old_active_balance.xbalance,
         });
     }
 
@@ -92,46 +104,219 @@ pub fn execute_assert_result(
         return Err(ContractError::DoNotTakeLockedBalance {});
     }
 
+    // a depeg of a single LSD should not be able to take down the whole vault
+    new_balances.assert_max_exposure(&config, &active_lsd_adapter.name)?;
+
+    // calculate fee
+    let fee_config = state.fee_config.load(deps.storage)?;
+    let fee_amount = profit * fee_config.protocol_performance_fee;
+    let (fee_msg, fee_attributes) = create_fee_msg(
+        &deps.querier,
+        &config,
+        fee_config,
+        &new_balances,
+        active_lsd_adapter,
+        active_lsd_balance,
+        fee_amount,
+    )?;
+
+    state.balance_checkpoint.remove(deps.storage);
+
+    let exchange_rate =
+        save_exchange_rate(deps.storage, &env, &state, &new_balances, fee_amount, total_lp_supply)?;
+
+    Ok(Response::new()
+        .add_optional_message(fee_msg)
+        .add_attributes(vec![
+            attr("action", "arb/assert_result"),
+            attr("type", active_lsd_adapter.name.clone()),
+            attr("result_token", result_token_string),
+            attr("received_xamount", received_x_amount),
+            attr("old_tvl", old_value),
+            attr("new_tvl", new_value),
+            attr("used_balance", used_balance),
+            attr("profit", profit),
+            attr("exchange_rate", exchange_rate.to_string()),
+            attr("fee_amount", fee_amount),
+        ])
+        .add_attributes(fee_attributes))
+}
+
+pub fn execute_assert_batch_result(
+    deps: DepsMut,
+    env: Env,
+    result_tokens: Vec<AssetInfo>,
+    min_total_profit: Decimal,
+) -> ContractResult {
+    let state = State::default();
+    let config = state.config.load(deps.storage)?;
+    let mut lsds = config.lsd_group(&env);
+
+    let old_balance = state.assert_is_nested(deps.storage)?;
+    let new_balances = lsds.get_total_assets_err(deps.as_ref(), &env, &state, &config)?;
+    let total_lp_supply = config.query_lp_supply(&deps.querier)?;
+
+    let old_value = old_balance.tvl_utoken;
+    let new_value = new_balances.tvl_utoken;
+
+    let used_balance = old_balance
+        .vault_available
+        .checked_sub(new_balances.vault_available)
+        .map_err(|e| ContractError::CalculationError("used balance".into(), e.to_string()))?;
+
+    // the profit is only asserted over the total value, as single legs might not be profitable
+    let profit = new_value
+        .checked_sub(old_value)
+        .map_err(|e| ContractError::CalculationError("profit".into(), e.to_string()))?;
+
+    let profit_percentage = Decimal::checked_from_ratio(profit, used_balance)
+        .map_err(|e| ContractError::CalculationError("profit percentage".into(), e.to_string()))?;
+
+    if profit_percentage < min_total_profit {
+        return Err(ContractError::NotEnoughProfit {});
+    }
+
+    if new_balances.vault_available < new_balances.locked_user_withdrawls {
+        // if locked balance bigger than the available balance, no arbitrage can be executed, as funds are marked for unbond
+        return Err(ContractError::DoNotTakeLockedBalance {});
+    }
+
+    // fees in xtoken are paid with the result token the vault holds the most value of
+    let mut names = vec![];
+    let mut fee_lsd_name: Option<String> = None;
+    let mut fee_lsd_value = Uint128::zero();
+    let mut received_value = Uint128::zero();
+    for result_token in result_tokens {
+        let lsd = lsds.get_adapter_by_asset(result_token)?;
+        new_balances.assert_max_exposure(&config, &lsd.name)?;
+
+        let balance = new_balances.get_by_name(&lsd.name)?;
+        let value = balance.xbalance * balance.xfactor;
+
+        let old_leg_balance = old_balance
+            .leg_balances
+            .iter()
+            .find(|leg_balance| leg_balance.name == lsd.name)
+            .ok_or(ContractError::NotExecuting {})?;
+        let received_x_amount =
+            balance.xbalance.checked_sub(old_leg_balance.xbalance).map_err(|e| {
+                ContractError::CalculationError("received xamount".into(), e.to_string())
+            })?;
+        received_value = received_value.checked_add(received_x_amount * balance.xfactor)?;
+
+        if fee_lsd_name.is_none() || value > fee_lsd_value {
+            fee_lsd_name = Some(lsd.name.clone());
+            fee_lsd_value = value;
+        }
+        names.push(lsd.name.clone());
+    }
+
+    // the profit needs to come from the result tokens only, e.g. not from a changed exchange rate
+    // of another LSD held by the vault. Each leg can be off by one from rounding.
+    let profit_by_xassets = received_value
+        .checked_sub(used_balance)
+        .map_err(|e| ContractError::CalculationError("profit_by_assets".into(), e.to_string()))?;
+    let rounding = Uint128::from(names.len() as u128);
+    if profit > profit_by_xassets + rounding || profit_by_xassets > profit + rounding {
+        return Err(ContractError::BatchProfitDoesNotMatch {
+            profit,
+            profit_by_xassets,
+        });
+    }
+
+    let fee_lsd =
+        lsds.get_adapter_by_name(&fee_lsd_name.ok_or(ContractError::NoArbitrageLegs {})?)?;
+    let fee_lsd_balance = new_balances.get_by_name(&fee_lsd.name)?;
+
     // calculate fee
     let fee_config = state.fee_config.load(deps.storage)?;
-    let fee_percent = fee_config.protocol_performance_fee;
-    let fee_amount = profit * fee_percent;
+    let fee_amount = profit * fee_config.protocol_performance_fee;
+    let (fee_msg, fee_attributes) = create_fee_msg(
+        &deps.querier,
+        &config,
+        fee_config,
+        &new_balances,
+        fee_lsd,
+        fee_lsd_balance,
+        fee_amount,
+    )?;
+
+    state.balance_checkpoint.remove(deps.storage);
+
+    let exchange_rate =
+        save_exchange_rate(deps.storage, &env, &state, &new_balances, fee_amount, total_lp_supply)?;
 
-    let (fee_msg, fee_attributes) = if fee_amount.is_zero() {
+    Ok(Response::new()
+        .add_optional_message(fee_msg)
+        .add_attributes(vec![
+            attr("action", "arb/assert_batch_result"),
+            attr("types", names.join(",")),
+            attr("old_tvl", old_value),
+            attr("new_tvl", new_value),
+            attr("used_balance", used_balance),
+            attr("profit", profit),
+            attr("exchange_rate", exchange_rate.to_string()),
+            attr("fee_amount", fee_amount),
+        ])
+        .add_attributes(fee_attributes))
+}
+
+fn create_fee_msg(
+    querier: &QuerierWrapper,
+    config: &ValidatedConfig,
+    fee_config: ValidatedFeeConfig,
+    new_balances: &BalancesDetails,
+    fee_lsd: &LsdWrapper,
+    fee_lsd_balance: &ClaimBalance,
+    fee_amount: Uint128,
+) -> CustomResult<(Option<CosmosMsg>, Vec<Attribute>)> {
+    Ok(if fee_amount.is_zero() {
         (None, vec![])
     } else if new_balances.vault_takeable >= fee_amount {
         // send fees in utoken if takeable allows it.
-        let utoken = native_asset(config.utoken, fee_amount);
-        let fee_msg = utoken.into_msg(&deps.querier, fee_config.protocol_fee_contract)?;
+        let utoken = native_asset(config.utoken.clone(), fee_amount);
+        let fee_msg = utoken.into_msg(querier, fee_config.protocol_fee_contract)?;
 
         (Some(fee_msg), vec![])
     } else {
         // send fees in xtoken otherwise
-        let fee_xamount = fee_amount * Decimal::one().div(active_lsd_balance.xfactor);
-
-        let fee_msg = active_lsd_adapter
-            .adapter
-            .asset()
-            .with_balance(fee_xamount)
-            .into_msg(&deps.querier, fee_config.protocol_fee_contract)?;
-
-        (
-            Some(fee_msg),
-            vec![
-                attr("fee_xamount", fee_xamount),
-                attr("fee_xfactor", active_lsd_balance.xfactor.to_string()),
-            ],
-        )
-    };
+        let fee_xamount = fee_amount * Decimal::one().div(fee_lsd_balance.xfactor);
 
-    state.balance_checkpoint.remove(deps.storage);
+        if fee_xamount.is_zero() {
+            // native xtokens cannot be sent with a zero amount
+            (None, vec![])
+        } else {
+            let fee_msg = fee_lsd
+                .adapter
+                .asset()
+                .with_balance(fee_xamount)
+                .into_msg(querier, fee_config.protocol_fee_contract)?;
+
+            (
+                Some(fee_msg),
+                vec![
+                    attr("fee_xamount", fee_xamount),
+                    attr("fee_xfactor", fee_lsd_balance.xfactor.to_string()),
+                ],
+            )
+        }
+    })
+}
 
+fn save_exchange_rate(
+    storage: &mut dyn Storage,
+    env: &Env,
+    state: &State,
+    new_balances: &BalancesDetails,
+    fee_amount: Uint128,
+    total_lp_supply: Uint128,
+) -> CustomResult<Decimal> {
     // we store the exchange rate daily to not create too much data.
     let new_vault_total = new_balances.vault_total - fee_amount;
 
     let exchange_rate = Decimal::from_ratio(new_vault_total, total_lp_supply);
     state.exchange_history.save(
-        deps.storage,
+        storage,
         env.block.time.seconds().div(DAY),
         &ExchangeHistory {
             exchange_rate,
@@ -139,19 +324,5 @@ pub fn execute_assert_result(
         },
     )?;
 
-    Ok(Response::new()
-        .add_optional_message(fee_msg)
-        .add_attributes(vec![
-            attr("action", "arb/assert_result"),
-            attr("type", active_lsd_adapter.name.clone()),
-            attr("result_token", result_token_string),
-            attr("received_xamount", received_x_amount),
-            attr("old_tvl", old_value),
-            attr("new_tvl", new_value),
-            attr("used_balance", used_balance),
-            attr("profit", profit),
-            attr("exchange_rate", exchange_rate.to_string()),
-            attr("fee_amount", fee_amount),
-        ])
-        .add_attributes(fee_attributes))
+    Ok(exchange_rate)
 }
@@ -1,11 +1,12 @@
 use cosmwasm_std::{DepsMut, Env, MessageInfo, Response, StdError};
 use eris::arb_vault::ExecuteMsg;
+use eris::helpers::bps::BasicPoints;
 use itertools::Itertools;
 
 use crate::{
     constants::MAX_UNBOND_TIME_S,
     error::{ContractError, ContractResult},
-    extensions::{ConfigEx, UtilizationMethodEx},
+    extensions::{ConfigEx, UtilizationAdjustmentEx, UtilizationMethodEx},
     state::State,
 };
 
@@ -15,20 +16,97 @@ pub fn execute_update_config(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> ContractResult {
+    let state = State::default();
+    state.assert_owner(deps.storage, &info.sender)?;
+
+    if is_timelocked(&msg) && state.timelock.delay(deps.storage)? > 0 {
+        // depositors get the time to withdraw before the update can be executed
+        let pending = state.timelock.propose(deps.storage, env.block.time.seconds(), msg)?;
+
+        return Ok(Response::new()
+            .add_attribute("action", "propose_timelocked_action")
+            .add_attribute("id", pending.id.to_string())
+            .add_attribute("executable_at", pending.executable_at.to_string()));
+    }
+
+    update_config(deps, env, msg)
+}
+
+pub fn execute_timelocked_action(deps: DepsMut, env: Env, id: u64) -> ContractResult {
+    let state = State::default();
+    let pending = state.timelock.take_executable(deps.storage, env.block.time.seconds(), id)?;
+
+    Ok(update_config(deps, env, pending.action)?.add_attribute("timelocked_action", id.to_string()))
+}
+
+pub fn cancel_timelocked_action(deps: DepsMut, info: MessageInfo, id: u64) -> ContractResult {
+    let state = State::default();
+    state.assert_owner(deps.storage, &info.sender)?;
+
+    state.timelock.cancel(deps.storage, id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_timelocked_action")
+        .add_attribute("id", id.to_string()))
+}
+
+/// LSD, utilization, fee and whitelist changes that have to wait for the timelock delay.
+///
+/// Exempt are `disable_lsd`, which only stops new arbs with an LSD, and `remove_lsd`, which only
+/// removes LSDs that hold no funds of the vault anymore.
+fn is_timelocked(msg: &ExecuteMsg) -> bool {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            utilization_method,
+            utilization_adjustment,
+            remove_utilization_adjustment,
+            unbond_time_s,
+            insert_lsd,
+            force_remove_lsd,
+            set_max_exposure,
+            set_policy,
+            fee_config,
+            set_whitelist,
+            remove_whitelist,
+            timelock_delay_s,
+            ..
+        } => {
+            utilization_method.is_some()
+                || utilization_adjustment.is_some()
+                || remove_utilization_adjustment.is_some()
+                || unbond_time_s.is_some()
+                || insert_lsd.is_some()
+                || force_remove_lsd.is_some()
+                || set_max_exposure.is_some()
+                || set_policy.is_some()
+                || fee_config.is_some()
+                || set_whitelist.is_some()
+                || remove_whitelist.is_some()
+                || timelock_delay_s.is_some()
+        },
+        _ => false,
+    }
+}
+
+fn update_config(deps: DepsMut, env: Env, msg: ExecuteMsg) -> ContractResult {
     match msg {
         ExecuteMsg::UpdateConfig {
             utilization_method,
+            utilization_adjustment,
+            remove_utilization_adjustment,
             unbond_time_s,
             insert_lsd,
             disable_lsd,
             remove_lsd,
             force_remove_lsd,
+            set_max_exposure,
+            set_policy,
             fee_config,
             remove_whitelist,
             set_whitelist,
+            timelock_delay_s,
         } => {
             let state = State::default();
-            state.assert_owner(deps.storage, &info.sender)?;
 
             let mut config = state.config.load(deps.storage)?;
 
@@ -47,12 +125,26 @@ pub fn execute_update_config(
                 config_changed = true;
             }
 
+            if let Some(utilization_adjustment) = utilization_adjustment {
+                utilization_adjustment.validate()?;
+                config.utilization_adjustment = Some(utilization_adjustment);
+                config_changed = true;
+            }
+
+            if let Some(remove_utilization_adjustment) = remove_utilization_adjustment {
+                if remove_utilization_adjustment {
+                    config.utilization_adjustment = None;
+                    config_changed = true;
+                }
+            }
+
             if let Some(insert_lsd) = insert_lsd {
                 let mut lsds = config.lsd_group(&env);
                 let lsd = lsds.get_adapter_by_name(&insert_lsd.name);
 
                 if lsd.is_err() {
                     config.lsds.push(insert_lsd.validate(deps.api)?);
+                    config.lsd_group(&env).assert_valid_assets(&config.utoken)?;
                     config_changed = true;
                 } else {
                     return Err(ContractError::AdapterNameDuplicate(insert_lsd.name));
@@ -92,6 +184,30 @@ pub fn execute_update_config(
                 config_changed = true;
             }
 
+            if let Some((name, max_exposure_bps)) = set_max_exposure {
+                if max_exposure_bps.unwrap_or_default() > BasicPoints::MAX {
+                    return Err(ContractError::ConfigTooHigh("max_exposure_bps".into()));
+                }
+
+                let lsd = config.lsds.iter_mut().find(|a| a.name == name);
+                if let Some(lsd) = lsd {
+                    lsd.max_exposure_bps = max_exposure_bps;
+                    config_changed = true;
+                } else {
+                    Err(ContractError::AdapterNotFound(name))?
+                }
+            }
+
+            if let Some((name, policy)) = set_policy {
+                let lsd = config.lsds.iter_mut().find(|a| a.name == name);
+                if let Some(lsd) = lsd {
+                    lsd.policy = policy;
+                    config_changed = true;
+                } else {
+                    Err(ContractError::AdapterNotFound(name))?
+                }
+            }
+
             if config_changed {
                 // after the config change, it still needs to be able to query all assets.
                 let mut lsds = config.lsd_group(&env);
@@ -118,6 +234,10 @@ pub fn execute_update_config(
                 }
             }
 
+            if let Some(timelock_delay_s) = timelock_delay_s {
+                state.timelock.set_delay(deps.storage, timelock_delay_s)?;
+            }
+
             Ok(Response::new().add_attribute("action", "update_config"))
         },
         _ => Err(StdError::generic_err("not supported").into()),
@@ -1,10 +1,13 @@
 use crate::asserts::{assert_has_funds, assert_max_amount, assert_min_profit};
+use crate::domain::swap;
 use crate::error::{ContractError, ContractResult};
 use crate::extensions::{BalancesEx, ConfigEx};
-use crate::helpers::{calc_fees, get_share_from_deposit};
+use crate::helpers::{calc_fees, get_share_from_deposit, get_utilization_factor};
 use crate::state::{BalanceCheckpoint, BalanceLocked, State, UnbondHistory};
 
-use astroport::asset::{native_asset, native_asset_info, Asset, AssetInfo};
+use astroport::asset::{
+    native_asset, native_asset_info, token_asset, Asset, AssetInfo, AssetInfoExt,
+};
 
 use cosmwasm_std::{
     attr, from_binary, to_json_binary, Addr, Coin, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Order,
@@ -12,9 +15,12 @@ use cosmwasm_std::{
 };
 
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use eris::arb_vault::{CallbackMsg, Cw20HookMsg, ExecuteSubMsg, ValidatedConfig};
+use eris::arb_vault::{
+    ArbLeg, CallbackMsg, ClaimBalance, Cw20HookMsg, ExecuteSubMsg, ValidatedConfig,
+};
 use eris::CustomResponse;
 use itertools::Itertools;
+use std::ops::Div;
 use std::vec;
 
 //----------------------------------------------------------------------------------------
@@ -34,21 +40,35 @@ pub fn receive_cw20(
             let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
             execute_unbond_user(deps, env, info, cw20_sender, cw20_msg.amount, immediate)
         },
-        // Cw20HookMsg::Swap {
-        //     ask_asset_info,
-        //     belief_price,
-        //     max_spread,
-        //     to,
-        // } => swap::execute_swap_cw20(
-        //     deps,
-        //     env,
-        //     cw20_msg.sender,
-        //     token_asset(info.sender, cw20_msg.amount),
-        //     ask_asset_info,
-        //     belief_price,
-        //     max_spread,
-        //     to,
-        // ),
+        Cw20HookMsg::WithdrawInKind {} => {
+            let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            execute_withdraw_in_kind(deps, env, info, cw20_sender, cw20_msg.amount)
+        },
+        Cw20HookMsg::Deposit {
+            receiver,
+        } => {
+            let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            execute_deposit_cw20(deps, env, info, cw20_sender, cw20_msg.amount, receiver)
+        },
+        Cw20HookMsg::Swap {
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+        } => {
+            let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            swap::execute_swap_cw20(
+                deps,
+                env,
+                info,
+                cw20_sender,
+                cw20_msg.amount,
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to,
+            )
+        },
     }
 }
 
@@ -71,7 +91,15 @@ pub fn execute_arbitrage(
     state.assert_not_nested(deps.storage)?;
     assert_has_funds(&message.funds_amount)?;
     assert_min_profit(&wanted_profit)?;
-    assert_max_amount(&config, &balances, &wanted_profit, &message.funds_amount)?;
+    let utilization_factor =
+        get_utilization_factor(deps.storage, &env, &state, &config, &balances)?;
+    assert_max_amount(
+        &config,
+        &balances,
+        &wanted_profit,
+        &message.funds_amount,
+        utilization_factor,
+    )?;
 
     // setup contract to call, by default the sender is called with the funds requested
     let contract_addr = if let Some(contract_addr) = message.contract_addr {
@@ -88,7 +116,8 @@ pub fn execute_arbitrage(
         &BalanceCheckpoint {
             vault_available: balances.vault_available,
             tvl_utoken: balances.tvl_utoken,
-            active_balance,
+            active_balance: Some(active_balance),
+            leg_balances: vec![],
         },
     )?;
 
@@ -113,6 +142,90 @@ pub fn execute_arbitrage(
         .add_attribute("action", "arb/execute_arbitrage"))
 }
 
+pub fn execute_arbitrage_batch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    legs: Vec<ArbLeg>,
+    min_total_profit: Decimal,
+) -> ContractResult {
+    let state = State::default();
+    let config = state.config.load(deps.storage)?;
+    let mut lsds = config.lsd_group(&env);
+    let balances = lsds.get_total_assets_err(deps.as_ref(), &env, &state, &config)?;
+
+    state.assert_sender_whitelisted(deps.storage, &info.sender)?;
+    state.assert_not_nested(deps.storage)?;
+
+    if legs.is_empty() {
+        return Err(ContractError::NoArbitrageLegs {});
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut result_tokens: Vec<AssetInfo> = vec![];
+    let mut leg_balances: Vec<ClaimBalance> = vec![];
+    let mut total_funds = Uint128::zero();
+
+    for leg in legs {
+        let lsd = lsds.get_adapter_by_asset(leg.result_token.clone())?;
+        lsd.assert_not_disabled()?;
+        let lsd_name = lsd.name.clone();
+        assert_has_funds(&leg.msg.funds_amount)?;
+
+        // by default the sender is called with the funds requested
+        let contract_addr = if let Some(contract_addr) = leg.msg.contract_addr {
+            deps.api.addr_validate(&contract_addr)?
+        } else {
+            info.sender.clone()
+        };
+        lsds.assert_not_lsd_contract(&contract_addr)?;
+
+        total_funds = total_funds.checked_add(leg.msg.funds_amount)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: leg.msg.msg,
+            funds: vec![Coin {
+                denom: config.utoken.clone(),
+                amount: leg.msg.funds_amount,
+            }],
+        }));
+
+        if !result_tokens.contains(&leg.result_token) {
+            leg_balances.push(balances.get_by_name(&lsd_name)?.clone());
+            result_tokens.push(leg.result_token);
+        }
+    }
+
+    // all legs are taken from the vault as a single flashloan
+    assert_min_profit(&min_total_profit)?;
+    let utilization_factor =
+        get_utilization_factor(deps.storage, &env, &state, &config, &balances)?;
+    assert_max_amount(&config, &balances, &min_total_profit, &total_funds, utilization_factor)?;
+
+    // create balance checkpoint with total value, as it needs to be higher after full execution.
+    state.balance_checkpoint.save(
+        deps.storage,
+        &BalanceCheckpoint {
+            vault_available: balances.vault_available,
+            tvl_utoken: balances.tvl_utoken,
+            active_balance: None,
+            leg_balances,
+        },
+    )?;
+
+    let validate_flashloan_result = CallbackMsg::AssertBatchResult {
+        result_tokens,
+        min_total_profit,
+    }
+    .into_cosmos_msg(&env.contract.address)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_message(validate_flashloan_result)
+        .add_attribute("action", "arb/execute_arbitrage_batch")
+        .add_attribute("total_funds", total_funds))
+}
+
 pub fn execute_withdraw_liquidity(
     deps: DepsMut,
     env: Env,
@@ -157,6 +270,22 @@ pub fn execute_unbond_liquidity(
     Ok(Response::new().add_messages(messages).add_attributes(attributes))
 }
 
+pub fn execute_crank(deps: DepsMut, env: Env) -> ContractResult {
+    let state = State::default();
+    let config = state.config.load(deps.storage)?;
+    let mut lsds = config.lsd_group(&env);
+
+    state.assert_not_nested(deps.storage)?;
+
+    let (messages, attributes) = lsds.get_crank_msgs(&deps, &env)?;
+
+    if messages.is_empty() {
+        return Err(ContractError::NothingToCrank {});
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(attributes))
+}
+
 pub fn execute_deposit(
     deps: DepsMut,
     env: Env,
@@ -165,47 +294,123 @@ pub fn execute_deposit(
     recipient: Option<String>,
 ) -> ContractResult {
     let state = State::default();
-    let config = state.config.load(deps.storage)?;
-    let mut lsds = config.lsd_group(&env);
 
     state.assert_not_nested(deps.storage)?;
     deposit.info.check(deps.api)?;
     deposit.assert_sent_native_token_balance(&info)?;
 
+    if !deposit.info.is_native_token() {
+        return Err(ContractError::Cw20DirectDeposit {});
+    }
+
+    deposit_asset(deps, env, &state, info.sender, deposit, recipient)
+}
+
+pub fn execute_deposit_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    recipient: Option<String>,
+) -> ContractResult {
+    let state = State::default();
+    state.assert_not_nested(deps.storage)?;
+
+    deposit_asset(deps, env, &state, sender, token_asset(info.sender, amount), recipient)
+}
+
+fn deposit_asset(
+    deps: DepsMut,
+    env: Env,
+    state: &State,
+    sender: Addr,
+    deposit: Asset,
+    recipient: Option<String>,
+) -> ContractResult {
+    let config = state.config.load(deps.storage)?;
+    let mut lsds = config.lsd_group(&env);
+
     if deposit.amount.is_zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
 
-    if deposit.info != native_asset_info(config.utoken.clone()) {
-        return Err(ContractError::AssetMismatch {});
+    let assets = lsds.get_total_assets_err(deps.as_ref(), &env, state, &config)?;
+
+    // LSD tokens are valued like a swap into the vault, as their market price can be below the
+    // exchange rate. The difference to the full value stays with the existing LPs.
+    let (deposit_amount, deposit_value, lsd_attributes) =
+        if deposit.info == native_asset_info(config.utoken.clone()) {
+            (deposit.amount, deposit.amount, vec![])
+        } else {
+            let lsd = lsds
+                .get_adapter_by_asset(deposit.info.clone())
+                .map_err(|_| ContractError::AssetMismatch {})?;
+            lsd.assert_not_disabled()?;
+            let name = lsd.name.clone();
+
+            // the deposited LSD is already held by the vault and must stay within its max exposure
+            assets.assert_max_exposure(&config, &name)?;
+
+            let mut balances = assets.clone();
+            swap::remove_offer(&mut balances, &name, deposit.amount)?;
+            let xfactor = balances.get_by_name(&name)?.xfactor;
+
+            let fee_config = state.fee_config.load(deps.storage)?;
+            let utilization_factor =
+                get_utilization_factor(deps.storage, &env, state, &config, &balances)?;
+            // no utoken is paid out, the exposure has been checked above
+            let simulation = swap::price_offer(
+                &config,
+                &fee_config,
+                &balances,
+                utilization_factor,
+                &name,
+                deposit.amount,
+            )?;
+
+            (
+                simulation.return_amount,
+                deposit.amount * xfactor,
+                vec![
+                    attr("deposit_asset", deposit.info.to_string()),
+                    attr("deposit_xamount", deposit.amount),
+                    attr("deposit_xfactor", xfactor.to_string()),
+                    attr("deposit_spread", simulation.spread_amount),
+                ],
+            )
+        };
+
+    if deposit_amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
     }
 
-    let deposit_amount = deposit.amount;
     let mut messages: Vec<CosmosMsg> = vec![];
 
-    let assets = lsds.get_total_assets_err(deps.as_ref(), &env, &state, &config)?;
-
-    // removing the deposit amount for correct share calculation
-    let vault_utoken = assets.vault_total.checked_sub(deposit_amount)?;
+    // removing the deposit value for correct share calculation
+    let vault_utoken = assets.vault_total.checked_sub(deposit_value)?;
     let share = get_share_from_deposit(&deps.querier, &config, vault_utoken, deposit_amount)?;
 
     // Mint LP tokens for the sender or for the receiver (if set)
     let recipient = if let Some(recipient) = recipient {
         deps.api.addr_validate(&recipient)?
     } else {
-        info.sender.clone()
+        sender.clone()
     };
 
     messages.push(mint_liquidity_token_message(&deps, &config, env, recipient.clone(), share)?);
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "arb/execute_deposit"),
-        attr("sender", info.sender.to_string()),
-        attr("recipient", recipient.to_string()),
-        attr("deposit_amount", deposit_amount),
-        attr("share", share.to_string()),
-        attr("vault_utoken_new", assets.vault_total),
-    ]))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "arb/execute_deposit"),
+            attr("sender", sender.to_string()),
+            attr("recipient", recipient.to_string()),
+            attr("deposit_amount", deposit_amount),
+            attr("share", share.to_string()),
+            attr("vault_utoken_new", assets.vault_total),
+        ])
+        .add_attributes(lsd_attributes))
 }
 
 pub fn execute_unbond_user(
@@ -279,6 +484,127 @@ pub fn execute_unbond_user(
     Ok(response)
 }
 
+pub fn execute_withdraw_in_kind(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    lp_amount: Uint128,
+) -> ContractResult {
+    let state = State::default();
+    let config = state.config.load(deps.storage)?;
+    let mut lsds = config.lsd_group(&env);
+
+    state.assert_not_nested(deps.storage)?;
+
+    if info.sender != config.lp_addr {
+        return Err(ContractError::ExpectingLPToken(info.sender.to_string()));
+    }
+
+    let total_lp_supply = config.query_lp_supply(&deps.querier)?;
+    let assets = lsds.get_total_assets_err(deps.as_ref(), &env, &state, &config)?;
+    let withdraw_amount = assets.vault_total.multiply_ratio(lp_amount, total_lp_supply);
+    let fee_config = state.fee_config.load(deps.storage)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut attributes = vec![
+        attr("action", "arb/execute_withdraw_in_kind"),
+        attr("from", sender.to_string()),
+        attr("withdraw_value", withdraw_amount),
+    ];
+
+    // the LSD tokens also back the locked user withdrawals, so the pro-rata share of every LSD
+    // token is scaled down to not exceed the withdraw value
+    let lsd_value = assets.lsd_xvalue.multiply_ratio(lp_amount, total_lp_supply);
+    let xshare = if lsd_value > withdraw_amount {
+        Decimal::from_ratio(withdraw_amount, lsd_value)
+    } else {
+        Decimal::one()
+    };
+
+    let mut xvalue = Uint128::zero();
+    for balance in assets.details.iter() {
+        let mut xamount = balance.xbalance.multiply_ratio(lp_amount, total_lp_supply) * xshare;
+        let remaining_value = withdraw_amount.checked_sub(xvalue)?;
+        if xamount * balance.xfactor > remaining_value {
+            // rounding of the scaled shares must not exceed the withdraw value either
+            xamount = remaining_value * Decimal::one().div(balance.xfactor);
+        }
+        if xamount.is_zero() {
+            continue;
+        }
+        xvalue += xamount * balance.xfactor;
+
+        let asset = lsds.get_adapter_by_name(&balance.name)?.adapter.asset();
+        let protocol_xfee = xamount * fee_config.protocol_withdraw_fee;
+        let receive_xamount = xamount.checked_sub(protocol_xfee)?;
+
+        if !receive_xamount.is_zero() {
+            messages
+                .push(asset.with_balance(receive_xamount).into_msg(&deps.querier, sender.clone())?);
+        }
+        if !protocol_xfee.is_zero() {
+            messages.push(
+                asset
+                    .with_balance(protocol_xfee)
+                    .into_msg(&deps.querier, fee_config.protocol_fee_contract.clone())?,
+            );
+        }
+
+        attributes.push(attr("type", balance.name.clone()));
+        attributes.push(attr("receive_xamount", receive_xamount));
+        attributes.push(attr("protocol_xfee", protocol_xfee));
+    }
+
+    // pro-rata share of the takeable utoken is paid immediately without pool fee, as it is taken
+    // from the takeable amount and not from the locked or unbonding funds
+    let utoken_amount = assets
+        .vault_takeable
+        .multiply_ratio(lp_amount, total_lp_supply)
+        .min(withdraw_amount.checked_sub(xvalue)?);
+
+    let response = if utoken_amount.is_zero() {
+        Response::new()
+    } else {
+        create_withdraw_msgs(
+            &deps.querier,
+            deps.storage,
+            &env,
+            &state,
+            &config,
+            sender.clone(),
+            utoken_amount,
+            Decimal::zero(),
+            Uint128::zero(),
+        )?
+    };
+
+    // the share of unbonding and withdrawable LSD claims has to wait for the unbond time
+    let unbond_amount = withdraw_amount.checked_sub(xvalue)?.checked_sub(utoken_amount)?;
+    if !unbond_amount.is_zero() {
+        state.add_to_unbond_history(
+            deps.storage,
+            sender,
+            UnbondHistory {
+                amount_asset: unbond_amount,
+                start_time: env.block.time.seconds(),
+                release_time: env.block.time.seconds() + config.unbond_time_s,
+            },
+        )?;
+    }
+
+    attributes.push(attr("xvalue", xvalue));
+    attributes.push(attr("utoken_amount", utoken_amount));
+    attributes.push(attr("unbond_amount", unbond_amount));
+
+    // always burn when receiving LP token
+    Ok(response
+        .add_messages(messages)
+        .add_attributes(attributes)
+        .add_message(create_burn_msg(&config, lp_amount)?)
+        .add_attribute("burnt_amount", lp_amount))
+}
+
 pub fn execute_withdraw_unbonding_immediate(
     deps: DepsMut,
     env: Env,
@@ -10,7 +10,7 @@ use eris::arb_vault::{Config, InstantiateMsg, LsdConfig, ValidatedConfig};
 use crate::{
     constants::{CONTRACT_NAME, CONTRACT_VERSION, INSTANTIATE_TOKEN_REPLY_ID},
     error::{ContractError, ContractResult, CustomResult},
-    extensions::UtilizationMethodEx,
+    extensions::{ConfigEx, UtilizationAdjustmentEx, UtilizationMethodEx},
     state::{BalanceLocked, State},
 };
 
@@ -30,6 +30,9 @@ pub fn instantiate(deps: DepsMut, env: Env, msg: InstantiateMsg) -> ContractResu
         .collect::<StdResult<Vec<LsdConfig<Addr>>>>()?;
 
     msg.utilization_method.validate()?;
+    if let Some(utilization_adjustment) = &msg.utilization_adjustment {
+        utilization_adjustment.validate()?;
+    }
 
     let config = ValidatedConfig {
         lp_addr: Addr::unchecked(""),
@@ -37,8 +40,11 @@ pub fn instantiate(deps: DepsMut, env: Env, msg: InstantiateMsg) -> ContractResu
         lsds,
         utoken: msg.utoken,
         utilization_method: msg.utilization_method,
+        utilization_adjustment: msg.utilization_adjustment,
     };
 
+    config.lsd_group(&env).assert_valid_assets(&config.utoken)?;
+
     state.owner.save(deps.storage, &deps.api.addr_validate(&msg.owner)?)?;
     state.config.save(deps.storage, &config)?;
     state.unbond_id.save(deps.storage, &0)?;
@@ -125,8 +125,12 @@ pub fn claim_ownership(deps: DepsMut, info: MessageInfo, env: Env) -> ContractRe
     // run callback
     state.owner.save(deps.storage, &ownership_proposal.owner)?;
 
+    // actions proposed by the previous owner are not executed on behalf of the new one
+    let cancelled = state.timelock.clear(deps.storage)?;
+
     Ok(Response::new().add_attributes(vec![
         attr("action", "claim_ownership"),
         attr("new_owner", ownership_proposal.owner),
+        attr("cancelled_actions", cancelled.len().to_string()),
     ]))
 }
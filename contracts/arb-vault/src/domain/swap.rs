@@ -1,68 +1,417 @@
-// use crate::extensions::ConfigEx;
-// use astroport::asset::{token_asset, Asset, AssetInfo};
-// use cosmwasm_std::{Addr, Decimal, DepsMut, Env, MessageInfo, Response};
-// use eris::helper::addr_opt_validate;
-
-// use crate::{
-//     error::{ContractError, ContractResult},
-//     state::State,
-// };
-
-// #[allow(clippy::too_many_arguments)]
-// pub(crate) fn execute_swap_native(
-//     deps: DepsMut,
-//     env: Env,
-//     info: MessageInfo,
-//     offer_asset: Asset,
-//     ask_asset_info: Option<AssetInfo>,
-//     belief_price: Option<Decimal>,
-//     max_spread: Option<Decimal>,
-//     to: Option<String>,
-// ) -> ContractResult {
-//     let receiver_addr = addr_opt_validate(deps.api, &to)?;
-//     let to_addr = receiver_addr.unwrap_or_else(|| info.sender.clone());
-
-//     offer_asset.info.check(deps.api)?;
-//     if !offer_asset.is_native_token() {
-//         return Err(ContractError::Cw20DirectSwap {});
-//     }
-//     offer_asset.assert_sent_native_token_balance(&info)?;
-
-//     execute_swap(deps, env, offer_asset, ask_asset_info, belief_price, max_spread, to_addr)
-// }
-
-// #[allow(clippy::too_many_arguments)]
-// pub(crate) fn execute_swap_cw20(
-//     deps: DepsMut,
-//     env: Env,
-//     cw20_sender: String,
-//     offer_asset: Asset,
-//     ask_asset_info: Option<AssetInfo>,
-//     belief_price: Option<Decimal>,
-//     max_spread: Option<Decimal>,
-//     to: Option<String>,
-// ) -> ContractResult {
-//     let receiver_addr = addr_opt_validate(deps.api, &to)?;
-//     let cw20_addr = deps.api.addr_validate(&cw20_sender)?;
-//     let to_addr = receiver_addr.unwrap_or(cw20_addr);
-
-//     execute_swap(deps, env, offer_asset, ask_asset_info, belief_price, max_spread, to_addr)
-// }
-
-// fn execute_swap(
-//     deps: DepsMut,
-//     env: Env,
-//     offer_asset: Asset,
-//     ask_asset_info: Option<AssetInfo>,
-//     belief_price: Option<Decimal>,
-//     max_spread: Option<Decimal>,
-//     to_addr: cosmwasm_std::Addr,
-// ) -> ContractResult {
-//     let state = State::default();
-//     let config = state.config.load(deps.storage)?;
-//     let lsds = config.lsd_group(&env);
-
-//     let mut allowed_assets = lsds.;
-
-//     Ok(Response::new().add_attribute("action", "arb/execute_swap"))
-// }
+use astroport::asset::{
+    native_asset, native_asset_info, token_asset, token_asset_info, Asset, AssetInfo,
+};
+use cosmwasm_std::{attr, Addr, Decimal, DepsMut, Env, MessageInfo, Response, Uint128};
+use eris::arb_vault::{
+    BalancesDetails, ReverseSimulationResponse, SimulationResponse, UtilizationMethod,
+    ValidatedConfig, ValidatedFeeConfig,
+};
+use eris::helper::addr_opt_validate;
+use eris::CustomResponse;
+use itertools::Itertools;
+
+use crate::{
+    asserts::assert_max_spread,
+    domain::execute::execute_deposit,
+    error::{ContractError, ContractResult, CustomResult},
+    extensions::{BalancesEx, ConfigEx},
+    helpers::get_utilization_factor,
+    state::State,
+};
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn execute_swap_native(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<String>,
+) -> ContractResult {
+    offer_asset.info.check(deps.api)?;
+    if !offer_asset.info.is_native_token() {
+        return Err(ContractError::Cw20DirectSwap {});
+    }
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let config = State::default().config.load(deps.storage)?;
+    if offer_asset.info == native_asset_info(config.utoken.clone()) {
+        // swapping the utoken into the LP token provides liquidity, which has to be asked for
+        // explicitly, as the vault doesn't sell LSD tokens
+        if ask_asset_info != Some(token_asset_info(config.lp_addr)) {
+            return Err(ContractError::AssetMismatch {});
+        }
+
+        return execute_deposit(deps, env, info, offer_asset, to);
+    }
+
+    let receiver_addr = addr_opt_validate(deps.api, &to)?;
+    let to_addr = receiver_addr.unwrap_or_else(|| info.sender.clone());
+
+    execute_swap(
+        deps,
+        env,
+        info.sender,
+        offer_asset,
+        ask_asset_info,
+        belief_price,
+        max_spread,
+        to_addr,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn execute_swap_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_sender: Addr,
+    amount: Uint128,
+    ask_asset_info: Option<AssetInfo>,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<String>,
+) -> ContractResult {
+    let receiver_addr = addr_opt_validate(deps.api, &to)?;
+    let to_addr = receiver_addr.unwrap_or_else(|| cw20_sender.clone());
+    let offer_asset = token_asset(info.sender, amount);
+
+    execute_swap(
+        deps,
+        env,
+        cw20_sender,
+        offer_asset,
+        ask_asset_info,
+        belief_price,
+        max_spread,
+        to_addr,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_swap(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to_addr: Addr,
+) -> ContractResult {
+    let state = State::default();
+    state.assert_not_nested(deps.storage)?;
+
+    let config = state.config.load(deps.storage)?;
+    let fee_config = state.fee_config.load(deps.storage)?;
+    assert_ask_utoken(&config, &ask_asset_info)?;
+
+    let mut lsds = config.lsd_group(&env);
+    let mut balances = lsds.get_total_assets_err(deps.as_ref(), &env, &state, &config)?;
+
+    let lsd = lsds
+        .get_adapter_by_asset(offer_asset.info.clone())
+        .map_err(|_| ContractError::AssetMismatch {})?;
+    lsd.assert_not_disabled()?;
+    let name = lsd.name.clone();
+
+    // the offered tokens are already part of the balances
+    remove_offer(&mut balances, &name, offer_asset.amount)?;
+
+    let utilization_factor =
+        get_utilization_factor(deps.storage, &env, &state, &config, &balances)?;
+    let swap = simulate_swap(
+        &config,
+        &fee_config,
+        &balances,
+        utilization_factor,
+        &name,
+        offer_asset.amount,
+    )?;
+
+    assert_max_spread(
+        belief_price,
+        max_spread,
+        offer_asset.amount,
+        swap.return_amount,
+        swap.spread_amount,
+    )?;
+
+    let return_msg = native_asset(config.utoken.clone(), swap.return_amount)
+        .into_msg(&deps.querier, to_addr.clone())?;
+
+    let commission_msg = if swap.commission_amount.is_zero() {
+        None
+    } else {
+        Some(
+            native_asset(config.utoken.clone(), swap.commission_amount)
+                .into_msg(&deps.querier, fee_config.protocol_fee_contract)?,
+        )
+    };
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "arb/execute_swap"),
+            attr("sender", sender.to_string()),
+            attr("receiver", to_addr.to_string()),
+            attr("offer_asset", offer_asset.info.to_string()),
+            attr("ask_asset", config.utoken),
+            attr("offer_amount", offer_asset.amount),
+            attr("return_amount", swap.return_amount),
+            attr("spread_amount", swap.spread_amount),
+            attr("commission_amount", swap.commission_amount),
+        ])
+        .add_message(return_msg)
+        .add_optional_message(commission_msg))
+}
+
+pub(crate) fn assert_ask_utoken(
+    config: &ValidatedConfig,
+    ask_asset_info: &Option<AssetInfo>,
+) -> CustomResult<()> {
+    if let Some(ask_asset_info) = ask_asset_info {
+        if *ask_asset_info != native_asset_info(config.utoken.clone()) {
+            return Err(ContractError::AssetMismatch {});
+        }
+    }
+
+    Ok(())
+}
+
+/// Sells the LSD to the vault for its exchange rate minus the lowest profit of the utilization
+/// method that is able to pay out the swap. The protocol fee is taken from the profit.
+pub(crate) fn simulate_swap(
+    config: &ValidatedConfig,
+    fee_config: &ValidatedFeeConfig,
+    balances: &BalancesDetails,
+    utilization_factor: Decimal,
+    name: &str,
+    offer_amount: Uint128,
+) -> CustomResult<SimulationResponse> {
+    let swap = price_offer(config, fee_config, balances, utilization_factor, name, offer_amount)?;
+
+    let mut new_balances = balances.clone();
+    add_offer(&mut new_balances, name, offer_amount, swap.return_amount + swap.commission_amount)?;
+    new_balances.assert_max_exposure(config, name)?;
+
+    Ok(swap)
+}
+
+/// Prices the offered LSD with the lowest profit of the utilization method that is able to pay it
+/// out, without checking the exposure of the vault afterwards.
+pub(crate) fn price_offer(
+    config: &ValidatedConfig,
+    fee_config: &ValidatedFeeConfig,
+    balances: &BalancesDetails,
+    utilization_factor: Decimal,
+    name: &str,
+    offer_amount: Uint128,
+) -> CustomResult<SimulationResponse> {
+    let claim = balances.get_by_name(name)?;
+    let value = offer_amount * claim.xfactor;
+
+    if value.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let discount = find_discount(config, fee_config, balances, utilization_factor, value)?;
+    let return_amount = value * (Decimal::one() - discount);
+    let spread_amount = value - return_amount;
+    let commission_amount = spread_amount * fee_config.protocol_performance_fee;
+
+    Ok(SimulationResponse {
+        return_amount,
+        spread_amount,
+        commission_amount,
+    })
+}
+
+/// Lowest profit at which the takeable amount covers the value minus the profit plus the
+/// commission. Steps are taken as they are, the Linear and Kink curves are interpolated between
+/// their points, as the takeable amount grows with the profit.
+fn find_discount(
+    config: &ValidatedConfig,
+    fee_config: &ValidatedFeeConfig,
+    balances: &BalancesDetails,
+    utilization_factor: Decimal,
+    value: Uint128,
+) -> CustomResult<Decimal> {
+    let is_paid_out = |discount: Decimal, takeable: Uint128| {
+        let return_amount = value * (Decimal::one() - discount);
+        let commission_amount = (value - return_amount) * fee_config.protocol_performance_fee;
+        return_amount + commission_amount <= takeable
+    };
+    let is_curve = !matches!(config.utilization_method, UtilizationMethod::Steps(_));
+
+    let mut previous: Option<Decimal> = None;
+    for (discount, takeable) in takeable_steps(config, balances, utilization_factor)? {
+        if !is_paid_out(discount, takeable) {
+            previous = Some(discount);
+            continue;
+        }
+
+        let mut low = match previous {
+            Some(previous) if is_curve => previous,
+            _ => return Ok(discount),
+        };
+
+        // bisection between the last point not paying out the value and this one
+        let mut high = discount;
+        while high.atomics() - low.atomics() > Uint128::one() {
+            let middle = Decimal::raw((low.atomics().u128() + high.atomics().u128()) / 2);
+            let takeable =
+                balances.calc_takeable_for_profit(config, &middle, utilization_factor)?;
+            if is_paid_out(middle, takeable) {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+
+        return Ok(high);
+    }
+
+    Err(ContractError::NotEnoughFundsTakeable {})
+}
+
+/// Smallest offer amount that returns the ask amount, checked against the forward simulation.
+pub(crate) fn reverse_simulate_swap(
+    config: &ValidatedConfig,
+    fee_config: &ValidatedFeeConfig,
+    balances: &BalancesDetails,
+    utilization_factor: Decimal,
+    name: &str,
+    ask_amount: Uint128,
+) -> CustomResult<ReverseSimulationResponse> {
+    let xfactor = balances.get_by_name(name)?.xfactor;
+
+    if ask_amount.is_zero() || xfactor.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    // the return of an offer, if it covers the ask amount
+    let simulate = |offer_amount: Uint128| {
+        match simulate_swap(config, fee_config, balances, utilization_factor, name, offer_amount) {
+            Ok(swap) if swap.return_amount >= ask_amount => Ok(Some(swap)),
+            // a miss is paid out by a later step or a larger offer
+            Ok(_)
+            | Err(ContractError::NotEnoughFundsTakeable {})
+            | Err(ContractError::InvalidZeroAmount {}) => Ok(None),
+            Err(e) => Err(e),
+        }
+    };
+
+    let steps = takeable_steps(config, balances, utilization_factor)?;
+
+    if let UtilizationMethod::Steps(_) = config.utilization_method {
+        for (discount, _) in steps {
+            // ceil(ask / ((1 - discount) * xfactor)) with the rounding of the forward simulation
+            let value = div_ceil(ask_amount, Decimal::one() - discount);
+            let offer_amount = div_ceil(value, xfactor);
+
+            // the step is chosen by the forward simulation, so that both always match
+            if let Some(swap) = simulate(offer_amount)? {
+                return Ok(ReverseSimulationResponse {
+                    offer_amount,
+                    spread_amount: swap.spread_amount,
+                    commission_amount: swap.commission_amount,
+                });
+            }
+        }
+
+        return Err(ContractError::NotEnoughFundsTakeable {});
+    }
+
+    // on the curves the profit grows with the offer, so that the smallest offer is bisected
+    // below the offer returning the ask amount at the highest profit
+    let max_discount = steps.last().ok_or(ContractError::NotEnoughFundsTakeable {})?.0;
+    let mut high = div_ceil(div_ceil(ask_amount, Decimal::one() - max_discount), xfactor);
+    let mut swap = simulate(high)?.ok_or(ContractError::NotEnoughFundsTakeable {})?;
+
+    let mut low = Uint128::zero();
+    while high - low > Uint128::one() {
+        let middle = low + (high - low) / Uint128::new(2);
+        match simulate(middle)? {
+            Some(middle_swap) => {
+                high = middle;
+                swap = middle_swap;
+            },
+            None => low = middle,
+        }
+    }
+
+    Ok(ReverseSimulationResponse {
+        offer_amount: high,
+        spread_amount: swap.spread_amount,
+        commission_amount: swap.commission_amount,
+    })
+}
+
+/// Smallest amount x with x * rate >= amount
+fn div_ceil(amount: Uint128, rate: Decimal) -> Uint128 {
+    let result = amount.multiply_ratio(Decimal::one().atomics(), rate.atomics());
+    if result * rate < amount {
+        result + Uint128::one()
+    } else {
+        result
+    }
+}
+
+/// Takeable amount per profit, ordered by the profit
+fn takeable_steps(
+    config: &ValidatedConfig,
+    balances: &BalancesDetails,
+    utilization_factor: Decimal,
+) -> CustomResult<Vec<(Decimal, Uint128)>> {
+    Ok(balances
+        .calc_all_takeable_steps(config, utilization_factor)?
+        .into_iter()
+        .filter(|step| step.0 < Decimal::one())
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .collect_vec())
+}
+
+pub(crate) fn remove_offer(
+    balances: &mut BalancesDetails,
+    name: &str,
+    offer_amount: Uint128,
+) -> CustomResult<()> {
+    let claim = balances
+        .details
+        .iter_mut()
+        .find(|claim| claim.name == *name)
+        .ok_or_else(|| ContractError::AdapterNotFound(name.into()))?;
+
+    let value = offer_amount * claim.xfactor;
+    claim.xbalance = claim.xbalance.checked_sub(offer_amount)?;
+
+    balances.lsd_xvalue = balances.lsd_xvalue.saturating_sub(value);
+    balances.tvl_utoken = balances.tvl_utoken.saturating_sub(value);
+    balances.vault_total = balances.vault_total.saturating_sub(value);
+    Ok(())
+}
+
+fn add_offer(
+    balances: &mut BalancesDetails,
+    name: &str,
+    offer_amount: Uint128,
+    paid_amount: Uint128,
+) -> CustomResult<()> {
+    let claim = balances
+        .details
+        .iter_mut()
+        .find(|claim| claim.name == *name)
+        .ok_or_else(|| ContractError::AdapterNotFound(name.into()))?;
+
+    let value = offer_amount * claim.xfactor;
+    claim.xbalance += offer_amount;
+
+    balances.lsd_xvalue += value;
+    balances.tvl_utoken = (balances.tvl_utoken + value).checked_sub(paid_amount)?;
+    balances.vault_total = (balances.vault_total + value).checked_sub(paid_amount)?;
+    balances.vault_available = balances.vault_available.checked_sub(paid_amount)?;
+    balances.vault_takeable = balances.vault_takeable.checked_sub(paid_amount)?;
+    Ok(())
+}
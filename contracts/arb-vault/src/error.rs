@@ -29,6 +29,9 @@ pub enum ContractError {
     #[error("Nothing to withdraw")]
     NothingToWithdraw {},
 
+    #[error("No LSD policy is due")]
+    NothingToCrank {},
+
     #[error("Not enough profit")]
     NotEnoughProfit {},
 
@@ -41,9 +44,25 @@ pub enum ContractError {
         old_balance: Uint128,
     },
 
+    #[error("Batch profit {profit} does not match profit_by_assets {profit_by_xassets}")]
+    BatchProfitDoesNotMatch {
+        profit_by_xassets: Uint128,
+        profit: Uint128,
+    },
+
     #[error("Not enough balance. Do not take from locked")]
     DoNotTakeLockedBalance {},
 
+    #[error("Exposure to {name} of {exposure_bps} bps exceeds the max of {max_exposure_bps} bps")]
+    MaxExposureExceeded {
+        name: String,
+        exposure_bps: u16,
+        max_exposure_bps: u16,
+    },
+
+    #[error("Arbitrage batch requires at least one leg")]
+    NoArbitrageLegs {},
+
     #[error("Not enough funds for the requested action")]
     NotEnoughFundsTakeable {},
 
@@ -84,6 +103,9 @@ pub enum ContractError {
     #[error("Specified {0} is too high")]
     ConfigTooHigh(String),
 
+    #[error("Adapter asset {0} is the utoken or used by another adapter")]
+    AdapterAssetInvalid(String),
+
     #[error("Adapter {0} is disabled")]
     AdapterDisabled(String),
 
@@ -138,6 +160,15 @@ pub enum ContractError {
 
     #[error("CW20 tokens can be swapped via Cw20::Send message only")]
     Cw20DirectSwap {},
+
+    #[error("Operation exceeds max spread limit")]
+    MaxSpreadAssertion {},
+
+    #[error("Allowed spread must be less than 50%")]
+    AllowedSpreadAssertion {},
+
+    #[error("CW20 tokens can be deposited via Cw20::Send message only")]
+    Cw20DirectDeposit {},
 }
 
 pub fn adapter_error(adapter: &str, msg: &str, orig: StdError) -> ContractError {
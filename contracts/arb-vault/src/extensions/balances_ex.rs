@@ -1,6 +1,9 @@
 use crate::error::{ContractError, CustomResult};
 use cosmwasm_std::{Decimal, Uint128};
-use eris::arb_vault::{BalancesDetails, ClaimBalance, UtilizationMethod, ValidatedConfig};
+use eris::arb_vault::{
+    BalancesDetails, ClaimBalance, LsdExposure, UtilizationMethod, ValidatedConfig,
+};
+use eris::helpers::bps::BasicPoints;
 
 pub trait BalancesEx {
     fn get_max_utilization_for_profit(
@@ -12,15 +15,21 @@ pub trait BalancesEx {
     fn calc_all_takeable_steps(
         &self,
         config: &ValidatedConfig,
+        utilization_factor: Decimal,
     ) -> CustomResult<Vec<(Decimal, Uint128)>>;
 
     fn calc_takeable_for_profit(
         &self,
         config: &ValidatedConfig,
         profit: &Decimal,
+        utilization_factor: Decimal,
     ) -> CustomResult<Uint128>;
 
     fn get_by_name(&self, name: &str) -> CustomResult<&ClaimBalance>;
+
+    fn calc_exposures(&self, config: &ValidatedConfig) -> Vec<LsdExposure>;
+
+    fn assert_max_exposure(&self, config: &ValidatedConfig, name: &str) -> CustomResult<()>;
 }
 
 impl BalancesEx for BalancesDetails {
@@ -38,37 +47,38 @@ impl BalancesEx for BalancesDetails {
 
                 Ok(step.1)
             },
+            method => interpolate_utilization(&curve_points(method), profit),
         }
     }
 
     fn calc_all_takeable_steps(
         &self,
         config: &ValidatedConfig,
+        utilization_factor: Decimal,
     ) -> CustomResult<Vec<(Decimal, Uint128)>> {
-        match config.utilization_method.clone() {
-            UtilizationMethod::Steps(steps) => steps
-                .into_iter()
-                .map(|step| {
-                    let max_utilization = step.1;
-                    let vault_takeable = calc_vault_takeable(
-                        max_utilization,
-                        self.vault_total,
-                        self.vault_takeable,
-                    )?;
-
-                    Ok((step.0, vault_takeable))
-                })
-                .collect::<CustomResult<Vec<(Decimal, Uint128)>>>(),
-        }
+        curve_points(config.utilization_method.clone())
+            .into_iter()
+            .map(|step| {
+                let max_utilization = adjust_utilization(step.1, utilization_factor);
+                let vault_takeable =
+                    calc_vault_takeable(max_utilization, self.vault_total, self.vault_takeable)?;
+
+                Ok((step.0, vault_takeable))
+            })
+            .collect::<CustomResult<Vec<(Decimal, Uint128)>>>()
     }
 
     fn calc_takeable_for_profit(
         &self,
         config: &ValidatedConfig,
         profit: &Decimal,
+        utilization_factor: Decimal,
     ) -> CustomResult<Uint128> {
         // same calculation as above
-        let max_utilization = self.get_max_utilization_for_profit(config, profit)?;
+        let max_utilization = adjust_utilization(
+            self.get_max_utilization_for_profit(config, profit)?,
+            utilization_factor,
+        );
         let vault_takeable =
             calc_vault_takeable(max_utilization, self.vault_total, self.vault_takeable)?;
 
@@ -82,6 +92,56 @@ impl BalancesEx for BalancesDetails {
             Err(ContractError::AdapterNotFound(name.into()))
         }
     }
+
+    fn calc_exposures(&self, config: &ValidatedConfig) -> Vec<LsdExposure> {
+        self.details
+            .iter()
+            .map(|claim| {
+                let value = exposure_value(claim);
+                LsdExposure {
+                    name: claim.name.clone(),
+                    value,
+                    exposure_bps: if self.tvl_utoken.is_zero() {
+                        0
+                    } else {
+                        // value is part of the tvl, so it never exceeds 10000 bps
+                        value.multiply_ratio(BasicPoints::MAX, self.tvl_utoken).u128() as u16
+                    },
+                    max_exposure_bps: config
+                        .lsds
+                        .iter()
+                        .find(|lsd| lsd.name == claim.name)
+                        .and_then(|lsd| lsd.max_exposure_bps),
+                }
+            })
+            .collect()
+    }
+
+    fn assert_max_exposure(&self, config: &ValidatedConfig, name: &str) -> CustomResult<()> {
+        let exposure = self
+            .calc_exposures(config)
+            .into_iter()
+            .find(|exposure| exposure.name == *name)
+            .ok_or_else(|| ContractError::AdapterNotFound(name.into()))?;
+
+        if let Some(max_exposure_bps) = exposure.max_exposure_bps {
+            let max_value = self.tvl_utoken.multiply_ratio(max_exposure_bps, BasicPoints::MAX);
+            if exposure.value > max_value {
+                return Err(ContractError::MaxExposureExceeded {
+                    name: exposure.name,
+                    exposure_bps: exposure.exposure_bps,
+                    max_exposure_bps,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Value held in a LSD, including the funds that are still unbonding or withdrawable
+fn exposure_value(claim: &ClaimBalance) -> Uint128 {
+    claim.xbalance * claim.xfactor + claim.unbonding + claim.withdrawable
 }
 
 fn calc_vault_takeable(
@@ -98,3 +158,50 @@ fn calc_vault_takeable(
         .checked_sub(vault_total)
         .unwrap_or_default())
 }
+
+/// Profit and utilization points describing the utilization method
+fn curve_points(method: UtilizationMethod) -> Vec<(Decimal, Decimal)> {
+    match method {
+        UtilizationMethod::Steps(steps) => steps,
+        UtilizationMethod::Linear {
+            min_profit,
+            max_profit,
+            min_util,
+            max_util,
+        } => vec![(min_profit, min_util), (max_profit, max_util)],
+        UtilizationMethod::Kink {
+            min_profit,
+            kink_profit,
+            max_profit,
+            min_util,
+            kink_util,
+            max_util,
+        } => vec![(min_profit, min_util), (kink_profit, kink_util), (max_profit, max_util)],
+    }
+}
+
+fn interpolate_utilization(
+    points: &[(Decimal, Decimal)],
+    profit: &Decimal,
+) -> CustomResult<Decimal> {
+    match points.first() {
+        Some(first) if *profit >= first.0 => (),
+        _ => return Err(ContractError::NotSupportedProfitStep(*profit)),
+    }
+
+    for pair in points.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if *profit < to.0 {
+            let progress = (*profit - from.0).checked_div(to.0 - from.0).map_err(|e| {
+                ContractError::CalculationError("utilization".into(), e.to_string())
+            })?;
+            return Ok(from.1 + (to.1 - from.1) * progress);
+        }
+    }
+
+    Ok(points.last().map(|point| point.1).unwrap_or_default())
+}
+
+fn adjust_utilization(utilization: Decimal, utilization_factor: Decimal) -> Decimal {
+    (utilization * utilization_factor).min(Decimal::one())
+}
@@ -2,7 +2,7 @@ pub mod balances_ex;
 pub mod config_ex;
 
 use cosmwasm_std::Decimal;
-use eris::arb_vault::UtilizationMethod;
+use eris::arb_vault::{UtilizationAdjustment, UtilizationMethod};
 
 use crate::error::{ContractError, CustomResult};
 
@@ -28,6 +28,71 @@ impl UtilizationMethodEx for UtilizationMethod {
                     }
                 }
             },
+            UtilizationMethod::Linear {
+                min_profit,
+                max_profit,
+                min_util,
+                max_util,
+            } => {
+                validate_curve(&[*min_profit, *max_profit], &[*min_util, *max_util])?;
+            },
+            UtilizationMethod::Kink {
+                min_profit,
+                kink_profit,
+                max_profit,
+                min_util,
+                kink_util,
+                max_util,
+            } => {
+                validate_curve(
+                    &[*min_profit, *kink_profit, *max_profit],
+                    &[*min_util, *kink_util, *max_util],
+                )?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Profits need to be strictly increasing and utilizations increasing up to 100 %
+fn validate_curve(profits: &[Decimal], utils: &[Decimal]) -> CustomResult<()> {
+    if profits[0] < Decimal::permille(5) {
+        // less than 0.5 % profit not allowed
+        return Err(ContractError::ConfigToLow("min profit".into()));
+    }
+
+    if profits.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(ContractError::ConfigToLow("curve profit".into()));
+    }
+
+    if utils.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err(ContractError::ConfigToLow("curve utilization".into()));
+    }
+
+    if utils.iter().any(|util| *util > Decimal::one()) {
+        return Err(ContractError::ConfigTooHigh("curve utilization".into()));
+    }
+
+    Ok(())
+}
+
+pub trait UtilizationAdjustmentEx {
+    fn validate(&self) -> CustomResult<()>;
+}
+
+impl UtilizationAdjustmentEx for UtilizationAdjustment {
+    fn validate(&self) -> CustomResult<()> {
+        if self.idle_increase > Decimal::one() {
+            return Err(ContractError::ConfigTooHigh("idle increase".into()));
+        }
+
+        if self.locked_threshold > Decimal::one() {
+            return Err(ContractError::ConfigTooHigh("locked threshold".into()));
+        }
+
+        if self.locked_decrease > Decimal::one() {
+            return Err(ContractError::ConfigTooHigh("locked decrease".into()));
         }
 
         Ok(())
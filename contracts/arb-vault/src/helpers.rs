@@ -1,7 +1,8 @@
 use crate::error::CustomResult;
 use crate::extensions::ConfigEx;
-use cosmwasm_std::{Decimal, QuerierWrapper, StdResult, Uint128};
-use eris::arb_vault::{ValidatedConfig, ValidatedFeeConfig};
+use crate::state::State;
+use cosmwasm_std::{Decimal, Env, Order, QuerierWrapper, StdResult, Storage, Uint128};
+use eris::arb_vault::{BalancesDetails, ValidatedConfig, ValidatedFeeConfig};
 use std::ops::Mul;
 
 //----------------------------------------------------------------------------------------
@@ -42,3 +43,41 @@ pub fn calc_fees(
 
     Ok((withdraw_protocol_fee, withdraw_pool_fee))
 }
+
+/// Factor applied to the utilization of the utilization method. It widens the utilization when no
+/// arbitrage was executed recently and tightens it when many user withdrawals are locked.
+pub fn get_utilization_factor(
+    storage: &dyn Storage,
+    env: &Env,
+    state: &State,
+    config: &ValidatedConfig,
+    balances: &BalancesDetails,
+) -> CustomResult<Decimal> {
+    let adjustment = match &config.utilization_adjustment {
+        Some(adjustment) => adjustment,
+        None => return Ok(Decimal::one()),
+    };
+
+    let mut factor = Decimal::one();
+
+    let last_execution = state
+        .exchange_history
+        .range(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map(|(_, history)| history.time_s);
+
+    let idle_since = last_execution.unwrap_or_default() + adjustment.idle_after_s;
+    if idle_since <= env.block.time.seconds() {
+        factor += adjustment.idle_increase;
+    }
+
+    if !balances.tvl_utoken.is_zero()
+        && Decimal::from_ratio(balances.locked_user_withdrawls, balances.tvl_utoken)
+            >= adjustment.locked_threshold
+    {
+        factor = factor * (Decimal::one() - adjustment.locked_decrease);
+    }
+
+    Ok(factor)
+}
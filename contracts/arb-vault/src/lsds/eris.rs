@@ -1,6 +1,7 @@
-use astroport::asset::{token_asset_info, AssetInfo};
+use astroport::asset::AssetInfo;
 use cosmwasm_std::{
-    to_json_binary, Addr, CosmosMsg, Decimal, Deps, QueryRequest, Uint128, WasmMsg, WasmQuery,
+    coins, to_json_binary, Addr, CosmosMsg, Decimal, Deps, QueryRequest, Uint128, WasmMsg,
+    WasmQuery,
 };
 use cw20::Cw20ExecuteMsg;
 use eris::hub::{
@@ -18,7 +19,7 @@ pub struct Eris {
 
     pub wallet: Addr,
     pub addr: Addr,
-    pub cw20: Addr,
+    pub asset: AssetInfo,
 }
 
 pub struct UndelegationCacheItem {
@@ -81,17 +82,32 @@ impl Eris {
     }
 
     fn get_unbond_msg(&self, amount: Uint128) -> CustomResult<CosmosMsg> {
-        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: self.cw20.to_string(),
-            funds: vec![],
-            msg: to_json_binary(&Cw20ExecuteMsg::Send {
-                contract: self.addr.to_string(),
-                amount,
-                msg: to_json_binary(&ReceiveMsg::QueueUnbond {
+        Ok(match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: self.addr.to_string(),
+                    amount,
+                    msg: to_json_binary(&ReceiveMsg::QueueUnbond {
+                        receiver: None,
+                        mint_receipt: false,
+                    })?,
+                })?,
+            }),
+            AssetInfo::NativeToken {
+                denom,
+            } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: self.addr.to_string(),
+                funds: coins(amount.u128(), denom),
+                msg: to_json_binary(&ExecuteMsg::QueueUnbond {
                     receiver: None,
+                    mint_receipt: false,
                 })?,
-            })?,
-        }))
+            }),
+        })
     }
 
     fn get_withdraw_unbonded_msg(&mut self) -> CustomResult<CosmosMsg> {
@@ -100,6 +116,8 @@ impl Eris {
             funds: vec![],
             msg: to_json_binary(&ExecuteMsg::WithdrawUnbonded {
                 receiver: None,
+                batch_ids: None,
+                limit: None,
             })?,
         }))
     }
@@ -139,7 +157,7 @@ impl Eris {
                             token_amount: item.shares,
                             reconciled: previous.reconciled,
                             exchange_rate: Decimal::from_ratio(
-                                previous.uluna_unclaimed,
+                                previous.utoken_unclaimed,
                                 previous.total_shares,
                             ),
                         }
@@ -165,11 +183,18 @@ impl Eris {
 
 impl LsdAdapter for Eris {
     fn used_contracts(&self) -> Vec<Addr> {
-        vec![self.cw20.clone(), self.addr.clone()]
+        match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => vec![contract_addr.clone(), self.addr.clone()],
+            AssetInfo::NativeToken {
+                ..
+            } => vec![self.addr.clone()],
+        }
     }
 
     fn asset(&self) -> AssetInfo {
-        token_asset_info(self.cw20.clone())
+        self.asset.clone()
     }
 
     fn unbond(&self, _deps: &Deps, amount: Uint128) -> CustomResult<Vec<CosmosMsg>> {
@@ -201,4 +226,8 @@ impl LsdAdapter for Eris {
     fn query_factor_x_to_normal(&mut self, deps: &Deps) -> CustomResult<Decimal> {
         Ok(self.cached_state(deps)?.exchange_rate)
     }
+
+    fn query_next_batch_time(&mut self, deps: &Deps) -> CustomResult<Option<u64>> {
+        Ok(Some(self.query_pending_batch(deps)?.est_unbond_start_time))
+    }
 }
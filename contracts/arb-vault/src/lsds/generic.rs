@@ -0,0 +1,128 @@
+use astroport::asset::AssetInfo;
+use cw20::Cw20ExecuteMsg;
+
+use cosmwasm_std::{
+    coins, to_json_binary, Addr, CosmosMsg, Decimal, Deps, QueryRequest, Uint128, WasmMsg,
+    WasmQuery,
+};
+use eris::lsd_adapter::{ExecuteMsg, QueryMsg, ReceiveMsg};
+use serde::de::DeserializeOwned;
+
+use crate::error::{adapter_error, CustomResult};
+
+use super::lsdadapter::LsdAdapter;
+
+/// Proxies the adapter calls to an external contract implementing `LsdInterface::V1`
+pub struct Generic {
+    pub exchange_rate_cache: Option<Decimal>,
+
+    pub wallet: Addr,
+    pub addr: Addr,
+    pub asset: AssetInfo,
+}
+
+impl Generic {
+    fn query<T: DeserializeOwned>(
+        &self,
+        deps: &Deps,
+        msg: &QueryMsg,
+        name: &str,
+    ) -> CustomResult<T> {
+        deps.querier
+            .query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: self.addr.to_string(),
+                msg: to_json_binary(msg)?,
+            }))
+            .map_err(|a| adapter_error("generic", name, a))
+    }
+
+    fn get_unbond_msg(&self, amount: Uint128) -> CustomResult<CosmosMsg> {
+        Ok(match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: self.addr.to_string(),
+                    amount,
+                    msg: to_json_binary(&ReceiveMsg::Unbond {})?,
+                })?,
+            }),
+            AssetInfo::NativeToken {
+                denom,
+            } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: self.addr.to_string(),
+                funds: coins(amount.u128(), denom),
+                msg: to_json_binary(&ExecuteMsg::Unbond {})?,
+            }),
+        })
+    }
+
+    fn get_withdraw_msg(&self) -> CustomResult<CosmosMsg> {
+        Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: self.addr.to_string(),
+            funds: vec![],
+            msg: to_json_binary(&ExecuteMsg::Withdraw {})?,
+        }))
+    }
+}
+
+impl LsdAdapter for Generic {
+    fn used_contracts(&self) -> Vec<Addr> {
+        match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => vec![contract_addr.clone(), self.addr.clone()],
+            AssetInfo::NativeToken {
+                ..
+            } => vec![self.addr.clone()],
+        }
+    }
+
+    fn asset(&self) -> AssetInfo {
+        self.asset.clone()
+    }
+
+    fn unbond(&self, _deps: &Deps, amount: Uint128) -> CustomResult<Vec<CosmosMsg>> {
+        Ok(vec![self.get_unbond_msg(amount)?])
+    }
+
+    fn query_unbonding(&mut self, deps: &Deps) -> CustomResult<Uint128> {
+        self.query(
+            deps,
+            &QueryMsg::Unbonding {
+                user: self.wallet.to_string(),
+            },
+            "query_unbonding",
+        )
+    }
+
+    fn withdraw(&mut self, _deps: &Deps, _amount: Uint128) -> CustomResult<Vec<CosmosMsg>> {
+        Ok(vec![self.get_withdraw_msg()?])
+    }
+
+    fn query_withdrawable(&mut self, deps: &Deps) -> CustomResult<Uint128> {
+        self.query(
+            deps,
+            &QueryMsg::Withdrawable {
+                user: self.wallet.to_string(),
+            },
+            "query_withdrawable",
+        )
+    }
+
+    fn query_factor_x_to_normal(&mut self, deps: &Deps) -> CustomResult<Decimal> {
+        if self.exchange_rate_cache.is_none() {
+            let exchange_rate =
+                self.query(deps, &QueryMsg::ExchangeRate {}, "query_exchange_rate")?;
+            self.exchange_rate_cache = Some(exchange_rate);
+        }
+
+        Ok(self.exchange_rate_cache.unwrap())
+    }
+
+    fn query_next_batch_time(&mut self, _deps: &Deps) -> CustomResult<Option<u64>> {
+        Ok(None)
+    }
+}
@@ -17,4 +17,7 @@ pub trait LsdAdapter {
     fn query_withdrawable(&mut self, deps: &Deps) -> CustomResult<Uint128>;
 
     fn query_factor_x_to_normal(&mut self, deps: &Deps) -> CustomResult<Decimal>;
+
+    /// Time in seconds when the next unbonding batch is submitted, None if unbonding is not batched
+    fn query_next_batch_time(&mut self, deps: &Deps) -> CustomResult<Option<u64>>;
 }
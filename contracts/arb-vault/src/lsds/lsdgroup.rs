@@ -1,6 +1,10 @@
-use astroport::asset::AssetInfo;
+use astroport::asset::{native_asset_info, AssetInfo};
 use cosmwasm_std::{attr, Addr, Attribute, CosmosMsg, Deps, DepsMut, Env, Uint128};
-use eris::arb_vault::{BalancesDetails, ClaimBalance, LsdConfig, LsdType, ValidatedConfig};
+use eris::arb_vault::{
+    BalancesDetails, ClaimBalance, LsdAction, LsdConfig, LsdType, NextAction, UnbondPolicy,
+    ValidatedConfig,
+};
+use eris::lsd_adapter::LsdInterface;
 use itertools::Itertools;
 
 use crate::{
@@ -9,7 +13,10 @@ use crate::{
     state::State,
 };
 
-use super::{eris::Eris, lsdwrapper::LsdWrapper, prism::Prism, stader::Stader, steak::Steak};
+use super::{
+    eris::Eris, generic::Generic, lsdwrapper::LsdWrapper, prism::Prism, stader::Stader,
+    steak::Steak,
+};
 
 pub struct LsdGroup {
     lsds: Vec<LsdWrapper>,
@@ -24,45 +31,56 @@ impl LsdGroup {
                     disabled: config.disabled,
                     name: config.name.clone(),
                     wallet: wallet_address.clone(),
+                    policy: config.policy.clone(),
                     adapter: match config.lsd_type.clone() {
                         LsdType::Eris {
                             addr,
-                            cw20,
+                            cw20_or_denom,
                         } => Box::new(Eris {
                             state_cache: None,
                             undelegation_records_cache: None,
                             addr,
-                            cw20,
+                            asset: cw20_or_denom,
                             wallet: wallet_address.clone(),
                         }),
                         LsdType::Backbone {
                             addr,
-                            cw20,
+                            cw20_or_denom,
                         } => Box::new(Steak {
                             state_cache: None,
                             undelegation_records_cache: None,
                             addr,
-                            cw20,
+                            asset: cw20_or_denom,
                             wallet: wallet_address.clone(),
                         }),
                         LsdType::Stader {
                             addr,
-                            cw20,
+                            cw20_or_denom,
                         } => Box::new(Stader {
                             state_cache: None,
                             undelegation_records_cache: None,
                             addr,
-                            cw20,
+                            asset: cw20_or_denom,
                             wallet: wallet_address.clone(),
                         }),
                         LsdType::Prism {
                             addr,
-                            cw20,
+                            cw20_or_denom,
                         } => Box::new(Prism {
                             state_cache: None,
                             unbonding_cache: None,
                             addr,
-                            cw20,
+                            asset: cw20_or_denom,
+                            wallet: wallet_address.clone(),
+                        }),
+                        LsdType::Generic {
+                            addr,
+                            cw20_or_denom,
+                            interface: LsdInterface::V1,
+                        } => Box::new(Generic {
+                            exchange_rate_cache: None,
+                            addr,
+                            asset: cw20_or_denom,
                             wallet: wallet_address.clone(),
                         }),
                     },
@@ -85,6 +103,10 @@ impl LsdGroup {
         result.ok_or_else(|| ContractError::AdapterNotFound(name.clone()))
     }
 
+    pub fn get_enabled_assets(&self) -> Vec<AssetInfo> {
+        self.lsds.iter().filter(|lsd| !lsd.disabled).map(|lsd| lsd.adapter.asset()).collect_vec()
+    }
+
     // pub fn get_unbonding(&mut self, deps: &Deps) -> CustomResult<Uint128> {
     //     self.lsds.iter_mut().map(|a| a.adapter.query_unbonding(deps)).sum()
     // }
@@ -140,6 +162,79 @@ impl LsdGroup {
         Ok((messages, attributes))
     }
 
+    /// Unbond and withdraw actions that are due according to the policy of each LSD
+    pub fn get_next_actions(&mut self, deps: &Deps, env: &Env) -> CustomResult<Vec<NextAction>> {
+        let mut actions: Vec<NextAction> = vec![];
+
+        for lsd in self.lsds.iter_mut() {
+            if let Some(withdraw) = lsd.policy.withdraw.clone() {
+                let withdrawable = lsd.adapter.query_withdrawable(deps)?;
+
+                if !withdrawable.is_zero() && withdrawable >= withdraw.min_withdrawable {
+                    actions.push(NextAction {
+                        name: lsd.name.clone(),
+                        action: LsdAction::Withdraw {},
+                        amount: withdrawable,
+                    });
+                }
+            }
+
+            if let Some(unbond) = lsd.policy.unbond.clone() {
+                let xbalance = lsd.adapter.asset().query_pool(&deps.querier, &lsd.wallet)?;
+
+                let is_due = match unbond {
+                    UnbondPolicy::Threshold {
+                        min_xbalance,
+                    } => xbalance >= min_xbalance,
+                    UnbondPolicy::BatchEpoch {
+                        window_s,
+                    } => match lsd.adapter.query_next_batch_time(deps)? {
+                        Some(next_batch_time) => {
+                            next_batch_time <= env.block.time.seconds() + window_s
+                        },
+                        None => true,
+                    },
+                };
+
+                if !xbalance.is_zero() && is_due {
+                    actions.push(NextAction {
+                        name: lsd.name.clone(),
+                        action: LsdAction::Unbond {},
+                        amount: xbalance,
+                    });
+                }
+            }
+        }
+
+        Ok(actions)
+    }
+
+    pub fn get_crank_msgs(
+        &mut self,
+        deps: &DepsMut,
+        env: &Env,
+    ) -> CustomResult<(Vec<CosmosMsg>, Vec<Attribute>)> {
+        let mut messages: Vec<CosmosMsg> = vec![];
+        let mut attributes: Vec<Attribute> = vec![attr("action", "arb/execute_crank")];
+
+        for action in self.get_next_actions(&deps.as_ref(), env)? {
+            let lsd = self.get_adapter_by_name(&action.name)?;
+
+            let (mut msgs, key) = match action.action {
+                LsdAction::Unbond {} => {
+                    (lsd.adapter.unbond(&deps.as_ref(), action.amount)?, "unbond_amount")
+                },
+                LsdAction::Withdraw {} => {
+                    (lsd.adapter.withdraw(&deps.as_ref(), action.amount)?, "withdraw_amount")
+                },
+            };
+            messages.append(&mut msgs);
+            attributes.push(attr("type", action.name));
+            attributes.push(attr(key, action.amount));
+        }
+        Ok((messages, attributes))
+    }
+
     pub(crate) fn get_total_assets_err(
         &mut self,
         deps: Deps,
@@ -202,4 +297,21 @@ impl LsdGroup {
 
         Ok(())
     }
+
+    /// Each adapter needs its own asset, which cannot be the utoken of the vault. Otherwise the
+    /// balances of the vault would be counted twice.
+    pub(crate) fn assert_valid_assets(&self, utoken: &str) -> CustomResult<()> {
+        let utoken = native_asset_info(utoken.to_string());
+        let mut assets: Vec<AssetInfo> = vec![];
+
+        for lsd in self.lsds.iter() {
+            let asset = lsd.adapter.asset();
+            if asset == utoken || assets.contains(&asset) {
+                return Err(ContractError::AdapterAssetInvalid(asset.to_string()));
+            }
+            assets.push(asset);
+        }
+
+        Ok(())
+    }
 }
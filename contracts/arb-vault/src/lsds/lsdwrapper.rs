@@ -1,5 +1,5 @@
 use cosmwasm_std::{Addr, Deps};
-use eris::arb_vault::ClaimBalance;
+use eris::arb_vault::{ClaimBalance, LsdPolicy};
 
 use crate::error::{ContractError, CustomResult};
 
@@ -10,6 +10,7 @@ pub struct LsdWrapper {
     pub disabled: bool,
     pub name: String,
     pub wallet: Addr,
+    pub policy: LsdPolicy,
 }
 
 impl LsdWrapper {
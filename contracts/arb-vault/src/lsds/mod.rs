@@ -1,4 +1,5 @@
 pub mod eris;
+pub mod generic;
 pub mod lsdadapter;
 pub mod lsdgroup;
 pub mod lsdwrapper;
@@ -1,4 +1,4 @@
-use astroport::asset::{token_asset_info, AssetInfo};
+use astroport::asset::AssetInfo;
 use cw20::Cw20ExecuteMsg;
 
 use cosmwasm_std::{
@@ -19,7 +19,7 @@ pub struct Prism {
 
     pub wallet: Addr,
     pub addr: Addr,
-    pub cw20: Addr,
+    pub asset: AssetInfo,
 }
 
 impl Prism {
@@ -75,8 +75,22 @@ impl Prism {
     }
 
     fn get_unbond_msg(&self, amount: Uint128) -> CustomResult<CosmosMsg> {
+        let cw20 = match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => contract_addr,
+            AssetInfo::NativeToken {
+                ..
+            } => {
+                return Err(adapter_error_empty(
+                    "prism",
+                    "unbonding a native denom is not supported",
+                ))
+            },
+        };
+
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: self.cw20.to_string(),
+            contract_addr: cw20.to_string(),
             funds: vec![],
             msg: to_json_binary(&Cw20ExecuteMsg::Send {
                 contract: self.addr.to_string(),
@@ -140,11 +154,18 @@ impl Prism {
 
 impl LsdAdapter for Prism {
     fn used_contracts(&self) -> Vec<Addr> {
-        vec![self.cw20.clone(), self.addr.clone()]
+        match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => vec![contract_addr.clone(), self.addr.clone()],
+            AssetInfo::NativeToken {
+                ..
+            } => vec![self.addr.clone()],
+        }
     }
 
     fn asset(&self) -> AssetInfo {
-        token_asset_info(self.cw20.clone())
+        self.asset.clone()
     }
 
     fn unbond(&self, _deps: &Deps, amount: Uint128) -> CustomResult<Vec<CosmosMsg>> {
@@ -168,4 +189,8 @@ impl LsdAdapter for Prism {
     fn query_factor_x_to_normal(&mut self, deps: &Deps) -> CustomResult<Decimal> {
         Ok(self.cached_state(deps)?.exchange_rate)
     }
+
+    fn query_next_batch_time(&mut self, _deps: &Deps) -> CustomResult<Option<u64>> {
+        Ok(None)
+    }
 }
@@ -1,4 +1,4 @@
-use astroport::asset::{token_asset_info, AssetInfo};
+use astroport::asset::AssetInfo;
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
     to_json_binary, Addr, CosmosMsg, Decimal, Deps, QueryRequest, Timestamp, Uint128, WasmMsg, WasmQuery,
@@ -38,7 +38,7 @@ pub struct Stader {
 
     pub wallet: Addr,
     pub addr: Addr,
-    pub cw20: Addr,
+    pub asset: AssetInfo,
 }
 
 impl Stader {
@@ -90,8 +90,22 @@ impl Stader {
     }
 
     fn get_unbond_msg(&self, amount: Uint128) -> CustomResult<CosmosMsg> {
+        let cw20 = match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => contract_addr,
+            AssetInfo::NativeToken {
+                ..
+            } => {
+                return Err(adapter_error_empty(
+                    "stader",
+                    "unbonding a native denom is not supported",
+                ))
+            },
+        };
+
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: self.cw20.to_string(),
+            contract_addr: cw20.to_string(),
             funds: vec![],
             msg: to_json_binary(&Cw20ExecuteMsg::Send {
                 contract: self.addr.to_string(),
@@ -162,11 +176,18 @@ pub struct UndelegationCacheItem {
 
 impl LsdAdapter for Stader {
     fn used_contracts(&self) -> Vec<Addr> {
-        vec![self.cw20.clone(), self.addr.clone()]
+        match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => vec![contract_addr.clone(), self.addr.clone()],
+            AssetInfo::NativeToken {
+                ..
+            } => vec![self.addr.clone()],
+        }
     }
 
     fn asset(&self) -> AssetInfo {
-        token_asset_info(self.cw20.clone())
+        self.asset.clone()
     }
 
     fn unbond(&self, _deps: &Deps, amount: Uint128) -> CustomResult<Vec<CosmosMsg>> {
@@ -215,4 +236,8 @@ impl LsdAdapter for Stader {
     fn query_factor_x_to_normal(&mut self, deps: &Deps) -> CustomResult<Decimal> {
         Ok(self.cached_state(deps)?.state.exchange_rate)
     }
+
+    fn query_next_batch_time(&mut self, _deps: &Deps) -> CustomResult<Option<u64>> {
+        Ok(None)
+    }
 }
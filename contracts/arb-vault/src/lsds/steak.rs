@@ -1,4 +1,4 @@
-use astroport::asset::{token_asset_info, AssetInfo};
+use astroport::asset::AssetInfo;
 use cosmwasm_std::{
     to_json_binary, Addr, CosmosMsg, Decimal, Deps, QueryRequest, Uint128, WasmMsg, WasmQuery,
 };
@@ -8,7 +8,7 @@ use steak::hub::{
     UnbondRequestsByUserResponseItem,
 };
 
-use crate::error::{adapter_error, CustomResult};
+use crate::error::{adapter_error, adapter_error_empty, CustomResult};
 
 use super::lsdadapter::LsdAdapter;
 
@@ -18,7 +18,7 @@ pub struct Steak {
 
     pub wallet: Addr,
     pub addr: Addr,
-    pub cw20: Addr,
+    pub asset: AssetInfo,
 }
 
 pub struct UndelegationCacheItem {
@@ -82,8 +82,22 @@ impl Steak {
     }
 
     fn get_unbond_msg(&self, amount: Uint128) -> CustomResult<CosmosMsg> {
+        let cw20 = match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => contract_addr,
+            AssetInfo::NativeToken {
+                ..
+            } => {
+                return Err(adapter_error_empty(
+                    "steak",
+                    "unbonding a native denom is not supported",
+                ))
+            },
+        };
+
         Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: self.cw20.to_string(),
+            contract_addr: cw20.to_string(),
             funds: vec![],
             msg: to_json_binary(&Cw20ExecuteMsg::Send {
                 contract: self.addr.to_string(),
@@ -167,11 +181,18 @@ impl Steak {
 
 impl LsdAdapter for Steak {
     fn used_contracts(&self) -> Vec<Addr> {
-        vec![self.cw20.clone(), self.addr.clone()]
+        match &self.asset {
+            AssetInfo::Token {
+                contract_addr,
+            } => vec![contract_addr.clone(), self.addr.clone()],
+            AssetInfo::NativeToken {
+                ..
+            } => vec![self.addr.clone()],
+        }
     }
 
     fn asset(&self) -> AssetInfo {
-        token_asset_info(self.cw20.clone())
+        self.asset.clone()
     }
 
     fn unbond(&self, _deps: &Deps, amount: Uint128) -> CustomResult<Vec<CosmosMsg>> {
@@ -203,4 +224,8 @@ impl LsdAdapter for Steak {
     fn query_factor_x_to_normal(&mut self, deps: &Deps) -> CustomResult<Decimal> {
         Ok(self.cached_state(deps)?.exchange_rate)
     }
+
+    fn query_next_batch_time(&mut self, deps: &Deps) -> CustomResult<Option<u64>> {
+        Ok(Some(self.query_pending_batch(deps)?.est_unbond_start_time))
+    }
 }
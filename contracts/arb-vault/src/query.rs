@@ -1,20 +1,26 @@
 use std::ops::Div;
 
+use crate::domain::swap::{assert_ask_utoken, reverse_simulate_swap, simulate_swap};
 use crate::error::{ContractError, CustomResult};
 use crate::extensions::{BalancesEx, ConfigEx};
-use crate::helpers::calc_fees;
+use crate::helpers::{calc_fees, get_utilization_factor};
 use crate::state::{State, UnbondHistory};
 
-use astroport::asset::{native_asset_info, token_asset_info};
+use astroport::asset::{native_asset_info, token_asset_info, Asset, AssetInfo};
 use cosmwasm_std::{Decimal, Deps, Env, Order, StdResult, Uint128};
 
 use cw_storage_plus::Bound;
 use eris::arb_vault::{
-    BalancesOptionalDetails, ConfigResponse, ExchangeHistory, ExchangeRatesResponse, PairInfo,
-    PairType, StateDetails, StateResponse, TakeableResponse, UnbondItem, UnbondRequestsResponse,
-    UserInfoResponse,
+    BalancesOptionalDetails, ConfigResponse, ExchangeHistory, ExchangeRatesResponse, ExecuteMsg,
+    ExposureResponse, NextActionsResponse, PairInfo, PairType, ReverseSimulationResponse,
+    SimulationResponse, StateDetails, StateResponse, TakeableResponse, UnbondItem,
+    UnbondRequestsResponse, UserInfoResponse,
 };
 use eris::constants::DAY;
+use eris::helpers::exchange_rate::{
+    compute_apr, compute_twap, load_exchange_rates, AprResponse, ExchangeRateTwapResponse,
+};
+use eris::helpers::timelock::PendingActionsResponse;
 use eris::voting_escrow::{DEFAULT_LIMIT, MAX_LIMIT};
 
 pub fn query_config(deps: Deps) -> CustomResult<ConfigResponse> {
@@ -40,18 +46,44 @@ pub fn query_takeable(
     let config = state.config.load(deps.storage)?;
     let mut lsds = config.lsd_group(&env);
     let balances = lsds.get_total_assets_err(deps, &env, &state, &config)?;
+    let utilization_factor =
+        get_utilization_factor(deps.storage, &env, &state, &config, &balances)?;
 
     Ok(TakeableResponse {
         takeable: match wanted_profit {
-            Some(wanted) => Some(balances.calc_takeable_for_profit(&config, &wanted)?),
+            Some(wanted) => {
+                Some(balances.calc_takeable_for_profit(&config, &wanted, utilization_factor)?)
+            },
             _ => None,
         },
-        steps: balances.calc_all_takeable_steps(&config).map_err(|e| {
+        steps: balances.calc_all_takeable_steps(&config, utilization_factor).map_err(|e| {
             ContractError::CalculationError("takeable for steps".into(), e.to_string())
         })?,
     })
 }
 
+pub fn query_exposure(deps: Deps, env: Env) -> CustomResult<ExposureResponse> {
+    let state = State::default();
+    let config = state.config.load(deps.storage)?;
+    let mut lsds = config.lsd_group(&env);
+    let balances = lsds.get_total_assets_err(deps, &env, &state, &config)?;
+
+    Ok(ExposureResponse {
+        tvl_utoken: balances.tvl_utoken,
+        exposures: balances.calc_exposures(&config),
+    })
+}
+
+pub fn query_next_actions(deps: Deps, env: Env) -> CustomResult<NextActionsResponse> {
+    let state = State::default();
+    let config = state.config.load(deps.storage)?;
+    let mut lsds = config.lsd_group(&env);
+
+    Ok(NextActionsResponse {
+        actions: lsds.get_next_actions(&deps, &env)?,
+    })
+}
+
 pub fn query_unbond_requests(
     deps: Deps,
     env: Env,
@@ -112,10 +144,12 @@ pub fn query_state(
     let total_lp_supply = config.query_lp_supply(&deps.querier)?;
     let balances = lsds.get_total_assets_err(deps, &env, &state, &config)?;
     let details = if include_details.unwrap_or_default() {
+        let utilization_factor =
+            get_utilization_factor(deps.storage, &env, &state, &config, &balances)?;
         Some(StateDetails {
-            takeable_steps: balances.calc_all_takeable_steps(&config).map_err(|e| {
-                ContractError::CalculationError("takeable for steps".into(), e.to_string())
-            })?,
+            takeable_steps: balances.calc_all_takeable_steps(&config, utilization_factor).map_err(
+                |e| ContractError::CalculationError("takeable for steps".into(), e.to_string()),
+            )?,
         })
     } else {
         None
@@ -212,14 +246,114 @@ pub fn query_exchange_rates(
     })
 }
 
+pub fn query_exchange_rate_twap(
+    deps: Deps,
+    env: Env,
+    window_s: u64,
+) -> StdResult<ExchangeRateTwapResponse> {
+    let now = env.block.time.seconds();
+    let exchange_rates = load_exchange_rates(
+        deps.storage,
+        &State::default().exchange_history,
+        now.saturating_sub(window_s) / DAY,
+        |_, history| (history.time_s, history.exchange_rate),
+    )?;
+
+    compute_twap(&exchange_rates, now, window_s)
+}
+
+pub fn query_apr(deps: Deps, env: Env, windows: Vec<u64>) -> StdResult<AprResponse> {
+    let now = env.block.time.seconds();
+    let max_window = windows.iter().max().copied().unwrap_or_default();
+    let exchange_rates = load_exchange_rates(
+        deps.storage,
+        &State::default().exchange_history,
+        now.saturating_sub(max_window) / DAY,
+        |_, history| (history.time_s, history.exchange_rate),
+    )?;
+
+    Ok(AprResponse {
+        aprs: windows
+            .into_iter()
+            .map(|window_s| compute_apr(&exchange_rates, now, window_s))
+            .collect(),
+    })
+}
+
+pub fn query_pending_actions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PendingActionsResponse<ExecuteMsg>> {
+    State::default().timelock.query_pending(deps.storage, start_after, limit)
+}
+
 pub fn query_pair(deps: Deps, env: Env) -> StdResult<PairInfo> {
     let state = State::default();
     let config = state.config.load(deps.storage)?;
 
+    let mut asset_infos =
+        vec![native_asset_info(config.utoken.clone()), token_asset_info(config.lp_addr.clone())];
+    asset_infos.extend(config.lsd_group(&env).get_enabled_assets());
+
     Ok(PairInfo {
-        asset_infos: vec![native_asset_info(config.utoken), token_asset_info(config.lp_addr)],
+        asset_infos,
         contract_addr: env.contract.address.clone(),
         liquidity_token: env.contract.address,
         pair_type: PairType::Custom("virtual".to_string()),
     })
 }
+
+pub fn query_simulation(
+    deps: Deps,
+    env: Env,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+) -> CustomResult<SimulationResponse> {
+    let state = State::default();
+    let config = state.config.load(deps.storage)?;
+    let fee_config = state.fee_config.load(deps.storage)?;
+    assert_ask_utoken(&config, &ask_asset_info)?;
+
+    let mut lsds = config.lsd_group(&env);
+    let balances = lsds.get_total_assets_err(deps, &env, &state, &config)?;
+    let lsd =
+        lsds.get_adapter_by_asset(offer_asset.info).map_err(|_| ContractError::AssetMismatch {})?;
+    lsd.assert_not_disabled()?;
+    let name = lsd.name.clone();
+
+    let utilization_factor =
+        get_utilization_factor(deps.storage, &env, &state, &config, &balances)?;
+    simulate_swap(&config, &fee_config, &balances, utilization_factor, &name, offer_asset.amount)
+}
+
+pub fn query_reverse_simulation(
+    deps: Deps,
+    env: Env,
+    offer_asset_info: Option<AssetInfo>,
+    ask_asset: Asset,
+) -> CustomResult<ReverseSimulationResponse> {
+    let state = State::default();
+    let config = state.config.load(deps.storage)?;
+    let fee_config = state.fee_config.load(deps.storage)?;
+    assert_ask_utoken(&config, &Some(ask_asset.info))?;
+
+    let offer_asset_info = offer_asset_info.ok_or(ContractError::AssetMismatch {})?;
+    let mut lsds = config.lsd_group(&env);
+    let balances = lsds.get_total_assets_err(deps, &env, &state, &config)?;
+    let lsd =
+        lsds.get_adapter_by_asset(offer_asset_info).map_err(|_| ContractError::AssetMismatch {})?;
+    lsd.assert_not_disabled()?;
+    let name = lsd.name.clone();
+
+    let utilization_factor =
+        get_utilization_factor(deps.storage, &env, &state, &config, &balances)?;
+    reverse_simulate_swap(
+        &config,
+        &fee_config,
+        &balances,
+        utilization_factor,
+        &name,
+        ask_asset.amount,
+    )
+}
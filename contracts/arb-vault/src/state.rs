@@ -1,14 +1,22 @@
 use crate::{domain::ownership::OwnershipProposal, error::ContractError};
+use astroport::asset::token_asset_info;
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Api, Decimal, StdResult, Storage, Uint128};
 use cw_storage_plus::{Item, Map};
-use eris::arb_vault::{ClaimBalance, ExchangeHistory, ValidatedConfig, ValidatedFeeConfig};
+use eris::arb_vault::{
+    ClaimBalance, ExchangeHistory, ExecuteMsg, LsdConfig, LsdPolicy, LsdType,
+    UtilizationAdjustment, UtilizationMethod, ValidatedConfig, ValidatedFeeConfig,
+};
+use eris::helpers::timelock::Timelock;
 
 #[cw_serde]
 pub struct BalanceCheckpoint {
     pub vault_available: Uint128,
     pub tvl_utoken: Uint128,
-    pub active_balance: ClaimBalance,
+    // only set for single arbitrage executions, batches are asserted over all assets
+    pub active_balance: Option<ClaimBalance>,
+    // only set for batch executions, one balance per result token
+    pub leg_balances: Vec<ClaimBalance>,
 }
 
 #[cw_serde]
@@ -39,6 +47,101 @@ impl UnbondHistory {
     }
 }
 
+/// Config as stored before the LSD types carried their `cw20_or_denom`, only read when migrating
+#[cw_serde]
+pub(crate) struct LegacyConfig {
+    pub utoken: String,
+    pub utilization_method: UtilizationMethod,
+    #[serde(default)]
+    pub utilization_adjustment: Option<UtilizationAdjustment>,
+    pub unbond_time_s: u64,
+    pub lp_addr: Addr,
+    pub lsds: Vec<LegacyLsdConfig>,
+}
+
+#[cw_serde]
+pub(crate) struct LegacyLsdConfig {
+    pub disabled: bool,
+    pub name: String,
+    pub lsd_type: LegacyLsdType,
+    #[serde(default)]
+    pub max_exposure_bps: Option<u16>,
+    #[serde(default)]
+    pub policy: LsdPolicy,
+}
+
+#[cw_serde]
+pub(crate) enum LegacyLsdType {
+    Eris {
+        addr: Addr,
+        cw20: Addr,
+    },
+    Backbone {
+        addr: Addr,
+        cw20: Addr,
+    },
+    Stader {
+        addr: Addr,
+        cw20: Addr,
+    },
+    Prism {
+        addr: Addr,
+        cw20: Addr,
+    },
+}
+
+impl LegacyConfig {
+    pub fn into_config(self) -> ValidatedConfig {
+        ValidatedConfig {
+            utoken: self.utoken,
+            utilization_method: self.utilization_method,
+            utilization_adjustment: self.utilization_adjustment,
+            unbond_time_s: self.unbond_time_s,
+            lp_addr: self.lp_addr,
+            lsds: self
+                .lsds
+                .into_iter()
+                .map(|lsd| LsdConfig {
+                    disabled: lsd.disabled,
+                    name: lsd.name,
+                    max_exposure_bps: lsd.max_exposure_bps,
+                    policy: lsd.policy,
+                    lsd_type: match lsd.lsd_type {
+                        LegacyLsdType::Eris {
+                            addr,
+                            cw20,
+                        } => LsdType::Eris {
+                            addr,
+                            cw20_or_denom: token_asset_info(cw20),
+                        },
+                        LegacyLsdType::Backbone {
+                            addr,
+                            cw20,
+                        } => LsdType::Backbone {
+                            addr,
+                            cw20_or_denom: token_asset_info(cw20),
+                        },
+                        LegacyLsdType::Stader {
+                            addr,
+                            cw20,
+                        } => LsdType::Stader {
+                            addr,
+                            cw20_or_denom: token_asset_info(cw20),
+                        },
+                        LegacyLsdType::Prism {
+                            addr,
+                            cw20,
+                        } => LsdType::Prism {
+                            addr,
+                            cw20_or_denom: token_asset_info(cw20),
+                        },
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
 pub(crate) struct State<'a> {
     pub config: Item<'a, ValidatedConfig>,
     pub fee_config: Item<'a, ValidatedFeeConfig>,
@@ -50,6 +153,7 @@ pub(crate) struct State<'a> {
     pub balance_checkpoint: Item<'a, BalanceCheckpoint>,
     pub balance_locked: Item<'a, BalanceLocked>,
     pub whitelisted_addrs: Item<'a, Vec<Addr>>,
+    pub timelock: Timelock<'a, ExecuteMsg>,
 }
 
 impl Default for State<'static> {
@@ -65,6 +169,7 @@ impl Default for State<'static> {
             balance_checkpoint: Item::new("balance_checkpoint"),
             balance_locked: Item::new("balance_locked"),
             whitelisted_addrs: Item::new("whitelisted_addrs"),
+            timelock: Timelock::new("timelock_delay", "timelock_next_id", "timelock_actions"),
         }
     }
 }
@@ -181,7 +181,7 @@ impl CustomQuerier {
                                 id,
                                 reconciled: id < 2,
                                 total_shares: Uint128::from(1000u128),
-                                uluna_unclaimed: Uint128::from(1100u128),
+                                utoken_unclaimed: Uint128::from(1100u128),
                                 est_unbond_end_time: 100,
                             })
                             .into(),
@@ -212,12 +212,14 @@ impl CustomQuerier {
                         eris::hub::QueryMsg::State {} => SystemResult::Ok(
                             to_json_binary(&eris::hub::StateResponse {
                                 total_ustake: Uint128::from(1000u128),
-                                total_uluna: Uint128::from(1100u128),
+                                total_utoken: Uint128::from(1100u128),
                                 exchange_rate: Decimal::from_str("1.1").unwrap(),
                                 unlocked_coins: vec![],
                                 unbonding: Uint128::new(0),
                                 available: Uint128::new(0),
-                                tvl_uluna: Uint128::new(1100),
+                                tvl_utoken: Uint128::new(1100),
+                                instant_unbond_buffer: Uint128::zero(),
+                                instant_unbond_utilization: Decimal::zero(),
                             })
                             .into(),
                         ),
@@ -269,6 +271,18 @@ impl CustomQuerier {
                         ),
                         _ => err_unsupported_query(msg),
                     };
+                } else if contract_addr == "generic" {
+                    return match from_binary(msg).unwrap() {
+                        eris::lsd_adapter::QueryMsg::Unbonding {
+                            ..
+                        } => SystemResult::Ok(to_json_binary(&self.unbonding_amount).into()),
+                        eris::lsd_adapter::QueryMsg::Withdrawable {
+                            ..
+                        } => SystemResult::Ok(to_json_binary(&self.withdrawable_amount).into()),
+                        eris::lsd_adapter::QueryMsg::ExchangeRate {} => SystemResult::Ok(
+                            to_json_binary(&Decimal::from_str("1.05").unwrap()).into(),
+                        ),
+                    };
                 } else if contract_addr == "stader" {
                     return match from_binary(msg).unwrap() {
                         stader::msg::QueryMsg::GetUserUndelegationRecords {
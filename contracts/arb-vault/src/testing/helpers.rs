@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use astroport::asset::token_asset_info;
 use cosmwasm_std::testing::{mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     from_binary, to_json_binary, Addr, BlockInfo, ContractInfo, Decimal, Deps, DepsMut, Env, Event,
@@ -10,7 +11,7 @@ use cw20::MinterResponse;
 use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
 use serde::de::DeserializeOwned;
 
-use eris::arb_vault::{InstantiateMsg, LsdConfig, QueryMsg};
+use eris::arb_vault::{InstantiateMsg, LsdConfig, LsdPolicy, QueryMsg};
 
 use crate::constants::INSTANTIATE_TOKEN_REPLY_ID;
 use crate::contract::{instantiate, query, reply};
@@ -84,34 +85,42 @@ pub fn create_default_lsd_configs() -> Vec<LsdConfig<String>> {
     vec![
         LsdConfig {
             disabled: false,
+            max_exposure_bps: None,
+            policy: LsdPolicy::default(),
             name: "eris".into(),
             lsd_type: eris::arb_vault::LsdType::Eris {
                 addr: "eris".into(),
-                cw20: "eriscw".into(),
+                cw20_or_denom: token_asset_info(Addr::unchecked("eriscw")),
             },
         },
         LsdConfig {
             disabled: false,
+            max_exposure_bps: None,
+            policy: LsdPolicy::default(),
             name: "backbone".into(),
             lsd_type: eris::arb_vault::LsdType::Backbone {
                 addr: "backbone".into(),
-                cw20: "backbonecw".into(),
+                cw20_or_denom: token_asset_info(Addr::unchecked("backbonecw")),
             },
         },
         LsdConfig {
             disabled: false,
+            max_exposure_bps: None,
+            policy: LsdPolicy::default(),
             name: "stader".into(),
             lsd_type: eris::arb_vault::LsdType::Stader {
                 addr: "stader".into(),
-                cw20: "stadercw".into(),
+                cw20_or_denom: token_asset_info(Addr::unchecked("stadercw")),
             },
         },
         LsdConfig {
             disabled: false,
+            max_exposure_bps: None,
+            policy: LsdPolicy::default(),
             name: "prism".into(),
             lsd_type: eris::arb_vault::LsdType::Prism {
                 addr: "prism".into(),
-                cw20: "prismcw".into(),
+                cw20_or_denom: token_asset_info(Addr::unchecked("prismcw")),
             },
         },
     ]
@@ -205,6 +214,7 @@ pub fn create_default_init() -> InstantiateMsg {
                 Decimal::from_ratio(100u128, 100u128),
             ),
         ]),
+        utilization_adjustment: None,
         unbond_time_s: 100,
         lsds: create_default_lsd_configs(),
         fee_config: eris::arb_vault::FeeConfig {
@@ -1,28 +1,46 @@
 use std::str::FromStr;
 
 use crate::{
-    contract::execute,
+    contract::{execute, instantiate, migrate},
     error::ContractError,
-    query::{query_state, query_takeable, query_unbond_requests},
-    testing::helpers::{_mock_env_at_timestamp, create_default_lsd_configs, mock_env, setup_test},
+    lsds::lsdgroup::LsdGroup,
+    query::{
+        query_apr, query_exchange_rate_twap, query_exposure, query_next_actions,
+        query_reverse_simulation, query_simulation, query_state, query_takeable,
+        query_unbond_requests,
+    },
+    state::{LegacyConfig, LegacyLsdConfig, LegacyLsdType, State},
+    testing::helpers::{
+        _mock_env_at_timestamp, create_default_init, create_default_lsd_configs, mock_dependencies,
+        mock_env, setup_test,
+    },
 };
 
-use crate::query::{query_config, query_user_info};
+use crate::query::{query_config, query_pending_actions, query_user_info};
 
-use astroport::asset::{native_asset, token_asset_info};
+use astroport::asset::{native_asset, native_asset_info, token_asset, token_asset_info, AssetInfo};
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::testing::{mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, coin, from_binary, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, OwnedDeps,
-    Response, Uint128, WasmMsg,
+    attr, coin, from_binary, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps,
+    OwnedDeps, Response, StdError, StdResult, Uint128, WasmMsg,
 };
 use eris::arb_vault::{
-    Balances, ClaimBalance, Config, ConfigResponse, Cw20HookMsg, ExecuteMsg, ExecuteSubMsg,
-    FeeConfig, StateDetails, StateResponse, TakeableResponse, UnbondItem, UnbondRequestsResponse,
-    UserInfoResponse, UtilizationMethod,
+    ArbLeg, Balances, CallbackMsg, ClaimBalance, Config, ConfigResponse, Cw20HookMsg,
+    ExchangeHistory, ExecuteMsg, ExecuteSubMsg, ExposureResponse, FeeConfig, LsdAction, LsdConfig,
+    LsdExposure, LsdPolicy, LsdType, MigrateMsg, NextAction, ReverseSimulationResponse,
+    SimulationResponse, StateDetails, StateResponse, TakeableResponse, UnbondItem, UnbondPolicy,
+    UnbondRequestsResponse, UserInfoResponse, UtilizationAdjustment, UtilizationMethod,
+    WithdrawPolicy,
 };
 
+use eris::constants::{DAY, HOUR};
+use eris::helpers::exchange_rate::{ExchangeRateTwapResponse, WindowApr};
+use eris::helpers::timelock::{PendingAction, PendingActionsResponse};
+use eris::lsd_adapter::LsdInterface;
+
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_storage_plus::Item;
 use itertools::Itertools;
 
 use super::custom_querier::CustomQuerier;
@@ -47,6 +65,7 @@ fn proper_initialization() {
                     (Decimal::from_ratio(20u128, 1000u128), Decimal::from_ratio(90u128, 100u128),),
                     (Decimal::from_ratio(25u128, 1000u128), Decimal::from_ratio(100u128, 100u128),),
                 ]),
+                utilization_adjustment: None,
                 unbond_time_s: 100,
                 lp_addr: Addr::unchecked("lptoken"),
                 lsds: create_default_lsd_configs()
@@ -72,14 +91,19 @@ fn update_config() {
 
     let upd_msg = ExecuteMsg::UpdateConfig {
         utilization_method: None,
+        utilization_adjustment: None,
+        remove_utilization_adjustment: None,
         unbond_time_s: Some(10u64),
         disable_lsd: None,
         insert_lsd: None,
         remove_lsd: None,
         force_remove_lsd: None,
+        set_max_exposure: None,
+        set_policy: None,
         fee_config: None,
         set_whitelist: None,
         remove_whitelist: None,
+        timelock_delay_s: None,
     };
 
     let res =
@@ -101,6 +125,7 @@ fn update_config() {
                     (Decimal::from_ratio(20u128, 1000u128), Decimal::from_ratio(90u128, 100u128),),
                     (Decimal::from_ratio(25u128, 1000u128), Decimal::from_ratio(100u128, 100u128),),
                 ]),
+                utilization_adjustment: None,
                 unbond_time_s: 10,
                 lp_addr: Addr::unchecked("lptoken"),
                 lsds: create_default_lsd_configs()
@@ -121,14 +146,19 @@ fn update_config() {
 
     let upd_msg = ExecuteMsg::UpdateConfig {
         utilization_method: Some(UtilizationMethod::Steps(vec![])),
+        utilization_adjustment: None,
+        remove_utilization_adjustment: None,
         unbond_time_s: None,
         disable_lsd: None,
         insert_lsd: None,
         remove_lsd: None,
         force_remove_lsd: None,
+        set_max_exposure: None,
+        set_policy: None,
         fee_config: None,
         remove_whitelist: None,
         set_whitelist: None,
+        timelock_delay_s: None,
     };
 
     let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), upd_msg).unwrap();
@@ -141,6 +171,7 @@ fn update_config() {
             config: Config {
                 utoken: "utoken".into(),
                 utilization_method: UtilizationMethod::Steps(vec![]),
+                utilization_adjustment: None,
                 unbond_time_s: 10,
                 lp_addr: Addr::unchecked("lptoken"),
                 lsds: create_default_lsd_configs()
@@ -160,6 +191,179 @@ fn update_config() {
     );
 }
 
+#[test]
+fn update_config_timelocked() {
+    let mut deps = setup_test();
+
+    let upd_msg =
+        |unbond_time_s: Option<u64>, timelock_delay_s: Option<u64>| ExecuteMsg::UpdateConfig {
+            utilization_method: None,
+            utilization_adjustment: None,
+            remove_utilization_adjustment: None,
+            unbond_time_s,
+            disable_lsd: None,
+            insert_lsd: None,
+            remove_lsd: None,
+            force_remove_lsd: None,
+            set_max_exposure: None,
+            set_policy: None,
+            fee_config: None,
+            set_whitelist: None,
+            remove_whitelist: None,
+            timelock_delay_s,
+        };
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), upd_msg(None, Some(86400)))
+        .unwrap();
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), upd_msg(Some(10), None))
+        .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "propose_timelocked_action"),
+            attr("id", "0"),
+            attr("executable_at", "86401")
+        ]
+    );
+
+    let config = query_config(deps.as_ref()).unwrap();
+    assert_ne!(config.config.unbond_time_s, 10);
+
+    let pending = query_pending_actions(deps.as_ref(), None, None).unwrap();
+    assert_eq!(
+        pending,
+        PendingActionsResponse {
+            delay_s: 86400,
+            actions: vec![PendingAction {
+                id: 0,
+                action: upd_msg(Some(10), None),
+                proposed_at: 1,
+                executable_at: 86401,
+            }]
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        _mock_env_at_timestamp(86400),
+        mock_info("user", &[]),
+        ExecuteMsg::ExecuteTimelockedAction {
+            id: 0,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, StdError::generic_err("action 0 is timelocked until 86401").into());
+
+    execute(
+        deps.as_mut(),
+        _mock_env_at_timestamp(86401),
+        mock_info("user", &[]),
+        ExecuteMsg::ExecuteTimelockedAction {
+            id: 0,
+        },
+    )
+    .unwrap();
+
+    let config = query_config(deps.as_ref()).unwrap();
+    assert_eq!(config.config.unbond_time_s, 10);
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), upd_msg(Some(20), None)).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[]),
+        ExecuteMsg::CancelTimelockedAction {
+            id: 1,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::CancelTimelockedAction {
+            id: 1,
+        },
+    )
+    .unwrap();
+
+    let pending = query_pending_actions(deps.as_ref(), None, None).unwrap();
+    assert_eq!(pending.actions, vec![]);
+
+    // a new owner doesn't inherit the actions proposed by the previous one
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), upd_msg(Some(30), None)).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: "new_owner".to_string(),
+            expires_in: 100,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        ExecuteMsg::ClaimOwnership {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_ownership"),
+            attr("new_owner", "new_owner"),
+            attr("cancelled_actions", "1")
+        ]
+    );
+
+    let pending = query_pending_actions(deps.as_ref(), None, None).unwrap();
+    assert_eq!(pending.actions, vec![]);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: "owner".to_string(),
+            expires_in: 100,
+        },
+    )
+    .unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), ExecuteMsg::ClaimOwnership {})
+        .unwrap();
+
+    // changing the utilization is timelocked as well
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            utilization_method: Some(UtilizationMethod::Steps(vec![])),
+            utilization_adjustment: None,
+            remove_utilization_adjustment: None,
+            unbond_time_s: None,
+            disable_lsd: None,
+            insert_lsd: None,
+            remove_lsd: None,
+            force_remove_lsd: None,
+            set_max_exposure: None,
+            set_policy: None,
+            fee_config: None,
+            set_whitelist: None,
+            remove_whitelist: None,
+            timelock_delay_s: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.attributes[0], attr("action", "propose_timelocked_action"));
+}
+
 #[test]
 fn provide_liquidity_wrong_token() {
     let mut deps = setup_test();
@@ -518,7 +722,9 @@ fn check_withdrawing() {
             assert_eq!(
                 sub_msg,
                 eris::hub::ExecuteMsg::WithdrawUnbonded {
-                    receiver: None
+                    receiver: None,
+                    batch_ids: None,
+                    limit: None,
                 }
             );
         },
@@ -2124,7 +2330,8 @@ fn execute_arb() {
                 assert_eq!(
                     sub_sub_msg,
                     eris::hub::ReceiveMsg::QueueUnbond {
-                        receiver: None
+                        receiver: None,
+                        mint_receipt: false,
                     }
                 );
             } else {
@@ -2149,6 +2356,1641 @@ fn execute_arb() {
     assert_eq!(takeable, Uint128::zero());
 }
 
+#[test]
+fn execute_arb_batch() {
+    let (mut deps, _res) = _unbonding_slow_120();
+
+    deps.querier.set_bank_balance(100_000000 + 120_000000);
+    deps.querier.with_unbonding(Uint128::new(24_000000u128));
+    deps.querier.with_withdrawable(Uint128::zero());
+
+    let old_state = query_state(deps.as_ref(), mock_env(), None).unwrap();
+    let old_tvl = old_state.balances.tvl_utoken;
+
+    let whitelist_info = mock_info("whitelisted_exec", &[]);
+    let contract_info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let min_total_profit = Decimal::from_str("0.015").unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        whitelist_info.clone(),
+        ExecuteMsg::ExecuteArbitrageBatch {
+            legs: vec![],
+            min_total_profit,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoArbitrageLegs {});
+
+    let legs = vec![
+        ArbLeg {
+            msg: ExecuteSubMsg {
+                contract_addr: None,
+                funds_amount: Uint128::new(20_000000u128),
+                msg: to_json_binary("exec_eris_swap").unwrap(),
+            },
+            result_token: token_asset_info(Addr::unchecked("eriscw")),
+        },
+        ArbLeg {
+            msg: ExecuteSubMsg {
+                contract_addr: Some("router".to_string()),
+                funds_amount: Uint128::new(20_000000u128),
+                msg: to_json_binary("exec_backbone_swap").unwrap(),
+            },
+            result_token: token_asset_info(Addr::unchecked("backbonecw")),
+        },
+    ];
+
+    // all legs together need to be takeable
+    let mut too_big_legs = legs.clone();
+    too_big_legs[1].msg.funds_amount = Uint128::new(80_000000u128);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        whitelist_info.clone(),
+        ExecuteMsg::ExecuteArbitrageBatch {
+            legs: too_big_legs,
+            min_total_profit,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotEnoughFundsTakeable {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        whitelist_info.clone(),
+        ExecuteMsg::ExecuteArbitrageBatch {
+            legs,
+            min_total_profit,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "arb/execute_arbitrage_batch"), attr("total_funds", "40000000")]
+    );
+    assert_eq!(
+        res.messages.into_iter().map(|a| a.msg).collect_vec(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "whitelisted_exec".to_string(),
+                msg: to_json_binary("exec_eris_swap").unwrap(),
+                funds: vec![coin(20_000000u128, "utoken")],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "router".to_string(),
+                msg: to_json_binary("exec_backbone_swap").unwrap(),
+                funds: vec![coin(20_000000u128, "utoken")],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                msg: to_json_binary(&ExecuteMsg::Callback(CallbackMsg::AssertBatchResult {
+                    result_tokens: vec![
+                        token_asset_info(Addr::unchecked("eriscw")),
+                        token_asset_info(Addr::unchecked("backbonecw")),
+                    ],
+                    min_total_profit,
+                }))
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    let assert_msg = ExecuteMsg::Callback(CallbackMsg::AssertBatchResult {
+        result_tokens: vec![
+            token_asset_info(Addr::unchecked("eriscw")),
+            token_asset_info(Addr::unchecked("backbonecw")),
+        ],
+        min_total_profit,
+    });
+
+    //
+    // APPLYING SUB MSGS TO NEW BALANCE
+    //
+    deps.querier.set_bank_balance(100_000000 + 120_000000 - 40_000000);
+    // eris leg loses 1 %: 20 luna -> 18 ampluna (19.8 luna)
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 18_000000);
+    // backbone leg only wins 2 %, in total 0.5 % profit
+    deps.querier.set_cw20_balance("backbonecw", MOCK_CONTRACT_ADDR, 20_400000);
+
+    let err =
+        execute(deps.as_mut(), mock_env(), contract_info.clone(), assert_msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NotEnoughProfit {});
+
+    // value received in another LSD than the result tokens does not count as profit of the legs
+    deps.querier.set_cw20_balance("stadercw", MOCK_CONTRACT_ADDR, 1_000000);
+    let err =
+        execute(deps.as_mut(), mock_env(), contract_info.clone(), assert_msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BatchProfitDoesNotMatch {
+            profit: Uint128::new(1220000u128),
+            profit_by_xassets: Uint128::new(200000u128),
+        }
+    );
+    deps.querier.set_cw20_balance("stadercw", MOCK_CONTRACT_ADDR, 0);
+
+    // backbone leg wins 4.5 %, in total 1.75 % profit
+    deps.querier.set_cw20_balance("backbonecw", MOCK_CONTRACT_ADDR, 20_900000);
+
+    let res = execute(deps.as_mut(), mock_env(), contract_info, assert_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "arb/assert_batch_result"),
+            attr("types", "eris,backbone"),
+            attr("old_tvl", old_tvl.to_string()),
+            attr("new_tvl", (old_tvl + Uint128::new(700000u128)).to_string()),
+            attr("used_balance", "40000000"),
+            attr("profit", "700000"),
+            attr("exchange_rate", "1.99573"),
+            attr("fee_amount", "7000"),
+        ]
+    );
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0].msg,
+        native_asset("utoken".to_string(), Uint128::new(7000))
+            .into_msg(&deps.as_ref().querier, "fee")
+            .unwrap()
+    );
+
+    // execution finished
+    let state = query_state(deps.as_ref(), mock_env(), None).unwrap();
+    assert_eq!(state.balances.tvl_utoken, old_tvl + Uint128::new(700000u128));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user", &[coin(100, "utoken")]),
+        ExecuteMsg::Deposit {
+            asset: native_asset("utoken".to_string(), Uint128::new(100)),
+            receiver: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn utilization_curves() {
+    let (mut deps, _res) = _unbonding_slow_120();
+
+    // vault_total 198.88, vault_takeable 100, locked 120, tvl 318.88
+    deps.querier.set_bank_balance(100_000000 + 120_000000);
+    deps.querier.with_unbonding(Uint128::new(24_000000u128));
+    deps.querier.with_withdrawable(Uint128::zero());
+
+    let update_utilization =
+        |method: Option<UtilizationMethod>, adjustment: Option<UtilizationAdjustment>| {
+            ExecuteMsg::UpdateConfig {
+                utilization_method: method,
+                utilization_adjustment: adjustment,
+                remove_utilization_adjustment: None,
+                unbond_time_s: None,
+                disable_lsd: None,
+                insert_lsd: None,
+                remove_lsd: None,
+                force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
+                fee_config: None,
+                set_whitelist: None,
+                remove_whitelist: None,
+                timelock_delay_s: None,
+            }
+        };
+    let owner = mock_info("owner", &[]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        update_utilization(
+            Some(UtilizationMethod::Linear {
+                min_profit: Decimal::from_str("0.004").unwrap(),
+                max_profit: Decimal::from_str("0.03").unwrap(),
+                min_util: Decimal::from_str("0.5").unwrap(),
+                max_util: Decimal::one(),
+            }),
+            None,
+        ),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ConfigToLow("min profit".into()));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        update_utilization(
+            Some(UtilizationMethod::Kink {
+                min_profit: Decimal::from_str("0.01").unwrap(),
+                kink_profit: Decimal::from_str("0.01").unwrap(),
+                max_profit: Decimal::from_str("0.03").unwrap(),
+                min_util: Decimal::from_str("0.5").unwrap(),
+                kink_util: Decimal::from_str("0.6").unwrap(),
+                max_util: Decimal::one(),
+            }),
+            None,
+        ),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ConfigToLow("curve profit".into()));
+
+    //
+    // LINEAR
+    //
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        update_utilization(
+            Some(UtilizationMethod::Linear {
+                min_profit: Decimal::from_str("0.01").unwrap(),
+                max_profit: Decimal::from_str("0.03").unwrap(),
+                min_util: Decimal::from_str("0.5").unwrap(),
+                max_util: Decimal::one(),
+            }),
+            None,
+        ),
+    )
+    .unwrap();
+
+    let err = query_takeable(deps.as_ref(), mock_env(), Some(Decimal::from_str("0.005").unwrap()))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotSupportedProfitStep(Decimal::from_str("0.005").unwrap()));
+
+    // 0.75 utilization: 198.88 * 0.75 + 100 - 198.88
+    let res = query_takeable(deps.as_ref(), mock_env(), Some(Decimal::from_str("0.02").unwrap()))
+        .unwrap();
+    assert_eq!(
+        res,
+        TakeableResponse {
+            takeable: Some(Uint128::new(50_280000u128)),
+            steps: vec![
+                (Decimal::from_str("0.01").unwrap(), Uint128::new(560000u128)),
+                (Decimal::from_str("0.03").unwrap(), Uint128::new(100_000000u128)),
+            ]
+        }
+    );
+
+    let res = query_takeable(deps.as_ref(), mock_env(), Some(Decimal::from_str("0.04").unwrap()))
+        .unwrap();
+    assert_eq!(res.takeable, Some(Uint128::new(100_000000u128)));
+
+    //
+    // KINK
+    //
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        update_utilization(
+            Some(UtilizationMethod::Kink {
+                min_profit: Decimal::from_str("0.01").unwrap(),
+                kink_profit: Decimal::from_str("0.02").unwrap(),
+                max_profit: Decimal::from_str("0.03").unwrap(),
+                min_util: Decimal::from_str("0.5").unwrap(),
+                kink_util: Decimal::from_str("0.6").unwrap(),
+                max_util: Decimal::one(),
+            }),
+            None,
+        ),
+    )
+    .unwrap();
+
+    // 0.8 utilization: 198.88 * 0.8 + 100 - 198.88
+    let res = query_takeable(deps.as_ref(), mock_env(), Some(Decimal::from_str("0.025").unwrap()))
+        .unwrap();
+    assert_eq!(res.takeable, Some(Uint128::new(60_224000u128)));
+    assert_eq!(res.steps.len(), 3);
+
+    //
+    // ADJUSTMENT
+    //
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        update_utilization(
+            Some(UtilizationMethod::Linear {
+                min_profit: Decimal::from_str("0.01").unwrap(),
+                max_profit: Decimal::from_str("0.03").unwrap(),
+                min_util: Decimal::from_str("0.5").unwrap(),
+                max_util: Decimal::one(),
+            }),
+            Some(UtilizationAdjustment {
+                idle_after_s: 100,
+                idle_increase: Decimal::from_str("0.1").unwrap(),
+                locked_threshold: Decimal::from_str("0.3").unwrap(),
+                locked_decrease: Decimal::from_str("0.2").unwrap(),
+            }),
+        ),
+    )
+    .unwrap();
+
+    let profit = Some(Decimal::from_str("0.02").unwrap());
+
+    // not idle yet, 37 % locked: 0.75 * 0.8 = 0.6 utilization
+    let res = query_takeable(deps.as_ref(), mock_env(), profit).unwrap();
+    assert_eq!(res.takeable, Some(Uint128::new(20_448000u128)));
+
+    // idle, 37 % locked: 0.75 * 1.1 * 0.8 = 0.66 utilization
+    let res = query_takeable(deps.as_ref(), _mock_env_at_timestamp(1000), profit).unwrap();
+    assert_eq!(res.takeable, Some(Uint128::new(32_380800u128)));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        update_utilization(
+            None,
+            Some(UtilizationAdjustment {
+                idle_after_s: 100,
+                idle_increase: Decimal::from_str("0.1").unwrap(),
+                locked_threshold: Decimal::from_str("0.5").unwrap(),
+                locked_decrease: Decimal::from_str("0.2").unwrap(),
+            }),
+        ),
+    )
+    .unwrap();
+
+    // idle, below the locked threshold: 0.75 * 1.1 = 0.825 utilization
+    let res = query_takeable(deps.as_ref(), _mock_env_at_timestamp(1000), profit).unwrap();
+    assert_eq!(res.takeable, Some(Uint128::new(65_196000u128)));
+
+    // arbitrages are always checked against the adjusted utilization
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("whitelisted_exec", &[]),
+        ExecuteMsg::ExecuteArbitrage {
+            msg: ExecuteSubMsg {
+                contract_addr: None,
+                funds_amount: Uint128::new(50_280001u128),
+                msg: to_json_binary("exec_any_swap").unwrap(),
+            },
+            result_token: token_asset_info(Addr::unchecked("eriscw")),
+            wanted_profit: Decimal::from_str("0.02").unwrap(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotEnoughFundsTakeable {});
+
+    // removing the adjustment goes back to the curve: 0.75 utilization
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner,
+        ExecuteMsg::UpdateConfig {
+            utilization_method: None,
+            utilization_adjustment: None,
+            remove_utilization_adjustment: Some(true),
+            unbond_time_s: None,
+            disable_lsd: None,
+            insert_lsd: None,
+            remove_lsd: None,
+            force_remove_lsd: None,
+            set_max_exposure: None,
+            set_policy: None,
+            fee_config: None,
+            set_whitelist: None,
+            remove_whitelist: None,
+            timelock_delay_s: None,
+        },
+    )
+    .unwrap();
+
+    let config = query_config(deps.as_ref()).unwrap();
+    assert_eq!(config.config.utilization_adjustment, None);
+
+    let res = query_takeable(deps.as_ref(), _mock_env_at_timestamp(1000), profit).unwrap();
+    assert_eq!(res.takeable, Some(Uint128::new(50_280000u128)));
+}
+
+#[test]
+fn deposit_and_withdraw_in_kind() {
+    let (mut deps, _res) = _provide_liquidity();
+
+    // cw20 LSD tokens can only be deposited through a cw20 send
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user2", &[]),
+        ExecuteMsg::Deposit {
+            asset: token_asset(Addr::unchecked("eriscw"), Uint128::new(10_000000u128)),
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Cw20DirectDeposit {});
+
+    //
+    // DEPOSIT LSD
+    //
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 10_000000u128);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("eriscw", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "user2".to_string(),
+            amount: Uint128::new(10_000000u128),
+            msg: to_json_binary(&Cw20HookMsg::Deposit {
+                receiver: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+
+    // 10 ampLUNA valued at 1.1 like a swap with the 1% profit step
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "arb/execute_deposit"),
+            attr("sender", "user2"),
+            attr("recipient", "user2"),
+            attr("deposit_amount", "10890000"),
+            attr("share", "10890000"),
+            attr("vault_utoken_new", "111000000"),
+            attr("deposit_asset", "eriscw"),
+            attr("deposit_xamount", "10000000"),
+            attr("deposit_xfactor", "1.1"),
+            attr("deposit_spread", "110000"),
+        ]
+    );
+    assert_eq!(res.messages.len(), 1);
+
+    deps.querier.set_cw20_total_supply("lptoken", 111_000000);
+    deps.querier.set_cw20_balance("lptoken", "user2", 11_000000);
+
+    //
+    // WITHDRAW IN KIND
+    //
+    // 10 ampLUNA unbonding (11 LUNA) -> vault_total 122
+    deps.querier.with_unbonding_eris(Uint128::new(10_000000u128));
+
+    let withdraw_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "user2".to_string(),
+        amount: Uint128::new(11_000000u128),
+        msg: to_json_binary(&Cw20HookMsg::WithdrawInKind {}).unwrap(),
+    });
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info("eriscw", &[]), withdraw_msg.clone())
+        .unwrap_err();
+    assert_eq!(err, ContractError::ExpectingLPToken("eriscw".to_string()));
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("lptoken", &[]), withdraw_msg).unwrap();
+
+    let attribute = |key: &str| -> String {
+        res.attributes.iter().find(|attr| attr.key == key).unwrap().value.clone()
+    };
+    // 122 * 11 / 111
+    assert_eq!(attribute("withdraw_value"), "12090090");
+    // 10 * 11 / 111 ampLUNA valued at 1.1
+    assert_eq!(attribute("receive_xamount"), "971171");
+    assert_eq!(attribute("protocol_xfee"), "19819");
+    assert_eq!(attribute("xvalue"), "1090089");
+    // 100 * 11 / 111 LUNA
+    assert_eq!(attribute("utoken_amount"), "9909909");
+    // remaining share of the unbonding ampLUNA
+    assert_eq!(attribute("unbond_amount"), "1090092");
+    assert_eq!(attribute("burnt_amount"), "11000000");
+
+    let querier = deps.as_ref().querier;
+    assert_eq!(
+        res.messages.into_iter().map(|a| a.msg).collect_vec(),
+        vec![
+            native_asset("utoken".to_string(), Uint128::new(9_711711u128))
+                .into_msg(&querier, "user2")
+                .unwrap(),
+            native_asset("utoken".to_string(), Uint128::new(198198u128))
+                .into_msg(&querier, "fee")
+                .unwrap(),
+            token_asset(Addr::unchecked("eriscw"), Uint128::new(971171u128))
+                .into_msg(&querier, "user2")
+                .unwrap(),
+            token_asset(Addr::unchecked("eriscw"), Uint128::new(19819u128))
+                .into_msg(&querier, "fee")
+                .unwrap(),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "lptoken".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::new(11_000000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    let requests =
+        query_unbond_requests(deps.as_ref(), mock_env(), "user2".to_string(), None, None).unwrap();
+    assert_eq!(requests.requests.len(), 1);
+    assert_eq!(requests.requests[0].amount_asset, Uint128::new(1_090092u128));
+}
+
+#[test]
+fn deposit_lsd_below_redemption_rate() {
+    let (mut deps, _res) = _provide_liquidity();
+
+    let deposit_msg = |amount: u128| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "user2".to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&Cw20HookMsg::Deposit {
+                receiver: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    // the market price of ampLUNA is below its exchange rate of 1.1, so that it can't be credited
+    // at the full value. 60 ampLUNA (66 LUNA) are above the takeable amount of the 1% step.
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 60_000000u128);
+    let res =
+        execute(deps.as_mut(), mock_env(), mock_info("eriscw", &[]), deposit_msg(60_000000u128))
+            .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "arb/execute_deposit"),
+            attr("sender", "user2"),
+            attr("recipient", "user2"),
+            attr("deposit_amount", "65010000"),
+            attr("share", "65010000"),
+            attr("vault_utoken_new", "166000000"),
+            attr("deposit_asset", "eriscw"),
+            attr("deposit_xamount", "60000000"),
+            attr("deposit_xfactor", "1.1"),
+            attr("deposit_spread", "990000"),
+        ]
+    );
+
+    // the vault only accepts as much LSD as it could buy
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 100_000000u128);
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("eriscw", &[]), deposit_msg(100_000000u128))
+            .unwrap_err();
+    assert_eq!(err, ContractError::NotEnoughFundsTakeable {});
+}
+
+#[test]
+fn withdraw_in_kind_with_locked_withdrawals() {
+    let (mut deps, _res) = _unbonding_slow_120();
+
+    // the 120 locked for the unbonding user were swapped into 109.09 ampLUNA (120 LUNA)
+    deps.querier.set_bank_balance(100_000000);
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 109_090909u128);
+    deps.querier.with_unbonding(Uint128::zero());
+    deps.querier.with_unbonding_eris(Uint128::zero());
+    deps.querier.with_withdrawable(Uint128::zero());
+    deps.querier.set_cw20_balance("lptoken", "user", 10_000000u128);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("lptoken", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "user".to_string(),
+            amount: Uint128::new(10_000000u128),
+            msg: to_json_binary(&Cw20HookMsg::WithdrawInKind {}).unwrap(),
+        }),
+    )
+    .unwrap();
+
+    let attribute = |key: &str| -> String {
+        res.attributes.iter().find(|attr| attr.key == key).unwrap().value.clone()
+    };
+    // 99.99 * 10 / 100, while the pro-rata ampLUNA would be worth 12
+    assert_eq!(attribute("withdraw_value"), "9999999");
+    // 10.909090 ampLUNA scaled down to 9.999999 / 11.999999
+    assert_eq!(attribute("receive_xamount"), "8909090");
+    assert_eq!(attribute("protocol_xfee"), "181818");
+    assert_eq!(attribute("xvalue"), "9999998");
+    assert_eq!(attribute("utoken_amount"), "0");
+    assert_eq!(attribute("unbond_amount"), "1");
+}
+
+#[test]
+fn max_exposure() {
+    let (mut deps, _res) = _provide_liquidity();
+
+    let set_max_exposure = |max_exposure_bps: Option<u16>| ExecuteMsg::UpdateConfig {
+        utilization_method: None,
+        utilization_adjustment: None,
+        remove_utilization_adjustment: None,
+        unbond_time_s: None,
+        disable_lsd: None,
+        insert_lsd: None,
+        remove_lsd: None,
+        force_remove_lsd: None,
+        set_max_exposure: Some(("eris".into(), max_exposure_bps)),
+        set_policy: None,
+        fee_config: None,
+        set_whitelist: None,
+        remove_whitelist: None,
+        timelock_delay_s: None,
+    };
+
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set_max_exposure(Some(10001)))
+            .unwrap_err();
+    assert_eq!(err, ContractError::ConfigTooHigh("max_exposure_bps".into()));
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set_max_exposure(Some(500)))
+        .unwrap();
+
+    let wanted_profit = Decimal::from_str("0.01").unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("whitelisted_exec", &[]),
+        ExecuteMsg::ExecuteArbitrage {
+            msg: ExecuteSubMsg {
+                contract_addr: None,
+                funds_amount: Uint128::new(10_000000u128),
+                msg: to_json_binary("exec_any_swap").unwrap(),
+            },
+            result_token: token_asset_info(Addr::unchecked("eriscw")),
+            wanted_profit,
+        },
+    )
+    .unwrap();
+
+    // 10 utoken swapped into 9.2 ampLUNA valued at 1.1
+    deps.querier.set_bank_balance(90_000000);
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 9_200000u128);
+
+    let exposure = query_exposure(deps.as_ref(), mock_env()).unwrap();
+    assert_eq!(
+        exposure,
+        ExposureResponse {
+            tvl_utoken: Uint128::new(100_120000u128),
+            exposures: vec![
+                LsdExposure {
+                    name: "eris".into(),
+                    value: Uint128::new(10_120000u128),
+                    exposure_bps: 1010,
+                    max_exposure_bps: Some(500),
+                },
+                LsdExposure {
+                    name: "backbone".into(),
+                    value: Uint128::zero(),
+                    exposure_bps: 0,
+                    max_exposure_bps: None,
+                },
+                LsdExposure {
+                    name: "stader".into(),
+                    value: Uint128::zero(),
+                    exposure_bps: 0,
+                    max_exposure_bps: None,
+                },
+                LsdExposure {
+                    name: "prism".into(),
+                    value: Uint128::zero(),
+                    exposure_bps: 0,
+                    max_exposure_bps: None,
+                },
+            ],
+        }
+    );
+
+    let assert_msg = ExecuteMsg::Callback(CallbackMsg::AssertResult {
+        result_token: token_asset_info(Addr::unchecked("eriscw")),
+        wanted_profit,
+    });
+
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info(MOCK_CONTRACT_ADDR, &[]), assert_msg.clone())
+            .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MaxExposureExceeded {
+            name: "eris".into(),
+            exposure_bps: 1010,
+            max_exposure_bps: 500,
+        }
+    );
+
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set_max_exposure(Some(1500)))
+        .unwrap();
+
+    let res =
+        execute(deps.as_mut(), mock_env(), mock_info(MOCK_CONTRACT_ADDR, &[]), assert_msg).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "arb/assert_result"));
+    assert_eq!(res.attributes[7], attr("profit", "120000"));
+
+    // LSD deposits are capped as well
+    let deposit_eris = |amount: u128| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "user2".to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&Cw20HookMsg::Deposit {
+                receiver: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    // 15.2 ampLUNA valued at 16.72 of 106.72
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 15_200000u128);
+    let err = execute(deps.as_mut(), mock_env(), mock_info("eriscw", &[]), deposit_eris(6_000000))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MaxExposureExceeded {
+            name: "eris".into(),
+            exposure_bps: 1566,
+            max_exposure_bps: 1500,
+        }
+    );
+
+    // 14.2 ampLUNA valued at 15.62 of 105.62
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 14_200000u128);
+    execute(deps.as_mut(), mock_env(), mock_info("eriscw", &[]), deposit_eris(5_000000)).unwrap();
+}
+
+#[test]
+fn crank_policies() {
+    let (mut deps, _res) = _provide_liquidity();
+
+    let set_policy = |name: &str, policy: LsdPolicy| ExecuteMsg::UpdateConfig {
+        utilization_method: None,
+        utilization_adjustment: None,
+        remove_utilization_adjustment: None,
+        unbond_time_s: None,
+        disable_lsd: None,
+        insert_lsd: None,
+        remove_lsd: None,
+        force_remove_lsd: None,
+        set_max_exposure: None,
+        set_policy: Some((name.into(), policy)),
+        fee_config: None,
+        set_whitelist: None,
+        remove_whitelist: None,
+        timelock_delay_s: None,
+    };
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info("user", &[]), ExecuteMsg::Crank {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::NothingToCrank {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        set_policy("unknown", LsdPolicy::default()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AdapterNotFound("unknown".into()));
+
+    for (name, policy) in [
+        (
+            "eris",
+            LsdPolicy {
+                unbond: Some(UnbondPolicy::BatchEpoch {
+                    window_s: 100,
+                }),
+                withdraw: None,
+            },
+        ),
+        (
+            "stader",
+            LsdPolicy {
+                unbond: Some(UnbondPolicy::Threshold {
+                    min_xbalance: Uint128::new(10_000000u128),
+                }),
+                withdraw: None,
+            },
+        ),
+        (
+            "prism",
+            LsdPolicy {
+                unbond: None,
+                withdraw: Some(WithdrawPolicy {
+                    min_withdrawable: Uint128::new(1_000000u128),
+                }),
+            },
+        ),
+    ] {
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set_policy(name, policy))
+            .unwrap();
+    }
+
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 10_000000u128);
+    deps.querier.set_cw20_balance("stadercw", MOCK_CONTRACT_ADDR, 5_000000u128);
+    deps.querier.with_withdrawable(Uint128::new(2_000000u128));
+
+    // the next eris batch is submitted at 123, stader is below its threshold
+    let res = query_next_actions(deps.as_ref(), _mock_env_at_timestamp(1)).unwrap();
+    assert_eq!(
+        res.actions,
+        vec![NextAction {
+            name: "prism".into(),
+            action: LsdAction::Withdraw {},
+            amount: Uint128::new(2_000000u128),
+        }]
+    );
+
+    deps.querier.set_cw20_balance("stadercw", MOCK_CONTRACT_ADDR, 10_000000u128);
+
+    let res = query_next_actions(deps.as_ref(), _mock_env_at_timestamp(23)).unwrap();
+    assert_eq!(
+        res.actions,
+        vec![
+            NextAction {
+                name: "eris".into(),
+                action: LsdAction::Unbond {},
+                amount: Uint128::new(10_000000u128),
+            },
+            NextAction {
+                name: "stader".into(),
+                action: LsdAction::Unbond {},
+                amount: Uint128::new(10_000000u128),
+            },
+            NextAction {
+                name: "prism".into(),
+                action: LsdAction::Withdraw {},
+                amount: Uint128::new(2_000000u128),
+            },
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        _mock_env_at_timestamp(23),
+        mock_info("user", &[]),
+        ExecuteMsg::Crank {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "arb/execute_crank"),
+            attr("type", "eris"),
+            attr("unbond_amount", "10000000"),
+            attr("type", "stader"),
+            attr("unbond_amount", "10000000"),
+            attr("type", "prism"),
+            attr("withdraw_amount", "2000000"),
+        ]
+    );
+    assert_eq!(res.messages.len(), 3);
+    match res.messages[0].msg.clone() {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            ..
+        }) => {
+            assert_eq!(contract_addr, "eriscw");
+            assert_eq!(
+                from_binary::<Cw20ExecuteMsg>(&msg).unwrap(),
+                Cw20ExecuteMsg::Send {
+                    contract: "eris".into(),
+                    amount: Uint128::new(10_000000u128),
+                    msg: to_json_binary(&eris::hub::ReceiveMsg::QueueUnbond {
+                        receiver: None,
+                        mint_receipt: false,
+                    })
+                    .unwrap(),
+                }
+            );
+        },
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn swap_lsd() {
+    let (mut deps, _res) = _provide_liquidity();
+
+    // 10 ampLUNA valued at 1.1 with the 1% profit step
+    let simulation = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        token_asset(Addr::unchecked("eriscw"), Uint128::new(10_000000u128)),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        simulation,
+        SimulationResponse {
+            return_amount: Uint128::new(10_890000u128),
+            spread_amount: Uint128::new(110000u128),
+            commission_amount: Uint128::new(1100u128),
+        }
+    );
+
+    let reverse_simulation = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Some(token_asset_info(Addr::unchecked("eriscw"))),
+        native_asset("utoken".to_string(), Uint128::new(10_890000u128)),
+    )
+    .unwrap();
+    assert_eq!(
+        reverse_simulation,
+        ReverseSimulationResponse {
+            offer_amount: Uint128::new(10_000000u128),
+            spread_amount: Uint128::new(110000u128),
+            commission_amount: Uint128::new(1100u128),
+        }
+    );
+
+    // rounded up to the smallest offer returning the ask amount
+    let reverse_simulation = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Some(token_asset_info(Addr::unchecked("eriscw"))),
+        native_asset("utoken".to_string(), Uint128::new(10_000000u128)),
+    )
+    .unwrap();
+    assert_eq!(
+        reverse_simulation,
+        ReverseSimulationResponse {
+            offer_amount: Uint128::new(9_182738u128),
+            spread_amount: Uint128::new(101011u128),
+            commission_amount: Uint128::new(1010u128),
+        }
+    );
+    let simulation = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        token_asset(Addr::unchecked("eriscw"), Uint128::new(9_182737u128)),
+        None,
+    )
+    .unwrap();
+    assert_eq!(simulation.return_amount, Uint128::new(9_999999u128));
+
+    // 66 LUNA is above the takeable amount of the 1% step, using the 1.5% step
+    let simulation = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        token_asset(Addr::unchecked("eriscw"), Uint128::new(60_000000u128)),
+        Some(native_asset_info("utoken".to_string())),
+    )
+    .unwrap();
+    assert_eq!(
+        simulation,
+        SimulationResponse {
+            return_amount: Uint128::new(65_010000u128),
+            spread_amount: Uint128::new(990000u128),
+            commission_amount: Uint128::new(9900u128),
+        }
+    );
+
+    // the 1% step misses the takeable amount as well
+    let reverse_simulation = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Some(token_asset_info(Addr::unchecked("eriscw"))),
+        native_asset("utoken".to_string(), Uint128::new(65_010000u128)),
+    )
+    .unwrap();
+    assert_eq!(
+        reverse_simulation,
+        ReverseSimulationResponse {
+            offer_amount: Uint128::new(60_000000u128),
+            spread_amount: Uint128::new(990000u128),
+            commission_amount: Uint128::new(9900u128),
+        }
+    );
+
+    let swap_msg = |belief_price: Option<&str>,
+                    max_spread: Option<&str>,
+                    ask_asset_info: Option<AssetInfo>| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "user2".to_string(),
+            amount: Uint128::new(10_000000u128),
+            msg: to_json_binary(&Cw20HookMsg::Swap {
+                ask_asset_info,
+                belief_price: belief_price.map(|price| Decimal::from_str(price).unwrap()),
+                max_spread: max_spread.map(|spread| Decimal::from_str(spread).unwrap()),
+                to: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    deps.querier.set_cw20_balance("eriscw", MOCK_CONTRACT_ADDR, 10_000000u128);
+
+    // without a belief price the 1% discount is the spread, which is above the default slippage
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("eriscw", &[]), swap_msg(None, None, None))
+            .unwrap_err();
+    assert_eq!(err, ContractError::MaxSpreadAssertion {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("eriscw", &[]),
+        swap_msg(None, Some("0.6"), None),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AllowedSpreadAssertion {});
+
+    // with a belief price the return is compared against it: 10 / 0.91 = 10.989 LUNA
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("eriscw", &[]),
+        swap_msg(Some("0.91"), None, None),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MaxSpreadAssertion {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("eriscw", &[]),
+        swap_msg(None, Some("0.02"), Some(token_asset_info(Addr::unchecked("stadercw")))),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
+
+    // 10 / 0.92 = 10.869 LUNA is below the return amount
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("eriscw", &[]),
+        swap_msg(Some("0.92"), None, None),
+    )
+    .unwrap();
+    assert_eq!(res.attributes[6], attr("return_amount", "10890000"));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("eriscw", &[]),
+        swap_msg(None, Some("0.02"), None),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "arb/execute_swap"),
+            attr("sender", "user2"),
+            attr("receiver", "user2"),
+            attr("offer_asset", "eriscw"),
+            attr("ask_asset", "utoken"),
+            attr("offer_amount", "10000000"),
+            attr("return_amount", "10890000"),
+            attr("spread_amount", "110000"),
+            attr("commission_amount", "1100"),
+        ]
+    );
+
+    let querier = deps.as_ref().querier;
+    assert_eq!(
+        res.messages.into_iter().map(|a| a.msg).collect_vec(),
+        vec![
+            native_asset("utoken".to_string(), Uint128::new(10_890000u128))
+                .into_msg(&querier, "user2")
+                .unwrap(),
+            native_asset("utoken".to_string(), Uint128::new(1100u128))
+                .into_msg(&querier, "fee")
+                .unwrap(),
+        ]
+    );
+}
+
+#[test]
+fn swap_lsd_on_utilization_curve() {
+    let (mut deps, _res) = _provide_liquidity();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            utilization_method: Some(UtilizationMethod::Linear {
+                min_profit: Decimal::from_str("0.01").unwrap(),
+                max_profit: Decimal::from_str("0.03").unwrap(),
+                min_util: Decimal::from_str("0.5").unwrap(),
+                max_util: Decimal::one(),
+            }),
+            utilization_adjustment: None,
+            remove_utilization_adjustment: None,
+            unbond_time_s: None,
+            disable_lsd: None,
+            insert_lsd: None,
+            remove_lsd: None,
+            force_remove_lsd: None,
+            set_max_exposure: None,
+            set_policy: None,
+            fee_config: None,
+            set_whitelist: None,
+            remove_whitelist: None,
+            timelock_delay_s: None,
+        },
+    )
+    .unwrap();
+
+    let simulate = |deps: Deps, offer_amount: u128| {
+        query_simulation(
+            deps,
+            mock_env(),
+            token_asset(Addr::unchecked("eriscw"), Uint128::new(offer_amount)),
+            None,
+        )
+        .unwrap()
+    };
+
+    // 11 LUNA are takeable at the minimum profit of 1%
+    assert_eq!(
+        simulate(deps.as_ref(), 10_000000u128),
+        SimulationResponse {
+            return_amount: Uint128::new(10_890000u128),
+            spread_amount: Uint128::new(110000u128),
+            commission_amount: Uint128::new(1100u128),
+        }
+    );
+
+    // 66 LUNA are above the takeable amount at 1%. Instead of the 3% of the next curve point, the
+    // profit is interpolated to 1.598%, where 50 + (profit - 1%) * 2500 LUNA are takeable.
+    assert_eq!(
+        simulate(deps.as_ref(), 60_000000u128),
+        SimulationResponse {
+            return_amount: Uint128::new(64_945169u128),
+            spread_amount: Uint128::new(1_054831u128),
+            commission_amount: Uint128::new(10548u128),
+        }
+    );
+
+    let reverse_simulate = |deps: Deps, ask_amount: u128| {
+        query_reverse_simulation(
+            deps,
+            mock_env(),
+            Some(token_asset_info(Addr::unchecked("eriscw"))),
+            native_asset("utoken".to_string(), Uint128::new(ask_amount)),
+        )
+        .unwrap()
+    };
+
+    assert_eq!(
+        reverse_simulate(deps.as_ref(), 64_945169u128),
+        ReverseSimulationResponse {
+            offer_amount: Uint128::new(60_000000u128),
+            spread_amount: Uint128::new(1_054831u128),
+            commission_amount: Uint128::new(10548u128),
+        }
+    );
+
+    // the smallest offer returning the ask amount
+    assert_eq!(
+        reverse_simulate(deps.as_ref(), 65_000000u128),
+        ReverseSimulationResponse {
+            offer_amount: Uint128::new(60_051996u128),
+            spread_amount: Uint128::new(1_057195u128),
+            commission_amount: Uint128::new(10571u128),
+        }
+    );
+    assert_eq!(simulate(deps.as_ref(), 60_051996u128).return_amount, Uint128::new(65_000000u128));
+    assert!(simulate(deps.as_ref(), 60_051995u128).return_amount < Uint128::new(65_000000u128));
+}
+
+#[test]
+fn swap_utoken_for_lp_token() {
+    let (mut deps, _res) = _provide_liquidity();
+
+    let swap_msg = |ask_asset_info: Option<AssetInfo>| ExecuteMsg::Swap {
+        offer_asset: native_asset("utoken".to_string(), Uint128::new(10_000000u128)),
+        ask_asset_info,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+    };
+    let info = mock_info("user2", &[coin(10_000000u128, "utoken")]);
+    deps.querier.set_bank_balance(110_000000);
+
+    // the vault doesn't sell LSD tokens, the utoken only buys the LP token
+    for ask_asset_info in [None, Some(token_asset_info(Addr::unchecked("eriscw")))] {
+        let err =
+            execute(deps.as_mut(), mock_env(), info.clone(), swap_msg(ask_asset_info)).unwrap_err();
+        assert_eq!(err, ContractError::AssetMismatch {});
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        swap_msg(Some(token_asset_info(Addr::unchecked("lptoken")))),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "arb/execute_deposit"),
+            attr("sender", "user2"),
+            attr("recipient", "user2"),
+            attr("deposit_amount", "10000000"),
+            attr("share", "10000000"),
+            attr("vault_utoken_new", "110000000"),
+        ]
+    );
+}
+
+#[test]
+fn generic_lsd_adapter() {
+    let mut deps = setup_test();
+
+    deps.querier.with_unbonding(Uint128::new(24_000000u128));
+    deps.querier.with_withdrawable(Uint128::new(10_000000u128));
+    deps.querier.set_cw20_balance("genericcw", MOCK_CONTRACT_ADDR, 5_000000u128);
+    deps.querier
+        .set_bank_balances(&[coin(100_000000u128, "utoken"), coin(7_000000u128, "ugeneric")]);
+
+    let configs = vec![
+        LsdConfig {
+            disabled: false,
+            max_exposure_bps: None,
+            policy: LsdPolicy::default(),
+            name: "generic_cw20".into(),
+            lsd_type: LsdType::Generic {
+                addr: "generic".into(),
+                cw20_or_denom: token_asset_info(Addr::unchecked("genericcw")),
+                interface: LsdInterface::V1,
+            },
+        },
+        LsdConfig {
+            disabled: false,
+            max_exposure_bps: None,
+            policy: LsdPolicy::default(),
+            name: "generic_native".into(),
+            lsd_type: LsdType::Generic {
+                addr: "generic".into(),
+                cw20_or_denom: native_asset_info("ugeneric".to_string()),
+                interface: LsdInterface::V1,
+            },
+        },
+    ]
+    .into_iter()
+    .map(|config| config.validate(deps.as_ref().api))
+    .collect::<StdResult<Vec<_>>>()
+    .unwrap();
+
+    let mut lsds =
+        LsdGroup::new(&configs.iter().collect_vec(), Addr::unchecked(MOCK_CONTRACT_ADDR));
+
+    let exchange_rate = Decimal::from_str("1.05").unwrap();
+    let balances = lsds.get_balances(&deps.as_ref(), &Addr::unchecked(MOCK_CONTRACT_ADDR)).unwrap();
+    assert_eq!(
+        balances,
+        vec![
+            ClaimBalance {
+                name: "generic_cw20".to_string(),
+                withdrawable: Uint128::new(10_000000u128),
+                unbonding: Uint128::new(24_000000u128),
+                xbalance: Uint128::new(5_000000u128),
+                xfactor: exchange_rate,
+            },
+            ClaimBalance {
+                name: "generic_native".to_string(),
+                withdrawable: Uint128::new(10_000000u128),
+                unbonding: Uint128::new(24_000000u128),
+                xbalance: Uint128::new(7_000000u128),
+                xfactor: exchange_rate,
+            },
+        ]
+    );
+
+    let (messages, _) = lsds.get_unbond_msgs(&deps.as_mut()).unwrap();
+    assert_eq!(
+        messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "genericcw".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: "generic".to_string(),
+                    amount: Uint128::new(5_000000u128),
+                    msg: to_json_binary(&eris::lsd_adapter::ReceiveMsg::Unbond {}).unwrap(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "generic".to_string(),
+                msg: to_json_binary(&eris::lsd_adapter::ExecuteMsg::Unbond {}).unwrap(),
+                funds: vec![coin(7_000000u128, "ugeneric")],
+            }),
+        ]
+    );
+
+    let (messages, _) = lsds.get_withdraw_msgs(&deps.as_mut()).unwrap();
+    let withdraw_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: "generic".to_string(),
+        msg: to_json_binary(&eris::lsd_adapter::ExecuteMsg::Withdraw {}).unwrap(),
+        funds: vec![],
+    });
+    assert_eq!(messages, vec![withdraw_msg.clone(), withdraw_msg]);
+
+    // the adapter and the cw20 token can't be called during an arbitrage
+    let err = lsds.assert_not_lsd_contract(&Addr::unchecked("generic")).unwrap_err();
+    assert_eq!(err, ContractError::CannotCallLsdContract {});
+    let err = lsds.assert_not_lsd_contract(&Addr::unchecked("genericcw")).unwrap_err();
+    assert_eq!(err, ContractError::CannotCallLsdContract {});
+}
+
+#[test]
+fn native_lsd_arbitrage() {
+    // the utoken cannot be used as an LSD asset
+    let mut deps = mock_dependencies();
+    let mut msg = create_default_init();
+    msg.lsds[0].lsd_type = LsdType::Eris {
+        addr: "eris".into(),
+        cw20_or_denom: native_asset_info("utoken".into()),
+    };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::AdapterAssetInvalid("utoken".into()));
+
+    let (mut deps, _res) = _unbonding_slow_120();
+    State::default()
+        .config
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.lsds[0].lsd_type = LsdType::Eris {
+                addr: Addr::unchecked("eris"),
+                cw20_or_denom: native_asset_info("ueris".into()),
+            };
+            Ok(config)
+        })
+        .unwrap();
+
+    deps.querier.set_bank_balance(100_000000 + 120_000000);
+    deps.querier.with_unbonding(Uint128::new(24_000000u128));
+    deps.querier.with_withdrawable(Uint128::zero());
+
+    let whitelist_info = mock_info("whitelisted_exec", &[]);
+    let contract_info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+
+    let wanted_profit = Decimal::from_str("0.015").unwrap();
+    let takeable = query_takeable(deps.as_ref(), mock_env(), Some(wanted_profit))
+        .unwrap()
+        .takeable
+        .expect("expects takeable");
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        whitelist_info.clone(),
+        ExecuteMsg::ExecuteArbitrage {
+            msg: ExecuteSubMsg {
+                contract_addr: None,
+                funds_amount: takeable,
+                msg: to_json_binary("exec_any_swap").unwrap(),
+            },
+            result_token: native_asset_info("ueris".to_string()),
+            wanted_profit,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 2);
+    let sub_msg: ExecuteMsg = match res.messages[1].msg.clone() {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            msg,
+            ..
+        }) => from_binary(&msg).unwrap(),
+        _ => panic!("DO NOT ENTER HERE"),
+    };
+
+    //
+    // APPLYING SUB MSG TO NEW BALANCE
+    //
+    // nearly all takeable utoken are used, so that the fee needs to be paid in the native xtoken
+    let used_balance = Uint128::new(99_999000u128);
+    let eris_exchange_rate = Decimal::from_str("1.1").unwrap();
+    let eris_amount = used_balance * ((Decimal::one() + wanted_profit) / eris_exchange_rate);
+    deps.querier.set_bank_balances(&[
+        coin(220_000000u128 - used_balance.u128(), "utoken"),
+        coin(eris_amount.u128(), "ueris"),
+    ]);
+
+    let res = execute(deps.as_mut(), mock_env(), contract_info, sub_msg).unwrap();
+
+    let attribute = |key: &str| -> String {
+        res.attributes.iter().find(|attr| attr.key == key).unwrap().value.clone()
+    };
+    assert_eq!(attribute("type"), "eris");
+    assert_eq!(attribute("result_token"), "ueris");
+    assert_eq!(attribute("received_xamount"), eris_amount.to_string());
+    assert_eq!(attribute("used_balance"), used_balance.to_string());
+
+    let fee_amount = Uint128::from_str(&attribute("fee_amount")).unwrap();
+    let fee_xamount = fee_amount * (Decimal::one() / eris_exchange_rate);
+    assert!(!fee_xamount.is_zero());
+    assert_eq!(attribute("fee_xamount"), fee_xamount.to_string());
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0].msg,
+        native_asset("ueris".to_string(), fee_xamount)
+            .into_msg(&deps.as_ref().querier, "fee")
+            .unwrap()
+    );
+
+    //
+    // UNBONDING THE NATIVE XTOKEN
+    //
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        whitelist_info,
+        ExecuteMsg::UnbondFromLiquidStaking {
+            names: Some(vec!["eris".to_string()]),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages.into_iter().map(|a| a.msg).collect_vec(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "eris".to_string(),
+            funds: vec![coin(eris_amount.u128(), "ueris")],
+            msg: to_json_binary(&eris::hub::ExecuteMsg::QueueUnbond {
+                receiver: None,
+                mint_receipt: false,
+            })
+            .unwrap(),
+        })]
+    );
+}
+
+#[test]
+fn native_lsd_unbonding() {
+    let mut deps = mock_dependencies();
+    deps.querier.set_bank_balances(&[
+        coin(100_000000u128, "utoken"),
+        coin(5_000000u128, "ueris"),
+        coin(7_000000u128, "ubackbone"),
+    ]);
+
+    let config = |name: &str, lsd_type: LsdType<String>| {
+        LsdConfig {
+            disabled: false,
+            max_exposure_bps: None,
+            policy: LsdPolicy::default(),
+            name: name.into(),
+            lsd_type,
+        }
+        .validate(deps.as_ref().api)
+        .unwrap()
+    };
+    let eris = config(
+        "eris",
+        LsdType::Eris {
+            addr: "eris".into(),
+            cw20_or_denom: native_asset_info("ueris".into()),
+        },
+    );
+    let backbone = config(
+        "backbone",
+        LsdType::Backbone {
+            addr: "backbone".into(),
+            cw20_or_denom: native_asset_info("ubackbone".into()),
+        },
+    );
+
+    // the eris hub accepts its native denom for unbonding
+    let mut lsds = LsdGroup::new(&[&eris], Addr::unchecked(MOCK_CONTRACT_ADDR));
+    let (messages, _) = lsds.get_unbond_msgs(&deps.as_mut()).unwrap();
+    assert_eq!(
+        messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "eris".to_string(),
+            funds: vec![coin(5_000000u128, "ueris")],
+            msg: to_json_binary(&eris::hub::ExecuteMsg::QueueUnbond {
+                receiver: None,
+                mint_receipt: false,
+            })
+            .unwrap(),
+        })]
+    );
+
+    // the other hubs only unbond their cw20 token
+    let mut lsds = LsdGroup::new(&[&backbone], Addr::unchecked(MOCK_CONTRACT_ADDR));
+    let err = lsds.get_unbond_msgs(&deps.as_mut()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AdapterErrorNotWrapped {
+            adapter: "steak".into(),
+            msg: "unbonding a native denom is not supported".into(),
+        }
+    );
+    assert_eq!(lsds.get_enabled_assets(), vec![native_asset_info("ubackbone".into())]);
+}
+
+#[test]
+fn migrating_legacy_lsd_config() {
+    let mut deps = setup_test();
+    let config = query_config(deps.as_ref()).unwrap().config;
+
+    // configs stored before the LSD types carried their cw20_or_denom
+    let lsd = |name: &str, lsd_type: LegacyLsdType| LegacyLsdConfig {
+        disabled: false,
+        name: name.into(),
+        lsd_type,
+        max_exposure_bps: None,
+        policy: LsdPolicy::default(),
+    };
+    Item::new("config")
+        .save(
+            deps.as_mut().storage,
+            &LegacyConfig {
+                utoken: config.utoken.clone(),
+                utilization_method: config.utilization_method.clone(),
+                utilization_adjustment: config.utilization_adjustment.clone(),
+                unbond_time_s: config.unbond_time_s,
+                lp_addr: config.lp_addr.clone(),
+                lsds: vec![
+                    lsd(
+                        "eris",
+                        LegacyLsdType::Eris {
+                            addr: Addr::unchecked("eris"),
+                            cw20: Addr::unchecked("eriscw"),
+                        },
+                    ),
+                    lsd(
+                        "backbone",
+                        LegacyLsdType::Backbone {
+                            addr: Addr::unchecked("backbone"),
+                            cw20: Addr::unchecked("backbonecw"),
+                        },
+                    ),
+                    lsd(
+                        "stader",
+                        LegacyLsdType::Stader {
+                            addr: Addr::unchecked("stader"),
+                            cw20: Addr::unchecked("stadercw"),
+                        },
+                    ),
+                    lsd(
+                        "prism",
+                        LegacyLsdType::Prism {
+                            addr: Addr::unchecked("prism"),
+                            cw20: Addr::unchecked("prismcw"),
+                        },
+                    ),
+                ],
+            },
+        )
+        .unwrap();
+    query_config(deps.as_ref()).unwrap_err();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    assert_eq!(query_config(deps.as_ref()).unwrap().config, config);
+}
+
+#[test]
+fn query_exchange_rate_twap_and_apr() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // the history is keyed by day, the points are at the time they have been saved
+    for (day, rate) in [(1u64, "1.0"), (2u64, "1.01")] {
+        state
+            .exchange_history
+            .save(
+                deps.as_mut().storage,
+                day,
+                &ExchangeHistory {
+                    exchange_rate: Decimal::from_str(rate).unwrap(),
+                    time_s: day * DAY + HOUR,
+                },
+            )
+            .unwrap();
+    }
+
+    let env = _mock_env_at_timestamp(2 * DAY + HOUR);
+    let twap = query_exchange_rate_twap(deps.as_ref(), env.clone(), DAY).unwrap();
+    assert_eq!(
+        twap,
+        ExchangeRateTwapResponse {
+            twap: Decimal::from_str("1.005").unwrap(),
+            start_time: DAY + HOUR,
+            end_time: 2 * DAY + HOUR,
+            partial: false,
+            stale: false,
+        }
+    );
+
+    // 1.0 -> 1.01 within 1 day, annualized
+    let apr = query_apr(deps.as_ref(), env, vec![DAY]).unwrap();
+    assert_eq!(
+        apr.aprs,
+        vec![WindowApr {
+            window_s: DAY,
+            apr: Some(Decimal::from_str("3.65").unwrap()),
+            partial: false,
+            stale: false,
+        }]
+    );
+}
+
 fn calc_takeable(total_value: Uint128, pool_takeable: Uint128, share: &str) -> Uint128 {
     // total value * share = total pool that can be used for that share
     // + takeable - total value
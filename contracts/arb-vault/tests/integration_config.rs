@@ -1,12 +1,12 @@
 use astroport::asset::{token_asset, token_asset_info};
-use cosmwasm_std::{coin, to_json_binary, Decimal, StdResult, Uint128};
+use cosmwasm_std::{coin, to_json_binary, Addr, Decimal, StdResult, Uint128};
 use eris_tests::mock_app;
 use eris_tests::{gov_helper::EscrowHelper, TerraAppExtension};
 use std::str::FromStr;
 use std::vec;
 
 use eris::{
-    arb_vault::{ExecuteMsg, LsdConfig, UtilizationMethod},
+    arb_vault::{ExecuteMsg, LsdConfig, LsdPolicy, UtilizationMethod},
     constants::DAY,
 };
 
@@ -33,14 +33,19 @@ fn update_config_utilization() -> StdResult<()> {
             router_ref,
             ExecuteMsg::UpdateConfig {
                 utilization_method: Some(UtilizationMethod::Steps(vec![(dec("0.1"), dec("1"))])),
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: None,
                 insert_lsd: None,
                 remove_lsd: None,
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
             "user",
         )
@@ -55,14 +60,19 @@ fn update_config_utilization() -> StdResult<()> {
                     dec("0.1"),
                     dec("1.00001"),
                 )])),
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: None,
                 insert_lsd: None,
                 remove_lsd: None,
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
         .unwrap_err();
@@ -73,14 +83,19 @@ fn update_config_utilization() -> StdResult<()> {
             router_ref,
             ExecuteMsg::UpdateConfig {
                 utilization_method: Some(UtilizationMethod::Steps(vec![(dec("0.1"), dec("1"))])),
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: None,
                 insert_lsd: None,
                 remove_lsd: None,
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
         .unwrap();
@@ -113,14 +128,19 @@ fn update_config_disable() -> StdResult<()> {
             router_ref,
             ExecuteMsg::UpdateConfig {
                 utilization_method: None,
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: Some("unknown".to_string()),
                 insert_lsd: None,
                 remove_lsd: None,
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
         .unwrap_err();
@@ -131,14 +151,19 @@ fn update_config_disable() -> StdResult<()> {
             router_ref,
             ExecuteMsg::UpdateConfig {
                 utilization_method: None,
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: Some("eris".to_string()),
                 insert_lsd: None,
                 remove_lsd: None,
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
         .unwrap();
@@ -149,10 +174,12 @@ fn update_config_disable() -> StdResult<()> {
         config.config.lsds,
         vec![LsdConfig {
             disabled: true,
+            max_exposure_bps: None,
+            policy: LsdPolicy::default(),
             name: "eris".to_string(),
             lsd_type: eris::arb_vault::LsdType::Eris {
                 addr: helper.base.hub.get_address(),
-                cw20: helper.get_ustake_addr()
+                cw20_or_denom: token_asset_info(helper.get_ustake_addr())
             }
         }]
     );
@@ -237,14 +264,19 @@ fn update_config_remove() -> StdResult<()> {
             router_ref,
             ExecuteMsg::UpdateConfig {
                 utilization_method: None,
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: None,
                 insert_lsd: None,
                 remove_lsd: Some("eris".to_string()),
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
         .unwrap();
@@ -266,21 +298,28 @@ fn update_config_insert() -> StdResult<()> {
             router_ref,
             ExecuteMsg::UpdateConfig {
                 utilization_method: None,
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: None,
                 insert_lsd: Some(LsdConfig {
                     disabled: false,
+                    max_exposure_bps: None,
+                    policy: LsdPolicy::default(),
                     name: "eris".to_string(),
                     lsd_type: eris::arb_vault::LsdType::Eris {
                         addr: "xx".to_string(),
-                        cw20: "yy".to_string(),
+                        cw20_or_denom: token_asset_info(Addr::unchecked("yy")),
                     },
                 }),
                 remove_lsd: None,
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
         .unwrap_err();
@@ -293,21 +332,28 @@ fn update_config_insert() -> StdResult<()> {
             router_ref,
             ExecuteMsg::UpdateConfig {
                 utilization_method: None,
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: None,
                 insert_lsd: Some(LsdConfig {
                     disabled: false,
+                    max_exposure_bps: None,
+                    policy: LsdPolicy::default(),
                     name: "other".to_string(),
                     lsd_type: eris::arb_vault::LsdType::Eris {
                         addr: "xxx".to_string(),
-                        cw20: "yyy".to_string(),
+                        cw20_or_denom: token_asset_info(Addr::unchecked("yyy")),
                     },
                 }),
                 remove_lsd: None,
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
         .unwrap_err();
@@ -317,21 +363,28 @@ fn update_config_insert() -> StdResult<()> {
             router_ref,
             ExecuteMsg::UpdateConfig {
                 utilization_method: None,
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: None,
                 insert_lsd: Some(LsdConfig {
                     disabled: false,
+                    max_exposure_bps: None,
+                    policy: LsdPolicy::default(),
                     name: "other".to_string(),
                     lsd_type: eris::arb_vault::LsdType::Stader {
                         addr: helper.base.stader.get_address_string(),
-                        cw20: helper.base.steak_token.get_address_string(),
+                        cw20_or_denom: token_asset_info(helper.base.steak_token.get_address()),
                     },
                 }),
                 remove_lsd: None,
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
         .unwrap();
@@ -343,18 +396,22 @@ fn update_config_insert() -> StdResult<()> {
         vec![
             LsdConfig {
                 disabled: false,
+                max_exposure_bps: None,
+                policy: LsdPolicy::default(),
                 name: "eris".to_string(),
                 lsd_type: eris::arb_vault::LsdType::Eris {
                     addr: helper.base.hub.get_address(),
-                    cw20: helper.get_ustake_addr()
+                    cw20_or_denom: token_asset_info(helper.get_ustake_addr())
                 }
             },
             LsdConfig {
                 disabled: false,
+                max_exposure_bps: None,
+                policy: LsdPolicy::default(),
                 name: "other".to_string(),
                 lsd_type: eris::arb_vault::LsdType::Stader {
                     addr: helper.base.stader.get_address(),
-                    cw20: helper.base.steak_token.get_address()
+                    cw20_or_denom: token_asset_info(helper.base.steak_token.get_address())
                 }
             }
         ]
@@ -368,14 +425,19 @@ fn assert_cant_remove(helper: &EscrowHelper, router_ref: &mut cw_multi_test::App
             router_ref,
             ExecuteMsg::UpdateConfig {
                 utilization_method: None,
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 disable_lsd: None,
                 insert_lsd: None,
                 remove_lsd: Some("eris".to_string()),
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
         .unwrap_err();
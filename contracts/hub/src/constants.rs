@@ -1,10 +1,19 @@
 use cosmwasm_std::Decimal;
+use eris::constants::DAY;
 
 pub const CONTRACT_NAME: &str = "eris-staking-hub";
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Denom staked by hubs that were instantiated before the denom became configurable
 pub const CONTRACT_DENOM: &str = "uluna";
+/// Slashing events within this period reduce the share of a validator in the gauges strategy
+pub const SLASHING_PENALTY_PERIOD: u64 = 90 * DAY;
 
 pub fn get_reward_fee_cap() -> Decimal {
     // 10% max reward fee
     Decimal::from_ratio(10_u128, 100_u128)
 }
+
+pub fn get_instant_unbond_fee_cap() -> Decimal {
+    // 10% max instant unbond fee
+    Decimal::from_ratio(10_u128, 100_u128)
+}
@@ -1,18 +1,23 @@
 use cosmwasm_std::{
-    entry_point, from_binary, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    StdResult,
+    entry_point, from_binary, to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply,
+    Response, StdError, StdResult, Storage, Uint128,
 };
 use cw2::set_contract_version;
 use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
 
 use eris::helper::unwrap_reply;
-use eris::hub::{CallbackMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg};
+use eris::hub::{
+    CallbackMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PauseOperation, QueryMsg, ReceiveMsg,
+    ReceiveNftMsg, SudoMsg,
+};
 
 use crate::claim::exec_claim;
 use crate::constants::{CONTRACT_DENOM, CONTRACT_NAME, CONTRACT_VERSION};
 use crate::error::{ContractError, ContractResult};
 use crate::helpers::parse_received_fund;
 use crate::state::State;
+use crate::types::StakeToken;
 use crate::{execute, gov, queries};
 
 #[entry_point]
@@ -28,50 +33,130 @@ pub fn instantiate(
 #[entry_point]
 pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> ContractResult {
     let api = deps.api;
+
+    if let Some(operation) = pause_operation(&msg) {
+        State::default().assert_not_paused(deps.storage, operation)?;
+    }
+
     match msg {
         ExecuteMsg::Receive(cw20_msg) => receive(deps, env, info, cw20_msg),
         ExecuteMsg::Bond {
             receiver,
-        } => execute::bond(
+            referral,
+        } => {
+            let denom = State::default().denom.load(deps.storage)?;
+            execute::bond(
+                deps,
+                env,
+                receiver.map(|s| api.addr_validate(&s)).transpose()?.unwrap_or(info.sender),
+                parse_received_fund(&info.funds, &denom)?,
+                false,
+                referral.map(|s| api.addr_validate(&s)).transpose()?,
+            )
+        },
+        ExecuteMsg::Donate {} => {
+            let denom = State::default().denom.load(deps.storage)?;
+            execute::bond(
+                deps,
+                env,
+                info.sender,
+                parse_received_fund(&info.funds, &denom)?,
+                true,
+                None,
+            )
+        },
+        ExecuteMsg::QueueUnbond {
+            receiver,
+            mint_receipt,
+        } => {
+            let ustake_to_burn = parse_received_stake_token(deps.storage, &info)?;
+            execute::queue_unbond(
+                deps,
+                env,
+                info.sender.clone(),
+                receiver.map(|s| api.addr_validate(&s)).transpose()?.unwrap_or(info.sender),
+                ustake_to_burn,
+                mint_receipt,
+            )
+        },
+        ExecuteMsg::InstantUnbond {
+            max_fee,
+            receiver,
+        } => {
+            let ustake_to_burn = parse_received_stake_token(deps.storage, &info)?;
+            execute::instant_unbond(
+                deps,
+                env,
+                info.sender.clone(),
+                receiver.map(|s| api.addr_validate(&s)).transpose()?.unwrap_or(info.sender),
+                ustake_to_burn,
+                max_fee,
+            )
+        },
+        ExecuteMsg::WithdrawUnbonded {
+            receiver,
+            batch_ids,
+            limit,
+        } => execute::withdraw_unbonded(
             deps,
             env,
+            info.sender.clone(),
             receiver.map(|s| api.addr_validate(&s)).transpose()?.unwrap_or(info.sender),
-            parse_received_fund(&info.funds, CONTRACT_DENOM)?,
-            false,
-        ),
-        ExecuteMsg::Donate {} => execute::bond(
-            deps,
-            env,
-            info.sender,
-            parse_received_fund(&info.funds, CONTRACT_DENOM)?,
-            true,
+            batch_ids,
+            limit,
         ),
-        ExecuteMsg::WithdrawUnbonded {
+        ExecuteMsg::ClaimReferralFees {
             receiver,
-        } => execute::withdraw_unbonded(
+        } => execute::claim_referral_fees(
             deps,
-            env,
             info.sender.clone(),
             receiver.map(|s| api.addr_validate(&s)).transpose()?.unwrap_or(info.sender),
         ),
-        ExecuteMsg::AddValidator {
-            validator,
-        } => execute::add_validator(deps, info.sender, validator),
+        ExecuteMsg::SyncReferredStake {
+            holders,
+        } => execute::sync_referred_stake(deps, holders),
+        ExecuteMsg::StakeTransferred {
+            from,
+            to,
+            amount,
+        } => {
+            let stake_token = State::default().load_stake_token(deps.storage)?;
+            match &stake_token {
+                StakeToken::Cw20(addr) if info.sender == *addr => (),
+                _ if stake_token.legacy() == Some(&info.sender) => (),
+                _ => return Err(ContractError::ExpectingStakeToken(info.sender.into())),
+            }
+            execute::release_transferred_stake(
+                deps,
+                env,
+                api.addr_validate(&from)?,
+                api.addr_validate(&to)?,
+                amount,
+            )
+        },
+        ExecuteMsg::ReceiveNft(cw721_msg) => receive_nft(deps, env, info, cw721_msg),
+        ExecuteMsg::TransferUnbondRequest {
+            id,
+            recipient,
+        } => execute::transfer_unbond_request(deps, info.sender, id, recipient),
         ExecuteMsg::RemoveValidator {
             validator,
         } => execute::remove_validator(deps, env, info.sender, validator),
+        ExecuteMsg::EvictInactiveValidators {} => execute::evict_inactive_validators(deps, env),
         ExecuteMsg::TransferOwnership {
             new_owner,
         } => execute::transfer_ownership(deps, info.sender, new_owner),
         ExecuteMsg::DropOwnershipProposal {} => execute::drop_ownership_proposal(deps, info.sender),
         ExecuteMsg::AcceptOwnership {} => execute::accept_ownership(deps, info.sender),
-        ExecuteMsg::Harvest {} => execute::harvest(deps, env),
+        ExecuteMsg::Harvest {} => execute::harvest(deps, env, info.sender),
         ExecuteMsg::TuneDelegations {} => execute::tune_delegations(deps, env, info.sender),
         ExecuteMsg::Rebalance {
             min_redelegation,
         } => execute::rebalance(deps, env, info.sender, min_redelegation),
-        ExecuteMsg::Reconcile {} => execute::reconcile(deps, env),
-        ExecuteMsg::SubmitBatch {} => execute::submit_batch(deps, env),
+        ExecuteMsg::Reconcile {
+            limit,
+        } => execute::reconcile(deps, env, info.sender, limit),
+        ExecuteMsg::SubmitBatch {} => execute::submit_batch(deps, env, info.sender),
         ExecuteMsg::Vote {
             proposal_id,
             vote,
@@ -80,7 +165,77 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> C
             proposal_id,
             votes,
         } => gov::vote_weighted(deps, env, info, proposal_id, votes),
+        ExecuteMsg::CastPropVote {
+            proposal_id,
+        } => gov::cast_prop_vote(deps, env, proposal_id),
         ExecuteMsg::Callback(callback_msg) => callback(deps, env, info, callback_msg),
+        ExecuteMsg::UpdateConfig {
+            ..
+        }
+        | ExecuteMsg::AddValidator {
+            ..
+        }
+        | ExecuteMsg::MigrateToTokenFactory {
+            ..
+        } => {
+            if is_timelocked(&msg) && State::default().timelock.delay(deps.storage)? > 0 {
+                execute::propose_timelocked_action(deps, env, info.sender, msg)
+            } else {
+                execute_timelockable(deps, env, info.sender, msg)
+            }
+        },
+        ExecuteMsg::ExecuteTimelockedAction {
+            id,
+        } => {
+            let state = State::default();
+            let pending =
+                state.timelock.take_executable(deps.storage, env.block.time.seconds(), id)?;
+            // the sender has been checked to be the owner when the action was proposed
+            let owner = state.owner.load(deps.storage)?;
+            Ok(execute_timelockable(deps, env, owner, pending.action)?
+                .add_attribute("timelocked_action", id.to_string()))
+        },
+        ExecuteMsg::CancelTimelockedAction {
+            id,
+        } => execute::cancel_timelocked_action(deps, info.sender, id),
+        ExecuteMsg::SetPause {
+            operations,
+            paused,
+        } => execute::set_pause(deps, info.sender, operations, paused),
+        ExecuteMsg::Claim {
+            claims,
+        } => exec_claim(deps, env, info, claims),
+    }
+}
+
+#[entry_point]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> ContractResult {
+    match msg {
+        SudoMsg::TrackBeforeSend {
+            from,
+            to,
+            amount,
+        } => {
+            let stake_denom = State::default().stake_denom.may_load(deps.storage)?;
+            if stake_denom.as_ref() != Some(&amount.denom) {
+                return Ok(Response::new());
+            }
+            execute::release_transferred_stake(
+                deps,
+                env,
+                Addr::unchecked(from),
+                Addr::unchecked(to),
+                amount.amount,
+            )
+        },
+        SudoMsg::BlockBeforeSend {
+            ..
+        } => Ok(Response::new()),
+    }
+}
+
+fn execute_timelockable(deps: DepsMut, env: Env, sender: Addr, msg: ExecuteMsg) -> ContractResult {
+    match msg {
         ExecuteMsg::UpdateConfig {
             protocol_fee_contract,
             protocol_reward_fee,
@@ -89,9 +244,18 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> C
             vote_operator,
             epoch_period,
             unbond_period,
+            instant_unbond,
+            validator_scoring,
+            unbond_receipt_nft,
+            fee_receivers,
+            referral_fee,
+            guardian,
+            timelock_delay_s,
+            keeper_tips,
+            prop_gauges,
         } => execute::update_config(
             deps,
-            info.sender,
+            sender,
             protocol_fee_contract,
             protocol_reward_fee,
             delegation_strategy,
@@ -99,36 +263,223 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> C
             vote_operator,
             epoch_period,
             unbond_period,
+            instant_unbond,
+            validator_scoring,
+            unbond_receipt_nft,
+            fee_receivers,
+            referral_fee,
+            guardian,
+            timelock_delay_s,
+            keeper_tips,
+            prop_gauges,
         ),
-        ExecuteMsg::Claim {
-            claims,
-        } => exec_claim(deps, env, info, claims),
+        ExecuteMsg::AddValidator {
+            validator,
+        } => execute::add_validator(deps, sender, validator),
+        ExecuteMsg::MigrateToTokenFactory {
+            subdenom,
+        } => execute::migrate_to_token_factory(deps, env, sender, subdenom),
+        _ => Err(StdError::generic_err(
+            "only config updates, new validators and the token-factory migration can be timelocked",
+        )
+        .into()),
+    }
+}
+
+/// Owner actions that have to wait for the timelock delay, as they change fees, the delegation
+/// strategy, the governance votes, the unbonding of stakers or the Stake token.
+///
+/// Exempt are `allow_donations`, which only restricts donations, the `guardian`, who can only
+/// pause operations and has to be replaceable at once if compromised, and removing validators,
+/// which only moves stake to validators that are already whitelisted and has to be possible as
+/// soon as a validator misbehaves.
+fn is_timelocked(msg: &ExecuteMsg) -> bool {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract,
+            protocol_reward_fee,
+            delegation_strategy,
+            vote_operator,
+            epoch_period,
+            unbond_period,
+            instant_unbond,
+            validator_scoring,
+            unbond_receipt_nft,
+            fee_receivers,
+            referral_fee,
+            timelock_delay_s,
+            keeper_tips,
+            prop_gauges,
+            ..
+        } => {
+            protocol_fee_contract.is_some()
+                || protocol_reward_fee.is_some()
+                || delegation_strategy.is_some()
+                || vote_operator.is_some()
+                || epoch_period.is_some()
+                || unbond_period.is_some()
+                || instant_unbond.is_some()
+                || validator_scoring.is_some()
+                || unbond_receipt_nft.is_some()
+                || fee_receivers.is_some()
+                || referral_fee.is_some()
+                || timelock_delay_s.is_some()
+                || keeper_tips.is_some()
+                || prop_gauges.is_some()
+        },
+        ExecuteMsg::AddValidator {
+            ..
+        }
+        | ExecuteMsg::MigrateToTokenFactory {
+            ..
+        } => true,
+        _ => false,
+    }
+}
+
+/// The class of operations a message belongs to, which can be paused by the guardian
+fn pause_operation(msg: &ExecuteMsg) -> Option<PauseOperation> {
+    match msg {
+        ExecuteMsg::Bond {
+            ..
+        }
+        | ExecuteMsg::Donate {} => Some(PauseOperation::Bond),
+        ExecuteMsg::QueueUnbond {
+            ..
+        }
+        | ExecuteMsg::InstantUnbond {
+            ..
+        }
+        | ExecuteMsg::SubmitBatch {}
+        | ExecuteMsg::TransferUnbondRequest {
+            ..
+        } => Some(PauseOperation::Unbond),
+        ExecuteMsg::Harvest {}
+        | ExecuteMsg::Reconcile {
+            ..
+        }
+        | ExecuteMsg::Callback(CallbackMsg::Reinvest {}) => Some(PauseOperation::Harvest),
+        ExecuteMsg::Rebalance {
+            ..
+        }
+        | ExecuteMsg::TuneDelegations {}
+        | ExecuteMsg::EvictInactiveValidators {} => Some(PauseOperation::Rebalance),
+        ExecuteMsg::WithdrawUnbonded {
+            ..
+        }
+        | ExecuteMsg::ClaimReferralFees {
+            ..
+        } => Some(PauseOperation::Withdraw),
+        _ => None,
     }
 }
 
 fn receive(deps: DepsMut, env: Env, info: MessageInfo, cw20_msg: Cw20ReceiveMsg) -> ContractResult {
     let api = deps.api;
-    match from_binary(&cw20_msg.msg)? {
+
+    let state = State::default();
+    let msg: ReceiveMsg = from_binary(&cw20_msg.msg)?;
+
+    match state.load_stake_token(deps.storage)? {
+        StakeToken::Cw20(stake_token) if info.sender == stake_token => (),
+        // the legacy token can only be converted to the token-factory denom
+        StakeToken::TokenFactory {
+            legacy: Some(legacy),
+            ..
+        } if info.sender == legacy => {
+            state.assert_not_paused(deps.storage, PauseOperation::Unbond)?;
+            return match msg {
+                ReceiveMsg::ConvertStakeToken {
+                    receiver,
+                } => execute::convert_stake_token(
+                    deps,
+                    env,
+                    api.addr_validate(&receiver.unwrap_or(cw20_msg.sender))?,
+                    cw20_msg.amount,
+                ),
+                _ => Err(ContractError::ExpectingStakeToken(info.sender.into())),
+            };
+        },
+        _ => return Err(ContractError::ExpectingStakeToken(info.sender.into())),
+    }
+
+    if let ReceiveMsg::QueueUnbond {
+        ..
+    }
+    | ReceiveMsg::InstantUnbond {
+        ..
+    } = msg
+    {
+        state.assert_not_paused(deps.storage, PauseOperation::Unbond)?;
+    }
+
+    match msg {
         ReceiveMsg::QueueUnbond {
             receiver,
-        } => {
-            let state = State::default();
+            mint_receipt,
+        } => execute::queue_unbond(
+            deps,
+            env,
+            api.addr_validate(&cw20_msg.sender)?,
+            api.addr_validate(&receiver.unwrap_or(cw20_msg.sender))?,
+            cw20_msg.amount,
+            mint_receipt,
+        ),
+        ReceiveMsg::InstantUnbond {
+            max_fee,
+            receiver,
+        } => execute::instant_unbond(
+            deps,
+            env,
+            api.addr_validate(&cw20_msg.sender)?,
+            api.addr_validate(&receiver.unwrap_or(cw20_msg.sender))?,
+            cw20_msg.amount,
+            max_fee,
+        ),
+        ReceiveMsg::ConvertStakeToken {
+            ..
+        } => Err(ContractError::NotTokenFactory {}),
+    }
+}
 
-            let stake_token = state.stake_token.load(deps.storage)?;
-            if info.sender != stake_token {
-                return Err(ContractError::ExpectingStakeToken(info.sender.into()));
-            }
+fn receive_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw721_msg: Cw721ReceiveMsg,
+) -> ContractResult {
+    let api = deps.api;
 
-            execute::queue_unbond(
-                deps,
-                env,
-                api.addr_validate(&receiver.unwrap_or(cw20_msg.sender))?,
-                cw20_msg.amount,
-            )
-        },
+    let state = State::default();
+    let nft = state.unbond_receipt_nft.may_load(deps.storage)?;
+    if nft.as_ref() != Some(&info.sender) {
+        return Err(ContractError::ExpectingUnbondReceipt(info.sender.into()));
+    }
+
+    state.assert_not_paused(deps.storage, PauseOperation::Withdraw)?;
+
+    match from_binary(&cw721_msg.msg)? {
+        ReceiveNftMsg::WithdrawUnbonded {
+            receiver,
+        } => execute::withdraw_unbond_receipt(
+            deps,
+            env,
+            cw721_msg.token_id,
+            api.addr_validate(&receiver.unwrap_or(cw721_msg.sender))?,
+        ),
     }
 }
 
+/// Amount of the token-factory stake token sent along, only available in token-factory mode
+fn parse_received_stake_token(
+    storage: &dyn Storage,
+    info: &MessageInfo,
+) -> Result<Uint128, ContractError> {
+    let stake_denom =
+        State::default().stake_denom.may_load(storage)?.ok_or(ContractError::NotTokenFactory {})?;
+    Ok(parse_received_fund(&info.funds, &stake_denom)?)
+}
+
 fn callback(
     deps: DepsMut,
     env: Env,
@@ -192,10 +543,54 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::SimulateWantedDelegations {
             period,
         } => to_json_binary(&queries::simulate_wanted_delegations(deps, env, period)?),
+        QueryMsg::SimulateDelegationStrategy {
+            strategy,
+            total_uluna,
+            overrides,
+        } => to_json_binary(&queries::simulate_delegation_strategy(
+            deps,
+            env,
+            strategy,
+            total_uluna,
+            overrides,
+        )?),
         QueryMsg::ExchangeRates {
             start_after,
             limit,
         } => to_json_binary(&queries::query_exchange_rates(deps, env, start_after, limit)?),
+        QueryMsg::ExchangeRateTwap {
+            window_s,
+        } => to_json_binary(&queries::exchange_rate_twap(deps, env, window_s)?),
+        QueryMsg::Apr {
+            windows,
+        } => to_json_binary(&queries::apr(deps, env, windows)?),
+        QueryMsg::SlashingEvents {
+            start_after,
+            limit,
+        } => to_json_binary(&queries::slashing_events(deps, start_after, limit)?),
+        QueryMsg::ValidatorScores {} => to_json_binary(&queries::validator_scores(deps)?),
+        QueryMsg::PauseState {} => to_json_binary(&queries::pause_state(deps)?),
+        QueryMsg::PendingKeeperActions {} => {
+            to_json_binary(&queries::pending_keeper_actions(deps, env)?)
+        },
+        QueryMsg::PendingActions {
+            start_after,
+            limit,
+        } => to_json_binary(&queries::pending_actions(deps, start_after, limit)?),
+        QueryMsg::UnbondReceipt {
+            token_id,
+        } => to_json_binary(&queries::unbond_receipt(deps, token_id)?),
+        QueryMsg::Referrals {
+            start_after,
+            limit,
+        } => to_json_binary(&queries::referrals(deps, start_after, limit)?),
+        QueryMsg::WithdrawableAmount {
+            user,
+        } => to_json_binary(&queries::withdrawable_amount(deps, env, user)?),
+        QueryMsg::RedelegationLocks {
+            start_after,
+            limit,
+        } => to_json_binary(&queries::redelegation_locks(deps, env, start_after, limit)?),
     }
 }
 
@@ -203,6 +598,12 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult {
     // let contract_version = get_contract_version(deps.storage)?;
 
+    // hubs instantiated before the denom became configurable stake the default denom
+    let state = State::default();
+    if state.denom.may_load(deps.storage)?.is_none() {
+        state.denom.save(deps.storage, &CONTRACT_DENOM.to_string())?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(Response::new()
@@ -1,5 +1,6 @@
-use cosmwasm_std::{OverflowError, Response, StdError, Uint128};
+use cosmwasm_std::{Decimal, OverflowError, Response, StdError, Uint128};
 use cw20_base::ContractError as cw20baseError;
+use eris::hub::PauseOperation;
 use thiserror::Error;
 
 pub type ContractResult = Result<Response, ContractError>;
@@ -25,12 +26,36 @@ pub enum ContractError {
     #[error("Unauthorized: sender is not vote operator")]
     UnauthorizedSenderNotVoteOperator {},
 
+    #[error("Unauthorized: sender is not guardian")]
+    UnauthorizedSenderNotGuardian {},
+
+    #[error("Operation {0} is paused")]
+    Paused(PauseOperation),
+
     #[error("Expecting stake token, received {0}")]
     ExpectingStakeToken(String),
 
+    #[error("Stake token is already a token-factory denom")]
+    AlreadyTokenFactory {},
+
+    #[error("Stake token is not a token-factory denom")]
+    NotTokenFactory {},
+
+    #[error("Expecting unbond receipt, received {0}")]
+    ExpectingUnbondReceipt(String),
+
+    #[error("Unbond receipts are disabled")]
+    UnbondReceiptsDisabled {},
+
+    #[error("Unbond receipt {0} has not finished unbonding")]
+    UnbondReceiptNotWithdrawable(String),
+
     #[error("Protocol_reward_fee greater than max")]
     ProtocolRewardFeeTooHigh {},
 
+    #[error("Referral fee can't be more than the whole protocol fee")]
+    ReferralFeeTooHigh {},
+
     #[error("{0} can't be zero")]
     CantBeZero(String),
 
@@ -55,6 +80,12 @@ pub enum ContractError {
     #[error("validator {0} is not whitelisted")]
     ValidatorNotWhitelisted(String),
 
+    #[error("No inactive validators to evict")]
+    NoInactiveValidators {},
+
+    #[error("Can't evict validators, none of the whitelisted validators is active")]
+    NoActiveValidators {},
+
     #[error("cannot find `instantiate` event")]
     CannotFindInstantiateEvent {},
 
@@ -64,6 +95,15 @@ pub enum ContractError {
     #[error("No vote operator set")]
     NoVoteOperatorSet {},
 
+    #[error("No prop gauges set")]
+    NoPropGaugesSet {},
+
+    #[error("Voting on proposal {0} has not ended in prop gauges")]
+    PropVotingNotEnded(u64),
+
+    #[error("Proposal {0} has not reached the quorum in prop gauges")]
+    PropQuorumNotReached(u64),
+
     #[error("Not all wanted undelegations calculated, missing: {0}")]
     ComputeUndelegationsWrong(u128),
 
@@ -73,6 +113,27 @@ pub enum ContractError {
     #[error("No claims provided.")]
     NoClaimsProvided {},
 
+    #[error("Instant unbonding is disabled")]
+    InstantUnbondDisabled {},
+
+    #[error("Instant unbond fees invalid: min_fee must not exceed max_fee and max_fee must not exceed the cap")]
+    InstantUnbondFeeInvalid {},
+
+    #[error("Instant unbond fee {0} is greater than the accepted max fee {1}")]
+    InstantUnbondFeeTooHigh(Decimal, Decimal),
+
+    #[error("Instant unbond buffer too small, available: {0}")]
+    InstantUnbondBufferTooSmall(Uint128),
+
+    #[error("Validator scoring invalid: max_commission must not exceed 100% and slashing_penalty_bps must not exceed 10000")]
+    ValidatorScoringInvalid {},
+
     #[error("The reconciliation has too big of a balance difference between actual and expected balance. Actual: {0}, Expected {1}")]
     ReconcileTooBig(Uint128, Uint128),
+
+    #[error("Stake can't be referred by its receiver")]
+    SelfReferral {},
+
+    #[error("Unbond receipt nft can't be changed while unbond receipts are outstanding")]
+    UnbondReceiptsOutstanding {},
 }
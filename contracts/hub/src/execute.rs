@@ -1,32 +1,44 @@
+use std::convert::TryFrom;
+
 use cosmwasm_std::{
-    attr, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, DistributionMsg, Env, Event,
-    Order, Response, StdResult, SubMsg, SubMsgResponse, Uint128, WasmMsg,
+    attr, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, DistributionMsg, Env,
+    Event, Order, QuerierWrapper, Response, StdError, StdResult, Storage, SubMsg, SubMsgResponse,
+    Uint128, WasmMsg,
 };
 
 use cw2::set_contract_version;
-use cw20::{Cw20ExecuteMsg, MinterResponse};
+use cw20::MinterResponse;
 use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
+use cw721_base::{ExecuteMsg as Cw721ExecuteMsg, Extension, MintMsg};
+use eris::helpers::bps::BasicPoints;
 use eris::{CustomResponse, DecimalCheckedOps};
 
 use eris::hub::{
-    Batch, CallbackMsg, DelegationStrategy, ExecuteMsg, FeeConfig, InstantiateMsg, PendingBatch,
-    UnbondRequest,
+    Batch, CallbackMsg, DelegationStrategy, ExecuteMsg, FeeConfig, FeePayout, FeeReceiver,
+    InstantUnbondConfig, InstantiateMsg, KeeperAction, KeeperTipConfig, PauseOperation,
+    PendingBatch, SlashingEvent, UnbondReceipt, UnbondRequest, ValidatorScoringConfig,
 };
 
-use crate::constants::{get_reward_fee_cap, CONTRACT_DENOM, CONTRACT_NAME, CONTRACT_VERSION};
+use crate::constants::{
+    get_instant_unbond_fee_cap, get_reward_fee_cap, CONTRACT_NAME, CONTRACT_VERSION,
+};
 use crate::error::{ContractError, ContractResult};
 use crate::helpers::{
     assert_validator_exists, assert_validators_exists, dedupe, get_wanted_delegations,
-    query_all_delegations, query_cw20_total_supply, query_delegation, query_delegations,
+    query_active_validators, query_all_delegations, query_delegation, query_delegations,
+    query_unbonding_entries,
 };
 use crate::math::{
-    compute_mint_amount, compute_redelegations_for_rebalancing, compute_redelegations_for_removal,
-    compute_unbond_amount, compute_undelegations, get_uluna_per_validator, mark_reconciled_batches,
-    reconcile_batches,
+    compute_backing, compute_instant_unbond_fee, compute_keeper_tip, compute_mint_amount,
+    compute_redelegations_for_rebalancing, compute_redelegations_for_removal,
+    compute_unbond_amount, compute_undelegations, compute_withdrawable_amount,
+    deduct_batch_slashing, get_utoken_per_validator, mark_reconciled_batches, reconcile_batches,
 };
-use crate::state::State;
+use crate::protos::token_factory::{token_factory_denom, MsgCreateDenom, MsgSetBeforeSendHook};
+use crate::scoring::refresh_validator_scores;
+use crate::state::{BatchUndelegations, State};
 use crate::types::gauges::TuneInfoGaugeLoader;
-use crate::types::{Coins, Delegation, SendFee};
+use crate::types::{burn_cw20_msg, Coins, Delegation, Redelegation, SendFee, StakeToken};
 
 //--------------------------------------------------------------------------------------------------
 // Instantiation
@@ -50,6 +62,7 @@ pub fn instantiate(deps: DepsMut, env: Env, msg: InstantiateMsg) -> ContractResu
     }
 
     state.owner.save(deps.storage, &deps.api.addr_validate(&msg.owner)?)?;
+    state.denom.save(deps.storage, &msg.denom)?;
     state.epoch_period.save(deps.storage, &msg.epoch_period)?;
     state.unbond_period.save(deps.storage, &msg.unbond_period)?;
 
@@ -71,6 +84,7 @@ pub fn instantiate(deps: DepsMut, env: Env, msg: InstantiateMsg) -> ContractResu
         &FeeConfig {
             protocol_fee_contract: deps.api.addr_validate(&msg.protocol_fee_contract)?,
             protocol_reward_fee: msg.protocol_reward_fee,
+            fee_receivers: vec![],
         },
     )?;
 
@@ -86,6 +100,29 @@ pub fn instantiate(deps: DepsMut, env: Env, msg: InstantiateMsg) -> ContractResu
     let delegation_strategy = msg.delegation_strategy.unwrap_or(DelegationStrategy::Uniform);
     state.delegation_strategy.save(deps.storage, &delegation_strategy.validate(deps.api)?)?;
 
+    if let Some(subdenom) = msg.token_factory_subdenom {
+        let denom = token_factory_denom(env.contract.address.as_str(), &subdenom);
+        state.stake_denom.save(deps.storage, &denom)?;
+
+        // transfers of the denom are reported to the hub to release referred stake
+        return Ok(Response::new()
+            .add_message(
+                MsgCreateDenom {
+                    sender: env.contract.address.to_string(),
+                    subdenom,
+                }
+                .to_cosmos_msg(),
+            )
+            .add_message(
+                MsgSetBeforeSendHook {
+                    sender: env.contract.address.to_string(),
+                    denom,
+                    cosmwasm_address: env.contract.address.to_string(),
+                }
+                .to_cosmos_msg(),
+            ));
+    }
+
     Ok(Response::new().add_submessage(SubMsg::reply_on_success(
         CosmosMsg::Wasm(WasmMsg::Instantiate {
             admin: Some(msg.owner), // use the owner as admin for now; can be changed later by a `MsgUpdateAdmin`
@@ -148,14 +185,20 @@ pub fn bond(
     receiver: Addr,
     token_to_bond: Uint128,
     donate: bool,
+    referral: Option<Addr>,
 ) -> ContractResult {
     let state = State::default();
-    let stake_token = state.stake_token.load(deps.storage)?;
+    let stake_token = state.load_stake_token(deps.storage)?;
+    let denom = state.denom.load(deps.storage)?;
+
+    if referral.as_ref() == Some(&receiver) {
+        return Err(ContractError::SelfReferral {});
+    }
 
     let (new_delegation, delegations) = find_new_delegation(&state, &deps, &env, token_to_bond)?;
 
     // Query the current supply of Staking Token and compute the amount to mint
-    let ustake_supply = query_cw20_total_supply(&deps.querier, &stake_token)?;
+    let ustake_supply = stake_token.query_total_supply(&deps.querier)?;
     let ustake_to_mint = if donate {
         match state.allow_donations.may_load(deps.storage)? {
             Some(false) => Err(ContractError::DonationsDisabled {})?,
@@ -166,37 +209,144 @@ pub fn bond(
 
         Uint128::zero()
     } else {
-        compute_mint_amount(ustake_supply, token_to_bond, &delegations)
+        let (ustake_outstanding, utoken_backing) = compute_backing(
+            &state,
+            deps.storage,
+            &env.contract.address,
+            ustake_supply,
+            &delegations,
+        )?;
+        compute_mint_amount(ustake_outstanding, token_to_bond, utoken_backing)
     };
 
-    let event = Event::new("erishub/bonded")
+    let mut event = Event::new("erishub/bonded")
         .add_attribute("receiver", receiver.clone())
         .add_attribute("uluna_bonded", token_to_bond)
         .add_attribute("ustake_minted", ustake_to_mint);
 
+    if let Some(partner) = referral {
+        let mut stats = state.load_referral(deps.storage, &partner)?;
+        stats.utoken_bonded += token_to_bond;
+        state.referrals.save(deps.storage, &partner, &stats)?;
+
+        // the partner earns fees on the minted tokens as long as the receiver holds them
+        state.add_referred_stake(deps.storage, &receiver, &partner, ustake_to_mint)?;
+
+        event = event.add_attribute("referral", partner);
+    }
+
     let mint_msg = if donate {
         None
     } else {
-        Some(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: stake_token.into(),
-            msg: to_json_binary(&Cw20ExecuteMsg::Mint {
-                recipient: receiver.to_string(),
-                amount: ustake_to_mint,
-            })?,
-            funds: vec![],
-        }))
+        Some(stake_token.mint_msg(&env.contract.address, &receiver, ustake_to_mint)?)
     };
 
     Ok(Response::new()
-        .add_message(new_delegation.to_cosmos_msg())
+        .add_message(new_delegation.to_cosmos_msg(&denom))
         .add_optional_message(mint_msg)
         .add_message(check_received_coin_msg(&deps, &env, Some(token_to_bond))?)
         .add_event(event)
         .add_attribute("action", "erishub/bond"))
 }
 
-pub fn harvest(deps: DepsMut, env: Env) -> ContractResult {
-    let withdraw_msgs = query_all_delegations(&deps.querier, &env.contract.address)?
+/// Sends the referral fees accrued by `partner` to `receiver`
+pub fn claim_referral_fees(deps: DepsMut, partner: Addr, receiver: Addr) -> ContractResult {
+    let state = State::default();
+    let denom = state.denom.load(deps.storage)?;
+
+    let mut stats = state.load_referral(deps.storage, &partner)?;
+    let utoken_claimed = stats.utoken_claimable;
+    if utoken_claimed.is_zero() {
+        return Err(ContractError::CantBeZero("claimable referral fees".into()));
+    }
+
+    stats.utoken_claimable = Uint128::zero();
+    state.referrals.save(deps.storage, &partner, &stats)?;
+
+    let referral_fees_unclaimed =
+        state.referral_fees_unclaimed.may_load(deps.storage)?.unwrap_or_default();
+    state
+        .referral_fees_unclaimed
+        .save(deps.storage, &referral_fees_unclaimed.saturating_sub(utoken_claimed))?;
+
+    let event = Event::new("erishub/referral_fees_claimed")
+        .add_attribute("partner", partner)
+        .add_attribute("receiver", receiver.clone())
+        .add_attribute("uluna_claimed", utoken_claimed);
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: receiver.to_string(),
+            amount: vec![Coin::new(utoken_claimed.u128(), denom)],
+        }))
+        .add_event(event)
+        .add_attribute("action", "erishub/claim_referral_fees"))
+}
+
+/// Caps the referred Stake tokens of each holder at their current balance, so that referral fees
+/// are no longer paid for tokens they transferred away. Can be called by anyone.
+pub fn sync_referred_stake(deps: DepsMut, holders: Vec<String>) -> ContractResult {
+    let state = State::default();
+    let stake_token = state.load_stake_token(deps.storage)?;
+
+    let mut events = vec![];
+    for holder in holders {
+        let holder = deps.api.addr_validate(&holder)?;
+        let ustake_referred = state
+            .referred_stakes
+            .prefix(&holder)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| Ok(item?.1))
+            .sum::<StdResult<Uint128>>()?;
+        let ustake_balance = stake_token.query_balance(&deps.querier, &holder)?;
+
+        if ustake_referred > ustake_balance {
+            let ustake_released = state.release_referred_stake(
+                deps.storage,
+                &holder,
+                ustake_referred - ustake_balance,
+            )?;
+            events.push(
+                Event::new("erishub/referred_stake_released")
+                    .add_attribute("holder", holder)
+                    .add_attribute("ustake_released", ustake_released),
+            );
+        }
+    }
+
+    Ok(Response::new().add_events(events).add_attribute("action", "erishub/sync_referred_stake"))
+}
+
+/// Releases the referred Stake tokens that `from` transferred. Tokens sent to the hub are released
+/// by the unbond or conversion they are sent for.
+pub fn release_transferred_stake(
+    deps: DepsMut,
+    env: Env,
+    from: Addr,
+    to: Addr,
+    amount: Uint128,
+) -> ContractResult {
+    let state = State::default();
+
+    let mut res = Response::new().add_attribute("action", "erishub/release_transferred_stake");
+    if to != env.contract.address {
+        let ustake_released = state.release_referred_stake(deps.storage, &from, amount)?;
+        if !ustake_released.is_zero() {
+            res = res.add_event(
+                Event::new("erishub/referred_stake_released")
+                    .add_attribute("holder", from)
+                    .add_attribute("ustake_released", ustake_released),
+            );
+        }
+    }
+
+    Ok(res)
+}
+
+pub fn harvest(deps: DepsMut, env: Env, keeper: Addr) -> ContractResult {
+    let state = State::default();
+    let denom = state.denom.load(deps.storage)?;
+    let withdraw_msgs = query_all_delegations(&deps.querier, &env.contract.address, &denom)?
         .into_iter()
         .map(|d| {
             CosmosMsg::Distribution(DistributionMsg::WithdrawDelegatorReward {
@@ -205,11 +355,79 @@ pub fn harvest(deps: DepsMut, env: Env) -> ContractResult {
         })
         .collect::<Vec<_>>();
 
-    Ok(Response::new()
+    let harvest_due_since = state.load_harvest_due_since(deps.storage)?;
+    let keeper_tip =
+        tip_keeper(&state, deps.storage, &env, &keeper, KeeperAction::Harvest, harvest_due_since)?;
+
+    // the tip is paid after the received rewards have been checked
+    let mut res = Response::new()
         .add_messages(withdraw_msgs)
         .add_message(check_received_coin_msg(&deps, &env, None)?)
-        .add_message(CallbackMsg::Reinvest {}.into_cosmos_msg(&env.contract.address)?)
-        .add_attribute("action", "erishub/harvest"))
+        .add_message(CallbackMsg::Reinvest {}.into_cosmos_msg(&env.contract.address)?);
+    if let Some((tip_msg, tip_event)) = keeper_tip {
+        res = res.add_message(tip_msg).add_event(tip_event);
+    }
+
+    Ok(res.add_attribute("action", "erishub/harvest"))
+}
+
+/// Pays the tip to the keeper executing `action`, if the action is overdue and the tip pool is
+/// not empty
+fn tip_keeper(
+    state: &State,
+    storage: &mut dyn Storage,
+    env: &Env,
+    keeper: &Addr,
+    action: KeeperAction,
+    due_since: u64,
+) -> StdResult<Option<(CosmosMsg, Event)>> {
+    // actions the hub triggers itself, like submitting the batch when queuing an unbond, are not
+    // tipped
+    if *keeper == env.contract.address {
+        return Ok(None);
+    }
+
+    let config = state.keeper_tips.may_load(storage)?;
+    let tip_pool = state.keeper_tip_pool.may_load(storage)?.unwrap_or_default();
+    let tip = compute_keeper_tip(config.as_ref(), tip_pool, env.block.time.seconds(), due_since);
+    if tip.is_zero() {
+        return Ok(None);
+    }
+
+    state.keeper_tip_pool.save(storage, &(tip_pool - tip))?;
+
+    let denom = state.denom.load(storage)?;
+    let msg = CosmosMsg::Bank(BankMsg::Send {
+        to_address: keeper.to_string(),
+        amount: vec![Coin::new(tip.u128(), denom)],
+    });
+
+    let event = Event::new("erishub/keeper_tipped")
+        .add_attribute("keeper", keeper)
+        .add_attribute("keeper_action", action.to_string())
+        .add_attribute("uluna_tip", tip);
+
+    Ok(Some((msg, event)))
+}
+
+/// Sets aside part of the protocol fee for keeper tips, until the tip pool reaches its target
+fn refill_keeper_tip_pool(
+    state: &State,
+    storage: &mut dyn Storage,
+    protocol_fee: Uint128,
+) -> StdResult<Uint128> {
+    let config = match state.keeper_tips.may_load(storage)? {
+        Some(config) => config,
+        None => return Ok(Uint128::zero()),
+    };
+
+    let tip_pool = state.keeper_tip_pool.may_load(storage)?.unwrap_or_default();
+    let refill = config.pool_target.saturating_sub(tip_pool).min(protocol_fee);
+    if !refill.is_zero() {
+        state.keeper_tip_pool.save(storage, &(tip_pool + refill))?;
+    }
+
+    Ok(refill)
 }
 
 /// This callback is used to take a current snapshot of the balance and add the received balance to the unlocked_coins state after the execution
@@ -219,8 +437,8 @@ fn check_received_coin_msg(
     // offset to account for funds being sent that should be ignored
     negative_offset: Option<Uint128>,
 ) -> StdResult<CosmosMsg> {
-    let mut amount =
-        deps.querier.query_balance(env.contract.address.to_string(), CONTRACT_DENOM)?.amount;
+    let denom = State::default().denom.load(deps.storage)?;
+    let mut amount = deps.querier.query_balance(env.contract.address.to_string(), &denom)?.amount;
 
     if let Some(negative_offset) = negative_offset {
         amount = amount.checked_sub(negative_offset)?;
@@ -229,7 +447,7 @@ fn check_received_coin_msg(
     CallbackMsg::CheckReceivedCoin {
         // 0. take current balance - offset
         snapshot: Coin {
-            denom: CONTRACT_DENOM.to_string(),
+            denom,
             amount,
         },
     }
@@ -246,59 +464,170 @@ pub fn reinvest(deps: DepsMut, env: Env) -> ContractResult {
     let state = State::default();
     let fee_config = state.fee_config.load(deps.storage)?;
     let mut unlocked_coins = state.unlocked_coins.load(deps.storage)?;
-    let stake_token = state.stake_token.load(deps.storage)?;
+    let stake_token = state.load_stake_token(deps.storage)?;
+    let denom = state.denom.load(deps.storage)?;
 
-    let uluna_available = unlocked_coins
+    let utoken_available = unlocked_coins
         .iter()
-        .find(|coin| coin.denom == CONTRACT_DENOM)
-        .ok_or_else(|| ContractError::NoTokensAvailable(CONTRACT_DENOM.into()))?
+        .find(|coin| coin.denom == denom)
+        .ok_or_else(|| ContractError::NoTokensAvailable(denom.clone()))?
         .amount;
 
-    let protocol_fee_amount = fee_config.protocol_reward_fee.checked_mul_uint(uluna_available)?;
-    let to_bond = uluna_available.saturating_sub(protocol_fee_amount);
+    let protocol_fee_amount = fee_config.protocol_reward_fee.checked_mul_uint(utoken_available)?;
+    let mut to_bond = utoken_available.saturating_sub(protocol_fee_amount);
+
+    // refill the instant unbond buffer up to its target before bonding the rest. Instant unbond
+    // fees flow back into the buffer, anything above the target belongs to the holders and is
+    // bonded again. Without a config the whole buffer is released.
+    let buffer_target = state
+        .instant_unbond_config
+        .may_load(deps.storage)?
+        .map(|instant_unbond| instant_unbond.buffer_target)
+        .unwrap_or_default();
+    let buffer = state.instant_unbond_buffer.may_load(deps.storage)?.unwrap_or_default();
+    let buffer_refill = buffer_target.saturating_sub(buffer).min(to_bond);
+    let buffer_released = buffer.saturating_sub(buffer_target);
+
+    if !buffer_refill.is_zero() || !buffer_released.is_zero() {
+        to_bond = to_bond - buffer_refill + buffer_released;
+        let new_buffer = buffer + buffer_refill - buffer_released;
+        state.instant_unbond_buffer.save(deps.storage, &new_buffer)?;
+    }
+
+    // referral partners receive a cut of the protocol fee generated by their referred stake. It
+    // stays in the contract until they claim it.
+    let ustake_supply = stake_token.query_total_supply(&deps.querier)?;
+    let referral_fee_amount =
+        accrue_referral_fees(&state, deps.storage, protocol_fee_amount, ustake_supply)?;
+
+    // keeper tips are paid from the protocol fee as well, the tip pool stays in the contract
+    let keeper_tip_refill =
+        refill_keeper_tip_pool(&state, deps.storage, protocol_fee_amount - referral_fee_amount)?;
+    let protocol_fee_to_pay = protocol_fee_amount - referral_fee_amount - keeper_tip_refill;
+
+    // split the protocol fee between the fee receivers, the rest goes to the fee contract
+    let to_bond_for_holders = to_bond;
+    let mut native_fees: Vec<SendFee> = vec![];
+    let mut stake_fees: Vec<(Addr, Uint128)> = vec![];
+    let mut remaining_fee = protocol_fee_to_pay;
+    for receiver in fee_config.fee_receivers {
+        let fee = protocol_fee_to_pay.multiply_ratio(receiver.share_bps, BasicPoints::MAX);
+        if fee.is_zero() {
+            continue;
+        }
+
+        remaining_fee -= fee;
+        match receiver.payout {
+            FeePayout::Native => native_fees.push(SendFee::new(receiver.address, fee.u128())),
+            FeePayout::StakeToken => {
+                to_bond += fee;
+                stake_fees.push((receiver.address, fee));
+            },
+        }
+    }
+
+    if !remaining_fee.is_zero() {
+        native_fees.push(SendFee::new(fee_config.protocol_fee_contract, remaining_fee.u128()));
+    }
 
     let (new_delegation, delegations) = find_new_delegation(&state, &deps, &env, to_bond)?;
 
-    unlocked_coins.retain(|coin| coin.denom != CONTRACT_DENOM);
+    unlocked_coins.retain(|coin| coin.denom != denom);
     state.unlocked_coins.save(deps.storage, &unlocked_coins)?;
 
     let event = Event::new("erishub/harvested")
         .add_attribute("uluna_bonded", to_bond)
-        .add_attribute("uluna_protocol_fee", protocol_fee_amount);
+        .add_attribute("uluna_protocol_fee", protocol_fee_amount)
+        .add_attribute("uluna_referral_fee", referral_fee_amount)
+        .add_attribute("uluna_keeper_tip_refill", keeper_tip_refill)
+        .add_attribute("uluna_buffer_refilled", buffer_refill)
+        .add_attribute("uluna_buffer_released", buffer_released);
+
+    let mut msgs = vec![];
+    let mut events = vec![event];
 
-    let mut msgs = vec![new_delegation.to_cosmos_msg()];
+    if !to_bond.is_zero() {
+        msgs.push(new_delegation.to_cosmos_msg(&denom));
+    }
+
+    for send_fee in native_fees {
+        events.push(
+            Event::new("erishub/fee_paid")
+                .add_attribute("receiver", &send_fee.to_address)
+                .add_attribute("uluna_fee", send_fee.amount.to_string()),
+        );
+        msgs.push(send_fee.to_cosmos_msg(&denom));
+    }
 
-    if !protocol_fee_amount.is_zero() {
-        let send_fee = SendFee::new(fee_config.protocol_fee_contract, protocol_fee_amount.u128());
-        msgs.push(send_fee.to_cosmos_msg());
+    // fees paid in Stake tokens are minted at the exchange rate after the rewards of the holders
+    // have been bonded
+    let (ustake_outstanding, utoken_backing) =
+        compute_backing(&state, deps.storage, &env.contract.address, ustake_supply, &delegations)?;
+    let utoken_backing = utoken_backing + to_bond_for_holders;
+    let mut ustake_minted = Uint128::zero();
+    for (receiver, fee) in stake_fees {
+        let ustake_to_mint = compute_mint_amount(ustake_outstanding, fee, utoken_backing);
+
+        events.push(
+            Event::new("erishub/fee_paid")
+                .add_attribute("receiver", &receiver)
+                .add_attribute("uluna_fee", fee)
+                .add_attribute("ustake_minted", ustake_to_mint),
+        );
+        msgs.push(stake_token.mint_msg(&env.contract.address, &receiver, ustake_to_mint)?);
+        ustake_minted += ustake_to_mint;
     }
 
     // update exchange_rate history
-    let utoken_staked: u128 = delegations.iter().map(|d| d.amount).sum();
-    let total_utoken = utoken_staked + to_bond.u128();
-    let exchange_rate = calc_current_exchange_rate(total_utoken, &deps, stake_token)?;
+    let total_utoken = utoken_backing + (to_bond - to_bond_for_holders);
+    let exchange_rate =
+        calc_current_exchange_rate(total_utoken, ustake_outstanding + ustake_minted);
     state.exchange_history.save(deps.storage, env.block.time.seconds(), &exchange_rate)?;
 
     Ok(Response::new()
         .add_messages(msgs)
-        .add_event(event)
+        .add_events(events)
         .add_attribute("action", "erishub/reinvest")
         .add_attribute("exchange_rate", exchange_rate.to_string()))
 }
 
-fn calc_current_exchange_rate(
-    total_utoken: u128,
-    deps: &DepsMut,
-    stake_token: Addr,
-) -> Result<Decimal, ContractError> {
-    let ustake_supply = query_cw20_total_supply(&deps.querier, &stake_token)?;
+/// Computes the referral partners' cut of the protocol fee and adds it to the referral fee index
+fn accrue_referral_fees(
+    state: &State,
+    storage: &mut dyn Storage,
+    protocol_fee_amount: Uint128,
+    ustake_supply: Uint128,
+) -> StdResult<Uint128> {
+    let referral_fee = state.referral_fee.may_load(storage)?.unwrap_or_default();
+    let total_ustake_referred = state.total_ustake_referred.may_load(storage)?.unwrap_or_default();
+    if referral_fee.is_zero() || total_ustake_referred.is_zero() {
+        return Ok(Uint128::zero());
+    }
 
-    let exchange_rate = if ustake_supply.is_zero() {
+    // referred Stake tokens may have been unbonded since, they can't exceed the supply
+    let referral_fee_amount = (protocol_fee_amount * referral_fee)
+        .multiply_ratio(total_ustake_referred.min(ustake_supply), ustake_supply);
+    if !referral_fee_amount.is_zero() {
+        let fee_index = state.referral_fee_index.may_load(storage)?.unwrap_or_default();
+        let fee_per_ustake = Decimal::from_ratio(referral_fee_amount, total_ustake_referred);
+        state.referral_fee_index.save(storage, &(fee_index + fee_per_ustake))?;
+
+        let referral_fees_unclaimed =
+            state.referral_fees_unclaimed.may_load(storage)?.unwrap_or_default();
+        state
+            .referral_fees_unclaimed
+            .save(storage, &(referral_fees_unclaimed + referral_fee_amount))?;
+    }
+
+    Ok(referral_fee_amount)
+}
+
+fn calc_current_exchange_rate(total_utoken: Uint128, ustake_supply: Uint128) -> Decimal {
+    if ustake_supply.is_zero() {
         Decimal::one()
     } else {
         Decimal::from_ratio(total_utoken, ustake_supply)
-    };
-    Ok(exchange_rate)
+    }
 }
 
 pub fn callback_received_coin(deps: DepsMut, env: Env, snapshot: Coin) -> ContractResult {
@@ -363,11 +692,13 @@ fn find_new_delegation(
         DelegationStrategy::Gauges {
             ..
         } => {
-            let current_delegations = query_all_delegations(&deps.querier, &env.contract.address)?;
+            let denom = state.denom.load(deps.storage)?;
+            let current_delegations =
+                query_all_delegations(&deps.querier, &env.contract.address, &denom)?;
             let utoken_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
             let validators = state.validators.load(deps.storage)?;
 
-            let (map, _, _, _) = get_uluna_per_validator(
+            let (map, _, _, _) = get_utoken_per_validator(
                 state,
                 deps.storage,
                 Uint128::new(utoken_staked).checked_add(utoken_to_bond)?.u128(),
@@ -410,39 +741,50 @@ fn find_new_delegation(
 pub fn queue_unbond(
     deps: DepsMut,
     env: Env,
+    sender: Addr,
     receiver: Addr,
     ustake_to_burn: Uint128,
+    mint_receipt: bool,
 ) -> ContractResult {
     let state = State::default();
-
-    let mut pending_batch = state.pending_batch.load(deps.storage)?;
-    pending_batch.ustake_to_burn += ustake_to_burn;
-    state.pending_batch.save(deps.storage, &pending_batch)?;
-
-    state.unbond_requests.update(
-        deps.storage,
-        (pending_batch.id, &receiver),
-        |x| -> StdResult<_> {
-            let mut request = x.unwrap_or_else(|| UnbondRequest {
+    state.release_referred_stake(deps.storage, &sender, ustake_to_burn)?;
+
+    let mut receipt_msg = None;
+    let mut receipt_token_id = None;
+    let (pending_batch, submit_msg) = if mint_receipt {
+        let nft = state
+            .unbond_receipt_nft
+            .may_load(deps.storage)?
+            .ok_or(ContractError::UnbondReceiptsDisabled {})?;
+        let token_id = state.next_receipt_id.may_load(deps.storage)?.unwrap_or(1);
+        state.next_receipt_id.save(deps.storage, &(token_id + 1))?;
+
+        let (pending_batch, submit_msg) =
+            increase_pending_batch(&state, deps.storage, &env, ustake_to_burn)?;
+
+        let token_id = token_id.to_string();
+        state.unbond_receipts.save(
+            deps.storage,
+            &token_id,
+            &UnbondReceipt {
+                token_id: token_id.clone(),
                 id: pending_batch.id,
-                user: receiver.clone(),
-                shares: Uint128::zero(),
-            });
-            request.shares += ustake_to_burn;
-            Ok(request)
-        },
-    )?;
+                shares: ustake_to_burn,
+            },
+        )?;
+        receipt_msg = Some(mint_receipt_msg(&nft, token_id.clone(), &receiver)?);
+        receipt_token_id = Some(token_id);
 
-    let mut msgs: Vec<CosmosMsg> = vec![];
-    let mut start_time = pending_batch.est_unbond_start_time.to_string();
-    if env.block.time.seconds() >= pending_batch.est_unbond_start_time {
-        start_time = "immediate".to_string();
-        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: env.contract.address.into(),
-            msg: to_json_binary(&ExecuteMsg::SubmitBatch {})?,
-            funds: vec![],
-        }));
-    }
+        (pending_batch, submit_msg)
+    } else {
+        add_to_pending_batch(&state, deps.storage, &env, &receiver, ustake_to_burn)?
+    };
+
+    let start_time = if submit_msg.is_some() {
+        "immediate".to_string()
+    } else {
+        pending_batch.est_unbond_start_time.to_string()
+    };
 
     let event = Event::new("erishub/unbond_queued")
         .add_attribute("est_unbond_start_time", start_time)
@@ -450,31 +792,169 @@ pub fn queue_unbond(
         .add_attribute("receiver", receiver)
         .add_attribute("ustake_to_burn", ustake_to_burn);
 
+    let event = match receipt_token_id {
+        Some(token_id) => event.add_attribute("token_id", token_id),
+        None => event,
+    };
+
     Ok(Response::new()
-        .add_messages(msgs)
+        .add_optional_message(receipt_msg)
+        .add_optional_message(submit_msg)
         .add_event(event)
         .add_attribute("action", "erishub/queue_unbond"))
 }
 
-pub fn submit_batch(deps: DepsMut, env: Env) -> ContractResult {
+fn mint_receipt_msg(nft: &Addr, token_id: String, owner: &Addr) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: nft.to_string(),
+        msg: to_json_binary(&Cw721ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id,
+            owner: owner.to_string(),
+            token_uri: None,
+            extension: None,
+        }))?,
+        funds: vec![],
+    }))
+}
+
+/// Adds the shares of `receiver` to the pending batch. If the batch is due, a message to submit it
+/// is returned as well.
+fn add_to_pending_batch(
+    state: &State,
+    storage: &mut dyn Storage,
+    env: &Env,
+    receiver: &Addr,
+    ustake_to_burn: Uint128,
+) -> StdResult<(PendingBatch, Option<CosmosMsg>)> {
+    let (pending_batch, submit_msg) = increase_pending_batch(state, storage, env, ustake_to_burn)?;
+
+    state.unbond_requests.update(storage, (pending_batch.id, receiver), |x| -> StdResult<_> {
+        let mut request = x.unwrap_or_else(|| UnbondRequest {
+            id: pending_batch.id,
+            user: receiver.clone(),
+            shares: Uint128::zero(),
+        });
+        request.shares += ustake_to_burn;
+        Ok(request)
+    })?;
+
+    Ok((pending_batch, submit_msg))
+}
+
+/// Adds shares to the pending batch. If the batch is due, a message to submit it is returned as well.
+fn increase_pending_batch(
+    state: &State,
+    storage: &mut dyn Storage,
+    env: &Env,
+    ustake_to_burn: Uint128,
+) -> StdResult<(PendingBatch, Option<CosmosMsg>)> {
+    let mut pending_batch = state.pending_batch.load(storage)?;
+    pending_batch.ustake_to_burn += ustake_to_burn;
+    state.pending_batch.save(storage, &pending_batch)?;
+
+    let submit_msg = if env.block.time.seconds() >= pending_batch.est_unbond_start_time {
+        Some(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_json_binary(&ExecuteMsg::SubmitBatch {})?,
+            funds: vec![],
+        }))
+    } else {
+        None
+    };
+
+    Ok((pending_batch, submit_msg))
+}
+
+/// The Luna value of the burned `ustake` is paid out of the instant unbond buffer minus a fee. The
+/// hub itself takes over the unbonding request, which refills the buffer once its batch is reconciled.
+pub fn instant_unbond(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    receiver: Addr,
+    ustake_to_burn: Uint128,
+    max_fee: Decimal,
+) -> ContractResult {
     let state = State::default();
-    let stake_token = state.stake_token.load(deps.storage)?;
+    state.release_referred_stake(deps.storage, &sender, ustake_to_burn)?;
+    let stake_token = state.load_stake_token(deps.storage)?;
+    let instant_unbond = state
+        .instant_unbond_config
+        .may_load(deps.storage)?
+        .ok_or(ContractError::InstantUnbondDisabled {})?;
+    let buffer = state.instant_unbond_buffer.may_load(deps.storage)?.unwrap_or_default();
+    let denom = state.denom.load(deps.storage)?;
+
+    let delegations = query_all_delegations(&deps.querier, &env.contract.address, &denom)?;
+    let ustake_supply = stake_token.query_total_supply(&deps.querier)?;
+    let (ustake_outstanding, utoken_backing) =
+        compute_backing(&state, deps.storage, &env.contract.address, ustake_supply, &delegations)?;
+    let utoken_to_unbond =
+        compute_unbond_amount(ustake_outstanding, ustake_to_burn, utoken_backing);
+
+    let fee = compute_instant_unbond_fee(&instant_unbond, buffer, utoken_to_unbond)?;
+    if fee > max_fee {
+        return Err(ContractError::InstantUnbondFeeTooHigh(fee, max_fee));
+    }
+
+    let utoken_fee = fee.checked_mul_uint(utoken_to_unbond)?;
+    let utoken_to_receive = utoken_to_unbond.checked_sub(utoken_fee)?;
+
+    if utoken_to_receive.is_zero() {
+        return Err(ContractError::CantBeZero("instant unbond amount".into()));
+    }
+
+    if utoken_to_receive > buffer {
+        return Err(ContractError::InstantUnbondBufferTooSmall(buffer));
+    }
+
+    state.instant_unbond_buffer.save(deps.storage, &(buffer - utoken_to_receive))?;
+
+    let (pending_batch, submit_msg) =
+        add_to_pending_batch(&state, deps.storage, &env, &env.contract.address, ustake_to_burn)?;
+
+    let payout_msg = CosmosMsg::Bank(BankMsg::Send {
+        to_address: receiver.to_string(),
+        amount: vec![Coin::new(utoken_to_receive.u128(), denom)],
+    });
+
+    let event = Event::new("erishub/instant_unbonded")
+        .add_attribute("id", pending_batch.id.to_string())
+        .add_attribute("receiver", receiver)
+        .add_attribute("ustake_to_burn", ustake_to_burn)
+        .add_attribute("uluna_received", utoken_to_receive)
+        .add_attribute("uluna_fee", utoken_fee)
+        .add_attribute("fee", fee.to_string());
+
+    Ok(Response::new()
+        .add_message(payout_msg)
+        .add_optional_message(submit_msg)
+        .add_event(event)
+        .add_attribute("action", "erishub/instant_unbond"))
+}
+
+pub fn submit_batch(deps: DepsMut, env: Env, keeper: Addr) -> ContractResult {
+    let state = State::default();
+    let stake_token = state.load_stake_token(deps.storage)?;
     let validators = state.validators.load(deps.storage)?;
     let unbond_period = state.unbond_period.load(deps.storage)?;
     let pending_batch = state.pending_batch.load(deps.storage)?;
+    let denom = state.denom.load(deps.storage)?;
 
     let current_time = env.block.time.seconds();
     if current_time < pending_batch.est_unbond_start_time {
         return Err(ContractError::SubmitBatchAfter(pending_batch.est_unbond_start_time));
     }
 
-    let delegations = query_all_delegations(&deps.querier, &env.contract.address)?;
-    let ustake_supply = query_cw20_total_supply(&deps.querier, &stake_token)?;
+    let delegations = query_all_delegations(&deps.querier, &env.contract.address, &denom)?;
+    let ustake_supply = stake_token.query_total_supply(&deps.querier)?;
+    let (ustake_outstanding, utoken_backing) =
+        compute_backing(&state, deps.storage, &env.contract.address, ustake_supply, &delegations)?;
 
-    let uluna_to_unbond =
-        compute_unbond_amount(ustake_supply, pending_batch.ustake_to_burn, &delegations);
+    let utoken_to_unbond =
+        compute_unbond_amount(ustake_outstanding, pending_batch.ustake_to_burn, utoken_backing);
     let new_undelegations =
-        compute_undelegations(&state, deps.storage, uluna_to_unbond, &delegations, validators)?;
+        compute_undelegations(&state, deps.storage, utoken_to_unbond, &delegations, validators)?;
 
     state.previous_batches.save(
         deps.storage,
@@ -483,11 +963,23 @@ pub fn submit_batch(deps: DepsMut, env: Env) -> ContractResult {
             id: pending_batch.id,
             reconciled: false,
             total_shares: pending_batch.ustake_to_burn,
-            uluna_unclaimed: uluna_to_unbond,
+            utoken_unclaimed: utoken_to_unbond,
             est_unbond_end_time: current_time + unbond_period,
         },
     )?;
 
+    state.batch_undelegations.save(
+        deps.storage,
+        pending_batch.id,
+        &BatchUndelegations {
+            height: env.block.height,
+            undelegations: new_undelegations
+                .iter()
+                .map(|d| (d.validator.clone(), Uint128::new(d.amount)))
+                .collect(),
+        },
+    )?;
+
     let epoch_period = state.epoch_period.load(deps.storage)?;
     state.pending_batch.save(
         deps.storage,
@@ -498,35 +990,54 @@ pub fn submit_batch(deps: DepsMut, env: Env) -> ContractResult {
         },
     )?;
 
-    let undelegate_msgs = new_undelegations.iter().map(|d| d.to_cosmos_msg()).collect::<Vec<_>>();
+    let undelegate_msgs =
+        new_undelegations.iter().map(|d| d.to_cosmos_msg(&denom)).collect::<Vec<_>>();
 
-    let burn_msg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: stake_token.into(),
-        msg: to_json_binary(&Cw20ExecuteMsg::Burn {
-            amount: pending_batch.ustake_to_burn,
-        })?,
-        funds: vec![],
-    });
+    let burn_msg = stake_token.burn_msg(&env.contract.address, pending_batch.ustake_to_burn)?;
 
     let event = Event::new("erishub/unbond_submitted")
         .add_attribute("id", pending_batch.id.to_string())
-        .add_attribute("uluna_unbonded", uluna_to_unbond)
+        .add_attribute("uluna_unbonded", utoken_to_unbond)
         .add_attribute("ustake_burned", pending_batch.ustake_to_burn);
 
-    Ok(Response::new()
+    // submitting an empty batch is not worth a tip
+    let keeper_tip = if pending_batch.ustake_to_burn.is_zero() {
+        None
+    } else {
+        tip_keeper(
+            &state,
+            deps.storage,
+            &env,
+            &keeper,
+            KeeperAction::SubmitBatch,
+            pending_batch.est_unbond_start_time,
+        )?
+    };
+
+    // the tip is paid after the received rewards have been checked
+    let mut res = Response::new()
         .add_messages(undelegate_msgs)
         .add_message(burn_msg)
         .add_message(check_received_coin_msg(&deps, &env, None)?)
-        .add_event(event)
-        .add_attribute("action", "erishub/unbond"))
+        .add_event(event);
+    if let Some((tip_msg, tip_event)) = keeper_tip {
+        res = res.add_message(tip_msg).add_event(tip_event);
+    }
+
+    Ok(res.add_attribute("action", "erishub/unbond"))
 }
 
-pub fn reconcile(deps: DepsMut, env: Env) -> ContractResult {
+pub fn reconcile(deps: DepsMut, env: Env, keeper: Addr, limit: Option<u32>) -> ContractResult {
     let state = State::default();
     let current_time = env.block.time.seconds();
 
-    // Load batches that have not been reconciled
-    let all_batches = state
+    if limit == Some(0) {
+        return Err(ContractError::CantBeZero("limit".into()));
+    }
+
+    // Only the oldest `limit` batches that have not been reconciled are loaded, checked for
+    // slashing and reconciled. The others are handled by the next calls.
+    let mut unreconciled = state
         .previous_batches
         .idx
         .reconciled
@@ -535,124 +1046,355 @@ pub fn reconcile(deps: DepsMut, env: Env) -> ContractResult {
         .map(|item| {
             let (_, v) = item?;
             Ok(v)
-        })
+        });
+    let selected_batches = (&mut unreconciled)
+        .take(limit.map_or(usize::MAX, |limit| limit as usize))
         .collect::<StdResult<Vec<_>>>()?;
 
-    let mut batches = all_batches
+    // batches beyond the limit that finished unbonding have already been paid out to the contract
+    let utoken_unbonded_later = unreconciled
+        .map(|batch| {
+            batch.map(|b| {
+                if current_time > b.est_unbond_end_time {
+                    b.utoken_unclaimed
+                } else {
+                    Uint128::zero()
+                }
+            })
+        })
+        .sum::<StdResult<Uint128>>()?;
+
+    // the chain removes unbonding entries once they are completed, so slashing can only be detected
+    // for batches that are still unbonding
+    let slashing_events = record_slashing_events(
+        &state,
+        deps.storage,
+        &deps.querier,
+        &env,
+        selected_batches.iter().filter(|b| current_time <= b.est_unbond_end_time),
+    )?;
+
+    let mut batches = selected_batches
         .into_iter()
         .filter(|b| current_time > b.est_unbond_end_time)
         .collect::<Vec<_>>();
 
-    let uluna_expected_received: Uint128 = batches.iter().map(|b| b.uluna_unclaimed).sum();
+    let utoken_expected_received: Uint128 = batches.iter().map(|b| b.utoken_unclaimed).sum();
 
-    if uluna_expected_received.is_zero() {
-        return Ok(Response::new());
+    if utoken_expected_received.is_zero() {
+        return Ok(Response::new().add_events(slashing_events));
     }
 
-    let unlocked_coins = state.unlocked_coins.load(deps.storage)?;
-    let uluna_expected_unlocked = Coins(unlocked_coins).find(CONTRACT_DENOM).amount;
+    // reconciled batches keep their funds in the contract until they are withdrawn
+    let utoken_reconciled_unclaimed = state
+        .previous_batches
+        .idx
+        .reconciled
+        .prefix(true.into())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| Ok(item?.1.utoken_unclaimed))
+        .sum::<StdResult<Uint128>>()?;
 
-    let uluna_expected = uluna_expected_received + uluna_expected_unlocked;
-    let uluna_actual = deps.querier.query_balance(&env.contract.address, CONTRACT_DENOM)?.amount;
-    let uluna_unbonded = uluna_actual - uluna_expected_unlocked;
-    if (uluna_unbonded) < Decimal::from_ratio(80u128, 100u128) * uluna_expected_received {
+    let denom = state.denom.load(deps.storage)?;
+    let unlocked_coins = state.unlocked_coins.load(deps.storage)?;
+    let buffer = state.instant_unbond_buffer.may_load(deps.storage)?.unwrap_or_default();
+    let tip_pool = state.keeper_tip_pool.may_load(deps.storage)?.unwrap_or_default();
+    let referral_fees = state.referral_fees_unclaimed.may_load(deps.storage)?.unwrap_or_default();
+    // the instant unbond buffer, the keeper tip pool, the unclaimed referral fees and the funds of
+    // other batches are part of the balance, but not of the amount unbonded by these batches
+    let utoken_expected_unlocked = Coins(unlocked_coins).find(&denom).amount
+        + buffer
+        + tip_pool
+        + referral_fees
+        + utoken_reconciled_unclaimed
+        + utoken_unbonded_later;
+
+    let utoken_expected = utoken_expected_received + utoken_expected_unlocked;
+    let utoken_actual = deps.querier.query_balance(&env.contract.address, denom)?.amount;
+    let utoken_unbonded = utoken_actual.saturating_sub(utoken_expected_unlocked);
+    if (utoken_unbonded) < Decimal::from_ratio(80u128, 100u128) * utoken_expected_received {
         // This case can happen when reconciliation is executed in the same block as the unlocks happen.
-        return Err(ContractError::ReconcileTooBig(uluna_unbonded, uluna_expected_received));
+        return Err(ContractError::ReconcileTooBig(utoken_unbonded, utoken_expected_received));
     }
 
-    if uluna_actual >= uluna_expected {
+    if utoken_actual >= utoken_expected {
         mark_reconciled_batches(&mut batches);
-        for batch in &batches {
-            state.previous_batches.save(deps.storage, batch.id, batch)?;
-        }
-        let ids = batches.iter().map(|b| b.id.to_string()).collect::<Vec<_>>().join(",");
-        let event = Event::new("erishub/reconciled")
-            .add_attribute("ids", ids)
-            .add_attribute("uluna_deducted", "0");
-        return Ok(Response::new().add_event(event).add_attribute("action", "erishub/reconcile"));
-    }
+    } else {
+        // the slashing recorded for a batch is deducted from it, only the rest of the shortfall
+        // is spread over all batches
+        let batch_slashing = batches
+            .iter()
+            .map(|batch| {
+                state
+                    .batch_slashing
+                    .prefix(batch.id)
+                    .range(deps.storage, None, None, Order::Ascending)
+                    .map(|item| Ok(item?.1))
+                    .sum::<StdResult<Uint128>>()
+            })
+            .collect::<StdResult<Vec<_>>>()?;
 
-    let uluna_to_deduct = uluna_expected - uluna_actual;
+        let utoken_to_spread =
+            deduct_batch_slashing(&mut batches, &batch_slashing, utoken_expected - utoken_actual);
+        reconcile_batches(&mut batches, utoken_to_spread);
+    }
 
-    reconcile_batches(&mut batches, uluna_to_deduct);
+    let utoken_to_deduct =
+        utoken_expected_received - batches.iter().map(|b| b.utoken_unclaimed).sum::<Uint128>();
 
     for batch in &batches {
         state.previous_batches.save(deps.storage, batch.id, batch)?;
+        remove_batch_undelegations(&state, deps.storage, batch.id)?;
     }
 
+    let utoken_buffer_refilled =
+        refill_instant_unbond_buffer(&state, deps.storage, &env.contract.address, &batches)?;
+
     let ids = batches.iter().map(|b| b.id.to_string()).collect::<Vec<_>>().join(",");
 
     let event = Event::new("erishub/reconciled")
         .add_attribute("ids", ids)
-        .add_attribute("uluna_deducted", uluna_to_deduct.to_string());
+        .add_attribute("uluna_deducted", utoken_to_deduct.to_string())
+        .add_attribute("uluna_buffer_refilled", utoken_buffer_refilled.to_string());
+
+    // batches can be reconciled once their unbonding has ended
+    let reconcile_due_since =
+        batches.iter().map(|b| b.est_unbond_end_time + 1).min().unwrap_or(current_time);
+    let keeper_tip = tip_keeper(
+        &state,
+        deps.storage,
+        &env,
+        &keeper,
+        KeeperAction::Reconcile,
+        reconcile_due_since,
+    )?;
+
+    let mut res = Response::new().add_events(slashing_events).add_event(event);
+    if let Some((tip_msg, tip_event)) = keeper_tip {
+        res = res.add_message(tip_msg).add_event(tip_event);
+    }
+
+    Ok(res.add_attribute("action", "erishub/reconcile"))
+}
+
+/// Compares the undelegations made for each batch with the unbonding entries of the validators and
+/// records any slashed amount that has not been recorded yet.
+fn record_slashing_events<'a>(
+    state: &State,
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    batches: impl Iterator<Item = &'a Batch>,
+) -> StdResult<Vec<Event>> {
+    let mut events: Vec<Event> = vec![];
+    let mut next_id = state
+        .slashing_events
+        .keys(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or(1, |id| id + 1);
+
+    for batch in batches {
+        let batch_undelegations = match state.batch_undelegations.may_load(storage, batch.id)? {
+            Some(batch_undelegations) => batch_undelegations,
+            None => continue,
+        };
+
+        for (validator, _) in batch_undelegations.undelegations {
+            // a failing query must not block reconciling, it is reported instead
+            let entries = match query_unbonding_entries(querier, &env.contract.address, &validator)
+            {
+                Ok(entries) => entries,
+                Err(err) => {
+                    events.push(
+                        Event::new("erishub/slashing_check_failed")
+                            .add_attribute("validator", validator)
+                            .add_attribute("batch_id", batch.id.to_string())
+                            .add_attribute("error", err.to_string()),
+                    );
+                    continue;
+                },
+            };
+
+            let slashed: Uint128 = entries
+                .iter()
+                .filter(|entry| entry.creation_height.u64() == batch_undelegations.height)
+                .map(|entry| entry.initial_balance.saturating_sub(entry.balance))
+                .sum();
+
+            let recorded = state
+                .batch_slashing
+                .may_load(storage, (batch.id, validator.as_str()))?
+                .unwrap_or_default();
+
+            if slashed > recorded {
+                let amount = slashed - recorded;
+                state.batch_slashing.save(storage, (batch.id, validator.as_str()), &slashed)?;
+                state.slashing_events.save(
+                    storage,
+                    next_id,
+                    &SlashingEvent {
+                        id: next_id,
+                        validator: validator.clone(),
+                        batch_id: batch.id,
+                        amount,
+                        time: env.block.time.seconds(),
+                    },
+                )?;
+                next_id += 1;
+
+                events.push(
+                    Event::new("erishub/slashed")
+                        .add_attribute("validator", validator)
+                        .add_attribute("batch_id", batch.id.to_string())
+                        .add_attribute("uluna_slashed", amount),
+                );
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// The undelegations of a batch are only needed until it has been reconciled
+fn remove_batch_undelegations(state: &State, storage: &mut dyn Storage, id: u64) -> StdResult<()> {
+    if let Some(batch_undelegations) = state.batch_undelegations.may_load(storage, id)? {
+        for (validator, _) in batch_undelegations.undelegations {
+            state.batch_slashing.remove(storage, (id, validator.as_str()));
+        }
+        state.batch_undelegations.remove(storage, id);
+    }
+    Ok(())
+}
+
+/// Withdraws the unbonding requests held by the hub itself (taken over through instant unbonds)
+/// from the reconciled batches and moves the utoken back into the instant unbond buffer.
+fn refill_instant_unbond_buffer(
+    state: &State,
+    storage: &mut dyn Storage,
+    contract: &Addr,
+    batches: &[Batch],
+) -> StdResult<Uint128> {
+    let mut utoken_refilled = Uint128::zero();
+
+    for batch in batches {
+        if let Some(request) = state.unbond_requests.may_load(storage, (batch.id, contract))? {
+            let mut batch = batch.clone();
+            let utoken_to_refill =
+                batch.utoken_unclaimed.multiply_ratio(request.shares, batch.total_shares);
+
+            utoken_refilled += utoken_to_refill;
+            batch.total_shares -= request.shares;
+            batch.utoken_unclaimed -= utoken_to_refill;
+
+            if batch.total_shares.is_zero() {
+                state.previous_batches.remove(storage, batch.id)?;
+            } else {
+                state.previous_batches.save(storage, batch.id, &batch)?;
+            }
+
+            state.unbond_requests.remove(storage, (batch.id, contract))?;
+        }
+    }
+
+    if !utoken_refilled.is_zero() {
+        let buffer = state.instant_unbond_buffer.may_load(storage)?.unwrap_or_default();
+        state.instant_unbond_buffer.save(storage, &buffer.checked_add(utoken_refilled)?)?;
+    }
 
-    Ok(Response::new().add_event(event).add_attribute("action", "erishub/reconcile"))
+    Ok(utoken_refilled)
 }
 
-pub fn withdraw_unbonded(deps: DepsMut, env: Env, user: Addr, receiver: Addr) -> ContractResult {
+pub fn withdraw_unbonded(
+    deps: DepsMut,
+    env: Env,
+    user: Addr,
+    receiver: Addr,
+    batch_ids: Option<Vec<u64>>,
+    limit: Option<u32>,
+) -> ContractResult {
     let state = State::default();
+    let denom = state.denom.load(deps.storage)?;
     let current_time = env.block.time.seconds();
 
-    // NOTE: If the user has too many unclaimed requests, this may not fit in the WASM memory...
-    // However, this is practically never going to happen. Who would create hundreds of unbonding
-    // requests and never claim them?
-    let requests = state
-        .unbond_requests
-        .idx
-        .user
-        .prefix(user.to_string())
-        .range(deps.storage, None, None, Order::Ascending)
-        .map(|item| {
-            let (_, v) = item?;
-            Ok(v)
-        })
-        .collect::<StdResult<Vec<_>>>()?;
-
     // NOTE: Luna in the following batches are withdrawn it the batch:
     // - is a _previous_ batch, not a _pending_ batch
     // - is reconciled
     // - has finished unbonding
     // If not sure whether the batches have been reconciled, the user should first invoke `ExecuteMsg::Reconcile`
     // before withdrawing.
-    let mut total_uluna_to_refund = Uint128::zero();
-    let mut ids: Vec<String> = vec![];
-    for request in &requests {
-        if let Ok(mut batch) = state.previous_batches.load(deps.storage, request.id) {
-            if batch.reconciled && batch.est_unbond_end_time < current_time {
-                let uluna_to_refund =
-                    batch.uluna_unclaimed.multiply_ratio(request.shares, batch.total_shares);
-
-                ids.push(request.id.to_string());
+    //
+    // Only the requested `batch_ids` are loaded, and requests are read until `limit` withdrawable
+    // ones are found, so users with many unclaimed requests can withdraw them in parts.
+    let storage: &dyn Storage = deps.storage;
+    let requests: Box<dyn Iterator<Item = StdResult<UnbondRequest>> + '_> =
+        match batch_ids {
+            Some(mut batch_ids) => {
+                batch_ids.sort_unstable();
+                batch_ids.dedup();
+                Box::new(batch_ids.into_iter().filter_map(|id| {
+                    state.unbond_requests.may_load(storage, (id, &user)).transpose()
+                }))
+            },
+            None => Box::new(
+                state
+                    .unbond_requests
+                    .idx
+                    .user
+                    .prefix(user.to_string())
+                    .range(storage, None, None, Order::Ascending)
+                    .map(|item| item.map(|(_, v)| v)),
+            ),
+        };
+
+    let limit = limit.map(|limit| limit as usize).unwrap_or(usize::MAX);
+    let withdrawals = requests
+        .filter_map(|request| {
+            request
+                .and_then(|request| {
+                    let batch = state.previous_batches.may_load(storage, request.id)?;
+                    Ok(batch.and_then(|batch| {
+                        compute_withdrawable_amount(&batch, request.shares, current_time)
+                            .map(|utoken_to_refund| (request, batch, utoken_to_refund))
+                    }))
+                })
+                .transpose()
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
 
-                total_uluna_to_refund += uluna_to_refund;
-                batch.total_shares -= request.shares;
-                batch.uluna_unclaimed -= uluna_to_refund;
+    let mut total_utoken_to_refund = Uint128::zero();
+    let mut ids: Vec<String> = vec![];
+    for (request, mut batch, utoken_to_refund) in withdrawals {
+        ids.push(request.id.to_string());
 
-                if batch.total_shares.is_zero() {
-                    state.previous_batches.remove(deps.storage, request.id)?;
-                } else {
-                    state.previous_batches.save(deps.storage, batch.id, &batch)?;
-                }
+        total_utoken_to_refund += utoken_to_refund;
+        batch.total_shares -= request.shares;
+        batch.utoken_unclaimed -= utoken_to_refund;
 
-                state.unbond_requests.remove(deps.storage, (request.id, &user))?;
-            }
+        if batch.total_shares.is_zero() {
+            state.previous_batches.remove(deps.storage, request.id)?;
+        } else {
+            state.previous_batches.save(deps.storage, batch.id, &batch)?;
         }
+
+        state.unbond_requests.remove(deps.storage, (request.id, &user))?;
     }
 
-    if total_uluna_to_refund.is_zero() {
+    if total_utoken_to_refund.is_zero() {
         return Err(ContractError::CantBeZero("withdrawable amount".into()));
     }
 
     let refund_msg = CosmosMsg::Bank(BankMsg::Send {
         to_address: receiver.clone().into(),
-        amount: vec![Coin::new(total_uluna_to_refund.u128(), CONTRACT_DENOM)],
+        amount: vec![Coin::new(total_utoken_to_refund.u128(), denom)],
     });
 
     let event = Event::new("erishub/unbonded_withdrawn")
         .add_attribute("ids", ids.join(","))
         .add_attribute("user", user)
         .add_attribute("receiver", receiver)
-        .add_attribute("uluna_refunded", total_uluna_to_refund);
+        .add_attribute("uluna_refunded", total_utoken_to_refund);
 
     Ok(Response::new()
         .add_message(refund_msg)
@@ -660,6 +1402,101 @@ pub fn withdraw_unbonded(deps: DepsMut, env: Env, user: Addr, receiver: Addr) ->
         .add_attribute("action", "erishub/withdraw_unbonded"))
 }
 
+/// Pays out the unbond receipt `token_id`, which has been sent to the hub, and burns it. Same as
+/// with `withdraw_unbonded`, its batch must be reconciled and finished unbonding.
+pub fn withdraw_unbond_receipt(
+    deps: DepsMut,
+    env: Env,
+    token_id: String,
+    receiver: Addr,
+) -> ContractResult {
+    let state = State::default();
+    let denom = state.denom.load(deps.storage)?;
+    let nft = state.unbond_receipt_nft.load(deps.storage)?;
+    let receipt = state.unbond_receipts.load(deps.storage, &token_id)?;
+
+    let mut batch = state
+        .previous_batches
+        .may_load(deps.storage, receipt.id)?
+        .filter(|batch| batch.reconciled && batch.est_unbond_end_time < env.block.time.seconds())
+        .ok_or_else(|| ContractError::UnbondReceiptNotWithdrawable(token_id.clone()))?;
+
+    let utoken_to_refund =
+        batch.utoken_unclaimed.multiply_ratio(receipt.shares, batch.total_shares);
+    if utoken_to_refund.is_zero() {
+        return Err(ContractError::CantBeZero("withdrawable amount".into()));
+    }
+
+    batch.total_shares -= receipt.shares;
+    batch.utoken_unclaimed -= utoken_to_refund;
+
+    if batch.total_shares.is_zero() {
+        state.previous_batches.remove(deps.storage, receipt.id)?;
+    } else {
+        state.previous_batches.save(deps.storage, batch.id, &batch)?;
+    }
+
+    state.unbond_receipts.remove(deps.storage, &token_id);
+
+    let refund_msg = CosmosMsg::Bank(BankMsg::Send {
+        to_address: receiver.to_string(),
+        amount: vec![Coin::new(utoken_to_refund.u128(), denom)],
+    });
+
+    let burn_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: nft.into(),
+        msg: to_json_binary(&Cw721ExecuteMsg::Burn {
+            token_id: token_id.clone(),
+        })?,
+        funds: vec![],
+    });
+
+    let event = Event::new("erishub/unbond_receipt_withdrawn")
+        .add_attribute("id", receipt.id.to_string())
+        .add_attribute("token_id", token_id)
+        .add_attribute("receiver", receiver)
+        .add_attribute("uluna_refunded", utoken_to_refund);
+
+    Ok(Response::new()
+        .add_message(refund_msg)
+        .add_message(burn_msg)
+        .add_event(event)
+        .add_attribute("action", "erishub/withdraw_unbond_receipt"))
+}
+
+/// Moves the unbonding request of `sender` in batch `id` to `recipient`, adding to the shares the
+/// recipient may already have in that batch
+pub fn transfer_unbond_request(
+    deps: DepsMut,
+    sender: Addr,
+    id: u64,
+    recipient: String,
+) -> ContractResult {
+    let state = State::default();
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let request = state.unbond_requests.load(deps.storage, (id, &sender))?;
+    state.unbond_requests.remove(deps.storage, (id, &sender))?;
+
+    state.unbond_requests.update(deps.storage, (id, &recipient), |x| -> StdResult<_> {
+        let mut transferred = x.unwrap_or_else(|| UnbondRequest {
+            id,
+            user: recipient.clone(),
+            shares: Uint128::zero(),
+        });
+        transferred.shares += request.shares;
+        Ok(transferred)
+    })?;
+
+    let event = Event::new("erishub/unbond_request_transferred")
+        .add_attribute("id", id.to_string())
+        .add_attribute("sender", sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("shares", request.shares);
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erishub/transfer_unbond_request"))
+}
+
 pub fn tune_delegations(deps: DepsMut, env: Env, sender: Addr) -> ContractResult {
     let state = State::default();
 
@@ -668,7 +1505,7 @@ pub fn tune_delegations(deps: DepsMut, env: Env, sender: Addr) -> ContractResult
     let (wanted_delegations, save) =
         get_wanted_delegations(&state, &env, deps.storage, &deps.querier, TuneInfoGaugeLoader {})?;
 
-    let attributes = if save {
+    let attributes: Vec<_> = if save {
         state.delegation_goal.save(deps.storage, &wanted_delegations)?;
 
         wanted_delegations
@@ -682,9 +1519,17 @@ pub fn tune_delegations(deps: DepsMut, env: Env, sender: Addr) -> ContractResult
         vec![]
     };
 
+    // scores are only applied to the delegation goal of the gauges strategy
+    let scores = refresh_validator_scores(&state, deps.storage, &deps.querier, &env)?;
+    let score_attributes = scores
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| attr("validator_score", format!("{0}={1}", a.0, a.1)));
+
     Ok(Response::new()
         .add_attribute("action", "erishub/tune_delegations")
-        .add_attributes(attributes))
+        .add_attributes(attributes)
+        .add_attributes(score_attributes))
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -700,18 +1545,28 @@ pub fn rebalance(
     let state = State::default();
     state.assert_owner(deps.storage, &sender)?;
 
-    let delegations = query_all_delegations(&deps.querier, &env.contract.address)?;
+    let denom = state.denom.load(deps.storage)?;
+    let delegations = query_all_delegations(&deps.querier, &env.contract.address, &denom)?;
     let validators = state.validators.load(deps.storage)?;
 
     let min_redelegation = min_redelegation.unwrap_or_default();
 
-    let new_redelegations =
-        compute_redelegations_for_rebalancing(&state, deps.storage, &delegations, validators)?
-            .into_iter()
-            .filter(|redelegation| redelegation.amount >= min_redelegation.u128())
-            .collect::<Vec<_>>();
+    refresh_validator_scores(&state, deps.storage, &deps.querier, &env)?;
+    let new_redelegations = compute_redelegations_for_rebalancing(
+        &state,
+        deps.storage,
+        &delegations,
+        validators,
+        env.block.time.seconds(),
+    )?
+    .into_iter()
+    .filter(|redelegation| redelegation.amount >= min_redelegation.u128())
+    .collect::<Vec<_>>();
+
+    state.save_redelegations(deps.storage, &new_redelegations, env.block.time.seconds())?;
 
-    let redelegate_msgs = new_redelegations.iter().map(|rd| rd.to_cosmos_msg()).collect::<Vec<_>>();
+    let redelegate_msgs =
+        new_redelegations.iter().map(|rd| rd.to_cosmos_msg(&denom)).collect::<Vec<_>>();
 
     let amount: u128 = new_redelegations.iter().map(|rd| rd.amount).sum();
 
@@ -771,21 +1626,40 @@ pub fn remove_validator(
     let delegation_strategy =
         state.delegation_strategy.may_load(deps.storage)?.unwrap_or(DelegationStrategy::Uniform);
 
+    let mut event = Event::new("erishub/validator_removed").add_attribute("validator", &validator);
+
     let redelegate_msgs = match delegation_strategy {
         DelegationStrategy::Uniform => {
             // only redelegate when old strategy
             let delegations = query_delegations(&deps.querier, &validators, &env.contract.address)?;
             let delegation_to_remove =
                 query_delegation(&deps.querier, &validator, &env.contract.address)?;
-            let new_redelegations = compute_redelegations_for_removal(
-                &state,
-                deps.storage,
-                &delegation_to_remove,
-                &delegations,
-                validators,
-            )?;
 
-            new_redelegations.iter().map(|d| d.to_cosmos_msg()).collect::<Vec<_>>()
+            // the chain rejects redelegating stake that was redelegated to the validator before it
+            // matured. The stake stays until a later rebalance moves it off the removed validator.
+            let locked =
+                state.redelegation_locked_validators(deps.storage, env.block.time.seconds())?;
+            if locked.contains(&validator) {
+                event =
+                    event.add_attribute("uluna_deferred", delegation_to_remove.amount.to_string());
+                vec![]
+            } else {
+                let new_redelegations = compute_redelegations_for_removal(
+                    &state,
+                    deps.storage,
+                    &delegation_to_remove,
+                    &delegations,
+                    validators,
+                )?;
+                state.save_redelegations(
+                    deps.storage,
+                    &new_redelegations,
+                    env.block.time.seconds(),
+                )?;
+
+                let denom = state.denom.load(deps.storage)?;
+                new_redelegations.iter().map(|d| d.to_cosmos_msg(&denom)).collect::<Vec<_>>()
+            }
         },
         DelegationStrategy::Gauges {
             ..
@@ -795,8 +1669,6 @@ pub fn remove_validator(
         },
     };
 
-    let event = Event::new("erishub/validator_removed").add_attribute("validator", validator);
-
     let check_msg = if !redelegate_msgs.is_empty() {
         // only check coins if a redelegation is happening
         Some(check_received_coin_msg(&deps, &env, None)?)
@@ -811,6 +1683,112 @@ pub fn remove_validator(
         .add_attribute("action", "erishub/remove_validator"))
 }
 
+pub fn evict_inactive_validators(deps: DepsMut, env: Env) -> ContractResult {
+    let state = State::default();
+
+    let active_validators = query_active_validators(&deps.querier)?;
+    let (validators, evicted): (Vec<_>, Vec<_>) = state
+        .validators
+        .load(deps.storage)?
+        .into_iter()
+        .partition(|validator| active_validators.contains(validator));
+
+    if evicted.is_empty() {
+        return Err(ContractError::NoInactiveValidators {});
+    }
+    if validators.is_empty() {
+        return Err(ContractError::NoActiveValidators {});
+    }
+
+    state.validators.save(deps.storage, &validators)?;
+
+    // the delegation goal is spread over the remaining validators, so that the stake of the evicted
+    // validators is moved right away instead of waiting for the next tune.
+    if let Some(mut goal) = state.delegation_goal.may_load(deps.storage)? {
+        goal.shares.retain(|(validator, _)| !evicted.contains(validator));
+        let total: Decimal = goal.shares.iter().map(|a| a.1).sum();
+
+        if total.is_zero() {
+            state.delegation_goal.remove(deps.storage);
+        } else {
+            goal.shares = goal
+                .shares
+                .into_iter()
+                .map(|(validator, share)| -> StdResult<(String, Decimal)> {
+                    let normalized = share
+                        .checked_div(total)
+                        .map_err(|_| StdError::generic_err("Could not divide by total"))?;
+                    Ok((validator, normalized))
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            state.delegation_goal.save(deps.storage, &goal)?;
+        }
+    }
+
+    let mut delegations = query_delegations(&deps.querier, &validators, &env.contract.address)?;
+    let mut redelegations: Vec<Redelegation> = vec![];
+    let mut events: Vec<Event> = vec![];
+    let locked = state.redelegation_locked_validators(deps.storage, env.block.time.seconds())?;
+
+    for validator in evicted {
+        let delegation_to_remove =
+            query_delegation(&deps.querier, &validator, &env.contract.address)?;
+
+        // stake redelegated to the validator can't be moved again before it matured, it is left
+        // for a later rebalance
+        if locked.contains(&validator) {
+            events.push(
+                Event::new("erishub/validator_evicted")
+                    .add_attribute("validator", validator)
+                    .add_attribute("uluna_moved", "0")
+                    .add_attribute("uluna_deferred", delegation_to_remove.amount.to_string()),
+            );
+            continue;
+        }
+
+        let new_redelegations = compute_redelegations_for_removal(
+            &state,
+            deps.storage,
+            &delegation_to_remove,
+            &delegations,
+            validators.clone(),
+        )?;
+
+        // following evictions need to account for the stake that has been moved already
+        for rd in new_redelegations.iter() {
+            if let Some(d) = delegations.iter_mut().find(|d| d.validator == rd.dst) {
+                d.amount += rd.amount;
+            }
+        }
+
+        events.push(
+            Event::new("erishub/validator_evicted")
+                .add_attribute("validator", validator)
+                .add_attribute("uluna_moved", delegation_to_remove.amount.to_string()),
+        );
+        redelegations.extend(new_redelegations);
+    }
+
+    state.save_redelegations(deps.storage, &redelegations, env.block.time.seconds())?;
+
+    let denom = state.denom.load(deps.storage)?;
+    let redelegate_msgs =
+        redelegations.iter().map(|rd| rd.to_cosmos_msg(&denom)).collect::<Vec<_>>();
+
+    let check_msg = if !redelegate_msgs.is_empty() {
+        // only check coins if a redelegation is happening
+        Some(check_received_coin_msg(&deps, &env, None)?)
+    } else {
+        None
+    };
+
+    Ok(Response::new()
+        .add_messages(redelegate_msgs)
+        .add_optional_message(check_msg)
+        .add_events(events)
+        .add_attribute("action", "erishub/evict_inactive_validators"))
+}
+
 pub fn transfer_ownership(deps: DepsMut, sender: Addr, new_owner: String) -> ContractResult {
     let state = State::default();
 
@@ -842,13 +1820,102 @@ pub fn accept_ownership(deps: DepsMut, sender: Addr) -> ContractResult {
     state.owner.save(deps.storage, &sender)?;
     state.new_owner.remove(deps.storage);
 
+    // actions proposed by the previous owner are not executed on behalf of the new one
+    let cancelled = state.timelock.clear(deps.storage)?;
+
     let event = Event::new("erishub/ownership_transferred")
         .add_attribute("new_owner", new_owner)
-        .add_attribute("previous_owner", previous_owner);
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("cancelled_actions", cancelled.len().to_string());
 
     Ok(Response::new().add_event(event).add_attribute("action", "erishub/transfer_ownership"))
 }
 
+/// Creates a token-factory denom that replaces the CW20 stake token. The CW20 tokens held by the
+/// hub for the pending batch are converted right away, all other holders convert theirs through
+/// `ReceiveMsg::ConvertStakeToken`. Until then the CW20 supply stays part of the total supply.
+pub fn migrate_to_token_factory(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    subdenom: String,
+) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    let legacy = match state.load_stake_token(deps.storage)? {
+        StakeToken::Cw20(addr) => addr,
+        StakeToken::TokenFactory {
+            ..
+        } => return Err(ContractError::AlreadyTokenFactory {}),
+    };
+
+    let denom = token_factory_denom(env.contract.address.as_str(), &subdenom);
+    state.stake_denom.save(deps.storage, &denom)?;
+    let stake_token = state.load_stake_token(deps.storage)?;
+
+    let mut msgs = vec![
+        MsgCreateDenom {
+            sender: env.contract.address.to_string(),
+            subdenom,
+        }
+        .to_cosmos_msg(),
+        MsgSetBeforeSendHook {
+            sender: env.contract.address.to_string(),
+            denom: denom.clone(),
+            cosmwasm_address: env.contract.address.to_string(),
+        }
+        .to_cosmos_msg(),
+    ];
+
+    let ustake_to_convert = state.pending_batch.load(deps.storage)?.ustake_to_burn;
+    if !ustake_to_convert.is_zero() {
+        msgs.push(burn_cw20_msg(&legacy, ustake_to_convert)?);
+        msgs.push(stake_token.mint_msg(
+            &env.contract.address,
+            &env.contract.address,
+            ustake_to_convert,
+        )?);
+    }
+
+    let event = Event::new("erishub/migrated_to_token_factory")
+        .add_attribute("legacy_stake_token", legacy)
+        .add_attribute("stake_token", denom)
+        .add_attribute("ustake_converted", ustake_to_convert);
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_event(event)
+        .add_attribute("action", "erishub/migrate_to_token_factory"))
+}
+
+/// Burns legacy CW20 stake tokens sent to the hub and mints the same amount of the token-factory
+/// denom to `receiver`
+pub fn convert_stake_token(
+    deps: DepsMut,
+    env: Env,
+    receiver: Addr,
+    ustake_to_convert: Uint128,
+) -> ContractResult {
+    let state = State::default();
+    let stake_token = state.load_stake_token(deps.storage)?;
+    let legacy = stake_token.legacy().ok_or(ContractError::NotTokenFactory {})?;
+
+    let burn_msg = burn_cw20_msg(legacy, ustake_to_convert)?;
+    let mint_msg = stake_token.mint_msg(&env.contract.address, &receiver, ustake_to_convert)?;
+
+    let event = Event::new("erishub/stake_token_converted")
+        .add_attribute("receiver", receiver)
+        .add_attribute("ustake_converted", ustake_to_convert);
+
+    Ok(Response::new()
+        .add_message(burn_msg)
+        .add_message(mint_msg)
+        .add_event(event)
+        .add_attribute("action", "erishub/convert_stake_token"))
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
@@ -860,12 +1927,21 @@ pub fn update_config(
     vote_operator: Option<String>,
     epoch_period: Option<u64>,
     unbond_period: Option<u64>,
+    instant_unbond: Option<InstantUnbondConfig>,
+    validator_scoring: Option<ValidatorScoringConfig>,
+    unbond_receipt_nft: Option<String>,
+    fee_receivers: Option<Vec<FeeReceiver<String>>>,
+    referral_fee: Option<Decimal>,
+    guardian: Option<String>,
+    timelock_delay_s: Option<u64>,
+    keeper_tips: Option<KeeperTipConfig>,
+    prop_gauges: Option<String>,
 ) -> ContractResult {
     let state = State::default();
 
     state.assert_owner(deps.storage, &sender)?;
 
-    if protocol_fee_contract.is_some() || protocol_reward_fee.is_some() {
+    if protocol_fee_contract.is_some() || protocol_reward_fee.is_some() || fee_receivers.is_some() {
         let mut fee_config = state.fee_config.load(deps.storage)?;
 
         if let Some(protocol_fee_contract) = protocol_fee_contract {
@@ -879,6 +1955,18 @@ pub fn update_config(
             fee_config.protocol_reward_fee = protocol_reward_fee;
         }
 
+        if let Some(fee_receivers) = fee_receivers {
+            // the shares of all receivers can't exceed the protocol fee
+            fee_receivers.iter().try_fold(BasicPoints::zero(), |sum, receiver| {
+                sum.checked_add(BasicPoints::try_from(receiver.share_bps)?)
+            })?;
+
+            fee_config.fee_receivers = fee_receivers
+                .into_iter()
+                .map(|receiver| receiver.validate(deps.api))
+                .collect::<StdResult<_>>()?;
+        }
+
         state.fee_config.save(deps.storage, &fee_config)?;
     }
 
@@ -908,5 +1996,136 @@ pub fn update_config(
         state.vote_operator.save(deps.storage, &deps.api.addr_validate(&vote_operator)?)?;
     }
 
+    if let Some(instant_unbond) = instant_unbond {
+        if instant_unbond.min_fee > instant_unbond.max_fee
+            || instant_unbond.max_fee > get_instant_unbond_fee_cap()
+        {
+            return Err(ContractError::InstantUnbondFeeInvalid {});
+        }
+        state.instant_unbond_config.save(deps.storage, &instant_unbond)?;
+    }
+
+    if let Some(validator_scoring) = validator_scoring {
+        if validator_scoring.max_commission > Decimal::one()
+            || validator_scoring.slashing_penalty_bps > BasicPoints::MAX
+        {
+            return Err(ContractError::ValidatorScoringInvalid {});
+        }
+        state.validator_scoring.save(deps.storage, &validator_scoring)?;
+    }
+
+    if let Some(referral_fee) = referral_fee {
+        if referral_fee > Decimal::one() {
+            return Err(ContractError::ReferralFeeTooHigh {});
+        }
+        state.referral_fee.save(deps.storage, &referral_fee)?;
+    }
+
+    if let Some(guardian) = guardian {
+        state.guardian.save(deps.storage, &deps.api.addr_validate(&guardian)?)?;
+    }
+
+    if let Some(unbond_receipt_nft) = unbond_receipt_nft {
+        let unbond_receipt_nft = deps.api.addr_validate(&unbond_receipt_nft)?;
+        let current = state.unbond_receipt_nft.may_load(deps.storage)?;
+        // receipts can only be withdrawn through the collection they were minted on
+        if current.as_ref() != Some(&unbond_receipt_nft)
+            && state
+                .unbond_receipts
+                .keys(deps.storage, None, None, Order::Ascending)
+                .next()
+                .is_some()
+        {
+            return Err(ContractError::UnbondReceiptsOutstanding {});
+        }
+        state.unbond_receipt_nft.save(deps.storage, &unbond_receipt_nft)?;
+    }
+
+    if let Some(timelock_delay_s) = timelock_delay_s {
+        state.timelock.set_delay(deps.storage, timelock_delay_s)?;
+    }
+
+    if let Some(keeper_tips) = keeper_tips {
+        state.keeper_tips.save(deps.storage, &keeper_tips)?;
+    }
+
+    if let Some(prop_gauges) = prop_gauges {
+        state.prop_gauges.save(deps.storage, &deps.api.addr_validate(&prop_gauges)?)?;
+    }
+
     Ok(Response::new().add_attribute("action", "erishub/update_config"))
 }
+
+/// Queues a config update of the owner. It can be executed by anyone once the timelock delay has
+/// elapsed, giving stakers the time to exit before it lands.
+pub fn propose_timelocked_action(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    action: ExecuteMsg,
+) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    let pending = state.timelock.propose(deps.storage, env.block.time.seconds(), action)?;
+
+    let event = Event::new("erishub/action_proposed")
+        .add_attribute("id", pending.id.to_string())
+        .add_attribute("executable_at", pending.executable_at.to_string());
+
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("action", "erishub/propose_timelocked_action"))
+}
+
+pub fn cancel_timelocked_action(deps: DepsMut, sender: Addr, id: u64) -> ContractResult {
+    let state = State::default();
+
+    state.assert_owner(deps.storage, &sender)?;
+
+    state.timelock.cancel(deps.storage, id)?;
+
+    let event = Event::new("erishub/action_cancelled").add_attribute("id", id.to_string());
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erishub/cancel_timelocked_action"))
+}
+
+/// Pauses or unpauses `operations`. Only the owner can unpause them again.
+pub fn set_pause(
+    deps: DepsMut,
+    sender: Addr,
+    operations: Vec<PauseOperation>,
+    paused: bool,
+) -> ContractResult {
+    let state = State::default();
+
+    if paused {
+        state
+            .assert_owner(deps.storage, &sender)
+            .or_else(|_| state.assert_guardian(deps.storage, &sender))?;
+    } else {
+        state.assert_owner(deps.storage, &sender)?;
+    }
+
+    let mut paused_operations = state.paused_operations.may_load(deps.storage)?.unwrap_or_default();
+    if paused {
+        for operation in &operations {
+            if !paused_operations.contains(operation) {
+                paused_operations.push(*operation);
+            }
+        }
+    } else {
+        paused_operations.retain(|operation| !operations.contains(operation));
+    }
+    state.paused_operations.save(deps.storage, &paused_operations)?;
+
+    let event = Event::new("erishub/pause_updated")
+        .add_attribute(
+            "operations",
+            operations.iter().map(|operation| operation.to_string()).collect::<Vec<_>>().join(","),
+        )
+        .add_attribute("paused", paused.to_string());
+
+    Ok(Response::new().add_event(event).add_attribute("action", "erishub/set_pause"))
+}
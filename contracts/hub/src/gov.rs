@@ -1,11 +1,14 @@
 use cosmwasm_std::{
-    CosmosMsg, Decimal, DepsMut, Env, Event, Fraction, GovMsg, MessageInfo, Response,
+    Addr, CosmosMsg, Decimal, DepsMut, Env, Event, Fraction, GovMsg, MessageInfo, Response,
+};
+use eris::prop_gauges::{
+    ConfigResponse as PropGaugesConfigResponse, PropDetailResponse, QueryMsg as PropGaugesQueryMsg,
 };
 use itertools::Itertools;
 use protobuf::SpecialFields;
 
 use crate::{
-    error::ContractResult,
+    error::{ContractError, ContractResult},
     protos::proto::{MsgVoteWeighted, VoteOption, WeightedVoteOption},
     state::State,
 };
@@ -42,9 +45,68 @@ pub fn vote_weighted(
 
     let event = Event::new("erishub/voted_weighted").add_attribute("prop", proposal_id.to_string());
 
-    let vote = MsgVoteWeighted {
+    let vote = weighted_vote_msg(&_env.contract.address, proposal_id, votes);
+
+    Ok(Response::new()
+        .add_message(vote)
+        .add_event(event)
+        .add_attribute("action", "erishub/vote_weighted"))
+}
+
+/// Casts the vote decided by the vAMP holders in prop_gauges, with the weights of the hub's vote
+/// mirroring their distribution. Anyone can trigger it once the vote ended with a quorum.
+pub fn cast_prop_vote(deps: DepsMut, env: Env, proposal_id: u64) -> ContractResult {
+    let state = State::default();
+    let prop_gauges =
+        state.prop_gauges.may_load(deps.storage)?.ok_or(ContractError::NoPropGaugesSet {})?;
+
+    let config: PropGaugesConfigResponse =
+        deps.querier.query_wasm_smart(&prop_gauges, &PropGaugesQueryMsg::Config {})?;
+    let prop = deps
+        .querier
+        .query_wasm_smart::<PropDetailResponse>(
+            &prop_gauges,
+            &PropGaugesQueryMsg::PropDetail {
+                user: None,
+                proposal_id,
+            },
+        )?
+        .prop;
+
+    if env.block.time.seconds() < prop.end_time_s {
+        return Err(ContractError::PropVotingNotEnded(proposal_id));
+    }
+
+    if prop.total_vp.is_zero() || !prop.reached_quorum(prop.total_vp, config.quorum_bps)? {
+        return Err(ContractError::PropQuorumNotReached(proposal_id));
+    }
+
+    let votes = prop.get_weighted_votes();
+    if votes.is_empty() {
+        return Err(ContractError::PropQuorumNotReached(proposal_id));
+    }
+
+    let event = Event::new("erishub/prop_vote_cast")
+        .add_attribute("prop", proposal_id.to_string())
+        .add_attribute("voted_vp", prop.voted_vp().to_string())
+        .add_attribute("total_vp", prop.total_vp.to_string());
+
+    let vote = weighted_vote_msg(&env.contract.address, proposal_id, votes);
+
+    Ok(Response::new()
+        .add_message(vote)
+        .add_event(event)
+        .add_attribute("action", "erishub/cast_prop_vote"))
+}
+
+fn weighted_vote_msg(
+    voter: &Addr,
+    proposal_id: u64,
+    votes: Vec<(Decimal, cosmwasm_std::VoteOption)>,
+) -> CosmosMsg {
+    MsgVoteWeighted {
         proposal_id,
-        voter: _env.contract.address.to_string(),
+        voter: voter.to_string(),
         options: votes
             .into_iter()
             .map(|vote| WeightedVoteOption {
@@ -61,12 +123,6 @@ pub fn vote_weighted(
             })
             .collect_vec(),
         special_fields: SpecialFields::default(),
-    };
-
-    let vote = vote.to_cosmos_msg();
-
-    Ok(Response::new()
-        .add_message(vote)
-        .add_event(event)
-        .add_attribute("action", "erishub/vote_weighted"))
+    }
+    .to_cosmos_msg()
 }
@@ -4,10 +4,10 @@ use std::{
 };
 
 use cosmwasm_std::{
-    Addr, Coin, Decimal, Env, QuerierWrapper, QueryRequest, StakingQuery, StdError, StdResult,
-    Storage, Uint128, ValidatorResponse,
+    to_json_vec, Addr, Coin, ContractResult, Decimal, Empty, Env, QuerierWrapper, QueryRequest,
+    StakingQuery, StdError, StdResult, Storage, SystemResult, Uint128, ValidatorResponse,
 };
-use cw20::{Cw20QueryMsg, TokenInfoResponse};
+use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 use eris::{
     governance_helper::get_period,
     helpers::bps::BasicPoints,
@@ -16,7 +16,11 @@ use eris::{
 use itertools::Itertools;
 
 use crate::{
-    constants::CONTRACT_DENOM,
+    constants::SLASHING_PENALTY_PERIOD,
+    protos::staking::{
+        decode_unbonding_delegation_response, QueryUnbondingDelegationRequest,
+        UnbondingDelegationEntry, UNBONDING_DELEGATION_PATH,
+    },
     state::State,
     types::{gauges::GaugeLoader, Delegation},
 };
@@ -31,6 +35,21 @@ pub(crate) fn query_cw20_total_supply(
     Ok(token_info.total_supply)
 }
 
+/// Query the CW20 token balance of `account`
+pub(crate) fn query_cw20_balance(
+    querier: &QuerierWrapper,
+    token_addr: &Addr,
+    account: &Addr,
+) -> StdResult<Uint128> {
+    let res: BalanceResponse = querier.query_wasm_smart(
+        token_addr,
+        &Cw20QueryMsg::Balance {
+            address: account.to_string(),
+        },
+    )?;
+    Ok(res.balance)
+}
+
 /// Query the amounts of Luna a staker is delegating to a specific validator
 pub(crate) fn query_delegation(
     querier: &QuerierWrapper,
@@ -58,14 +77,21 @@ pub(crate) fn query_delegations(
         .collect()
 }
 
+/// Query the validators that are currently bonded. Jailed, tombstoned or unbonded validators are
+/// not part of the active set.
+pub(crate) fn query_active_validators(querier: &QuerierWrapper) -> StdResult<HashSet<String>> {
+    Ok(querier.query_all_validators()?.into_iter().map(|v| v.address).collect())
+}
+
 pub(crate) fn query_all_delegations(
     querier: &QuerierWrapper,
     delegator_addr: &Addr,
+    denom: &str,
 ) -> StdResult<Vec<Delegation>> {
     let result: Vec<_> = querier
         .query_all_delegations(delegator_addr)?
         .into_iter()
-        .filter(|d| d.amount.denom == CONTRACT_DENOM && !d.amount.amount.is_zero())
+        .filter(|d| d.amount.denom == denom && !d.amount.amount.is_zero())
         .map(|d| Delegation {
             validator: d.validator,
             amount: d.amount.amount.u128(),
@@ -75,6 +101,35 @@ pub(crate) fn query_all_delegations(
     Ok(result)
 }
 
+/// Query the unbonding entries of a delegator with a validator. The stargate query needs to be
+/// whitelisted by the chain and returns the protobuf encoded response.
+pub(crate) fn query_unbonding_entries(
+    querier: &QuerierWrapper,
+    delegator_addr: &Addr,
+    validator: &str,
+) -> StdResult<Vec<UnbondingDelegationEntry>> {
+    let request: QueryRequest<Empty> = QueryRequest::Stargate {
+        path: UNBONDING_DELEGATION_PATH.to_string(),
+        data: QueryUnbondingDelegationRequest {
+            delegator_addr: delegator_addr.to_string(),
+            validator_addr: validator.to_string(),
+        }
+        .to_binary(),
+    };
+
+    let response = match querier.raw_query(&to_json_vec(&request)?) {
+        SystemResult::Err(system_err) => {
+            return Err(StdError::generic_err(format!("Querier system error: {}", system_err)))
+        },
+        SystemResult::Ok(ContractResult::Err(contract_err)) => {
+            return Err(StdError::generic_err(format!("Querier contract error: {}", contract_err)))
+        },
+        SystemResult::Ok(ContractResult::Ok(value)) => value,
+    };
+
+    decode_unbonding_delegation_response(response.as_slice())
+}
+
 /// Find the amount of a denom sent along a message, assert it is non-zero, and no other denom were
 /// sent together
 pub(crate) fn parse_received_fund(funds: &[Coin], denom: &str) -> StdResult<Uint128> {
@@ -138,6 +193,19 @@ pub(crate) fn get_wanted_delegations(
     let delegation_strategy =
         state.delegation_strategy.may_load(storage)?.unwrap_or(DelegationStrategy::Uniform {});
 
+    compute_wanted_delegations(state, env, storage, querier, loader, delegation_strategy)
+}
+
+/// Calculates the wanted delegations of the provided delegation strategy, which does not need to
+/// be the stored one. Used to preview a strategy before it is applied.
+pub(crate) fn compute_wanted_delegations(
+    state: &State,
+    env: &Env,
+    storage: &dyn Storage,
+    querier: &QuerierWrapper,
+    loader: impl GaugeLoader,
+    delegation_strategy: DelegationStrategy<Addr>,
+) -> StdResult<(WantedDelegationsShare, bool)> {
     match delegation_strategy {
         DelegationStrategy::Uniform {} => {
             let validators = state.validators.load(storage)?;
@@ -164,10 +232,30 @@ pub(crate) fn get_wanted_delegations(
             min_delegation_bps,
             max_delegation_bps,
             validator_count,
+            slashing_penalty_bps,
         } => {
             let min_delegation = BasicPoints::try_from(min_delegation_bps)?.decimal();
             let max_delegation = BasicPoints::try_from(max_delegation_bps)?.decimal();
 
+            // with validator scoring the scores are applied to the unclamped shares when delegating,
+            // followed by the min and max delegation. Scoring also takes care of slashing penalties.
+            let validator_scoring = state.validator_scoring.may_load(storage)?.is_some();
+
+            // validators that caused a slashing event within the penalty period only keep part of
+            // their score
+            let slashing_penalty = if validator_scoring {
+                None
+            } else if let Some(slashing_penalty_bps) = slashing_penalty_bps {
+                let penalty_start =
+                    env.block.time.seconds().saturating_sub(SLASHING_PENALTY_PERIOD);
+                Some((
+                    BasicPoints::try_from(slashing_penalty_bps)?.reverse().decimal(),
+                    state.slashed_validators_since(storage, penalty_start)?,
+                ))
+            } else {
+                None
+            };
+
             let vamp_factor = BasicPoints::try_from(amp_factor_bps)?.decimal();
             let emp_factor = Decimal::one().checked_sub(vamp_factor)?;
 
@@ -194,13 +282,23 @@ pub(crate) fn get_wanted_delegations(
                         Decimal::from_ratio(vamp, vamp_context.sum)
                     };
 
-                    let score = Decimal::min(total_share, max_delegation);
+                    let mut score = if validator_scoring {
+                        total_share
+                    } else {
+                        Decimal::min(total_share, max_delegation)
+                    };
+
+                    if let Some((remaining, slashed_validators)) = &slashing_penalty {
+                        if slashed_validators.contains(&val) {
+                            score = score.checked_mul(*remaining)?;
+                        }
+                    }
 
                     Ok((val, score, total_share))
                 })
                 .collect::<StdResult<Vec<_>>>()?
                 .into_iter()
-                .filter(|(_, amount, _)| *amount > min_delegation)
+                .filter(|(_, amount, _)| validator_scoring || *amount > min_delegation)
                 .sorted_by(|(_, _, a), (_, _, b)| b.cmp(a)) // Sort in descending order
                 .take(validator_count.into())
                 .collect();
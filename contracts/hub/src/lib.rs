@@ -7,6 +7,7 @@ pub mod gov;
 pub mod helpers;
 pub mod math;
 pub mod queries;
+pub mod scoring;
 pub mod state;
 pub mod types;
 
@@ -2,12 +2,16 @@ use std::{
     cmp,
     cmp::Ordering,
     collections::{HashMap, HashSet},
+    convert::TryFrom,
 };
 
-use cosmwasm_std::{Addr, QuerierWrapper, StdResult, Storage, Uint128};
+use cosmwasm_std::{Addr, Decimal, Order, QuerierWrapper, StdError, StdResult, Storage, Uint128};
 
 use eris::{
-    hub::{Batch, WantedDelegationsShare},
+    helpers::bps::BasicPoints,
+    hub::{
+        Batch, DelegationStrategy, InstantUnbondConfig, KeeperTipConfig, WantedDelegationsShare,
+    },
     DecimalCheckedOps,
 };
 
@@ -25,40 +29,134 @@ type UtokenPerValidator =
 // Minting/burning logics
 //--------------------------------------------------------------------------------------------------
 
-/// Compute the amount of Stake token to mint for a specific Luna stake amount. If current total
-/// staked amount is zero, we use 1 ustake = 1 uluna; otherwise, we calculate base on the current
-/// uluna per ustake ratio.
-pub(crate) fn compute_mint_amount(
+/// Compute the Stake token supply and the `utoken` backing it.
+///
+/// The instant unbond buffer is funded from the rewards of the holders, so it backs the Stake token
+/// next to the delegations. Stake tokens the hub took over through instant unbonds were bought back
+/// by the buffer: while they wait in the pending batch they are not part of the supply, and once
+/// their batch is submitted the unbonding `utoken` count toward the backing until reconciling moves
+/// them into the buffer.
+pub(crate) fn compute_backing(
+    state: &State,
+    storage: &dyn Storage,
+    contract: &Addr,
     ustake_supply: Uint128,
-    uluna_to_bond: Uint128,
     current_delegations: &[Delegation],
+) -> StdResult<(Uint128, Uint128)> {
+    let utoken_bonded: u128 = current_delegations.iter().map(|d| d.amount).sum();
+    let buffer = state.instant_unbond_buffer.may_load(storage)?.unwrap_or_default();
+
+    let pending_batch = state.pending_batch.load(storage)?;
+    let ustake_bought_back = state
+        .unbond_requests
+        .may_load(storage, (pending_batch.id, contract))?
+        .map(|request| request.shares)
+        .unwrap_or_default();
+
+    let batches = state
+        .previous_batches
+        .idx
+        .reconciled
+        .prefix(false.into())
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, batch)| batch))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut utoken_unbonding = Uint128::zero();
+    for batch in batches {
+        if let Some(request) = state.unbond_requests.may_load(storage, (batch.id, contract))? {
+            utoken_unbonding +=
+                batch.utoken_unclaimed.multiply_ratio(request.shares, batch.total_shares);
+        }
+    }
+
+    let ustake_outstanding = ustake_supply.checked_sub(ustake_bought_back)?;
+    let utoken_backing =
+        Uint128::new(utoken_bonded).checked_add(buffer)?.checked_add(utoken_unbonding)?;
+
+    Ok((ustake_outstanding, utoken_backing))
+}
+
+/// Compute the amount of Stake token to mint for a specific Luna stake amount. If the backing is
+/// zero, we use 1 ustake = 1 utoken; otherwise, we calculate base on the current utoken per ustake
+/// ratio.
+pub(crate) fn compute_mint_amount(
+    ustake_supply: Uint128,
+    utoken_to_bond: Uint128,
+    utoken_backing: Uint128,
 ) -> Uint128 {
-    let uluna_bonded: u128 = current_delegations.iter().map(|d| d.amount).sum();
-    if uluna_bonded == 0 {
-        uluna_to_bond
+    if utoken_backing.is_zero() {
+        utoken_to_bond
     } else {
-        ustake_supply.multiply_ratio(uluna_to_bond, uluna_bonded)
+        ustake_supply.multiply_ratio(utoken_to_bond, utoken_backing)
     }
 }
 
-/// Compute the amount of `uluna` to unbond for a specific `ustake` burn amount
+/// Compute the amount of `utoken` to unbond for a specific `ustake` burn amount
 ///
 /// There is no way `ustake` total supply is zero when the user is senting a non-zero amount of `ustake`
 /// to burn, so we don't need to handle division-by-zero here
 pub(crate) fn compute_unbond_amount(
     ustake_supply: Uint128,
     ustake_to_burn: Uint128,
-    current_delegations: &[Delegation],
+    utoken_backing: Uint128,
 ) -> Uint128 {
-    let uluna_bonded: u128 = current_delegations.iter().map(|d| d.amount).sum();
-    Uint128::new(uluna_bonded).multiply_ratio(ustake_to_burn, ustake_supply)
+    utoken_backing.multiply_ratio(ustake_to_burn, ustake_supply)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Instant unbond logics
+//--------------------------------------------------------------------------------------------------
+
+/// Compute the utilization of the instant unbond buffer. An empty buffer (or a zero target) is fully
+/// utilized, a buffer at or above its target is not utilized at all.
+pub(crate) fn compute_buffer_utilization(buffer: Uint128, buffer_target: Uint128) -> Decimal {
+    if buffer_target.is_zero() {
+        return Decimal::one();
+    }
+
+    Decimal::one() - Decimal::from_ratio(cmp::min(buffer, buffer_target), buffer_target)
+}
+
+/// Compute the fee for instantly unbonding `utoken_to_unbond` against the buffer. The fee scales
+/// linearly between `min_fee` and `max_fee` based on the utilization after the unbond.
+pub(crate) fn compute_instant_unbond_fee(
+    config: &InstantUnbondConfig,
+    buffer: Uint128,
+    utoken_to_unbond: Uint128,
+) -> StdResult<Decimal> {
+    let utilization =
+        compute_buffer_utilization(buffer.saturating_sub(utoken_to_unbond), config.buffer_target);
+    let fee_range = config.max_fee.checked_sub(config.min_fee)?;
+
+    Ok(config.min_fee.checked_add(fee_range.checked_mul(utilization)?)?)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Keeper tip logics
+//--------------------------------------------------------------------------------------------------
+
+/// Compute the tip for executing an action that is due since `due_since`. Only actions that are
+/// overdue by more than the grace period are tipped, and the tip can't exceed the tip pool.
+pub(crate) fn compute_keeper_tip(
+    config: Option<&KeeperTipConfig>,
+    tip_pool: Uint128,
+    current_time: u64,
+    due_since: u64,
+) -> Uint128 {
+    match config {
+        Some(config) if current_time >= due_since.saturating_add(config.grace_period_s) => {
+            cmp::min(config.tip, tip_pool)
+        },
+        _ => Uint128::zero(),
+    }
 }
 
 //--------------------------------------------------------------------------------------------------
 // Delegation logics
 //--------------------------------------------------------------------------------------------------
 
-/// Given the current delegations made to validators, and a specific amount of `uluna` to unstake,
+/// Given the current delegations made to validators, and a specific amount of `utoken` to unstake,
 /// compute the undelegations to make such that the delegated amount to each validator is as even
 /// as possible.
 ///
@@ -67,44 +165,44 @@ pub(crate) fn compute_unbond_amount(
 pub(crate) fn compute_undelegations(
     state: &State,
     storage: &dyn Storage,
-    uluna_to_unbond: Uint128,
+    utoken_to_unbond: Uint128,
     current_delegations: &[Delegation],
     validators: Vec<String>,
 ) -> Result<Vec<Undelegation>, ContractError> {
-    let uluna_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
-    let uluna_to_distribute = uluna_staked - uluna_to_unbond.u128();
+    let utoken_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
+    let utoken_to_distribute = utoken_staked - utoken_to_unbond.u128();
 
-    let (uluna_per_validator, mut add, mut remove, _) =
-        get_uluna_per_validator(state, storage, uluna_to_distribute, &validators, None)?;
+    let (utoken_per_validator, mut add, mut remove, _) =
+        get_utoken_per_validator(state, storage, utoken_to_distribute, &validators, None)?;
 
     let mut new_undelegations: Vec<Undelegation> = vec![];
-    let mut uluna_available = uluna_to_unbond.u128();
+    let mut utoken_available = utoken_to_unbond.u128();
     for d in merge_with_validators(current_delegations, validators).iter() {
-        let uluna_for_validator =
-            get_uluna_for_validator(&uluna_per_validator, d, &mut add, &mut remove);
+        let utoken_for_validator =
+            get_utoken_for_validator(&utoken_per_validator, d, &mut add, &mut remove);
 
-        let mut uluna_to_undelegate = if d.amount < uluna_for_validator {
+        let mut utoken_to_undelegate = if d.amount < utoken_for_validator {
             0
         } else {
-            d.amount - uluna_for_validator
+            d.amount - utoken_for_validator
         };
 
-        if uluna_to_undelegate > 0 {
-            uluna_to_undelegate = std::cmp::min(uluna_to_undelegate, uluna_available);
-            uluna_available -= uluna_to_undelegate;
+        if utoken_to_undelegate > 0 {
+            utoken_to_undelegate = std::cmp::min(utoken_to_undelegate, utoken_available);
+            utoken_available -= utoken_to_undelegate;
 
-            if uluna_to_undelegate > 0 {
-                new_undelegations.push(Undelegation::new(&d.validator, uluna_to_undelegate));
+            if utoken_to_undelegate > 0 {
+                new_undelegations.push(Undelegation::new(&d.validator, utoken_to_undelegate));
             }
 
-            if uluna_available == 0 {
+            if utoken_available == 0 {
                 break;
             }
         }
     }
 
-    if uluna_available > 0 {
-        Err(ContractError::ComputeUndelegationsWrong(uluna_available))?
+    if utoken_available > 0 {
+        Err(ContractError::ComputeUndelegationsWrong(utoken_available))?
     }
 
     Ok(new_undelegations)
@@ -123,36 +221,36 @@ pub(crate) fn compute_redelegations_for_removal(
     current_delegations: &[Delegation],
     validators: Vec<String>,
 ) -> StdResult<Vec<Redelegation>> {
-    let uluna_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
-    let uluna_to_distribute = uluna_staked + delegation_to_remove.amount;
+    let utoken_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
+    let utoken_to_distribute = utoken_staked + delegation_to_remove.amount;
 
-    let (uluna_per_validator, mut add, mut remove, _) =
-        get_uluna_per_validator(state, storage, uluna_to_distribute, &validators, None)?;
+    let (utoken_per_validator, mut add, mut remove, _) =
+        get_utoken_per_validator(state, storage, utoken_to_distribute, &validators, None)?;
 
     let mut new_redelegations: Vec<Redelegation> = vec![];
-    let mut uluna_available = delegation_to_remove.amount;
+    let mut utoken_available = delegation_to_remove.amount;
     for d in merge_with_validators(current_delegations, validators).iter() {
-        let uluna_for_validator =
-            get_uluna_for_validator(&uluna_per_validator, d, &mut add, &mut remove);
+        let utoken_for_validator =
+            get_utoken_for_validator(&utoken_per_validator, d, &mut add, &mut remove);
 
-        let mut uluna_to_redelegate = if d.amount > uluna_for_validator {
+        let mut utoken_to_redelegate = if d.amount > utoken_for_validator {
             0
         } else {
-            uluna_for_validator - d.amount
+            utoken_for_validator - d.amount
         };
 
-        uluna_to_redelegate = std::cmp::min(uluna_to_redelegate, uluna_available);
-        uluna_available -= uluna_to_redelegate;
+        utoken_to_redelegate = std::cmp::min(utoken_to_redelegate, utoken_available);
+        utoken_available -= utoken_to_redelegate;
 
-        if uluna_to_redelegate > 0 {
+        if utoken_to_redelegate > 0 {
             new_redelegations.push(Redelegation::new(
                 &delegation_to_remove.validator,
                 &d.validator,
-                uluna_to_redelegate,
+                utoken_to_redelegate,
             ));
         }
 
-        if uluna_available == 0 {
+        if utoken_available == 0 {
             break;
         }
     }
@@ -181,25 +279,25 @@ fn merge_with_validators(
     delegations
 }
 
-fn get_uluna_for_validator(
-    uluna_per_validator: &HashMap<String, Uint128>,
+fn get_utoken_for_validator(
+    utoken_per_validator: &HashMap<String, Uint128>,
     delegation: &Delegation,
     add: &mut Option<u128>,
     remove: &mut Option<u128>,
 ) -> u128 {
-    let mut uluna_for_validator =
-        uluna_per_validator.get(&delegation.validator).map(|a| a.u128()).unwrap_or_default();
+    let mut utoken_for_validator =
+        utoken_per_validator.get(&delegation.validator).map(|a| a.u128()).unwrap_or_default();
     if let Some(add_set) = *add {
-        uluna_for_validator += add_set;
+        utoken_for_validator += add_set;
         *add = None;
     }
     if let Some(remove_set) = *remove {
-        if uluna_for_validator >= remove_set {
-            uluna_for_validator -= remove_set;
+        if utoken_for_validator >= remove_set {
+            utoken_for_validator -= remove_set;
             *remove = None;
         }
     }
-    uluna_for_validator
+    utoken_for_validator
 }
 
 /// Compute redelegation moves that will make each validator's delegation the targeted amount (hopefully
@@ -211,11 +309,25 @@ pub(crate) fn compute_redelegations_for_rebalancing(
     storage: &dyn Storage,
     current_delegations: &[Delegation],
     validators: Vec<String>,
+    current_time: u64,
 ) -> StdResult<Vec<Redelegation>> {
-    let uluna_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
+    let utoken_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
+
+    let targets = get_utoken_per_validator(state, storage, utoken_staked, &validators, None)?;
+    let locked = state.redelegation_locked_validators(storage, current_time)?;
+
+    Ok(compute_redelegations_for_targets(current_delegations, validators, targets, &locked))
+}
 
-    let (uluna_per_validator, mut add, mut remove, _) =
-        get_uluna_per_validator(state, storage, uluna_staked, &validators, None)?;
+/// Compute the redelegation moves from the current delegations to the targeted amounts per validator.
+/// Validators in `locked` received a redelegation recently and are not used as source.
+pub(crate) fn compute_redelegations_for_targets(
+    current_delegations: &[Delegation],
+    validators: Vec<String>,
+    targets: UtokenPerValidator,
+    locked: &HashSet<String>,
+) -> Vec<Redelegation> {
+    let (utoken_per_validator, mut add, mut remove, _) = targets;
 
     // If a validator's current delegated amount is greater than the target amount, Luna will be
     // redelegated _from_ them. They will be put in `src_validators` vector
@@ -224,15 +336,20 @@ pub(crate) fn compute_redelegations_for_rebalancing(
     let mut src_delegations: Vec<Delegation> = vec![];
     let mut dst_delegations: Vec<Delegation> = vec![];
     for d in merge_with_validators(current_delegations, validators).iter() {
-        let uluna_for_validator =
-            get_uluna_for_validator(&uluna_per_validator, d, &mut add, &mut remove);
+        let utoken_for_validator =
+            get_utoken_for_validator(&utoken_per_validator, d, &mut add, &mut remove);
 
-        match d.amount.cmp(&uluna_for_validator) {
+        match d.amount.cmp(&utoken_for_validator) {
             Ordering::Greater => {
-                src_delegations.push(Delegation::new(&d.validator, d.amount - uluna_for_validator));
+                // redelegating from a validator that received a redelegation fails until it matured
+                if !locked.contains(&d.validator) {
+                    src_delegations
+                        .push(Delegation::new(&d.validator, d.amount - utoken_for_validator));
+                }
             },
             Ordering::Less => {
-                dst_delegations.push(Delegation::new(&d.validator, uluna_for_validator - d.amount));
+                dst_delegations
+                    .push(Delegation::new(&d.validator, utoken_for_validator - d.amount));
             },
             Ordering::Equal => (),
         }
@@ -242,33 +359,33 @@ pub(crate) fn compute_redelegations_for_rebalancing(
     while !src_delegations.is_empty() && !dst_delegations.is_empty() {
         let src_delegation = src_delegations[0].clone();
         let dst_delegation = dst_delegations[0].clone();
-        let uluna_to_redelegate = cmp::min(src_delegation.amount, dst_delegation.amount);
+        let utoken_to_redelegate = cmp::min(src_delegation.amount, dst_delegation.amount);
 
-        if src_delegation.amount == uluna_to_redelegate {
+        if src_delegation.amount == utoken_to_redelegate {
             src_delegations.remove(0);
         } else {
-            src_delegations[0].amount -= uluna_to_redelegate;
+            src_delegations[0].amount -= utoken_to_redelegate;
         }
 
-        if dst_delegation.amount == uluna_to_redelegate {
+        if dst_delegation.amount == utoken_to_redelegate {
             dst_delegations.remove(0);
         } else {
-            dst_delegations[0].amount -= uluna_to_redelegate;
+            dst_delegations[0].amount -= utoken_to_redelegate;
         }
 
         new_redelegations.push(Redelegation::new(
             &src_delegation.validator,
             &dst_delegation.validator,
-            uluna_to_redelegate,
+            utoken_to_redelegate,
         ));
     }
 
-    Ok(new_redelegations)
+    new_redelegations
 }
 
-/// Load uluna per validator
+/// Load utoken per validator
 /// If no goal is provided, the stored goal or uniform distribution is used.
-pub(crate) fn get_uluna_per_validator_prepared(
+pub(crate) fn get_utoken_per_validator_prepared(
     state: &State,
     storage: &dyn Storage,
     querier: &QuerierWrapper,
@@ -276,43 +393,71 @@ pub(crate) fn get_uluna_per_validator_prepared(
     goal: Option<WantedDelegationsShare>,
     validators: Option<Vec<String>>,
 ) -> StdResult<UtokenPerValidator> {
-    let current_delegations = query_all_delegations(querier, contract)?;
-    let uluna_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
+    let denom = state.denom.load(storage)?;
+    let current_delegations = query_all_delegations(querier, contract, &denom)?;
+    let utoken_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
     let validators = if let Some(validators) = validators {
         validators
     } else {
         state.validators.load(storage)?
     };
 
-    get_uluna_per_validator(state, storage, uluna_staked, &validators, goal)
+    get_utoken_per_validator(state, storage, utoken_staked, &validators, goal)
 }
 
-pub(crate) fn get_uluna_per_validator(
+pub(crate) fn get_utoken_per_validator(
     state: &State,
     storage: &dyn Storage,
-    uluna_staked: u128,
+    utoken_staked: u128,
     validators: &[String],
     goal: Option<WantedDelegationsShare>,
 ) -> StdResult<UtokenPerValidator> {
-    let uluna_staked_uint = Uint128::new(uluna_staked);
+    let delegation_strategy =
+        state.delegation_strategy.may_load(storage)?.unwrap_or(DelegationStrategy::Uniform {});
+
+    get_utoken_per_validator_for_strategy(
+        state,
+        storage,
+        utoken_staked,
+        validators,
+        goal,
+        &delegation_strategy,
+    )
+}
+
+/// Same as `get_utoken_per_validator`, but applies the min and max delegation of the provided
+/// strategy instead of the stored one
+pub(crate) fn get_utoken_per_validator_for_strategy(
+    state: &State,
+    storage: &dyn Storage,
+    utoken_staked: u128,
+    validators: &[String],
+    goal: Option<WantedDelegationsShare>,
+    delegation_strategy: &DelegationStrategy<Addr>,
+) -> StdResult<UtokenPerValidator> {
+    let utoken_staked_uint = Uint128::new(utoken_staked);
     let delegation_goal = if goal.is_some() {
         goal
     } else {
         state.delegation_goal.may_load(storage)?
     };
 
-    let uluna_per_validator: Option<HashMap<_, _>> =
+    let utoken_per_validator: Option<HashMap<_, _>> =
         if let Some(delegation_goal) = delegation_goal.clone() {
             if !delegation_goal.shares.is_empty() {
                 // calculate via distribution
                 Some(
-                    delegation_goal
-                        .shares
-                        .into_iter()
-                        .map(|d| -> StdResult<(String, Uint128)> {
-                            Ok((d.0, d.1.checked_mul_uint(uluna_staked_uint)?))
-                        })
-                        .collect::<StdResult<HashMap<_, _>>>()?,
+                    apply_validator_scores(
+                        state,
+                        storage,
+                        delegation_strategy,
+                        delegation_goal.shares,
+                    )?
+                    .into_iter()
+                    .map(|d| -> StdResult<(String, Uint128)> {
+                        Ok((d.0, d.1.checked_mul_uint(utoken_staked_uint)?))
+                    })
+                    .collect::<StdResult<HashMap<_, _>>>()?,
                 )
             } else {
                 None
@@ -321,86 +466,156 @@ pub(crate) fn get_uluna_per_validator(
             None
         };
 
-    let uluna_per_validator = uluna_per_validator.unwrap_or_else(|| {
+    let utoken_per_validator = utoken_per_validator.unwrap_or_else(|| {
         let validator_count = validators.len() as u128;
-        let uluna_per_validator = uluna_staked / validator_count;
-        validators.iter().map(|d| (d.clone(), Uint128::new(uluna_per_validator))).collect()
+        let utoken_per_validator = utoken_staked / validator_count;
+        validators.iter().map(|d| (d.clone(), Uint128::new(utoken_per_validator))).collect()
     });
-    let total: u128 = uluna_per_validator.iter().map(|a| a.1.u128()).sum();
-    let add = if total < uluna_staked {
-        Some(uluna_staked - total)
+    let total: u128 = utoken_per_validator.iter().map(|a| a.1.u128()).sum();
+    let add = if total < utoken_staked {
+        Some(utoken_staked - total)
     } else {
         None
     };
-    let remove = if total > uluna_staked {
-        Some(total - uluna_staked)
+    let remove = if total > utoken_staked {
+        Some(total - utoken_staked)
     } else {
         None
     };
-    Ok((uluna_per_validator, add, remove, delegation_goal))
+    Ok((utoken_per_validator, add, remove, delegation_goal))
+}
+
+/// Weights the shares of the delegation goal by the current validator scores. With validator
+/// scoring the goal is not clamped, the min and max delegation of the gauges strategy are applied
+/// to the weighted shares before normalizing them.
+fn apply_validator_scores(
+    state: &State,
+    storage: &dyn Storage,
+    delegation_strategy: &DelegationStrategy<Addr>,
+    shares: Vec<(String, Decimal)>,
+) -> StdResult<Vec<(String, Decimal)>> {
+    if state.validator_scoring.may_load(storage)?.is_none() {
+        return Ok(shares);
+    }
+
+    let scores: HashMap<String, Decimal> =
+        state.validator_scores.may_load(storage)?.unwrap_or_default().into_iter().collect();
+
+    let (min_delegation, max_delegation) = match delegation_strategy {
+        DelegationStrategy::Gauges {
+            min_delegation_bps,
+            max_delegation_bps,
+            ..
+        } => (
+            BasicPoints::try_from(*min_delegation_bps)?.decimal(),
+            BasicPoints::try_from(*max_delegation_bps)?.decimal(),
+        ),
+        _ => return Ok(shares),
+    };
+
+    let mut weighted: Vec<(String, Decimal)> = vec![];
+    for (validator, share) in shares.iter() {
+        let score = scores.get(validator).copied().unwrap_or_else(Decimal::one);
+        let share = Decimal::min(share.checked_mul(score)?, max_delegation);
+        if share > min_delegation {
+            weighted.push((validator.clone(), share));
+        }
+    }
+
+    let total: Decimal = weighted.iter().map(|a| a.1).sum();
+    if total.is_zero() {
+        // keep delegating to the goal, if no validator qualifies
+        return Ok(shares);
+    }
+
+    weighted
+        .into_iter()
+        .map(|(validator, share)| -> StdResult<(String, Decimal)> {
+            let normalized = share
+                .checked_div(total)
+                .map_err(|_| StdError::generic_err("Could not divide by total"))?;
+            Ok((validator, normalized))
+        })
+        .collect()
 }
 
 //--------------------------------------------------------------------------------------------------
 // Batch logics
 //--------------------------------------------------------------------------------------------------
 
-/// If the received uluna amount after the unbonding period is less than expected, e.g. due to rounding
+/// Deducts the slashing recorded for each batch (same order as `batches`) from it, up to the total
+/// `utoken_to_deduct`. Returns the part that could not be attributed to a batch.
+pub(crate) fn deduct_batch_slashing(
+    batches: &mut [Batch],
+    batch_slashing: &[Uint128],
+    utoken_to_deduct: Uint128,
+) -> Uint128 {
+    let mut remaining = utoken_to_deduct;
+    for (batch, slashed) in batches.iter_mut().zip(batch_slashing) {
+        let deduct = (*slashed).min(batch.utoken_unclaimed).min(remaining);
+        batch.utoken_unclaimed -= deduct;
+        remaining -= deduct;
+    }
+    remaining
+}
+
+/// If the received utoken amount after the unbonding period is less than expected, e.g. due to rounding
 /// error or the validator(s) being slashed, then deduct the difference in amount evenly from each
 /// unreconciled batch.
 ///
 /// The idea of "reconciling" is based on Stader's implementation:
 /// https://github.com/stader-labs/stader-liquid-token/blob/v0.2.1/contracts/staking/src/contract.rs#L968-L1048
-pub(crate) fn reconcile_batches(batches: &mut [Batch], uluna_to_deduct: Uint128) {
+pub(crate) fn reconcile_batches(batches: &mut [Batch], utoken_to_deduct: Uint128) {
     let batch_count = batches.len() as u128;
-    let uluna_per_batch = uluna_to_deduct.u128() / batch_count;
-    let remainder = uluna_to_deduct.u128() % batch_count;
+    let utoken_per_batch = utoken_to_deduct.u128() / batch_count;
+    let remainder = utoken_to_deduct.u128() % batch_count;
     let mut underflows: HashMap<usize, Uint128> = HashMap::default();
 
     for (i, batch) in batches.iter_mut().enumerate() {
         let remainder_for_batch: u128 = u128::from((i + 1) as u128 <= remainder);
-        let uluna_for_batch = uluna_per_batch + remainder_for_batch;
-        let uluna_for_batch = Uint128::new(uluna_for_batch);
+        let utoken_for_batch = utoken_per_batch + remainder_for_batch;
+        let utoken_for_batch = Uint128::new(utoken_for_batch);
 
         // check for underflow
-        if batch.uluna_unclaimed < uluna_for_batch && batch_count > 1 {
-            underflows.insert(i, uluna_for_batch - batch.uluna_unclaimed);
+        if batch.utoken_unclaimed < utoken_for_batch && batch_count > 1 {
+            underflows.insert(i, utoken_for_batch - batch.utoken_unclaimed);
         }
 
-        batch.uluna_unclaimed = batch.uluna_unclaimed.saturating_sub(uluna_for_batch);
+        batch.utoken_unclaimed = batch.utoken_unclaimed.saturating_sub(utoken_for_batch);
         batch.reconciled = true;
     }
 
     if !underflows.is_empty() {
         let batch_count: u128 = batch_count - (underflows.len() as u128);
         let to_deduct: Uint128 = underflows.iter().map(|v| v.1).sum();
-        let uluna_per_batch = to_deduct.u128() / batch_count;
+        let utoken_per_batch = to_deduct.u128() / batch_count;
         let remainder = to_deduct.u128() % batch_count;
         let mut remaining_underflow = Uint128::zero();
         // distribute the underflows uniformly accross non-underflowing batches
         for (i, batch) in batches.iter_mut().enumerate() {
-            if !batch.uluna_unclaimed.is_zero() {
+            if !batch.utoken_unclaimed.is_zero() {
                 let remainder_for_batch: u128 = u128::from((i + 1) as u128 <= remainder);
-                let uluna_for_batch = uluna_per_batch + remainder_for_batch;
-                let uluna_for_batch = Uint128::new(uluna_for_batch);
+                let utoken_for_batch = utoken_per_batch + remainder_for_batch;
+                let utoken_for_batch = Uint128::new(utoken_for_batch);
 
-                if batch.uluna_unclaimed < uluna_for_batch && batch_count > 1 {
-                    remaining_underflow += uluna_for_batch - batch.uluna_unclaimed;
+                if batch.utoken_unclaimed < utoken_for_batch && batch_count > 1 {
+                    remaining_underflow += utoken_for_batch - batch.utoken_unclaimed;
                 }
 
-                batch.uluna_unclaimed = batch.uluna_unclaimed.saturating_sub(uluna_for_batch);
+                batch.utoken_unclaimed = batch.utoken_unclaimed.saturating_sub(utoken_for_batch);
             }
         }
 
         if !remaining_underflow.is_zero() {
             // the remaining underflow will be applied by oldest batch first.
             for batch in batches.iter_mut() {
-                if !batch.uluna_unclaimed.is_zero() && !remaining_underflow.is_zero() {
-                    if batch.uluna_unclaimed >= remaining_underflow {
-                        batch.uluna_unclaimed -= remaining_underflow;
+                if !batch.utoken_unclaimed.is_zero() && !remaining_underflow.is_zero() {
+                    if batch.utoken_unclaimed >= remaining_underflow {
+                        batch.utoken_unclaimed -= remaining_underflow;
                         remaining_underflow = Uint128::zero()
                     } else {
-                        remaining_underflow -= batch.uluna_unclaimed;
-                        batch.uluna_unclaimed = Uint128::zero();
+                        remaining_underflow -= batch.utoken_unclaimed;
+                        batch.utoken_unclaimed = Uint128::zero();
                     }
                 }
             }
@@ -418,3 +633,17 @@ pub(crate) fn mark_reconciled_batches(batches: &mut [Batch]) {
         batch.reconciled = true;
     }
 }
+
+/// Compute the amount of `utoken` that can be withdrawn for `shares` of a batch. Returns `None` if
+/// the batch is not reconciled or has not finished unbonding yet.
+pub(crate) fn compute_withdrawable_amount(
+    batch: &Batch,
+    shares: Uint128,
+    current_time: u64,
+) -> Option<Uint128> {
+    if batch.reconciled && batch.est_unbond_end_time < current_time {
+        Some(batch.utoken_unclaimed.multiply_ratio(shares, batch.total_shares))
+    } else {
+        None
+    }
+}
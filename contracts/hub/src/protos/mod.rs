@@ -1,9 +1,11 @@
 use cosmwasm_std::{Binary, CosmosMsg};
-use protobuf::Message;
+use protobuf::{CodedOutputStream, Message};
 
 use self::proto::MsgVoteWeighted;
 
 pub mod proto;
+pub mod staking;
+pub mod token_factory;
 
 impl MsgVoteWeighted {
     pub fn to_cosmos_msg(&self) -> CosmosMsg {
@@ -15,3 +17,16 @@ impl MsgVoteWeighted {
         }
     }
 }
+
+/// Encodes a protobuf message by writing its fields to the output stream
+pub(crate) fn encode(
+    write: impl FnOnce(&mut CodedOutputStream) -> protobuf::Result<()>,
+) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec![];
+    {
+        let mut os = CodedOutputStream::vec(&mut bytes);
+        write(&mut os).unwrap();
+        os.flush().unwrap();
+    }
+    bytes
+}
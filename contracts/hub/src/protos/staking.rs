@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{Binary, StdError, StdResult, Uint128};
+use protobuf::{CodedInputStream, UnknownFields};
+
+use super::encode;
+
+/// Stargate path of the staking module's `UnbondingDelegation` query
+pub const UNBONDING_DELEGATION_PATH: &str = "/cosmos.staking.v1beta1.Query/UnbondingDelegation";
+
+/// Queries the unbonding entries of `delegator_addr` with `validator_addr`
+pub struct QueryUnbondingDelegationRequest {
+    pub delegator_addr: String,
+    pub validator_addr: String,
+}
+
+/// Entry of an unbonding delegation, only containing the fields needed to detect slashing
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UnbondingDelegationEntry {
+    pub creation_height: u64,
+    pub initial_balance: Uint128,
+    pub balance: Uint128,
+}
+
+impl QueryUnbondingDelegationRequest {
+    pub fn to_binary(&self) -> Binary {
+        Binary::from(encode(|os| {
+            os.write_string(1, &self.delegator_addr)?;
+            os.write_string(2, &self.validator_addr)
+        }))
+    }
+}
+
+/// Decodes a `QueryUnbondingDelegationResponse` into the entries of its unbonding delegation
+pub fn decode_unbonding_delegation_response(
+    bytes: &[u8],
+) -> StdResult<Vec<UnbondingDelegationEntry>> {
+    let parse_err =
+        |e: protobuf::Error| StdError::parse_err("QueryUnbondingDelegationResponse", e.to_string());
+
+    // QueryUnbondingDelegationResponse { UnbondingDelegation unbond = 1; }
+    let mut unbond: Vec<u8> = vec![];
+    decode(bytes, |tag, is| {
+        Ok(match tag {
+            10 => {
+                unbond = is.read_bytes()?;
+                true
+            },
+            _ => false,
+        })
+    })
+    .map_err(parse_err)?;
+
+    // UnbondingDelegation { repeated UnbondingDelegationEntry entries = 3; }
+    let mut entries: Vec<Vec<u8>> = vec![];
+    decode(&unbond, |tag, is| {
+        Ok(match tag {
+            26 => {
+                entries.push(is.read_bytes()?);
+                true
+            },
+            _ => false,
+        })
+    })
+    .map_err(parse_err)?;
+
+    entries
+        .iter()
+        .map(|entry| {
+            // UnbondingDelegationEntry { int64 creation_height = 1; string initial_balance = 3;
+            // string balance = 4; }
+            let mut creation_height = 0i64;
+            let mut initial_balance = String::new();
+            let mut balance = String::new();
+            decode(entry, |tag, is| {
+                Ok(match tag {
+                    8 => {
+                        creation_height = is.read_int64()?;
+                        true
+                    },
+                    26 => {
+                        initial_balance = is.read_string()?;
+                        true
+                    },
+                    34 => {
+                        balance = is.read_string()?;
+                        true
+                    },
+                    _ => false,
+                })
+            })
+            .map_err(parse_err)?;
+
+            Ok(UnbondingDelegationEntry {
+                creation_height: creation_height as u64,
+                initial_balance: parse_int(&initial_balance)?,
+                balance: parse_int(&balance)?,
+            })
+        })
+        .collect()
+}
+
+/// Reads all fields of a message. Fields not handled by `read_field` are skipped.
+fn decode(
+    bytes: &[u8],
+    mut read_field: impl FnMut(u32, &mut CodedInputStream) -> protobuf::Result<bool>,
+) -> protobuf::Result<()> {
+    let mut is = CodedInputStream::from_bytes(bytes);
+    while let Some(tag) = is.read_raw_tag_or_eof()? {
+        if !read_field(tag, &mut is)? {
+            protobuf::rt::read_unknown_or_skip_group(tag, &mut is, &mut UnknownFields::new())?;
+        }
+    }
+    Ok(())
+}
+
+/// Amounts are encoded as `cosmos.Int` strings, which are omitted when zero
+fn parse_int(value: &str) -> StdResult<Uint128> {
+    if value.is_empty() {
+        Ok(Uint128::zero())
+    } else {
+        Uint128::from_str(value)
+    }
+}
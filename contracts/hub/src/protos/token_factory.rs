@@ -0,0 +1,100 @@
+use cosmwasm_std::{Binary, Coin, CosmosMsg};
+
+use super::encode;
+
+/// Type URL prefix of the token-factory module, shared by all chains forking the Osmosis module
+const TYPE_URL_PREFIX: &str = "/osmosis.tokenfactory.v1beta1";
+
+/// Creates the denom `factory/{sender}/{subdenom}`
+pub struct MsgCreateDenom {
+    pub sender: String,
+    pub subdenom: String,
+}
+
+/// Mints `amount` of a denom administrated by `sender` to `mint_to_address`
+pub struct MsgMint {
+    pub sender: String,
+    pub amount: Coin,
+    pub mint_to_address: String,
+}
+
+/// Burns `amount` of a denom administrated by `sender` from `burn_from_address`
+pub struct MsgBurn {
+    pub sender: String,
+    pub amount: Coin,
+    pub burn_from_address: String,
+}
+
+/// Lets the token-factory module call the `sudo` entry point of `cosmwasm_address` before `denom`
+/// is sent
+pub struct MsgSetBeforeSendHook {
+    pub sender: String,
+    pub denom: String,
+    pub cosmwasm_address: String,
+}
+
+impl MsgCreateDenom {
+    pub fn to_cosmos_msg(&self) -> CosmosMsg {
+        let value = encode(|os| {
+            os.write_string(1, &self.sender)?;
+            os.write_string(2, &self.subdenom)
+        });
+
+        to_stargate_msg("MsgCreateDenom", value)
+    }
+}
+
+impl MsgMint {
+    pub fn to_cosmos_msg(&self) -> CosmosMsg {
+        let value = encode(|os| {
+            os.write_string(1, &self.sender)?;
+            os.write_bytes(2, &encode_coin(&self.amount))?;
+            os.write_string(3, &self.mint_to_address)
+        });
+
+        to_stargate_msg("MsgMint", value)
+    }
+}
+
+impl MsgBurn {
+    pub fn to_cosmos_msg(&self) -> CosmosMsg {
+        let value = encode(|os| {
+            os.write_string(1, &self.sender)?;
+            os.write_bytes(2, &encode_coin(&self.amount))?;
+            os.write_string(3, &self.burn_from_address)
+        });
+
+        to_stargate_msg("MsgBurn", value)
+    }
+}
+
+impl MsgSetBeforeSendHook {
+    pub fn to_cosmos_msg(&self) -> CosmosMsg {
+        let value = encode(|os| {
+            os.write_string(1, &self.sender)?;
+            os.write_string(2, &self.denom)?;
+            os.write_string(3, &self.cosmwasm_address)
+        });
+
+        to_stargate_msg("MsgSetBeforeSendHook", value)
+    }
+}
+
+/// Returns the denom created by `MsgCreateDenom`
+pub fn token_factory_denom(creator: &str, subdenom: &str) -> String {
+    format!("factory/{}/{}", creator, subdenom)
+}
+
+fn to_stargate_msg(name: &str, value: Vec<u8>) -> CosmosMsg {
+    CosmosMsg::Stargate {
+        type_url: format!("{}.{}", TYPE_URL_PREFIX, name),
+        value: Binary::from(value),
+    }
+}
+
+fn encode_coin(coin: &Coin) -> Vec<u8> {
+    encode(|os| {
+        os.write_string(1, &coin.denom)?;
+        os.write_string(2, &coin.amount.to_string())
+    })
+}
@@ -5,29 +5,44 @@ use cw_storage_plus::Bound;
 
 use eris::constants::DAY;
 use eris::governance_helper::get_period;
+use eris::helpers::exchange_rate::{
+    compute_apr, compute_twap, load_exchange_rates, AprResponse, ExchangeRateTwapResponse,
+};
+use eris::helpers::timelock::PendingActionsResponse;
 use eris::hub::{
-    Batch, ConfigResponse, ExchangeRatesResponse, PendingBatch, StateResponse,
+    Batch, ConfigResponse, DelegationStrategy, ExchangeRatesResponse, ExecuteMsg, GaugeOverrides,
+    KeeperAction, PauseStateResponse, PendingBatch, PendingKeeperAction,
+    PendingKeeperActionsResponse, RedelegationItem, RedelegationLock, ReferralResponseItem,
+    SimulateDelegationStrategyResponse, SlashingEvent, StateResponse, UnbondReceipt,
     UnbondRequestsByBatchResponseItem, UnbondRequestsByUserResponseItem,
-    UnbondRequestsByUserResponseItemDetails, WantedDelegationsResponse,
+    UnbondRequestsByUserResponseItemDetails, WantedDelegationsResponse, WithdrawableAmountResponse,
+    WithdrawableBatch,
 };
 use itertools::Itertools;
 
-use crate::constants::CONTRACT_DENOM;
-use crate::helpers::{get_wanted_delegations, query_all_delegations, query_cw20_total_supply};
-use crate::math::get_uluna_per_validator_prepared;
+use crate::helpers::{compute_wanted_delegations, get_wanted_delegations, query_all_delegations};
+use crate::math::{
+    compute_backing, compute_buffer_utilization, compute_keeper_tip,
+    compute_redelegations_for_targets, compute_withdrawable_amount,
+    get_utoken_per_validator_for_strategy, get_utoken_per_validator_prepared,
+};
 use crate::state::State;
-use crate::types::gauges::PeriodGaugeLoader;
+use crate::types::gauges::{OverrideGaugeLoader, PeriodGaugeLoader};
 
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 
 pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
     let state = State::default();
+    let stake_token = state.load_stake_token(deps.storage)?;
 
     Ok(ConfigResponse {
         owner: state.owner.load(deps.storage)?.into(),
         new_owner: state.new_owner.may_load(deps.storage)?.map(|addr| addr.into()),
-        stake_token: state.stake_token.load(deps.storage)?.into(),
+        stake_token: stake_token.id(),
+        stake_token_type: stake_token.token_type(),
+        legacy_stake_token: stake_token.legacy().map(|addr| addr.to_string()),
+        denom: state.denom.load(deps.storage)?,
         epoch_period: state.epoch_period.load(deps.storage)?,
         unbond_period: state.unbond_period.load(deps.storage)?,
         validators: state.validators.load(deps.storage)?,
@@ -45,6 +60,7 @@ pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
                 min_delegation_bps,
                 max_delegation_bps,
                 validator_count,
+                slashing_penalty_bps,
             } => eris::hub::DelegationStrategy::Gauges {
                 amp_gauges: amp_gauges.to_string(),
                 emp_gauges: emp_gauges.map(|a| a.to_string()),
@@ -52,19 +68,27 @@ pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
                 min_delegation_bps,
                 max_delegation_bps,
                 validator_count,
+                slashing_penalty_bps,
             },
         },
+        instant_unbond: state.instant_unbond_config.may_load(deps.storage)?,
+        validator_scoring: state.validator_scoring.may_load(deps.storage)?,
+        unbond_receipt_nft: state
+            .unbond_receipt_nft
+            .may_load(deps.storage)?
+            .map(|addr| addr.into()),
     })
 }
 
 pub fn state(deps: Deps, env: Env) -> StdResult<StateResponse> {
     let state = State::default();
 
-    let stake_token = state.stake_token.load(deps.storage)?;
-    let total_ustake = query_cw20_total_supply(&deps.querier, &stake_token)?;
+    let stake_token = state.load_stake_token(deps.storage)?;
+    let total_ustake = stake_token.query_total_supply(&deps.querier)?;
 
-    let delegations = query_all_delegations(&deps.querier, &env.contract.address)?;
-    let total_uluna: u128 = delegations.iter().map(|d| d.amount).sum();
+    let denom = state.denom.load(deps.storage)?;
+    let delegations = query_all_delegations(&deps.querier, &env.contract.address, &denom)?;
+    let total_utoken: u128 = delegations.iter().map(|d| d.amount).sum();
 
     // only not reconciled batches are relevant as they are still unbonding and estimated unbond time in the future.
     let unbonding: u128 = state
@@ -78,34 +102,46 @@ pub fn state(deps: Deps, env: Env) -> StdResult<StateResponse> {
             v
         })
         .filter(|item| item.est_unbond_end_time > env.block.time.seconds())
-        .map(|item| item.uluna_unclaimed.u128())
+        .map(|item| item.utoken_unclaimed.u128())
         .sum();
 
-    let available = deps.querier.query_balance(&env.contract.address, CONTRACT_DENOM)?.amount;
+    let available = deps.querier.query_balance(&env.contract.address, denom)?.amount;
 
-    let exchange_rate = if total_ustake.is_zero() {
+    let (ustake_outstanding, utoken_backing) =
+        compute_backing(&state, deps.storage, &env.contract.address, total_ustake, &delegations)?;
+    let exchange_rate = if ustake_outstanding.is_zero() {
         Decimal::one()
     } else {
-        Decimal::from_ratio(total_uluna, total_ustake)
+        Decimal::from_ratio(utoken_backing, ustake_outstanding)
     };
 
+    let instant_unbond_buffer =
+        state.instant_unbond_buffer.may_load(deps.storage)?.unwrap_or_default();
+    let instant_unbond_utilization = state
+        .instant_unbond_config
+        .may_load(deps.storage)?
+        .map(|config| compute_buffer_utilization(instant_unbond_buffer, config.buffer_target))
+        .unwrap_or_default();
+
     Ok(StateResponse {
         total_ustake,
-        total_uluna: Uint128::new(total_uluna),
+        total_utoken: Uint128::new(total_utoken),
         exchange_rate,
         unlocked_coins: state.unlocked_coins.load(deps.storage)?,
         unbonding: Uint128::from(unbonding),
         available,
-        tvl_uluna: Uint128::from(total_uluna)
+        tvl_utoken: Uint128::from(total_utoken)
             .checked_add(Uint128::from(unbonding))?
             .checked_add(available)?,
+        instant_unbond_buffer,
+        instant_unbond_utilization,
     })
 }
 
 pub fn wanted_delegations(deps: Deps, env: Env) -> StdResult<WantedDelegationsResponse> {
     let state = State::default();
 
-    let (delegations, _, _, share) = get_uluna_per_validator_prepared(
+    let (delegations, _, _, share) = get_utoken_per_validator_prepared(
         &state,
         deps.storage,
         &deps.querier,
@@ -141,7 +177,7 @@ pub fn simulate_wanted_delegations(
         },
     )?;
 
-    let (delegations, _, _, share) = get_uluna_per_validator_prepared(
+    let (delegations, _, _, share) = get_utoken_per_validator_prepared(
         &state,
         deps.storage,
         &deps.querier,
@@ -156,6 +192,71 @@ pub fn simulate_wanted_delegations(
     })
 }
 
+pub fn simulate_delegation_strategy(
+    deps: Deps,
+    env: Env,
+    strategy: DelegationStrategy,
+    total_uluna: Option<Uint128>,
+    overrides: Option<GaugeOverrides>,
+) -> StdResult<SimulateDelegationStrategyResponse> {
+    let state = State::default();
+
+    let strategy = strategy.validate(deps.api)?;
+    let overrides = overrides.unwrap_or_default();
+    let validators = state.validators.load(deps.storage)?;
+
+    let (delegation_goal, _) = compute_wanted_delegations(
+        &state,
+        &env,
+        deps.storage,
+        &deps.querier,
+        OverrideGaugeLoader {
+            time: env.block.time.seconds(),
+            vamp_points: overrides.vamp_points,
+            emp_points: overrides.emp_points,
+        },
+        strategy.clone(),
+    )?;
+
+    let denom = state.denom.load(deps.storage)?;
+    let current_delegations = query_all_delegations(&deps.querier, &env.contract.address, &denom)?;
+    let utoken_staked: u128 = current_delegations.iter().map(|d| d.amount).sum();
+
+    let (delegations, _, _, _) = get_utoken_per_validator_for_strategy(
+        &state,
+        deps.storage,
+        total_uluna.map_or(utoken_staked, |amount| amount.u128()),
+        &validators,
+        Some(delegation_goal.clone()),
+        &strategy,
+    )?;
+
+    // redelegations always move the currently delegated amount
+    let targets = get_utoken_per_validator_for_strategy(
+        &state,
+        deps.storage,
+        utoken_staked,
+        &validators,
+        Some(delegation_goal),
+        &strategy,
+    )?;
+    let locked = state.redelegation_locked_validators(deps.storage, env.block.time.seconds())?;
+    let redelegations =
+        compute_redelegations_for_targets(&current_delegations, validators, targets, &locked);
+
+    Ok(SimulateDelegationStrategyResponse {
+        delegations: sort_delegations(delegations),
+        redelegations: redelegations
+            .into_iter()
+            .map(|rd| RedelegationItem {
+                src: rd.src,
+                dst: rd.dst,
+                amount: Uint128::new(rd.amount),
+            })
+            .collect(),
+    })
+}
+
 /// Sort delegations by amount descending and then by address ascending
 fn sort_delegations(
     delegations: std::collections::HashMap<String, Uint128>,
@@ -350,3 +451,235 @@ pub fn query_exchange_rates(
         apr,
     })
 }
+
+pub fn exchange_rate_twap(
+    deps: Deps,
+    env: Env,
+    window_s: u64,
+) -> StdResult<ExchangeRateTwapResponse> {
+    let state = State::default();
+    let now = env.block.time.seconds();
+    let exchange_rates = load_exchange_rates(
+        deps.storage,
+        &state.exchange_history,
+        now.saturating_sub(window_s),
+        |time, rate| (time, rate),
+    )?;
+
+    compute_twap(&exchange_rates, now, window_s)
+}
+
+pub fn apr(deps: Deps, env: Env, windows: Vec<u64>) -> StdResult<AprResponse> {
+    let state = State::default();
+    let now = env.block.time.seconds();
+    let max_window = windows.iter().max().copied().unwrap_or_default();
+    let exchange_rates = load_exchange_rates(
+        deps.storage,
+        &state.exchange_history,
+        now.saturating_sub(max_window),
+        |time, rate| (time, rate),
+    )?;
+
+    Ok(AprResponse {
+        aprs: windows
+            .into_iter()
+            .map(|window_s| compute_apr(&exchange_rates, now, window_s))
+            .collect(),
+    })
+}
+
+pub fn slashing_events(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<SlashingEvent>> {
+    let state = State::default();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_after.map(Bound::exclusive);
+
+    state
+        .slashing_events
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            let (_, v) = item?;
+            Ok(v)
+        })
+        .collect()
+}
+
+pub fn validator_scores(deps: Deps) -> StdResult<Vec<(String, Decimal)>> {
+    let state = State::default();
+    Ok(state.validator_scores.may_load(deps.storage)?.unwrap_or_default())
+}
+
+pub fn unbond_receipt(deps: Deps, token_id: String) -> StdResult<UnbondReceipt> {
+    let state = State::default();
+    state.unbond_receipts.load(deps.storage, &token_id)
+}
+
+pub fn pause_state(deps: Deps) -> StdResult<PauseStateResponse> {
+    let state = State::default();
+
+    Ok(PauseStateResponse {
+        guardian: state.guardian.may_load(deps.storage)?.map(|addr| addr.into()),
+        paused: state.paused_operations.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn pending_keeper_actions(deps: Deps, env: Env) -> StdResult<PendingKeeperActionsResponse> {
+    let state = State::default();
+    let current_time = env.block.time.seconds();
+    let keeper_tips = state.keeper_tips.may_load(deps.storage)?;
+    let tip_pool = state.keeper_tip_pool.may_load(deps.storage)?.unwrap_or_default();
+
+    let mut due_actions: Vec<(KeeperAction, u64)> = vec![];
+
+    let pending_batch = state.pending_batch.load(deps.storage)?;
+    if !pending_batch.ustake_to_burn.is_zero()
+        && current_time >= pending_batch.est_unbond_start_time
+    {
+        due_actions.push((KeeperAction::SubmitBatch, pending_batch.est_unbond_start_time));
+    }
+
+    // batches can be reconciled once their unbonding has ended
+    let reconcile_due_since = state
+        .previous_batches
+        .idx
+        .reconciled
+        .prefix(false.into())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (_, batch) = item?;
+            Ok(batch.est_unbond_end_time + 1)
+        })
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .filter(|due_since| *due_since <= current_time)
+        .min();
+    if let Some(due_since) = reconcile_due_since {
+        due_actions.push((KeeperAction::Reconcile, due_since));
+    }
+
+    let harvest_due_since = state.load_harvest_due_since(deps.storage)?;
+    if current_time >= harvest_due_since {
+        due_actions.push((KeeperAction::Harvest, harvest_due_since));
+    }
+
+    Ok(PendingKeeperActionsResponse {
+        actions: due_actions
+            .into_iter()
+            .map(|(action, due_since)| PendingKeeperAction {
+                action,
+                due_since,
+                tip: compute_keeper_tip(keeper_tips.as_ref(), tip_pool, current_time, due_since),
+            })
+            .collect(),
+        keeper_tips,
+        tip_pool,
+    })
+}
+
+pub fn pending_actions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PendingActionsResponse<ExecuteMsg>> {
+    State::default().timelock.query_pending(deps.storage, start_after, limit)
+}
+
+pub fn referrals(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ReferralResponseItem>> {
+    let state = State::default();
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = start_after.map(|s| deps.api.addr_validate(&s)).transpose()?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    state
+        .referrals
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|partner| {
+            let partner = partner?;
+            Ok(ReferralResponseItem {
+                stats: state.load_referral(deps.storage, &partner)?,
+                partner: partner.into(),
+            })
+        })
+        .collect()
+}
+
+pub fn redelegation_locks(
+    deps: Deps,
+    env: Env,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<RedelegationLock>> {
+    let state = State::default();
+    let current_time = env.block.time.seconds();
+    let unbond_period = state.unbond_period.load(deps.storage)?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start =
+        start_after.as_ref().map(|(src, dst)| Bound::exclusive((src.as_str(), dst.as_str())));
+
+    state
+        .redelegations
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, time)) => time + unbond_period > current_time,
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| {
+            let ((src, dst), time) = item?;
+            Ok(RedelegationLock {
+                src,
+                dst,
+                redelegated_at: time,
+                locked_until: time + unbond_period,
+            })
+        })
+        .collect()
+}
+
+pub fn withdrawable_amount(
+    deps: Deps,
+    env: Env,
+    user: String,
+) -> StdResult<WithdrawableAmountResponse> {
+    let state = State::default();
+    let current_time = env.block.time.seconds();
+
+    let mut total_utoken = Uint128::zero();
+    let mut batches: Vec<WithdrawableBatch> = vec![];
+    for item in state.unbond_requests.idx.user.prefix(user).range(
+        deps.storage,
+        None,
+        None,
+        Order::Ascending,
+    ) {
+        let (_, request) = item?;
+        let withdrawable = state
+            .previous_batches
+            .may_load(deps.storage, request.id)?
+            .and_then(|batch| compute_withdrawable_amount(&batch, request.shares, current_time));
+
+        if let Some(utoken) = withdrawable.filter(|utoken| !utoken.is_zero()) {
+            total_utoken += utoken;
+            batches.push(WithdrawableBatch {
+                id: request.id,
+                utoken,
+            });
+        }
+    }
+
+    Ok(WithdrawableAmountResponse {
+        total_utoken,
+        batches,
+    })
+}
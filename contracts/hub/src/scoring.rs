@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use cosmwasm_std::{Decimal, Env, Order, QuerierWrapper, StdResult, Storage};
+use eris::helpers::bps::BasicPoints;
+use eris::hub::ValidatorScoringConfig;
+
+use crate::state::State;
+
+/// Computes a score multiplier between 0 and 1 for each validator, which is applied to its share of
+/// the delegation goal.
+///
+/// Validators that are not part of the active set (e.g. jailed or tombstoned) or charge more than the
+/// max commission receive a score of zero. All others are scored by the part of the rewards they pass
+/// on to their delegators, reduced by the penalty for each slashing event within the lookback period.
+pub(crate) fn compute_validator_scores(
+    state: &State,
+    storage: &dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &ValidatorScoringConfig,
+    validators: &[String],
+) -> StdResult<Vec<(String, Decimal)>> {
+    // the staking module only returns validators that are bonded
+    let active_validators: HashMap<String, Decimal> = querier
+        .query_all_validators()?
+        .into_iter()
+        .map(|validator| (validator.address, validator.commission))
+        .collect();
+
+    let slashing_counts = count_slashing_events(
+        state,
+        storage,
+        env.block.time.seconds().saturating_sub(config.slashing_lookback),
+    )?;
+
+    validators
+        .iter()
+        .map(|validator| -> StdResult<(String, Decimal)> {
+            let score = match active_validators.get(validator) {
+                Some(commission) if *commission <= config.max_commission => {
+                    let slashings = slashing_counts.get(validator).copied().unwrap_or_default();
+                    let penalty = Decimal::min(
+                        Decimal::from_ratio(
+                            u128::from(config.slashing_penalty_bps) * slashings,
+                            u128::from(BasicPoints::MAX),
+                        ),
+                        Decimal::one(),
+                    );
+
+                    Decimal::one()
+                        .checked_sub(*commission)?
+                        .checked_mul(Decimal::one().checked_sub(penalty)?)?
+                },
+                _ => Decimal::zero(),
+            };
+
+            Ok((validator.clone(), score))
+        })
+        .collect()
+}
+
+/// Recomputes the scores of the validators in the delegation goal, so that a validator that gets
+/// jailed or raises its commission loses its delegation at the next rebalance instead of the next
+/// tune. Bonding, reinvesting and unbonding use the stored scores, as querying all validators on
+/// every delegation is too expensive. Scores are removed if scoring is disabled or there is no
+/// goal.
+pub(crate) fn refresh_validator_scores(
+    state: &State,
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+) -> StdResult<Option<Vec<(String, Decimal)>>> {
+    let validator_scoring = state.validator_scoring.may_load(storage)?;
+    let delegation_goal = state.delegation_goal.may_load(storage)?;
+
+    match (validator_scoring, delegation_goal) {
+        (Some(validator_scoring), Some(delegation_goal)) if !delegation_goal.shares.is_empty() => {
+            let validators: Vec<_> = delegation_goal.shares.into_iter().map(|a| a.0).collect();
+            let scores = compute_validator_scores(
+                state,
+                storage,
+                querier,
+                env,
+                &validator_scoring,
+                &validators,
+            )?;
+            state.validator_scores.save(storage, &scores)?;
+            Ok(Some(scores))
+        },
+        _ => {
+            state.validator_scores.remove(storage);
+            Ok(None)
+        },
+    }
+}
+
+/// Counts the slashing events of each validator that have been detected after `since`
+fn count_slashing_events(
+    state: &State,
+    storage: &dyn Storage,
+    since: u64,
+) -> StdResult<HashMap<String, u128>> {
+    let mut counts: HashMap<String, u128> = HashMap::new();
+    for item in state.slashing_events.range(storage, None, None, Order::Descending) {
+        let (_, event) = item?;
+        if event.time <= since {
+            break;
+        }
+        *counts.entry(event.validator).or_default() += 1;
+    }
+    Ok(counts)
+}
@@ -1,20 +1,34 @@
-use cosmwasm_std::{Addr, Coin, Decimal, Storage};
+use std::collections::HashSet;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Decimal, Order, StdResult, Storage, Uint128};
 use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
+use eris::helpers::timelock::Timelock;
 use eris::hub::{
-    Batch, DelegationStrategy, FeeConfig, PendingBatch, UnbondRequest, WantedDelegationsShare,
+    Batch, DelegationStrategy, ExecuteMsg, FeeConfig, InstantUnbondConfig, KeeperTipConfig,
+    PauseOperation, PendingBatch, ReferralStats, SlashingEvent, UnbondReceipt, UnbondRequest,
+    ValidatorScoringConfig, WantedDelegationsShare,
 };
 use itertools::Itertools;
 
-use crate::{error::ContractError, types::BooleanKey};
+use crate::{
+    error::ContractError,
+    types::{BooleanKey, Redelegation, StakeToken},
+};
 
 pub(crate) struct State<'a> {
     /// Account who can call certain privileged functions
     pub owner: Item<'a, Addr>,
     /// Pending ownership transfer, awaiting acceptance by the new owner
     pub new_owner: Item<'a, Addr>,
-    /// Address of the Liquid Staking token
+    /// Address of the Liquid Staking token. In token-factory mode, the CW20 token the hub migrated
+    /// from, if any
     pub stake_token: Item<'a, Addr>,
+    /// Token-factory denom of the Liquid Staking token, only set in token-factory mode
+    pub stake_denom: Item<'a, String>,
+    /// Denom of the native token that is staked
+    pub denom: Item<'a, String>,
     /// How often the unbonding queue is to be executed
     pub epoch_period: Item<'a, u64>,
     /// The staking module's unbonding time, in seconds
@@ -41,6 +55,52 @@ pub(crate) struct State<'a> {
     pub allow_donations: Item<'a, bool>,
     // history of the exchange_rate
     pub exchange_history: Map<'a, u64, Decimal>,
+    /// Configuration of the instant unbond buffer
+    pub instant_unbond_config: Item<'a, InstantUnbondConfig>,
+    /// Amount of utoken held by the contract that is reserved for instant unbonds
+    pub instant_unbond_buffer: Item<'a, Uint128>,
+    /// Undelegations made per validator when a batch was submitted
+    pub batch_undelegations: Map<'a, u64, BatchUndelegations>,
+    /// Amount already recorded as slashed per batch and validator
+    pub batch_slashing: Map<'a, (u64, &'a str), Uint128>,
+    /// History of detected slashing events
+    pub slashing_events: Map<'a, u64, SlashingEvent>,
+    /// Configuration of the validator scoring
+    pub validator_scoring: Item<'a, ValidatorScoringConfig>,
+    /// Score multiplier per validator, computed during the last tune
+    pub validator_scores: Item<'a, Vec<(String, Decimal)>>,
+    /// Cw721 contract minting unbond receipts
+    pub unbond_receipt_nft: Item<'a, Addr>,
+    /// Unbonding claims represented by unbond receipts, by token ID
+    pub unbond_receipts: Map<'a, &'a str, UnbondReceipt>,
+    /// Token ID of the next unbond receipt
+    pub next_receipt_id: Item<'a, u64>,
+    /// Cut of the reward fee generated by referred stake that is paid to the partner
+    pub referral_fee: Item<'a, Decimal>,
+    /// Referral fee accrued per referred Stake token
+    pub referral_fee_index: Item<'a, Decimal>,
+    /// Stake tokens referred by all partners
+    pub total_ustake_referred: Item<'a, Uint128>,
+    /// Stats per referral partner
+    pub referrals: Map<'a, &'a Addr, ReferralStats>,
+    /// Referred Stake tokens still held by the account they were minted to, by holder and partner
+    pub referred_stakes: Map<'a, (&'a Addr, &'a Addr), Uint128>,
+    /// Referral fees accrued by all partners that have not been claimed yet
+    pub referral_fees_unclaimed: Item<'a, Uint128>,
+    /// Account that can pause operations
+    pub guardian: Item<'a, Addr>,
+    /// Operations that are currently paused
+    pub paused_operations: Item<'a, Vec<PauseOperation>>,
+    /// Config updates waiting for their timelock delay
+    pub timelock: Timelock<'a, ExecuteMsg>,
+    /// Tips paid to keepers executing overdue actions
+    pub keeper_tips: Item<'a, KeeperTipConfig>,
+    /// Amount of utoken set aside from the protocol fee to pay keeper tips
+    pub keeper_tip_pool: Item<'a, Uint128>,
+    /// prop_gauges contract deciding the votes that anyone can cast on behalf of the hub
+    pub prop_gauges: Item<'a, Addr>,
+    /// Time of the last redelegation per source and destination validator
+    pub redelegations: Map<'a, (&'a str, &'a str), u64>,
 }
 
 impl Default for State<'static> {
@@ -63,6 +123,8 @@ impl Default for State<'static> {
             owner: Item::new("owner"),
             new_owner: Item::new("new_owner"),
             stake_token: Item::new("stake_token"),
+            stake_denom: Item::new("stake_denom"),
+            denom: Item::new("denom"),
             epoch_period: Item::new("epoch_period"),
             unbond_period: Item::new("unbond_period"),
             validators: Item::new("validators"),
@@ -76,11 +138,123 @@ impl Default for State<'static> {
             vote_operator: Item::new("vote_operator"),
             allow_donations: Item::new("allow_donations"),
             exchange_history: Map::new("exchange_history"),
+            instant_unbond_config: Item::new("instant_unbond_config"),
+            instant_unbond_buffer: Item::new("instant_unbond_buffer"),
+            batch_undelegations: Map::new("batch_undelegations"),
+            batch_slashing: Map::new("batch_slashing"),
+            slashing_events: Map::new("slashing_events"),
+            validator_scoring: Item::new("validator_scoring"),
+            validator_scores: Item::new("validator_scores"),
+            unbond_receipt_nft: Item::new("unbond_receipt_nft"),
+            unbond_receipts: Map::new("unbond_receipts"),
+            next_receipt_id: Item::new("next_receipt_id"),
+            referral_fee: Item::new("referral_fee"),
+            referral_fee_index: Item::new("referral_fee_index"),
+            total_ustake_referred: Item::new("total_ustake_referred"),
+            referrals: Map::new("referrals"),
+            referred_stakes: Map::new("referred_stakes"),
+            referral_fees_unclaimed: Item::new("referral_fees_unclaimed"),
+            guardian: Item::new("guardian"),
+            paused_operations: Item::new("paused_operations"),
+            timelock: Timelock::new("timelock_delay", "timelock_next_id", "timelock_actions"),
+            keeper_tips: Item::new("keeper_tips"),
+            keeper_tip_pool: Item::new("keeper_tip_pool"),
+            prop_gauges: Item::new("prop_gauges"),
+            redelegations: Map::new("redelegations"),
         }
     }
 }
 
 impl<'a> State<'a> {
+    /// Time from which rewards are expected to be harvested again. Each harvest records the
+    /// exchange rate.
+    pub fn load_harvest_due_since(&self, storage: &dyn Storage) -> StdResult<u64> {
+        let last_harvest = self
+            .exchange_history
+            .keys(storage, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .unwrap_or_default();
+        let harvest_interval_s =
+            self.keeper_tips.may_load(storage)?.map_or(0, |config| config.harvest_interval_s);
+
+        Ok(last_harvest + harvest_interval_s)
+    }
+
+    /// Loads the stats of a referral partner, including the fees accrued since the last update
+    pub fn load_referral(&self, storage: &dyn Storage, partner: &Addr) -> StdResult<ReferralStats> {
+        let fee_index = self.referral_fee_index.may_load(storage)?.unwrap_or_default();
+        let mut stats = self.referrals.may_load(storage, partner)?.unwrap_or_default();
+
+        stats.utoken_claimable += stats.ustake_referred * (fee_index - stats.fee_index);
+        stats.fee_index = fee_index;
+        Ok(stats)
+    }
+
+    /// Credits `ustake_amount` Stake tokens minted to `holder` to the referral `partner`
+    pub fn add_referred_stake(
+        &self,
+        storage: &mut dyn Storage,
+        holder: &Addr,
+        partner: &Addr,
+        ustake_amount: Uint128,
+    ) -> StdResult<()> {
+        let mut stats = self.load_referral(storage, partner)?;
+        stats.ustake_referred += ustake_amount;
+        self.referrals.save(storage, partner, &stats)?;
+
+        self.referred_stakes.update(storage, (holder, partner), |referred| -> StdResult<_> {
+            Ok(referred.unwrap_or_default() + ustake_amount)
+        })?;
+
+        let total_ustake_referred =
+            self.total_ustake_referred.may_load(storage)?.unwrap_or_default();
+        self.total_ustake_referred.save(storage, &(total_ustake_referred + ustake_amount))
+    }
+
+    /// Stops crediting up to `ustake_amount` Stake tokens of `holder` to their referral partners, as
+    /// they have been unbonded or transferred. Returns the amount released.
+    pub fn release_referred_stake(
+        &self,
+        storage: &mut dyn Storage,
+        holder: &Addr,
+        ustake_amount: Uint128,
+    ) -> StdResult<Uint128> {
+        let referred_stakes = self
+            .referred_stakes
+            .prefix(holder)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut ustake_to_release = ustake_amount;
+        for (partner, referred) in referred_stakes {
+            if ustake_to_release.is_zero() {
+                break;
+            }
+
+            let released = referred.min(ustake_to_release);
+            ustake_to_release -= released;
+
+            if released == referred {
+                self.referred_stakes.remove(storage, (holder, &partner));
+            } else {
+                self.referred_stakes.save(storage, (holder, &partner), &(referred - released))?;
+            }
+
+            let mut stats = self.load_referral(storage, &partner)?;
+            stats.ustake_referred = stats.ustake_referred.saturating_sub(released);
+            self.referrals.save(storage, &partner, &stats)?;
+        }
+
+        let ustake_released = ustake_amount - ustake_to_release;
+        let total_ustake_referred =
+            self.total_ustake_referred.may_load(storage)?.unwrap_or_default();
+        self.total_ustake_referred
+            .save(storage, &total_ustake_referred.saturating_sub(ustake_released))?;
+
+        Ok(ustake_released)
+    }
+
     pub fn assert_owner(&self, storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
         let owner = self.owner.load(storage)?;
         if *sender == owner {
@@ -90,6 +264,30 @@ impl<'a> State<'a> {
         }
     }
 
+    pub fn assert_guardian(
+        &self,
+        storage: &dyn Storage,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        match self.guardian.may_load(storage)? {
+            Some(guardian) if *sender == guardian => Ok(()),
+            _ => Err(ContractError::UnauthorizedSenderNotGuardian {}),
+        }
+    }
+
+    pub fn assert_not_paused(
+        &self,
+        storage: &dyn Storage,
+        operation: PauseOperation,
+    ) -> Result<(), ContractError> {
+        let paused_operations = self.paused_operations.may_load(storage)?.unwrap_or_default();
+        if paused_operations.contains(&operation) {
+            Err(ContractError::Paused(operation))
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn assert_vote_operator(
         &self,
         storage: &dyn Storage,
@@ -105,6 +303,78 @@ impl<'a> State<'a> {
         }
     }
 
+    /// Returns the Liquid Staking token, either the CW20 token or the token-factory denom
+    pub fn load_stake_token(&self, storage: &dyn Storage) -> StdResult<StakeToken> {
+        match self.stake_denom.may_load(storage)? {
+            Some(denom) => Ok(StakeToken::TokenFactory {
+                denom,
+                legacy: self.stake_token.may_load(storage)?,
+            }),
+            None => Ok(StakeToken::Cw20(self.stake_token.load(storage)?)),
+        }
+    }
+
+    /// Returns the validators that caused a slashing event after the provided time
+    pub fn slashed_validators_since(
+        &self,
+        storage: &dyn Storage,
+        time: u64,
+    ) -> StdResult<HashSet<String>> {
+        let mut validators = HashSet::new();
+        for item in self.slashing_events.range(storage, None, None, Order::Descending) {
+            let (_, event) = item?;
+            if event.time <= time {
+                break;
+            }
+            validators.insert(event.validator);
+        }
+        Ok(validators)
+    }
+
+    /// Records the time of the redelegations that are executed, and prunes the ones that matured
+    pub fn save_redelegations(
+        &self,
+        storage: &mut dyn Storage,
+        redelegations: &[Redelegation],
+        time: u64,
+    ) -> StdResult<()> {
+        let unbond_period = self.unbond_period.load(storage)?;
+        let matured = self
+            .redelegations
+            .range(storage, None, None, Order::Ascending)
+            .filter(|item| {
+                item.as_ref().map_or(true, |(_, rd_time)| rd_time + unbond_period <= time)
+            })
+            .map(|item| Ok(item?.0))
+            .collect::<StdResult<Vec<_>>>()?;
+        for (src, dst) in matured {
+            self.redelegations.remove(storage, (&src, &dst));
+        }
+
+        for rd in redelegations {
+            self.redelegations.save(storage, (&rd.src, &rd.dst), &time)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the validators that received a redelegation within the unbonding period. Redelegating
+    /// from them is rejected by the chain until the incoming redelegation matured.
+    pub fn redelegation_locked_validators(
+        &self,
+        storage: &dyn Storage,
+        current_time: u64,
+    ) -> StdResult<HashSet<String>> {
+        let unbond_period = self.unbond_period.load(storage)?;
+        let mut validators = HashSet::new();
+        for item in self.redelegations.range(storage, None, None, Order::Ascending) {
+            let ((_, dst), time) = item?;
+            if time + unbond_period > current_time {
+                validators.insert(dst);
+            }
+        }
+        Ok(validators)
+    }
+
     /// active validators returns the list of delegation goal, or if not available (uniform mode) uses the validators list.
     pub fn _active_validators(&self, storage: &dyn Storage) -> Vec<String> {
         self.delegation_goal
@@ -114,6 +384,14 @@ impl<'a> State<'a> {
     }
 }
 
+#[cw_serde]
+pub(crate) struct BatchUndelegations {
+    /// Block height at which the batch was submitted
+    pub height: u64,
+    /// Amount of utoken undelegated from each validator
+    pub undelegations: Vec<(String, Uint128)>,
+}
+
 pub(crate) struct PreviousBatchesIndexes<'a> {
     // pk goes to second tuple element
     pub reconciled: MultiIndex<'a, BooleanKey, Batch, Vec<u8>>,
@@ -2,11 +2,20 @@ use std::collections::HashMap;
 
 use cosmwasm_std::testing::{BankQuerier, StakingQuerier, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, Addr, Coin, Empty, FullDelegation, Querier, QuerierResult,
-    QueryRequest, SystemError, WasmQuery,
+    from_binary, from_slice, to_json_binary, Addr, BankQuery, Binary, Coin, ContractResult,
+    Decimal, Empty, FullDelegation, Querier, QuerierResult, QueryRequest, SupplyResponse,
+    SystemError, SystemResult, Validator, WasmQuery,
 };
 use cw20::Cw20QueryMsg;
+use eris::prop_gauges::{
+    ConfigResponse as PropGaugesConfigResponse, PropDetailResponse, PropInfo,
+    QueryMsg as PropGaugesQueryMsg,
+};
 
+use crate::protos::encode;
+use crate::protos::staking::{
+    QueryUnbondingDelegationRequest, UnbondingDelegationEntry, UNBONDING_DELEGATION_PATH,
+};
 use crate::types::Delegation;
 
 use super::cw20_querier::Cw20Querier;
@@ -16,7 +25,13 @@ use super::helpers::err_unsupported_query;
 pub(super) struct CustomQuerier {
     pub cw20_querier: Cw20Querier,
     pub bank_querier: BankQuerier,
+    pub bank_supplies: HashMap<String, u128>,
     pub staking_querier: StakingQuerier,
+    pub staking_validators: Vec<Validator>,
+    pub staking_delegations: Vec<FullDelegation>,
+    pub unbonding_entries: HashMap<String, Vec<UnbondingDelegationEntry>>,
+    pub prop_gauges_quorum_bps: u16,
+    pub props: HashMap<u64, PropInfo>,
 }
 
 impl Querier for CustomQuerier {
@@ -58,8 +73,12 @@ impl CustomQuerier {
         self.bank_querier = BankQuerier::new(&[(MOCK_CONTRACT_ADDR, balances)])
     }
 
+    pub fn set_bank_supply(&mut self, denom: &str, supply: u128) {
+        self.bank_supplies.insert(denom.to_string(), supply);
+    }
+
     pub fn set_staking_delegations(&mut self, delegations: &[Delegation]) {
-        let fds = delegations
+        self.staking_delegations = delegations
             .iter()
             .map(|d| FullDelegation {
                 delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
@@ -70,7 +89,69 @@ impl CustomQuerier {
             })
             .collect::<Vec<_>>();
 
-        self.staking_querier = StakingQuerier::new("uluna", &[], &fds);
+        self.staking_querier =
+            StakingQuerier::new("uluna", &self.staking_validators, &self.staking_delegations);
+    }
+
+    pub fn set_staking_validators(&mut self, validators: &[(&str, Decimal)]) {
+        self.staking_validators = validators
+            .iter()
+            .map(|(address, commission)| Validator {
+                address: address.to_string(),
+                commission: *commission,
+                max_commission: Decimal::one(),
+                max_change_rate: Decimal::one(),
+            })
+            .collect::<Vec<_>>();
+
+        self.staking_querier =
+            StakingQuerier::new("uluna", &self.staking_validators, &self.staking_delegations);
+    }
+
+    pub fn set_unbonding_entries(
+        &mut self,
+        validator: &str,
+        entries: Vec<UnbondingDelegationEntry>,
+    ) {
+        self.unbonding_entries.insert(validator.to_string(), entries);
+    }
+
+    pub fn set_prop_gauges_quorum(&mut self, quorum_bps: u16) {
+        self.prop_gauges_quorum_bps = quorum_bps;
+    }
+
+    pub fn set_prop(&mut self, proposal_id: u64, prop: PropInfo) {
+        self.props.insert(proposal_id, prop);
+    }
+
+    fn handle_prop_gauges_query(&self, query: PropGaugesQueryMsg) -> QuerierResult {
+        let res = match query {
+            PropGaugesQueryMsg::Config {} => to_json_binary(&PropGaugesConfigResponse {
+                owner: Addr::unchecked("owner"),
+                escrow_addr: Addr::unchecked("escrow"),
+                hub_addr: Addr::unchecked(MOCK_CONTRACT_ADDR),
+                quorum_bps: self.prop_gauges_quorum_bps,
+                use_weighted_vote: true,
+            }),
+            PropGaugesQueryMsg::PropDetail {
+                proposal_id,
+                ..
+            } => match self.props.get(&proposal_id) {
+                Some(prop) => to_json_binary(&PropDetailResponse {
+                    prop: prop.clone(),
+                    user: None,
+                }),
+                None => {
+                    return SystemResult::Ok(ContractResult::Err(format!(
+                        "proposal {} not found",
+                        proposal_id
+                    )))
+                },
+            },
+            _ => return err_unsupported_query(query),
+        };
+
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
     }
 
     pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
@@ -83,14 +164,72 @@ impl CustomQuerier {
                     return self.cw20_querier.handle_query(contract_addr, query);
                 }
 
+                if let Ok(query) = from_binary::<PropGaugesQueryMsg>(msg) {
+                    return self.handle_prop_gauges_query(query);
+                }
+
                 err_unsupported_query(msg)
             },
 
+            QueryRequest::Bank(BankQuery::Supply {
+                denom,
+            }) if self.bank_supplies.contains_key(denom) => SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&SupplyResponse::new(Coin::new(self.bank_supplies[denom], denom)))
+                    .unwrap(),
+            )),
+
             QueryRequest::Bank(query) => self.bank_querier.query(query),
 
             QueryRequest::Staking(query) => self.staking_querier.query(query),
 
+            QueryRequest::Stargate {
+                path,
+                data,
+            } if path == UNBONDING_DELEGATION_PATH => {
+                let entries = self.unbonding_entries.iter().find(|(validator, _)| {
+                    QueryUnbondingDelegationRequest {
+                        delegator_addr: MOCK_CONTRACT_ADDR.to_string(),
+                        validator_addr: validator.to_string(),
+                    }
+                    .to_binary()
+                        == *data
+                });
+
+                match entries {
+                    Some((_, entries)) => SystemResult::Ok(ContractResult::Ok(
+                        encode_unbonding_delegation_response(entries),
+                    )),
+                    None => err_unsupported_query(request),
+                }
+            },
+
             _ => err_unsupported_query(request),
         }
     }
 }
+
+/// Encodes the entries the same way as the staking module's `QueryUnbondingDelegationResponse`
+fn encode_unbonding_delegation_response(entries: &[UnbondingDelegationEntry]) -> Binary {
+    let entries: Vec<Vec<u8>> = entries
+        .iter()
+        .map(|entry| {
+            encode(|os| {
+                os.write_int64(1, entry.creation_height as i64)?;
+                // completion_time
+                os.write_bytes(2, &encode(|os| os.write_int64(1, 1_700_000_000)))?;
+                os.write_string(3, &entry.initial_balance.to_string())?;
+                os.write_string(4, &entry.balance.to_string())
+            })
+        })
+        .collect();
+
+    let unbond = encode(|os| {
+        os.write_string(1, MOCK_CONTRACT_ADDR)?;
+        for entry in &entries {
+            os.write_bytes(3, entry)?;
+        }
+        Ok(())
+    });
+
+    Binary::from(encode(|os| os.write_bytes(1, &unbond)))
+}
@@ -77,6 +77,8 @@ pub(super) fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
             name: "Stake Token".to_string(),
             symbol: "STAKE".to_string(),
             decimals: 6,
+            denom: CONTRACT_DENOM.to_string(),
+            token_factory_subdenom: None,
             epoch_period: 259200,   // 3 * 24 * 60 * 60 = 3 days
             unbond_period: 1814400, // 21 * 24 * 60 * 60 = 21 days
             validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
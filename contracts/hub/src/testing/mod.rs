@@ -4,4 +4,5 @@ mod helpers;
 pub mod tests_claim;
 pub mod tests_exchange_rates;
 mod tests_gauges;
+mod tests_token_factory;
 mod tests_uniform;
@@ -1,7 +1,10 @@
+use std::str::FromStr;
+
 use cosmwasm_std::testing::{mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{attr, Coin, Decimal, Uint128};
 
 use eris::constants::DAY;
+use eris::helpers::exchange_rate::{AprResponse, ExchangeRateTwapResponse, WindowApr};
 use eris::hub::{CallbackMsg, ExchangeRatesResponse, ExecuteMsg, QueryMsg};
 use eris::DecimalCheckedOps;
 
@@ -87,3 +90,107 @@ fn reinvesting_check_exchange_rates() {
     // 10.00232 -> 10.0043 within 1 day
     assert_eq!(res.apr.map(|a| a.to_string()), Some("0.00019795407465468".to_string()));
 }
+
+//--------------------------------------------------------------------------------------------------
+// Queries
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn querying_exchange_rate_twap_and_apr() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    for (time, rate) in [(0, "1.0"), (DAY, "1.1"), (2 * DAY, "1.2")] {
+        state
+            .exchange_history
+            .save(deps.as_mut().storage, time, &Decimal::from_str(rate).unwrap())
+            .unwrap();
+    }
+
+    // (1.15 + 1.2) / 2, the rate stays unchanged after the last point
+    let res: ExchangeRateTwapResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::ExchangeRateTwap {
+            window_s: 2 * DAY,
+        },
+        3 * DAY,
+    );
+    assert_eq!(
+        res,
+        ExchangeRateTwapResponse {
+            twap: Decimal::from_str("1.175").unwrap(),
+            start_time: DAY,
+            end_time: 3 * DAY,
+            partial: false,
+            stale: false,
+        }
+    );
+
+    // the rate at the start of the window is interpolated: (1.075 * 0.5 + 1.15) / 1.5
+    let res: ExchangeRateTwapResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::ExchangeRateTwap {
+            window_s: DAY * 3 / 2,
+        },
+        2 * DAY,
+    );
+    assert_eq!(res.twap, Decimal::from_str("1.125").unwrap());
+    assert_eq!(res.start_time, DAY / 2);
+
+    // window is longer than the history
+    let res: ExchangeRateTwapResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::ExchangeRateTwap {
+            window_s: 10 * DAY,
+        },
+        3 * DAY,
+    );
+    assert_eq!(res.twap, Decimal::from_str("1.133333333333333333").unwrap());
+    assert_eq!(res.start_time, 0);
+    assert!(res.partial);
+    assert!(!res.stale);
+
+    // no update within the window
+    let res: ExchangeRateTwapResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::ExchangeRateTwap {
+            window_s: DAY / 2,
+        },
+        3 * DAY,
+    );
+    assert_eq!(res.twap, Decimal::from_str("1.2").unwrap());
+    assert!(res.stale);
+
+    let res: AprResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::Apr {
+            windows: vec![DAY / 2, 2 * DAY, 10 * DAY],
+        },
+        3 * DAY,
+    );
+    assert_eq!(
+        res.aprs,
+        vec![
+            WindowApr {
+                window_s: DAY / 2,
+                apr: None,
+                partial: false,
+                stale: true,
+            },
+            // 1.1 -> 1.2 within 1 day, annualized
+            WindowApr {
+                window_s: 2 * DAY,
+                apr: Some(Decimal::from_str("33.181818181818181818").unwrap()),
+                partial: false,
+                stale: false,
+            },
+            // 1.0 -> 1.2 within 2 days, annualized
+            WindowApr {
+                window_s: 10 * DAY,
+                apr: Some(Decimal::from_str("36.5").unwrap()),
+                partial: true,
+                stale: false,
+            },
+        ]
+    );
+}
@@ -4,28 +4,38 @@ use std::vec;
 use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     coin, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DistributionMsg, Event, Order,
-    OwnedDeps, Reply, StdError, StdResult, SubMsg, SubMsgResponse, Uint128, WasmMsg,
+    OwnedDeps, QuerierWrapper, Reply, StdError, StdResult, SubMsg, SubMsgResponse, Uint128,
+    WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, MinterResponse};
 use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
+use eris::amp_gauges::GaugeInfoResponse as AmpGauge;
+use eris::constants::DAY;
+use eris::emp_gauges::GaugeInfoResponse as EmpGauge;
+use eris::governance_helper::EPOCH_START;
 use eris::DecimalCheckedOps;
 
 use eris::hub::{
-    Batch, CallbackMsg, ConfigResponse, ExecuteMsg, FeeConfig, InstantiateMsg, PendingBatch,
-    QueryMsg, ReceiveMsg, StateResponse, UnbondRequest, UnbondRequestsByBatchResponseItem,
-    UnbondRequestsByUserResponseItem, UnbondRequestsByUserResponseItemDetails,
+    Batch, CallbackMsg, ConfigResponse, DelegationStrategy, ExecuteMsg, FeeConfig, GaugeOverrides,
+    InstantiateMsg, PendingBatch, QueryMsg, ReceiveMsg, RedelegationItem,
+    SimulateDelegationStrategyResponse, SlashingEvent, StakeTokenType, StateResponse,
+    UnbondRequest, UnbondRequestsByBatchResponseItem, UnbondRequestsByUserResponseItem,
+    UnbondRequestsByUserResponseItemDetails, ValidatorScoringConfig, WantedDelegationsShare,
 };
 use itertools::Itertools;
 
 use crate::constants::CONTRACT_DENOM;
 use crate::contract::{execute, instantiate, reply};
 use crate::error::ContractError;
-use crate::helpers::{dedupe, parse_received_fund};
+use crate::helpers::{dedupe, get_wanted_delegations, parse_received_fund};
 use crate::math::{
-    compute_redelegations_for_rebalancing, compute_redelegations_for_removal, compute_undelegations,
+    compute_redelegations_for_rebalancing, compute_redelegations_for_removal,
+    compute_undelegations, get_utoken_per_validator,
 };
+use crate::scoring::compute_validator_scores;
 use crate::state::State;
 use crate::testing::helpers::{query_helper_env, STAKE_DENOM};
+use crate::types::gauges::GaugeLoader;
 use crate::types::{Coins, Delegation, Redelegation, SendFee, Undelegation};
 
 use super::custom_querier::CustomQuerier;
@@ -48,6 +58,8 @@ fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
             name: "Stake Token".to_string(),
             symbol: "STAKE".to_string(),
             decimals: 6,
+            denom: CONTRACT_DENOM.to_string(),
+            token_factory_subdenom: None,
             epoch_period: 259200,   // 3 * 24 * 60 * 60 = 3 days
             unbond_period: 1814400, // 21 * 24 * 60 * 60 = 21 days
             validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
@@ -60,6 +72,7 @@ fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
                 min_delegation_bps: 100,
                 max_delegation_bps: 2500,
                 validator_count: 5,
+                slashing_penalty_bps: None,
             }),
             vote_operator: None,
         },
@@ -132,12 +145,16 @@ fn proper_instantiation() {
             owner: "owner".to_string(),
             new_owner: None,
             stake_token: STAKE_DENOM.to_string(),
+            stake_token_type: StakeTokenType::Cw20,
+            legacy_stake_token: None,
+            denom: CONTRACT_DENOM.to_string(),
             epoch_period: 259200,
             unbond_period: 1814400,
             validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
             fee_config: FeeConfig {
                 protocol_fee_contract: Addr::unchecked("fee"),
-                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128)
+                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+                fee_receivers: vec![]
             },
             delegation_strategy: eris::hub::DelegationStrategy::Gauges {
                 amp_gauges: "amp_gauges".to_string(),
@@ -146,7 +163,11 @@ fn proper_instantiation() {
                 min_delegation_bps: 100,
                 max_delegation_bps: 2500,
                 validator_count: 5,
-            }
+                slashing_penalty_bps: None,
+            },
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
         }
     );
 
@@ -155,12 +176,14 @@ fn proper_instantiation() {
         res,
         StateResponse {
             total_ustake: Uint128::zero(),
-            total_uluna: Uint128::zero(),
+            total_utoken: Uint128::zero(),
             exchange_rate: Decimal::one(),
             unlocked_coins: vec![],
             unbonding: Uint128::zero(),
             available: Uint128::zero(),
-            tvl_uluna: Uint128::zero(),
+            tvl_utoken: Uint128::zero(),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         },
     );
 
@@ -189,12 +212,16 @@ fn bonding() {
         mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: None,
+            referral: None,
         },
     )
     .unwrap();
 
     assert_eq!(res.messages.len(), 3);
-    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg(CONTRACT_DENOM))
+    );
     assert_eq!(
         res.messages[1],
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -228,12 +255,16 @@ fn bonding() {
         mock_info("user_2", &[Coin::new(12345, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: Some("user_3".to_string()),
+            referral: None,
         },
     )
     .unwrap();
 
     assert_eq!(res.messages.len(), 3);
-    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("charlie", 12345).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("charlie", 12345).to_cosmos_msg(CONTRACT_DENOM))
+    );
     assert_eq!(
         res.messages[1],
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -262,12 +293,14 @@ fn bonding() {
         res,
         StateResponse {
             total_ustake: Uint128::new(1012043),
-            total_uluna: Uint128::new(1037345),
+            total_utoken: Uint128::new(1037345),
             exchange_rate: Decimal::from_ratio(1037345u128, 1012043u128),
             unlocked_coins: vec![],
             unbonding: Uint128::zero(),
             available: Uint128::new(12567),
-            tvl_uluna: Uint128::new(1037345 + 12567),
+            tvl_utoken: Uint128::new(1037345 + 12567),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         }
     );
 }
@@ -285,12 +318,16 @@ fn donating() {
         mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: None,
+            referral: None,
         },
     )
     .unwrap();
 
     assert_eq!(res.messages.len(), 3);
-    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg(CONTRACT_DENOM))
+    );
     assert_eq!(
         res.messages[1],
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -320,12 +357,14 @@ fn donating() {
         res,
         StateResponse {
             total_ustake: Uint128::new(1000000),
-            total_uluna: Uint128::new(1025000),
+            total_utoken: Uint128::new(1025000),
             exchange_rate: Decimal::from_ratio(1025000u128, 1000000u128),
             unlocked_coins: vec![],
             unbonding: Uint128::zero(),
             available: Uint128::new(100),
-            tvl_uluna: Uint128::new(1025100),
+            tvl_utoken: Uint128::new(1025100),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         }
     );
 
@@ -352,6 +391,15 @@ fn donating() {
             vote_operator: None,
             epoch_period: None,
             unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
         },
     )
     .unwrap();
@@ -366,7 +414,10 @@ fn donating() {
     .unwrap();
 
     assert_eq!(res.messages.len(), 2);
-    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("charlie", 12345).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("charlie", 12345).to_cosmos_msg(CONTRACT_DENOM))
+    );
     assert_eq!(res.messages[1], check_received_coin(100));
 
     deps.querier.set_bank_balances(&[coin(100, CONTRACT_DENOM)]);
@@ -383,12 +434,14 @@ fn donating() {
         res,
         StateResponse {
             total_ustake: Uint128::new(1000000),
-            total_uluna: Uint128::new(1037345),
+            total_utoken: Uint128::new(1037345),
             exchange_rate: Decimal::from_ratio(1037345u128, 1000000u128),
             unlocked_coins: vec![],
             unbonding: Uint128::zero(),
             available: Uint128::new(100),
-            tvl_uluna: Uint128::new(1037345 + 100),
+            tvl_utoken: Uint128::new(1037345 + 100),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         }
     );
 }
@@ -516,12 +569,12 @@ fn reinvesting() {
 
     assert_eq!(
         res.messages[0],
-        SubMsg::new(Delegation::new("bob", delegated.u128()).to_cosmos_msg())
+        SubMsg::new(Delegation::new("bob", delegated.u128()).to_cosmos_msg(CONTRACT_DENOM))
     );
 
     assert_eq!(
         res.messages[1],
-        SubMsg::new(SendFee::new(Addr::unchecked("fee"), fee.u128()).to_cosmos_msg())
+        SubMsg::new(SendFee::new(Addr::unchecked("fee"), fee.u128()).to_cosmos_msg(CONTRACT_DENOM))
     );
 
     // Storage should have been updated
@@ -550,6 +603,7 @@ fn queuing_unbond() {
             amount: Uint128::new(69420),
             msg: to_json_binary(&ReceiveMsg::QueueUnbond {
                 receiver: None,
+                mint_receipt: false,
             })
             .unwrap(),
         }),
@@ -569,6 +623,7 @@ fn queuing_unbond() {
             amount: Uint128::new(23456),
             msg: to_json_binary(&ReceiveMsg::QueueUnbond {
                 receiver: None,
+                mint_receipt: false,
             })
             .unwrap(),
         }),
@@ -588,6 +643,7 @@ fn queuing_unbond() {
             amount: Uint128::new(69420),
             msg: to_json_binary(&ReceiveMsg::QueueUnbond {
                 receiver: Some("user_3".to_string()),
+                mint_receipt: false,
             })
             .unwrap(),
         }),
@@ -715,9 +771,18 @@ fn submitting_batch() {
     .unwrap();
 
     assert_eq!(res.messages.len(), 5);
-    assert_eq!(res.messages[0], SubMsg::new(Undelegation::new("alice", 31732).to_cosmos_msg()));
-    assert_eq!(res.messages[1], SubMsg::new(Undelegation::new("bob", 31733).to_cosmos_msg()));
-    assert_eq!(res.messages[2], SubMsg::new(Undelegation::new("charlie", 31732).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Undelegation::new("alice", 31732).to_cosmos_msg(CONTRACT_DENOM))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(Undelegation::new("bob", 31733).to_cosmos_msg(CONTRACT_DENOM))
+    );
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(Undelegation::new("charlie", 31732).to_cosmos_msg(CONTRACT_DENOM))
+    );
     assert_eq!(
         res.messages[3],
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -750,7 +815,7 @@ fn submitting_batch() {
             id: 1,
             reconciled: false,
             total_shares: Uint128::new(92876),
-            uluna_unclaimed: Uint128::new(95197),
+            utoken_unclaimed: Uint128::new(95197),
             est_unbond_end_time: 2083601 // 269,201 + 1,814,400
         }
     );
@@ -760,12 +825,14 @@ fn submitting_batch() {
         res,
         StateResponse {
             total_ustake: Uint128::from(1012043u128),
-            total_uluna: Uint128::from(1037345u128),
+            total_utoken: Uint128::from(1037345u128),
             exchange_rate: Decimal::from_ratio(1037345u128, 1012043u128),
             unlocked_coins: vec![],
             unbonding: Uint128::from(95197u128),
             available: Uint128::zero(),
-            tvl_uluna: Uint128::from(95197u128 + 1037345u128),
+            tvl_utoken: Uint128::from(95197u128 + 1037345u128),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         },
     );
 }
@@ -780,29 +847,29 @@ fn reconciling() {
             id: 1,
             reconciled: true,
             total_shares: Uint128::new(92876),
-            uluna_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
             est_unbond_end_time: 10000,
         },
         Batch {
             id: 2,
             reconciled: false,
             total_shares: Uint128::new(1345),
-            uluna_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
+            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
             est_unbond_end_time: 20000,
         },
         Batch {
             id: 3,
             reconciled: false,
             total_shares: Uint128::new(1456),
-            uluna_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
+            utoken_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
             est_unbond_end_time: 30000,
         },
         Batch {
             id: 4,
             reconciled: false,
             total_shares: Uint128::new(1567),
-            uluna_unclaimed: Uint128::new(1629), // 1.040 Token per Stake
-            est_unbond_end_time: 40000,          // not yet finished unbonding, ignored
+            utoken_unclaimed: Uint128::new(1629), // 1.040 Token per Stake
+            est_unbond_end_time: 40000,           // not yet finished unbonding, ignored
         },
     ];
 
@@ -830,7 +897,8 @@ fn reconciling() {
         .unwrap();
 
     deps.querier.set_bank_balances(&[
-        Coin::new(12345, CONTRACT_DENOM),
+        // batch 1 has been reconciled, but not withdrawn yet
+        Coin::new(95197 + 12345, CONTRACT_DENOM),
         Coin::new(234, "ukrw"),
         Coin::new(345, "uusd"),
         Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
@@ -840,15 +908,17 @@ fn reconciling() {
         deps.as_mut(),
         mock_env_at_timestamp(35000),
         mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
     )
     .unwrap();
 
     // Expected received: batch 2 + batch 3 = 1385 + 1506 = 2891
-    // Expected unlocked: 10000
-    // Expected: 12891
-    // Actual: 12345
-    // Shortfall: 12891 - 12345 = 456
+    // Expected unlocked: 10000 + batch 1 = 10000 + 95197 = 105197
+    // Expected: 108088
+    // Actual: 107542
+    // Shortfall: 108088 - 107542 = 546
     //
     // utoken per batch: 546 / 2 = 273
     // remainder: 0
@@ -861,7 +931,7 @@ fn reconciling() {
             id: 2,
             reconciled: true,
             total_shares: Uint128::new(1345),
-            uluna_unclaimed: Uint128::new(1112), // 1385 - 273
+            utoken_unclaimed: Uint128::new(1112), // 1385 - 273
             est_unbond_end_time: 20000,
         }
     );
@@ -873,7 +943,7 @@ fn reconciling() {
             id: 3,
             reconciled: true,
             total_shares: Uint128::new(1456),
-            uluna_unclaimed: Uint128::new(1233), // 1506 - 273
+            utoken_unclaimed: Uint128::new(1233), // 1506 - 273
             est_unbond_end_time: 30000,
         }
     );
@@ -896,28 +966,28 @@ fn reconciling_even_when_everything_ok() {
             id: 1,
             reconciled: true,
             total_shares: Uint128::new(100000),
-            uluna_unclaimed: Uint128::new(100000),
+            utoken_unclaimed: Uint128::new(100000),
             est_unbond_end_time: 10000,
         },
         Batch {
             id: 2,
             reconciled: false,
             total_shares: Uint128::new(1000),
-            uluna_unclaimed: Uint128::new(1000),
+            utoken_unclaimed: Uint128::new(1000),
             est_unbond_end_time: 20000,
         },
         Batch {
             id: 3,
             reconciled: false,
             total_shares: Uint128::new(1500),
-            uluna_unclaimed: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
             est_unbond_end_time: 30000,
         },
         Batch {
             id: 4,
             reconciled: false,
             total_shares: Uint128::new(1500),
-            uluna_unclaimed: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
             est_unbond_end_time: 40000, // not yet finished unbonding, ignored
         },
     ];
@@ -934,13 +1004,16 @@ fn reconciling_even_when_everything_ok() {
         .save(deps.as_mut().storage, &vec![Coin::new(1000, CONTRACT_DENOM)])
         .unwrap();
 
-    deps.querier.set_bank_balances(&[Coin::new(3500, CONTRACT_DENOM)]);
+    // batch 1 has been reconciled, but not withdrawn yet
+    deps.querier.set_bank_balances(&[Coin::new(100000 + 3500, CONTRACT_DENOM)]);
 
     execute(
         deps.as_mut(),
         mock_env_at_timestamp(35000),
         mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
     )
     .unwrap();
 
@@ -951,7 +1024,7 @@ fn reconciling_even_when_everything_ok() {
             id: 2,
             reconciled: true,
             total_shares: Uint128::new(1000),
-            uluna_unclaimed: Uint128::new(1000),
+            utoken_unclaimed: Uint128::new(1000),
             est_unbond_end_time: 20000,
         }
     );
@@ -963,7 +1036,7 @@ fn reconciling_even_when_everything_ok() {
             id: 3,
             reconciled: true,
             total_shares: Uint128::new(1500),
-            uluna_unclaimed: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
             est_unbond_end_time: 30000,
         }
     );
@@ -1029,28 +1102,28 @@ fn withdrawing_unbonded() {
             id: 1,
             reconciled: true,
             total_shares: Uint128::new(92876),
-            uluna_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
             est_unbond_end_time: 10000,
         },
         Batch {
             id: 2,
             reconciled: true,
             total_shares: Uint128::new(34567),
-            uluna_unclaimed: Uint128::new(35604), // 1.030 Token per Stake
+            utoken_unclaimed: Uint128::new(35604), // 1.030 Token per Stake
             est_unbond_end_time: 20000,
         },
         Batch {
             id: 3,
             reconciled: false, // finished unbonding, but not reconciled; ignored
             total_shares: Uint128::new(45678),
-            uluna_unclaimed: Uint128::new(47276), // 1.035 Token per Stake
+            utoken_unclaimed: Uint128::new(47276), // 1.035 Token per Stake
             est_unbond_end_time: 20000,
         },
         Batch {
             id: 4,
             reconciled: true,
             total_shares: Uint128::new(56789),
-            uluna_unclaimed: Uint128::new(59060), // 1.040 Token per Stake
+            utoken_unclaimed: Uint128::new(59060), // 1.040 Token per Stake
             est_unbond_end_time: 30000, // reconciled, but not yet finished unbonding; ignored
         },
     ];
@@ -1081,6 +1154,8 @@ fn withdrawing_unbonded() {
         mock_info("user_1", &[]),
         ExecuteMsg::WithdrawUnbonded {
             receiver: None,
+            batch_ids: None,
+            limit: None,
         },
     )
     .unwrap_err();
@@ -1104,6 +1179,8 @@ fn withdrawing_unbonded() {
         mock_info("user_1", &[]),
         ExecuteMsg::WithdrawUnbonded {
             receiver: None,
+            batch_ids: None,
+            limit: None,
         },
     )
     .unwrap();
@@ -1125,7 +1202,7 @@ fn withdrawing_unbonded() {
             id: 1,
             reconciled: true,
             total_shares: Uint128::new(69420),
-            uluna_unclaimed: Uint128::new(71155),
+            utoken_unclaimed: Uint128::new(71155),
             est_unbond_end_time: 10000,
         }
     );
@@ -1168,6 +1245,8 @@ fn withdrawing_unbonded() {
         mock_info("user_3", &[]),
         ExecuteMsg::WithdrawUnbonded {
             receiver: Some("user_2".to_string()),
+            batch_ids: None,
+            limit: None,
         },
     )
     .unwrap();
@@ -1312,6 +1391,70 @@ fn removing_validator() {
     assert_eq!(validators, vec![String::from("alice"), String::from("bob")],);
 }
 
+#[test]
+fn evicting_inactive_validators() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    state
+        .delegation_goal
+        .save(
+            deps.as_mut().storage,
+            &WantedDelegationsShare {
+                tune_time: 10000,
+                tune_period: 0,
+                shares: vec![
+                    ("alice".to_string(), Decimal::percent(50)),
+                    ("bob".to_string(), Decimal::percent(25)),
+                    ("charlie".to_string(), Decimal::percent(25)),
+                ],
+            },
+        )
+        .unwrap();
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 500),
+        Delegation::new("bob", 250),
+        Delegation::new("charlie", 250),
+    ]);
+    deps.querier
+        .set_staking_validators(&[("alice", Decimal::percent(5)), ("bob", Decimal::percent(5))]);
+
+    // Target:  alice 2/3, bob 1/3 of 1000
+    // Alice:   666 + 1 - 500 = 167
+    // Bob:     333 - 250 = 83
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::EvictInactiveValidators {},
+    )
+    .unwrap();
+
+    // Gauges redelegates away from evicted validators right away
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Redelegation::new("charlie", "alice", 167).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(Redelegation::new("charlie", "bob", 83).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+    assert_eq!(res.messages[2], check_received_coin(0));
+
+    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    assert_eq!(validators, vec![String::from("alice"), String::from("bob")]);
+
+    let goal = state.delegation_goal.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        goal.shares,
+        vec![
+            ("alice".to_string(), Decimal::from_ratio(2u128, 3u128)),
+            ("bob".to_string(), Decimal::from_ratio(1u128, 3u128)),
+        ]
+    );
+}
+
 #[test]
 fn transferring_ownership() {
     let mut deps = setup_test();
@@ -1421,7 +1564,8 @@ fn update_fee() {
         config,
         FeeConfig {
             protocol_fee_contract: Addr::unchecked("fee"),
-            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128)
+            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+            fee_receivers: vec![]
         }
     );
 
@@ -1437,6 +1581,15 @@ fn update_fee() {
             vote_operator: None,
             epoch_period: None,
             unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
         },
     )
     .unwrap_err();
@@ -1454,6 +1607,15 @@ fn update_fee() {
             vote_operator: None,
             epoch_period: None,
             unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
         },
     )
     .unwrap_err();
@@ -1471,6 +1633,15 @@ fn update_fee() {
             vote_operator: None,
             epoch_period: None,
             unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
         },
     )
     .unwrap();
@@ -1482,11 +1653,333 @@ fn update_fee() {
         config,
         FeeConfig {
             protocol_fee_contract: Addr::unchecked("fee-new"),
-            protocol_reward_fee: Decimal::from_ratio(10u128, 100u128)
+            protocol_reward_fee: Decimal::from_ratio(10u128, 100u128),
+            fee_receivers: vec![]
         }
     );
 }
 
+fn validator_scoring_config() -> ValidatorScoringConfig {
+    ValidatorScoringConfig {
+        max_commission: Decimal::percent(10),
+        slashing_penalty_bps: 2500,
+        slashing_lookback: 10000,
+    }
+}
+
+#[test]
+fn update_validator_scoring() {
+    let mut deps = setup_test();
+
+    let update_msg = |validator_scoring| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        delegation_strategy: None,
+        allow_donations: None,
+        vote_operator: None,
+        epoch_period: None,
+        unbond_period: None,
+        instant_unbond: None,
+        validator_scoring: Some(validator_scoring),
+        unbond_receipt_nft: None,
+        fee_receivers: None,
+        referral_fee: None,
+        guardian: None,
+        timelock_delay_s: None,
+        keeper_tips: None,
+        prop_gauges: None,
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_msg(ValidatorScoringConfig {
+            max_commission: Decimal::percent(101),
+            ..validator_scoring_config()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ValidatorScoringInvalid {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_msg(ValidatorScoringConfig {
+            slashing_penalty_bps: 10001,
+            ..validator_scoring_config()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ValidatorScoringInvalid {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_msg(validator_scoring_config()),
+    )
+    .unwrap();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.validator_scoring, Some(validator_scoring_config()));
+}
+
+#[test]
+fn scoring_validators() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // bob charges too much and dave is not part of the active set
+    deps.querier.set_staking_validators(&[
+        ("alice", Decimal::percent(5)),
+        ("bob", Decimal::percent(20)),
+        ("charlie", Decimal::percent(5)),
+    ]);
+
+    for (id, time) in [(1u64, 5000u64), (2, 12000), (3, 15000)] {
+        state
+            .slashing_events
+            .save(
+                deps.as_mut().storage,
+                id,
+                &SlashingEvent {
+                    id,
+                    validator: "charlie".to_string(),
+                    batch_id: 1,
+                    amount: Uint128::new(100),
+                    time,
+                },
+            )
+            .unwrap();
+    }
+
+    let deps_ref = deps.as_ref();
+    let scores = compute_validator_scores(
+        &state,
+        deps_ref.storage,
+        &deps_ref.querier,
+        &mock_env_at_timestamp(20000),
+        &validator_scoring_config(),
+        &["alice".to_string(), "bob".to_string(), "charlie".to_string(), "dave".to_string()],
+    )
+    .unwrap();
+
+    // charlie has been slashed twice within the lookback period
+    assert_eq!(
+        scores,
+        vec![
+            ("alice".to_string(), Decimal::percent(95)),
+            ("bob".to_string(), Decimal::zero()),
+            ("charlie".to_string(), Decimal::from_str("0.475").unwrap()),
+            ("dave".to_string(), Decimal::zero()),
+        ]
+    );
+}
+
+#[test]
+fn applying_validator_scores() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    state
+        .delegation_strategy
+        .save(
+            deps.as_mut().storage,
+            &DelegationStrategy::Gauges {
+                amp_gauges: Addr::unchecked("amp_gauges"),
+                emp_gauges: None,
+                amp_factor_bps: 10000,
+                min_delegation_bps: 100,
+                max_delegation_bps: 5000,
+                validator_count: 5,
+                slashing_penalty_bps: None,
+            },
+        )
+        .unwrap();
+    state
+        .delegation_goal
+        .save(
+            deps.as_mut().storage,
+            &WantedDelegationsShare {
+                tune_time: 10000,
+                tune_period: 0,
+                shares: vec![
+                    ("alice".to_string(), Decimal::percent(50)),
+                    ("bob".to_string(), Decimal::percent(25)),
+                    ("charlie".to_string(), Decimal::percent(25)),
+                ],
+            },
+        )
+        .unwrap();
+
+    state.validator_scoring.save(deps.as_mut().storage, &validator_scoring_config()).unwrap();
+
+    let validators = vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()];
+    let (utoken_per_validator, _, _, _) =
+        get_utoken_per_validator(&state, deps.as_ref().storage, 1000, &validators, None).unwrap();
+    assert_eq!(utoken_per_validator.get("alice"), Some(&Uint128::new(500)));
+    assert_eq!(utoken_per_validator.get("bob"), Some(&Uint128::new(250)));
+    assert_eq!(utoken_per_validator.get("charlie"), Some(&Uint128::new(250)));
+
+    state
+        .validator_scores
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                ("alice".to_string(), Decimal::one()),
+                ("bob".to_string(), Decimal::zero()),
+                ("charlie".to_string(), Decimal::percent(50)),
+            ],
+        )
+        .unwrap();
+
+    // bob loses all delegations, charlie only keeps half of the score
+    let (utoken_per_validator, add, remove, _) =
+        get_utoken_per_validator(&state, deps.as_ref().storage, 1000, &validators, None).unwrap();
+    assert_eq!(utoken_per_validator.get("alice"), Some(&Uint128::new(800)));
+    assert_eq!(utoken_per_validator.get("bob"), None);
+    assert_eq!(utoken_per_validator.get("charlie"), Some(&Uint128::new(200)));
+    assert_eq!(add, None);
+    assert_eq!(remove, None);
+}
+
+#[test]
+fn rescoring_validators_without_tune() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    state.validator_scoring.save(deps.as_mut().storage, &validator_scoring_config()).unwrap();
+    state
+        .delegation_goal
+        .save(
+            deps.as_mut().storage,
+            &WantedDelegationsShare {
+                tune_time: 10000,
+                tune_period: 0,
+                shares: vec![
+                    ("alice".to_string(), Decimal::percent(50)),
+                    ("bob".to_string(), Decimal::percent(25)),
+                    ("charlie".to_string(), Decimal::percent(25)),
+                ],
+            },
+        )
+        .unwrap();
+
+    deps.querier.set_staking_validators(&[
+        ("alice", Decimal::percent(5)),
+        ("bob", Decimal::percent(5)),
+        ("charlie", Decimal::percent(5)),
+    ]);
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 500),
+        Delegation::new("bob", 250),
+        Delegation::new("charlie", 250),
+    ]);
+
+    let rebalance_msg = ExecuteMsg::Rebalance {
+        min_redelegation: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), rebalance_msg.clone()).unwrap();
+
+    let scores: Vec<(String, Decimal)> = query_helper(deps.as_ref(), QueryMsg::ValidatorScores {});
+    assert_eq!(
+        scores,
+        vec![
+            ("alice".to_string(), Decimal::percent(95)),
+            ("bob".to_string(), Decimal::percent(95)),
+            ("charlie".to_string(), Decimal::percent(95)),
+        ]
+    );
+
+    // bob got jailed, it loses its score at the next rebalance
+    deps.querier.set_staking_validators(&[
+        ("alice", Decimal::percent(5)),
+        ("charlie", Decimal::percent(5)),
+    ]);
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), rebalance_msg).unwrap();
+
+    let scores: Vec<(String, Decimal)> = query_helper(deps.as_ref(), QueryMsg::ValidatorScores {});
+    assert_eq!(scores[1], ("bob".to_string(), Decimal::zero()));
+}
+
+#[test]
+fn scoring_replaces_gauge_limits_and_penalty() {
+    let mut deps = setup_test();
+    let state = State::default();
+    let env = mock_env_at_timestamp(EPOCH_START + 10000);
+
+    state
+        .delegation_strategy
+        .save(
+            deps.as_mut().storage,
+            &DelegationStrategy::Gauges {
+                amp_gauges: Addr::unchecked("amp_gauges"),
+                emp_gauges: None,
+                amp_factor_bps: 10000,
+                min_delegation_bps: 100,
+                max_delegation_bps: 5000,
+                validator_count: 5,
+                slashing_penalty_bps: Some(5000),
+            },
+        )
+        .unwrap();
+    state
+        .slashing_events
+        .save(
+            deps.as_mut().storage,
+            1u64,
+            &SlashingEvent {
+                id: 1,
+                validator: "charlie".to_string(),
+                batch_id: 1,
+                amount: Uint128::new(100),
+                time: EPOCH_START + 5000,
+            },
+        )
+        .unwrap();
+
+    let loader = || MockGaugeLoader {
+        vamp_points: vec![
+            ("alice".to_string(), Uint128::new(100)),
+            ("bob".to_string(), Uint128::new(100)),
+            ("charlie".to_string(), Uint128::new(600)),
+        ],
+    };
+
+    // charlie is clamped to the max delegation and penalized for the slashing
+    let deps_ref = deps.as_ref();
+    let (goal, _) =
+        get_wanted_delegations(&state, &env, deps_ref.storage, &deps_ref.querier, loader())
+            .unwrap();
+    assert_eq!(
+        goal.shares,
+        vec![
+            ("charlie".to_string(), Decimal::percent(50)),
+            ("alice".to_string(), Decimal::percent(25)),
+            ("bob".to_string(), Decimal::percent(25)),
+        ]
+    );
+
+    // with scoring, the goal keeps the gauge shares. The scores, including the slashing penalty,
+    // are applied before the min and max delegation when delegating.
+    state.validator_scoring.save(deps.as_mut().storage, &validator_scoring_config()).unwrap();
+
+    let deps_ref = deps.as_ref();
+    let (goal, _) =
+        get_wanted_delegations(&state, &env, deps_ref.storage, &deps_ref.querier, loader())
+            .unwrap();
+    assert_eq!(
+        goal.shares,
+        vec![
+            ("charlie".to_string(), Decimal::percent(75)),
+            ("alice".to_string(), Decimal::permille(125)),
+            ("bob".to_string(), Decimal::permille(125)),
+        ]
+    );
+}
+
 //--------------------------------------------------------------------------------------------------
 // Queries
 //--------------------------------------------------------------------------------------------------
@@ -1500,28 +1993,28 @@ fn querying_previous_batches() {
             id: 1,
             reconciled: false,
             total_shares: Uint128::new(123),
-            uluna_unclaimed: Uint128::new(678),
+            utoken_unclaimed: Uint128::new(678),
             est_unbond_end_time: 10000,
         },
         Batch {
             id: 2,
             reconciled: true,
             total_shares: Uint128::new(234),
-            uluna_unclaimed: Uint128::new(789),
+            utoken_unclaimed: Uint128::new(789),
             est_unbond_end_time: 15000,
         },
         Batch {
             id: 3,
             reconciled: false,
             total_shares: Uint128::new(345),
-            uluna_unclaimed: Uint128::new(890),
+            utoken_unclaimed: Uint128::new(890),
             est_unbond_end_time: 20000,
         },
         Batch {
             id: 4,
             reconciled: true,
             total_shares: Uint128::new(456),
-            uluna_unclaimed: Uint128::new(999),
+            utoken_unclaimed: Uint128::new(999),
             est_unbond_end_time: 25000,
         },
     ];
@@ -1729,14 +2222,14 @@ fn querying_unbond_requests_details() {
             id: 1,
             reconciled: false,
             total_shares: Uint128::new(123),
-            uluna_unclaimed: Uint128::new(678),
+            utoken_unclaimed: Uint128::new(678),
             est_unbond_end_time: 10000,
         },
         Batch {
             id: 2,
             reconciled: false,
             total_shares: Uint128::new(234),
-            uluna_unclaimed: Uint128::new(789),
+            utoken_unclaimed: Uint128::new(789),
             est_unbond_end_time: 15000,
         },
     ];
@@ -1838,7 +2331,7 @@ fn computing_redelegations_for_removal() -> StdResult<()> {
         Delegation::new("dave", 10000),
     ];
     // Suppose Dave will be removed
-    // uluna_per_validator = (13000 + 12000 + 11000 + 10000) / 3 = 15333
+    // utoken_per_validator = (13000 + 12000 + 11000 + 10000) / 3 = 15333
     // remainder = 1
     // to Alice:   15333 + 1 - 13000 = 2334
     // to Bob:     15333 + 0 - 12000 = 3333
@@ -1872,7 +2365,7 @@ fn computing_redelegations_for_rebalancing() -> StdResult<()> {
         Delegation::new("dave", 40471),
         Delegation::new("evan", 2345),
     ];
-    // uluna_per_validator = (69420 + 88888 + 1234 + 40471 + 2345) / 4 = 40471
+    // utoken_per_validator = (69420 + 88888 + 1234 + 40471 + 2345) / 4 = 40471
     // remainer = 3
     // src_delegations:
     //  - alice:   69420 - (40471 + 3) = 28946
@@ -1906,7 +2399,8 @@ fn computing_redelegations_for_rebalancing() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         expected,
     );
@@ -1940,7 +2434,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         vec![],
     );
@@ -1956,7 +2451,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         vec![Redelegation::new("unlisted", "alice", 25000)],
     );
@@ -1972,7 +2468,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         vec![
             Redelegation::new("unlisted", "alice", 25000),
@@ -1990,7 +2487,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         vec![
             Redelegation::new("charlie", "alice", 27500),
@@ -2046,6 +2544,208 @@ fn receiving_funds() {
     assert_eq!(amount, Uint128::new(69420));
 }
 
+struct MockGaugeLoader {
+    vamp_points: Vec<(String, Uint128)>,
+}
+
+impl GaugeLoader for MockGaugeLoader {
+    fn get_amp_tune_info(&self, _: &QuerierWrapper, _: Addr) -> StdResult<AmpGauge> {
+        Ok(AmpGauge {
+            tune_ts: 0,
+            vamp_points: self.vamp_points.clone(),
+        })
+    }
+
+    fn get_emp_tune_info(&self, _: &QuerierWrapper, _: Addr) -> StdResult<EmpGauge> {
+        Err(StdError::generic_err("no emp gauges"))
+    }
+}
+
+#[test]
+fn penalizing_slashed_validators() {
+    let mut deps = setup_test();
+    let state = State::default();
+    let env = mock_env_at_timestamp(EPOCH_START + 10000);
+
+    state
+        .delegation_strategy
+        .save(
+            deps.as_mut().storage,
+            &DelegationStrategy::Gauges {
+                amp_gauges: Addr::unchecked("amp_gauges"),
+                emp_gauges: None,
+                amp_factor_bps: 10000,
+                min_delegation_bps: 100,
+                max_delegation_bps: 10000,
+                validator_count: 5,
+                slashing_penalty_bps: Some(5000),
+            },
+        )
+        .unwrap();
+
+    let loader = || MockGaugeLoader {
+        vamp_points: vec![
+            ("alice".to_string(), Uint128::new(100)),
+            ("bob".to_string(), Uint128::new(100)),
+            ("charlie".to_string(), Uint128::new(200)),
+        ],
+    };
+
+    let deps_ref = deps.as_ref();
+    let (goal, _) =
+        get_wanted_delegations(&state, &env, deps_ref.storage, &deps_ref.querier, loader())
+            .unwrap();
+    assert_eq!(
+        goal.shares,
+        vec![
+            ("charlie".to_string(), Decimal::percent(50)),
+            ("alice".to_string(), Decimal::percent(25)),
+            ("bob".to_string(), Decimal::percent(25)),
+        ]
+    );
+
+    state
+        .slashing_events
+        .save(
+            deps.as_mut().storage,
+            1u64,
+            &SlashingEvent {
+                id: 1,
+                validator: "charlie".to_string(),
+                batch_id: 1,
+                amount: Uint128::new(100),
+                time: EPOCH_START + 5000,
+            },
+        )
+        .unwrap();
+
+    // charlie only keeps half of its score
+    let deps_ref = deps.as_ref();
+    let (goal, _) =
+        get_wanted_delegations(&state, &env, deps_ref.storage, &deps_ref.querier, loader())
+            .unwrap();
+    assert_eq!(
+        goal.shares,
+        vec![
+            ("charlie".to_string(), Decimal::from_ratio(1u128, 3u128)),
+            ("alice".to_string(), Decimal::from_ratio(1u128, 3u128)),
+            ("bob".to_string(), Decimal::from_ratio(1u128, 3u128)),
+        ]
+    );
+
+    // the penalty is kept after a tune
+    state
+        .delegation_goal
+        .save(
+            deps.as_mut().storage,
+            &WantedDelegationsShare {
+                tune_time: EPOCH_START + 6000,
+                tune_period: 0,
+                shares: vec![],
+            },
+        )
+        .unwrap();
+
+    let deps_ref = deps.as_ref();
+    let (goal, _) =
+        get_wanted_delegations(&state, &env, deps_ref.storage, &deps_ref.querier, loader())
+            .unwrap();
+    assert_eq!(goal.shares[0], ("charlie".to_string(), Decimal::from_ratio(1u128, 3u128)));
+
+    // until the penalty period has passed
+    let env = mock_env_at_timestamp(EPOCH_START + 5000 + 90 * DAY);
+    let deps_ref = deps.as_ref();
+    let (goal, _) =
+        get_wanted_delegations(&state, &env, deps_ref.storage, &deps_ref.querier, loader())
+            .unwrap();
+    assert_eq!(goal.shares[0], ("charlie".to_string(), Decimal::percent(50)));
+}
+
+#[test]
+fn simulating_delegation_strategy() {
+    let mut deps = setup_test();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 600),
+        Delegation::new("bob", 300),
+        Delegation::new("charlie", 300),
+    ]);
+
+    let res: SimulateDelegationStrategyResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::SimulateDelegationStrategy {
+            strategy: DelegationStrategy::Gauges {
+                amp_gauges: "amp_gauges".to_string(),
+                emp_gauges: None,
+                amp_factor_bps: 10000,
+                min_delegation_bps: 100,
+                max_delegation_bps: 10000,
+                validator_count: 5,
+                slashing_penalty_bps: None,
+            },
+            total_uluna: Some(Uint128::new(2000)),
+            overrides: Some(GaugeOverrides {
+                vamp_points: Some(vec![
+                    ("alice".to_string(), Uint128::new(100)),
+                    ("bob".to_string(), Uint128::new(100)),
+                    ("charlie".to_string(), Uint128::new(200)),
+                ]),
+                emp_points: None,
+            }),
+        },
+    );
+
+    assert_eq!(
+        res,
+        SimulateDelegationStrategyResponse {
+            delegations: vec![
+                ("charlie".to_string(), Uint128::new(1000)),
+                ("alice".to_string(), Uint128::new(500)),
+                ("bob".to_string(), Uint128::new(500)),
+            ],
+            redelegations: vec![RedelegationItem {
+                src: "alice".to_string(),
+                dst: "charlie".to_string(),
+                amount: Uint128::new(300),
+            }],
+        }
+    );
+
+    // without an amount, the currently delegated amount is distributed. The rounding remainder
+    // of the uniform shares is added to the first validator when rebalancing.
+    let res: SimulateDelegationStrategyResponse = query_helper(
+        deps.as_ref(),
+        QueryMsg::SimulateDelegationStrategy {
+            strategy: DelegationStrategy::Uniform {},
+            total_uluna: None,
+            overrides: None,
+        },
+    );
+
+    assert_eq!(
+        res,
+        SimulateDelegationStrategyResponse {
+            delegations: vec![
+                ("alice".to_string(), Uint128::new(399)),
+                ("bob".to_string(), Uint128::new(399)),
+                ("charlie".to_string(), Uint128::new(399)),
+            ],
+            redelegations: vec![
+                RedelegationItem {
+                    src: "alice".to_string(),
+                    dst: "bob".to_string(),
+                    amount: Uint128::new(99),
+                },
+                RedelegationItem {
+                    src: "alice".to_string(),
+                    dst: "charlie".to_string(),
+                    amount: Uint128::new(99),
+                },
+            ],
+        }
+    );
+}
+
 #[test]
 fn running_dedup() {
     let mut validators = vec![
@@ -0,0 +1,495 @@
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    coin, to_json_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Event, OwnedDeps, StdError,
+    SubMsg, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+
+use eris::hub::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, PendingBatch, QueryMsg, ReceiveMsg, StakeTokenType,
+    StateResponse, SudoMsg, UnbondRequestsByBatchResponseItem,
+};
+
+use crate::constants::CONTRACT_DENOM;
+use crate::contract::{execute, instantiate, sudo};
+use crate::error::ContractError;
+use crate::protos::token_factory::{MsgBurn, MsgCreateDenom, MsgMint, MsgSetBeforeSendHook};
+use crate::state::State;
+use crate::types::Delegation;
+
+use super::custom_querier::CustomQuerier;
+use super::helpers::{
+    mock_dependencies, mock_env_at_timestamp, query_helper, setup_test as setup_cw20_test,
+    STAKE_DENOM,
+};
+
+const TOKEN_FACTORY_DENOM: &str = "factory/cosmos2contract/stake";
+
+fn setup_test() -> OwnedDeps<MockStorage, MockApi, CustomQuerier> {
+    let mut deps = mock_dependencies();
+
+    let res = instantiate(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("deployer", &[]),
+        InstantiateMsg {
+            cw20_code_id: 69420,
+            owner: "owner".to_string(),
+            name: "Stake Token".to_string(),
+            symbol: "STAKE".to_string(),
+            decimals: 6,
+            denom: CONTRACT_DENOM.to_string(),
+            token_factory_subdenom: Some("stake".to_string()),
+            epoch_period: 259200,   // 3 * 24 * 60 * 60 = 3 days
+            unbond_period: 1814400, // 21 * 24 * 60 * 60 = 21 days
+            validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
+            protocol_fee_contract: "fee".to_string(),
+            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+            delegation_strategy: None,
+            vote_operator: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.messages[0], SubMsg::new(create_denom_msg()));
+    assert_eq!(res.messages[1], SubMsg::new(set_before_send_hook_msg()));
+
+    deps.querier.set_bank_supply(TOKEN_FACTORY_DENOM, 0);
+    deps
+}
+
+fn create_denom_msg() -> CosmosMsg {
+    MsgCreateDenom {
+        sender: MOCK_CONTRACT_ADDR.to_string(),
+        subdenom: "stake".to_string(),
+    }
+    .to_cosmos_msg()
+}
+
+fn set_before_send_hook_msg() -> CosmosMsg {
+    MsgSetBeforeSendHook {
+        sender: MOCK_CONTRACT_ADDR.to_string(),
+        denom: TOKEN_FACTORY_DENOM.to_string(),
+        cosmwasm_address: MOCK_CONTRACT_ADDR.to_string(),
+    }
+    .to_cosmos_msg()
+}
+
+fn mint_msg(recipient: &str, amount: u128) -> CosmosMsg {
+    MsgMint {
+        sender: MOCK_CONTRACT_ADDR.to_string(),
+        amount: coin(amount, TOKEN_FACTORY_DENOM),
+        mint_to_address: recipient.to_string(),
+    }
+    .to_cosmos_msg()
+}
+
+fn burn_cw20_msg(amount: u128) -> CosmosMsg {
+    CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: STAKE_DENOM.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::Burn {
+            amount: Uint128::new(amount),
+        })
+        .unwrap(),
+        funds: vec![],
+    })
+}
+
+fn receive_msg(sender: &str, amount: u128, msg: ReceiveMsg) -> ExecuteMsg {
+    ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: sender.to_string(),
+        amount: Uint128::new(amount),
+        msg: to_json_binary(&msg).unwrap(),
+    })
+}
+
+#[test]
+fn encoding_token_factory_msgs() {
+    assert_eq!(
+        create_denom_msg(),
+        CosmosMsg::Stargate {
+            type_url: "/osmosis.tokenfactory.v1beta1.MsgCreateDenom".to_string(),
+            value: Binary::from(b"\x0a\x0fcosmos2contract\x12\x05stake".to_vec()),
+        }
+    );
+
+    assert_eq!(
+        MsgMint {
+            sender: "hub".to_string(),
+            amount: coin(100, "d"),
+            mint_to_address: "user".to_string(),
+        }
+        .to_cosmos_msg(),
+        CosmosMsg::Stargate {
+            type_url: "/osmosis.tokenfactory.v1beta1.MsgMint".to_string(),
+            value: Binary::from(b"\x0a\x03hub\x12\x08\x0a\x01d\x12\x03100\x1a\x04user".to_vec()),
+        }
+    );
+
+    assert_eq!(
+        MsgBurn {
+            sender: "hub".to_string(),
+            amount: coin(100, "d"),
+            burn_from_address: "hub".to_string(),
+        }
+        .to_cosmos_msg(),
+        CosmosMsg::Stargate {
+            type_url: "/osmosis.tokenfactory.v1beta1.MsgBurn".to_string(),
+            value: Binary::from(b"\x0a\x03hub\x12\x08\x0a\x01d\x12\x03100\x1a\x03hub".to_vec()),
+        }
+    );
+
+    assert_eq!(
+        MsgSetBeforeSendHook {
+            sender: "hub".to_string(),
+            denom: "d".to_string(),
+            cosmwasm_address: "hub".to_string(),
+        }
+        .to_cosmos_msg(),
+        CosmosMsg::Stargate {
+            type_url: "/osmosis.tokenfactory.v1beta1.MsgSetBeforeSendHook".to_string(),
+            value: Binary::from(b"\x0a\x03hub\x12\x01d\x1a\x03hub".to_vec()),
+        }
+    );
+}
+
+#[test]
+fn proper_instantiation() {
+    let deps = setup_test();
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.stake_token, TOKEN_FACTORY_DENOM.to_string());
+    assert_eq!(res.stake_token_type, StakeTokenType::TokenFactory);
+    assert_eq!(res.legacy_stake_token, None);
+}
+
+#[test]
+fn bonding() {
+    let mut deps = setup_test();
+
+    deps.querier.set_bank_balances(&[coin(1000100, CONTRACT_DENOM)]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            referral: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg(CONTRACT_DENOM))
+    );
+    assert_eq!(res.messages[1], SubMsg::new(mint_msg("user_1", 1000000)));
+
+    // The exchange rate is based on the supply of the token-factory denom
+    deps.querier.set_bank_balances(&[coin(12345 + 222, CONTRACT_DENOM)]);
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 341666),
+    ]);
+    deps.querier.set_bank_supply(TOKEN_FACTORY_DENOM, 1000000);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_2", &[Coin::new(12345, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: Some("user_3".to_string()),
+            referral: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(res.messages[1], SubMsg::new(mint_msg("user_3", 12043)));
+}
+
+#[test]
+fn releasing_referred_stake_on_transfer() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    deps.querier.set_bank_balances(&[coin(100000, CONTRACT_DENOM)]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(100000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            referral: Some("partner".to_string()),
+        },
+    )
+    .unwrap();
+
+    let transfer = |to: &str, amount: u128| SudoMsg::TrackBeforeSend {
+        from: "user_1".to_string(),
+        to: to.to_string(),
+        amount: coin(amount, TOKEN_FACTORY_DENOM),
+    };
+
+    // Sending to the hub is released by the unbond it is sent for
+    sudo(deps.as_mut(), mock_env(), transfer(MOCK_CONTRACT_ADDR, 10000)).unwrap();
+    let stats = state.load_referral(deps.as_ref().storage, &Addr::unchecked("partner")).unwrap();
+    assert_eq!(stats.ustake_referred, Uint128::new(100000));
+
+    // Other denoms are ignored
+    sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::TrackBeforeSend {
+            from: "user_1".to_string(),
+            to: "user_2".to_string(),
+            amount: coin(10000, CONTRACT_DENOM),
+        },
+    )
+    .unwrap();
+    let stats = state.load_referral(deps.as_ref().storage, &Addr::unchecked("partner")).unwrap();
+    assert_eq!(stats.ustake_referred, Uint128::new(100000));
+
+    let res = sudo(deps.as_mut(), mock_env(), transfer("user_2", 30000)).unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/referred_stake_released")
+            .add_attribute("holder", "user_1")
+            .add_attribute("ustake_released", "30000")]
+    );
+
+    let stats = state.load_referral(deps.as_ref().storage, &Addr::unchecked("partner")).unwrap();
+    assert_eq!(stats.ustake_referred, Uint128::new(70000));
+    let total = state.total_ustake_referred.load(deps.as_ref().storage).unwrap();
+    assert_eq!(total, Uint128::new(70000));
+}
+
+#[test]
+fn queuing_unbond() {
+    let mut deps = setup_test();
+
+    // Only the token-factory denom is accepted
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info("user_1", &[Coin::new(23456, CONTRACT_DENOM)]),
+        ExecuteMsg::QueueUnbond {
+            receiver: None,
+            mint_receipt: false,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(format!(
+            "expected {} deposit, received {}",
+            TOKEN_FACTORY_DENOM, CONTRACT_DENOM
+        )))
+    );
+
+    // There is no CW20 stake token that could be received
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info(STAKE_DENOM, &[]),
+        receive_msg(
+            "user_1",
+            23456,
+            ReceiveMsg::QueueUnbond {
+                receiver: None,
+                mint_receipt: false,
+            },
+        ),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::ExpectingStakeToken(STAKE_DENOM.to_string()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345), // est_unbond_start_time = 269200
+        mock_info("user_1", &[Coin::new(23456, TOKEN_FACTORY_DENOM)]),
+        ExecuteMsg::QueueUnbond {
+            receiver: Some("user_2".to_string()),
+            mint_receipt: false,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let res: PendingBatch = query_helper(deps.as_ref(), QueryMsg::PendingBatch {});
+    assert_eq!(res.ustake_to_burn, Uint128::new(23456));
+
+    let res: Vec<UnbondRequestsByBatchResponseItem> = query_helper(
+        deps.as_ref(),
+        QueryMsg::UnbondRequestsByBatch {
+            id: 1,
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(
+        res,
+        vec![UnbondRequestsByBatchResponseItem {
+            user: "user_2".to_string(),
+            shares: Uint128::new(23456),
+        }]
+    );
+
+    // The submitted batch burns the token-factory denom held by the hub
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 345782),
+        Delegation::new("bob", 345782),
+        Delegation::new("charlie", 345781),
+    ]);
+    deps.querier.set_bank_supply(TOKEN_FACTORY_DENOM, 1012043);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(269201),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+
+    assert!(res.messages.contains(&SubMsg::new(
+        MsgBurn {
+            sender: MOCK_CONTRACT_ADDR.to_string(),
+            amount: coin(23456, TOKEN_FACTORY_DENOM),
+            burn_from_address: MOCK_CONTRACT_ADDR.to_string(),
+        }
+        .to_cosmos_msg()
+    )));
+}
+
+#[test]
+fn migrating_to_token_factory() {
+    let mut deps = setup_cw20_test();
+
+    // Conversions are only possible after the migration
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(STAKE_DENOM, &[]),
+        receive_msg(
+            "user_1",
+            100,
+            ReceiveMsg::ConvertStakeToken {
+                receiver: None,
+            },
+        ),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::NotTokenFactory {});
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info(STAKE_DENOM, &[]),
+        receive_msg(
+            "user_1",
+            23456,
+            ReceiveMsg::QueueUnbond {
+                receiver: None,
+                mint_receipt: false,
+            },
+        ),
+    )
+    .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::MigrateToTokenFactory {
+            subdenom: "stake".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // The CW20 tokens held by the hub for the pending batch are converted right away
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::MigrateToTokenFactory {
+            subdenom: "stake".to_string(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 4);
+    assert_eq!(res.messages[0], SubMsg::new(create_denom_msg()));
+    assert_eq!(res.messages[1], SubMsg::new(set_before_send_hook_msg()));
+    assert_eq!(res.messages[2], SubMsg::new(burn_cw20_msg(23456)));
+    assert_eq!(res.messages[3], SubMsg::new(mint_msg(MOCK_CONTRACT_ADDR, 23456)));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::MigrateToTokenFactory {
+            subdenom: "stake".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::AlreadyTokenFactory {});
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.stake_token, TOKEN_FACTORY_DENOM.to_string());
+    assert_eq!(res.stake_token_type, StakeTokenType::TokenFactory);
+    assert_eq!(res.legacy_stake_token, Some(STAKE_DENOM.to_string()));
+
+    // Unconverted CW20 tokens are still part of the supply
+    deps.querier.set_cw20_total_supply(STAKE_DENOM, 976544);
+    deps.querier.set_bank_supply(TOKEN_FACTORY_DENOM, 23456);
+
+    let res: StateResponse = query_helper(deps.as_ref(), QueryMsg::State {});
+    assert_eq!(res.total_ustake, Uint128::new(1000000));
+
+    // The legacy token can not be unbonded anymore, only converted
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(STAKE_DENOM, &[]),
+        receive_msg(
+            "user_1",
+            100,
+            ReceiveMsg::QueueUnbond {
+                receiver: None,
+                mint_receipt: false,
+            },
+        ),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::ExpectingStakeToken(STAKE_DENOM.to_string()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(STAKE_DENOM, &[]),
+        receive_msg(
+            "user_1",
+            100,
+            ReceiveMsg::ConvertStakeToken {
+                receiver: Some("user_2".to_string()),
+            },
+        ),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.messages[0], SubMsg::new(burn_cw20_msg(100)));
+    assert_eq!(res.messages[1], SubMsg::new(mint_msg("user_2", 100)));
+}
@@ -3,29 +3,39 @@ use std::vec;
 
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    coin, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, DistributionMsg, Event, Fraction,
-    GovMsg, Order, StdError, StdResult, SubMsg, Uint128, VoteOption, WasmMsg,
+    attr, coin, from_slice, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut,
+    DistributionMsg, Event, Fraction, GovMsg, Order, StdError, StdResult, SubMsg, Uint128,
+    VoteOption, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
+use cw721::Cw721ReceiveMsg;
+use cw721_base::{ExecuteMsg as Cw721ExecuteMsg, Extension, MintMsg};
+use eris::helpers::timelock::{PendingAction, PendingActionsResponse};
+use eris::prop_gauges::PropInfo;
 use eris::DecimalCheckedOps;
 
 use eris::hub::{
-    Batch, CallbackMsg, ConfigResponse, ExecuteMsg, FeeConfig, PendingBatch, QueryMsg, ReceiveMsg,
-    StateResponse, UnbondRequest, UnbondRequestsByBatchResponseItem,
-    UnbondRequestsByUserResponseItem, UnbondRequestsByUserResponseItemDetails,
+    Batch, CallbackMsg, ConfigResponse, ExecuteMsg, FeeConfig, FeePayout, FeeReceiver,
+    InstantUnbondConfig, InstantiateMsg, KeeperAction, KeeperTipConfig, PauseOperation,
+    PauseStateResponse, PendingBatch, PendingKeeperAction, PendingKeeperActionsResponse, QueryMsg,
+    ReceiveMsg, ReceiveNftMsg, RedelegationLock, ReferralResponseItem, ReferralStats,
+    SlashingEvent, StakeTokenType, StateResponse, UnbondReceipt, UnbondRequest,
+    UnbondRequestsByBatchResponseItem, UnbondRequestsByUserResponseItem,
+    UnbondRequestsByUserResponseItemDetails, WithdrawableAmountResponse, WithdrawableBatch,
 };
 use itertools::Itertools;
 use protobuf::SpecialFields;
 
 use crate::constants::CONTRACT_DENOM;
-use crate::contract::execute;
-use crate::error::ContractError;
+use crate::contract::{execute, query};
+use crate::error::{ContractError, ContractResult};
 use crate::helpers::{dedupe, parse_received_fund};
 use crate::math::{
     compute_redelegations_for_rebalancing, compute_redelegations_for_removal, compute_undelegations,
 };
 use crate::protos::proto::{self, MsgVoteWeighted, WeightedVoteOption};
-use crate::state::State;
+use crate::protos::staking::UnbondingDelegationEntry;
+use crate::state::{BatchUndelegations, State};
 use crate::testing::helpers::{query_helper_env, setup_test, STAKE_DENOM};
 use crate::types::{Coins, Delegation, Redelegation, SendFee, Undelegation};
 
@@ -46,14 +56,21 @@ fn proper_instantiation() {
             owner: "owner".to_string(),
             new_owner: None,
             stake_token: STAKE_DENOM.to_string(),
+            stake_token_type: StakeTokenType::Cw20,
+            legacy_stake_token: None,
+            denom: CONTRACT_DENOM.to_string(),
             epoch_period: 259200,
             unbond_period: 1814400,
             validators: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
             fee_config: FeeConfig {
                 protocol_fee_contract: Addr::unchecked("fee"),
-                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128)
+                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+                fee_receivers: vec![]
             },
-            delegation_strategy: eris::hub::DelegationStrategy::Uniform
+            delegation_strategy: eris::hub::DelegationStrategy::Uniform,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
         }
     );
 
@@ -62,15 +79,24 @@ fn proper_instantiation() {
         res,
         StateResponse {
             total_ustake: Uint128::zero(),
-            total_uluna: Uint128::zero(),
+            total_utoken: Uint128::zero(),
             exchange_rate: Decimal::one(),
             unlocked_coins: vec![],
             unbonding: Uint128::zero(),
             available: Uint128::zero(),
-            tvl_uluna: Uint128::zero(),
+            tvl_utoken: Uint128::zero(),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         },
     );
 
+    // the JSON fields from before the hub became generic over the denom are still accepted
+    let json =
+        String::from_utf8(query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap().0).unwrap();
+    assert!(json.contains("\"total_utoken\":\"0\"") && json.contains("\"tvl_utoken\":\"0\""));
+    let legacy: StateResponse = from_slice(json.replace("_utoken", "_uluna").as_bytes()).unwrap();
+    assert_eq!(legacy, res);
+
     let res: PendingBatch = query_helper(deps.as_ref(), QueryMsg::PendingBatch {});
     assert_eq!(
         res,
@@ -82,6 +108,39 @@ fn proper_instantiation() {
     );
 }
 
+#[test]
+fn accepting_legacy_json_fields() {
+    let msg: InstantiateMsg = from_slice(
+        br#"{
+            "cw20_code_id": 69420,
+            "owner": "owner",
+            "name": "Stake Token",
+            "symbol": "STAKE",
+            "decimals": 6,
+            "epoch_period": 259200,
+            "unbond_period": 1814400,
+            "validators": ["alice"],
+            "protocol_fee_contract": "fee",
+            "protocol_reward_fee": "0.01"
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(msg.denom, "uluna".to_string());
+
+    // batches saved before the hub became generic over the denom
+    let batch: Batch = from_slice(
+        br#"{
+            "id": 1,
+            "reconciled": false,
+            "total_shares": "1000",
+            "uluna_unclaimed": "1025",
+            "est_unbond_end_time": 20000
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(1025));
+}
+
 #[test]
 fn bonding() {
     let mut deps = setup_test();
@@ -96,12 +155,16 @@ fn bonding() {
         mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: None,
+            referral: None,
         },
     )
     .unwrap();
 
     assert_eq!(res.messages.len(), 3);
-    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg(CONTRACT_DENOM))
+    );
     assert_eq!(
         res.messages[1],
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -135,12 +198,16 @@ fn bonding() {
         mock_info("user_2", &[Coin::new(12345, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: Some("user_3".to_string()),
+            referral: None,
         },
     )
     .unwrap();
 
     assert_eq!(res.messages.len(), 3);
-    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("charlie", 12345).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("charlie", 12345).to_cosmos_msg(CONTRACT_DENOM))
+    );
     assert_eq!(
         res.messages[1],
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -169,16 +236,74 @@ fn bonding() {
         res,
         StateResponse {
             total_ustake: Uint128::new(1012043),
-            total_uluna: Uint128::new(1037345),
+            total_utoken: Uint128::new(1037345),
             exchange_rate: Decimal::from_ratio(1037345u128, 1012043u128),
             unlocked_coins: vec![],
             unbonding: Uint128::zero(),
             available: Uint128::new(12567),
-            tvl_uluna: Uint128::new(1037345 + 12567),
+            tvl_utoken: Uint128::new(1037345 + 12567),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         }
     );
 }
 
+#[test]
+fn bonding_other_denom() {
+    let mut deps = setup_test();
+
+    State::default().denom.save(deps.as_mut().storage, &"uinj".to_string()).unwrap();
+    deps.querier.set_bank_balances(&[coin(1000100, "uinj")]);
+
+    // Deposits of the default denom are rejected once the hub stakes a different one
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            referral: None,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err("expected uinj deposit, received uluna"))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(1000000, "uinj")]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            referral: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg("uinj"))
+    );
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_json_binary(&ExecuteMsg::Callback(CallbackMsg::CheckReceivedCoin {
+                snapshot: coin(100, "uinj"),
+            }))
+            .unwrap(),
+            funds: vec![],
+        }))
+    );
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.denom, "uinj".to_string());
+}
+
 #[test]
 fn donating() {
     let mut deps = setup_test();
@@ -192,12 +317,16 @@ fn donating() {
         mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
         ExecuteMsg::Bond {
             receiver: None,
+            referral: None,
         },
     )
     .unwrap();
 
     assert_eq!(res.messages.len(), 3);
-    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("alice", 1000000).to_cosmos_msg(CONTRACT_DENOM))
+    );
     assert_eq!(
         res.messages[1],
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -227,12 +356,14 @@ fn donating() {
         res,
         StateResponse {
             total_ustake: Uint128::new(1000000),
-            total_uluna: Uint128::new(1025000),
+            total_utoken: Uint128::new(1025000),
             exchange_rate: Decimal::from_ratio(1025000u128, 1000000u128),
             unlocked_coins: vec![],
             unbonding: Uint128::zero(),
             available: Uint128::new(100),
-            tvl_uluna: Uint128::new(1025100),
+            tvl_utoken: Uint128::new(1025100),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         }
     );
 
@@ -259,6 +390,15 @@ fn donating() {
             vote_operator: None,
             epoch_period: None,
             unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
         },
     )
     .unwrap();
@@ -273,7 +413,10 @@ fn donating() {
     .unwrap();
 
     assert_eq!(res.messages.len(), 2);
-    assert_eq!(res.messages[0], SubMsg::new(Delegation::new("charlie", 12345).to_cosmos_msg()));
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("charlie", 12345).to_cosmos_msg(CONTRACT_DENOM))
+    );
     assert_eq!(res.messages[1], check_received_coin(100));
 
     deps.querier.set_bank_balances(&[coin(100, CONTRACT_DENOM)]);
@@ -290,12 +433,14 @@ fn donating() {
         res,
         StateResponse {
             total_ustake: Uint128::new(1000000),
-            total_uluna: Uint128::new(1037345),
+            total_utoken: Uint128::new(1037345),
             exchange_rate: Decimal::from_ratio(1037345u128, 1000000u128),
             unlocked_coins: vec![],
             unbonding: Uint128::zero(),
             available: Uint128::new(100),
-            tvl_uluna: Uint128::new(1037345 + 100),
+            tvl_utoken: Uint128::new(1037345 + 100),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         }
     );
 }
@@ -423,12 +568,12 @@ fn reinvesting() {
 
     assert_eq!(
         res.messages[0],
-        SubMsg::new(Delegation::new("bob", delegated.u128()).to_cosmos_msg())
+        SubMsg::new(Delegation::new("bob", delegated.u128()).to_cosmos_msg(CONTRACT_DENOM))
     );
 
     assert_eq!(
         res.messages[1],
-        SubMsg::new(SendFee::new(Addr::unchecked("fee"), fee.u128()).to_cosmos_msg())
+        SubMsg::new(SendFee::new(Addr::unchecked("fee"), fee.u128()).to_cosmos_msg(CONTRACT_DENOM))
     );
 
     // Storage should have been updated
@@ -443,683 +588,882 @@ fn reinvesting() {
 }
 
 #[test]
-fn queuing_unbond() {
+fn reinvesting_with_fee_receivers() {
     let mut deps = setup_test();
     let state = State::default();
 
-    // Only Stake token is accepted for unbonding requests
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
+    deps.querier.set_cw20_total_supply(STAKE_DENOM, 1000000);
+
+    let update_fee_receivers = |fee_receivers: Vec<FeeReceiver<String>>| ExecuteMsg::UpdateConfig {
+        protocol_fee_contract: None,
+        protocol_reward_fee: None,
+        delegation_strategy: None,
+        allow_donations: None,
+        vote_operator: None,
+        epoch_period: None,
+        unbond_period: None,
+        instant_unbond: None,
+        validator_scoring: None,
+        unbond_receipt_nft: None,
+        fee_receivers: Some(fee_receivers),
+        referral_fee: None,
+        guardian: None,
+        timelock_delay_s: None,
+        keeper_tips: None,
+        prop_gauges: None,
+    };
+
+    // The shares can't exceed the whole protocol fee
     let err = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("random_token", &[]),
-        ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
-            sender: "hacker".to_string(),
-            amount: Uint128::new(69420),
-            msg: to_json_binary(&ReceiveMsg::QueueUnbond {
-                receiver: None,
-            })
-            .unwrap(),
-        }),
+        mock_info("owner", &[]),
+        update_fee_receivers(vec![
+            FeeReceiver {
+                address: "treasury".to_string(),
+                share_bps: 6000,
+                payout: FeePayout::Native,
+            },
+            FeeReceiver {
+                address: "pool".to_string(),
+                share_bps: 5000,
+                payout: FeePayout::StakeToken,
+            },
+        ]),
     )
     .unwrap_err();
+    assert_eq!(err, ContractError::Std(StdError::generic_err("Basic points sum exceeds limit")));
 
-    assert_eq!(err, ContractError::ExpectingStakeToken("random_token".into()));
-
-    // User 1 creates an unbonding request before `est_unbond_start_time` is reached. The unbond
-    // request is saved, but not the pending batch is not submitted for unbonding
-    let res = execute(
+    execute(
         deps.as_mut(),
-        mock_env_at_timestamp(12345), // est_unbond_start_time = 269200
-        mock_info(STAKE_DENOM, &[]),
-        ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
-            sender: "user_1".to_string(),
-            amount: Uint128::new(23456),
-            msg: to_json_binary(&ReceiveMsg::QueueUnbond {
-                receiver: None,
-            })
-            .unwrap(),
-        }),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_fee_receivers(vec![
+            FeeReceiver {
+                address: "treasury".to_string(),
+                share_bps: 5000,
+                payout: FeePayout::Native,
+            },
+            FeeReceiver {
+                address: "pool".to_string(),
+                share_bps: 3000,
+                payout: FeePayout::StakeToken,
+            },
+        ]),
     )
     .unwrap();
 
-    assert_eq!(res.messages.len(), 0);
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(10000, CONTRACT_DENOM)])
+        .unwrap();
 
-    // User 2 creates an unbonding request after `est_unbond_start_time` is reached. The unbond
-    // request is saved, and the pending is automatically submitted for unbonding
     let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(269201), // est_unbond_start_time = 269200
-        mock_info(STAKE_DENOM, &[]),
-        ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
-            sender: "user_2".to_string(),
-            amount: Uint128::new(69420),
-            msg: to_json_binary(&ReceiveMsg::QueueUnbond {
-                receiver: Some("user_3".to_string()),
-            })
-            .unwrap(),
-        }),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
     )
     .unwrap();
 
-    assert_eq!(res.messages.len(), 1);
+    // Protocol fee: 10000 * 1% = 100
+    // Treasury: 50 utoken, pool: 30 utoken bonded for Stake tokens, fee contract: the remaining 20
+    // Stake tokens minted after the holders' 9900 utoken are bonded: 1000000 * 30 / 1009900 = 29
     assert_eq!(
-        res.messages[0],
-        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
-            msg: to_json_binary(&ExecuteMsg::SubmitBatch {}).unwrap(),
-            funds: vec![]
-        }))
+        res.messages,
+        vec![
+            SubMsg::new(Delegation::new("bob", 9930).to_cosmos_msg(CONTRACT_DENOM)),
+            SubMsg::new(
+                SendFee::new(Addr::unchecked("treasury"), 50).to_cosmos_msg(CONTRACT_DENOM)
+            ),
+            SubMsg::new(SendFee::new(Addr::unchecked("fee"), 20).to_cosmos_msg(CONTRACT_DENOM)),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: STAKE_DENOM.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: "pool".to_string(),
+                    amount: Uint128::new(29)
+                })
+                .unwrap(),
+                funds: vec![]
+            })),
+        ]
     );
 
-    // The users' unbonding requests should have been saved
-    let ubr1 = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
-        .unwrap();
-    let ubr2 = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_3")))
-        .unwrap();
-
-    assert_eq!(
-        ubr1,
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(23456)
-        }
-    );
     assert_eq!(
-        ubr2,
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_3"),
-            shares: Uint128::new(69420)
-        }
+        res.events[1..],
+        vec![
+            Event::new("erishub/fee_paid")
+                .add_attribute("receiver", "treasury")
+                .add_attribute("uluna_fee", "50"),
+            Event::new("erishub/fee_paid")
+                .add_attribute("receiver", "fee")
+                .add_attribute("uluna_fee", "20"),
+            Event::new("erishub/fee_paid")
+                .add_attribute("receiver", "pool")
+                .add_attribute("uluna_fee", "30")
+                .add_attribute("ustake_minted", "29"),
+        ]
     );
 
-    // Pending batch should have been updated
-    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
-    assert_eq!(
-        pending_batch,
-        PendingBatch {
-            id: 1,
-            ustake_to_burn: Uint128::new(92876), // 23,456 + 69,420
-            est_unbond_start_time: 269200
-        }
-    );
+    // The minted Stake tokens are part of the exchange rate
+    let exchange_rate = state
+        .exchange_history
+        .load(deps.as_ref().storage, mock_env().block.time.seconds())
+        .unwrap();
+    assert_eq!(exchange_rate, Decimal::from_ratio(1009930u128, 1000029u128));
 }
 
 #[test]
-fn submitting_batch() {
+fn bonding_with_referral() {
     let mut deps = setup_test();
     let state = State::default();
 
-    // utoken bonded: 1,037,345
-    // ustake supply: 1,012,043
-    // utoken per ustake: 1.025
     deps.querier.set_staking_delegations(&[
-        Delegation::new("alice", 345782),
-        Delegation::new("bob", 345782),
-        Delegation::new("charlie", 345781),
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
     ]);
-    deps.querier.set_cw20_total_supply(STAKE_DENOM, 1012043);
+    deps.querier.set_cw20_total_supply(STAKE_DENOM, 1000000);
+    deps.querier.set_bank_balances(&[coin(100000, CONTRACT_DENOM)]);
 
-    // We continue from the contract state at the end of the last test
-    let unbond_requests = vec![
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(23456),
-        },
-        UnbondRequest {
-            id: 1,
-            user: Addr::unchecked("user_3"),
-            shares: Uint128::new(69420),
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: Some(Decimal::percent(50)),
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
         },
-    ];
+    )
+    .unwrap();
 
-    for unbond_request in &unbond_requests {
-        state
-            .unbond_requests
-            .save(
-                deps.as_mut().storage,
-                (unbond_request.id, &Addr::unchecked(unbond_request.user.clone())),
-                unbond_request,
-            )
-            .unwrap();
-    }
+    // Stake can't be referred by its own receiver
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(100000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            referral: Some("user_1".to_string()),
+        },
+    )
+    .unwrap_err();
 
-    state
-        .pending_batch
-        .save(
-            deps.as_mut().storage,
-            &PendingBatch {
-                id: 1,
-                ustake_to_burn: Uint128::new(92876), // 23,456 + 69,420
-                est_unbond_start_time: 269200,
-            },
-        )
-        .unwrap();
+    assert_eq!(err, ContractError::SelfReferral {});
 
-    // Anyone can invoke `submit_batch`. Here we continue from the previous test and assume it is
-    // invoked automatically as user 2 submits the unbonding request
-    //
-    // ustake to burn: 23,456 + 69,420 = 92,876
-    // utoken to unbond: 1,037,345 * 92,876 / 1,012,043 = 95,197
-    //
-    // Target: (1,037,345 - 95,197) / 3 = 314,049
-    // Remainer: 1
-    // Alice:   345,782 - (314,049 + 1) = 31,732
-    // Bob:     345,782 - (314,049 + 0) = 31,733
-    // Charlie: 345,781 - (314,049 + 0) = 31,732
     let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(269201),
-        mock_info(MOCK_CONTRACT_ADDR, &[]),
-        ExecuteMsg::SubmitBatch {},
+        mock_env(),
+        mock_info("user_1", &[Coin::new(100000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            referral: Some("partner".to_string()),
+        },
     )
     .unwrap();
 
-    assert_eq!(res.messages.len(), 5);
-    assert_eq!(res.messages[0], SubMsg::new(Undelegation::new("alice", 31732).to_cosmos_msg()));
-    assert_eq!(res.messages[1], SubMsg::new(Undelegation::new("bob", 31733).to_cosmos_msg()));
-    assert_eq!(res.messages[2], SubMsg::new(Undelegation::new("charlie", 31732).to_cosmos_msg()));
     assert_eq!(
-        res.messages[3],
-        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: STAKE_DENOM.to_string(),
-            msg: to_json_binary(&Cw20ExecuteMsg::Burn {
-                amount: Uint128::new(92876)
-            })
-            .unwrap(),
-            funds: vec![]
-        }))
+        res.events,
+        vec![Event::new("erishub/bonded")
+            .add_attribute("receiver", "user_1")
+            .add_attribute("uluna_bonded", "100000")
+            .add_attribute("ustake_minted", "100000")
+            .add_attribute("referral", "partner")]
     );
-    assert_eq!(res.messages[4], check_received_coin(0));
 
-    // A new pending batch should have been created
-    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
-    assert_eq!(
-        pending_batch,
-        PendingBatch {
-            id: 2,
-            ustake_to_burn: Uint128::zero(),
-            est_unbond_start_time: 528401 // 269,201 + 259,200
-        }
-    );
+    // The referred stake generates 100000 / 1100000 of the protocol fee after the bond
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 433333),
+        Delegation::new("charlie", 333333),
+    ]);
+    deps.querier.set_cw20_total_supply(STAKE_DENOM, 1100000);
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(110000, CONTRACT_DENOM)])
+        .unwrap();
 
-    // Previous batch should have been updated
-    let previous_batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+    )
+    .unwrap();
+
+    // Protocol fee: 110000 * 1% = 1100
+    // Referral fee: 1100 * 50% * 100000 / 1100000 = 50, which stays in the contract
     assert_eq!(
-        previous_batch,
-        Batch {
-            id: 1,
-            reconciled: false,
-            total_shares: Uint128::new(92876),
-            uluna_unclaimed: Uint128::new(95197),
-            est_unbond_end_time: 2083601 // 269,201 + 1,814,400
-        }
+        res.messages,
+        vec![
+            SubMsg::new(Delegation::new("charlie", 108900).to_cosmos_msg(CONTRACT_DENOM)),
+            SubMsg::new(SendFee::new(Addr::unchecked("fee"), 1050).to_cosmos_msg(CONTRACT_DENOM)),
+        ]
     );
 
-    let res: StateResponse = query_helper_env(deps.as_ref(), QueryMsg::State {}, 2083600);
+    let res: Vec<ReferralResponseItem> = query_helper(
+        deps.as_ref(),
+        QueryMsg::Referrals {
+            start_after: None,
+            limit: None,
+        },
+    );
     assert_eq!(
         res,
-        StateResponse {
-            total_ustake: Uint128::from(1012043u128),
-            total_uluna: Uint128::from(1037345u128),
-            exchange_rate: Decimal::from_ratio(1037345u128, 1012043u128),
-            unlocked_coins: vec![],
-            unbonding: Uint128::from(95197u128),
-            available: Uint128::zero(),
-            tvl_uluna: Uint128::from(95197u128 + 1037345u128),
-        },
+        vec![ReferralResponseItem {
+            partner: "partner".to_string(),
+            stats: ReferralStats {
+                utoken_bonded: Uint128::new(100000),
+                ustake_referred: Uint128::new(100000),
+                fee_index: Decimal::from_ratio(50u128, 100000u128),
+                utoken_claimable: Uint128::new(50),
+            }
+        }]
     );
-}
-
-#[test]
-fn reconciling() {
-    let mut deps = setup_test();
-    let state = State::default();
-
-    let previous_batches = vec![
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(92876),
-            uluna_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
-            est_unbond_end_time: 10000,
-        },
-        Batch {
-            id: 2,
-            reconciled: false,
-            total_shares: Uint128::new(1345),
-            uluna_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 3,
-            reconciled: false,
-            total_shares: Uint128::new(1456),
-            uluna_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
-            est_unbond_end_time: 30000,
-        },
-        Batch {
-            id: 4,
-            reconciled: false,
-            total_shares: Uint128::new(1567),
-            uluna_unclaimed: Uint128::new(1629), // 1.040 Token per Stake
-            est_unbond_end_time: 40000,          // not yet finished unbonding, ignored
-        },
-    ];
-
-    for previous_batch in &previous_batches {
-        state
-            .previous_batches
-            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
-            .unwrap();
-    }
-
-    state
-        .unlocked_coins
-        .save(
-            deps.as_mut().storage,
-            &vec![
-                Coin::new(10000, CONTRACT_DENOM),
-                Coin::new(234, "ukrw"),
-                Coin::new(345, "uusd"),
-                Coin::new(
-                    69420,
-                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
-                ),
-            ],
-        )
-        .unwrap();
 
-    deps.querier.set_bank_balances(&[
-        Coin::new(12345, CONTRACT_DENOM),
-        Coin::new(234, "ukrw"),
-        Coin::new(345, "uusd"),
-        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
-    ]);
+    // The unclaimed fees are expected in the balance when reconciling
+    let referral_fees_unclaimed =
+        state.referral_fees_unclaimed.load(deps.as_ref().storage).unwrap();
+    assert_eq!(referral_fees_unclaimed, Uint128::new(50));
 
-    execute(
+    let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(35000),
-        mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        mock_env(),
+        mock_info("partner", &[]),
+        ExecuteMsg::ClaimReferralFees {
+            receiver: None,
+        },
     )
     .unwrap();
 
-    // Expected received: batch 2 + batch 3 = 1385 + 1506 = 2891
-    // Expected unlocked: 10000
-    // Expected: 12891
-    // Actual: 12345
-    // Shortfall: 12891 - 12345 = 456
-    //
-    // utoken per batch: 546 / 2 = 273
-    // remainder: 0
-    // batch 2: 1385 - 273 = 1112
-    // batch 3: 1506 - 273 = 1233
-    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
     assert_eq!(
-        batch,
-        Batch {
-            id: 2,
-            reconciled: true,
-            total_shares: Uint128::new(1345),
-            uluna_unclaimed: Uint128::new(1112), // 1385 - 273
-            est_unbond_end_time: 20000,
-        }
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "partner".to_string(),
+            amount: vec![Coin::new(50, CONTRACT_DENOM)]
+        }))]
     );
 
-    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
-    assert_eq!(
-        batch,
-        Batch {
-            id: 3,
-            reconciled: true,
-            total_shares: Uint128::new(1456),
-            uluna_unclaimed: Uint128::new(1233), // 1506 - 273
-            est_unbond_end_time: 30000,
-        }
-    );
+    let referral_fees_unclaimed =
+        state.referral_fees_unclaimed.load(deps.as_ref().storage).unwrap();
+    assert_eq!(referral_fees_unclaimed, Uint128::zero());
 
-    // Batches 1 and 4 should not have changed
-    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
-    assert_eq!(batch, previous_batches[0]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("partner", &[]),
+        ExecuteMsg::ClaimReferralFees {
+            receiver: None,
+        },
+    )
+    .unwrap_err();
 
-    let batch = state.previous_batches.load(deps.as_ref().storage, 4u64).unwrap();
-    assert_eq!(batch, previous_batches[3]);
+    assert_eq!(err, ContractError::CantBeZero("claimable referral fees".into()));
 }
 
 #[test]
-fn reconciling_even_when_everything_ok() {
+fn releasing_referred_stake() {
     let mut deps = setup_test();
     let state = State::default();
 
-    let previous_batches = vec![
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(100000),
-            uluna_unclaimed: Uint128::new(100000),
-            est_unbond_end_time: 10000,
-        },
-        Batch {
-            id: 2,
-            reconciled: false,
-            total_shares: Uint128::new(1000),
-            uluna_unclaimed: Uint128::new(1000),
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 3,
-            reconciled: false,
-            total_shares: Uint128::new(1500),
-            uluna_unclaimed: Uint128::new(1500),
-            est_unbond_end_time: 30000,
-        },
-        Batch {
-            id: 4,
-            reconciled: false,
-            total_shares: Uint128::new(1500),
-            uluna_unclaimed: Uint128::new(1500),
-            est_unbond_end_time: 40000, // not yet finished unbonding, ignored
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
+    deps.querier.set_cw20_total_supply(STAKE_DENOM, 1000000);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[Coin::new(100000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            referral: Some("partner".to_string()),
         },
-    ];
+    )
+    .unwrap();
 
-    for previous_batch in &previous_batches {
-        state
-            .previous_batches
-            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
-            .unwrap();
-    }
+    let referred = |deps: Deps| {
+        let stats = state.load_referral(deps.storage, &Addr::unchecked("partner")).unwrap();
+        let total = state.total_ustake_referred.load(deps.storage).unwrap();
+        (stats.ustake_referred.u128(), total.u128())
+    };
+    assert_eq!(referred(deps.as_ref()), (100000, 100000));
 
-    state
-        .unlocked_coins
-        .save(deps.as_mut().storage, &vec![Coin::new(1000, CONTRACT_DENOM)])
-        .unwrap();
+    // Unbonding referred stake stops the referral fees on it
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(STAKE_DENOM, &[]),
+        ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            sender: "user_1".to_string(),
+            amount: Uint128::new(30000),
+            msg: to_json_binary(&ReceiveMsg::QueueUnbond {
+                receiver: None,
+                mint_receipt: false,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
 
-    deps.querier.set_bank_balances(&[Coin::new(3500, CONTRACT_DENOM)]);
+    assert_eq!(referred(deps.as_ref()), (70000, 70000));
 
-    execute(
+    // So does transferring it away, once anyone syncs the holder
+    deps.querier.set_cw20_balance(STAKE_DENOM, "user_1", 50000);
+    deps.querier.set_cw20_balance(STAKE_DENOM, "user_2", 0);
+
+    let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(35000),
-        mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::SyncReferredStake {
+            holders: vec!["user_1".to_string(), "user_2".to_string()],
+        },
     )
     .unwrap();
 
-    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
     assert_eq!(
-        batch,
-        Batch {
-            id: 2,
-            reconciled: true,
-            total_shares: Uint128::new(1000),
-            uluna_unclaimed: Uint128::new(1000),
-            est_unbond_end_time: 20000,
-        }
+        res.events,
+        vec![Event::new("erishub/referred_stake_released")
+            .add_attribute("holder", "user_1")
+            .add_attribute("ustake_released", "20000")]
     );
+    assert_eq!(referred(deps.as_ref()), (50000, 50000));
 
-    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
-    assert_eq!(
-        batch,
-        Batch {
-            id: 3,
-            reconciled: true,
-            total_shares: Uint128::new(1500),
-            uluna_unclaimed: Uint128::new(1500),
-            est_unbond_end_time: 30000,
-        }
-    );
+    // The Stake token reports transfers right away
+    let transferred = |to: &str, amount: u128| ExecuteMsg::StakeTransferred {
+        from: "user_1".to_string(),
+        to: to.to_string(),
+        amount: Uint128::new(amount),
+    };
 
-    // Batches 1 and 4 should not have changed
-    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
-    assert_eq!(batch, previous_batches[0]);
-
-    let batch = state.previous_batches.load(deps.as_ref().storage, 4u64).unwrap();
-    assert_eq!(batch, previous_batches[3]);
-}
+    let err =
+        execute(deps.as_mut(), mock_env(), mock_info("user_1", &[]), transferred("user_2", 1))
+            .unwrap_err();
+    assert_eq!(err, ContractError::ExpectingStakeToken("user_1".to_string()));
 
-#[test]
-fn reconciling_underflow() {
-    let mut deps = setup_test();
-    let state = State::default();
-    let previous_batches = vec![
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(92876),
-            uluna_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
-            est_unbond_end_time: 10000,
-        },
-        Batch {
-            id: 2,
-            reconciled: false,
-            total_shares: Uint128::new(1345),
-            uluna_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 3,
-            reconciled: false,
-            total_shares: Uint128::new(1456),
-            uluna_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
-            est_unbond_end_time: 30000,
-        },
-        Batch {
-            id: 4,
-            reconciled: false,
-            total_shares: Uint128::new(1),
-            uluna_unclaimed: Uint128::new(1),
-            est_unbond_end_time: 30001,
-        },
-    ];
-    for previous_batch in &previous_batches {
-        state
-            .previous_batches
-            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
-            .unwrap();
-    }
-    state
-        .unlocked_coins
-        .save(
-            deps.as_mut().storage,
-            &vec![
-                Coin::new(10000, CONTRACT_DENOM),
-                Coin::new(234, "ukrw"),
-                Coin::new(345, "uusd"),
-                Coin::new(
-                    69420,
-                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
-                ),
-            ],
-        )
-        .unwrap();
-    deps.querier.set_bank_balances(&[
-        Coin::new(12345, CONTRACT_DENOM),
-        Coin::new(234, "ukrw"),
-        Coin::new(345, "uusd"),
-        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
-    ]);
+    // Tokens sent to the hub are released by the unbond they are sent for
     execute(
         deps.as_mut(),
-        mock_env_at_timestamp(35000),
-        mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        mock_env(),
+        mock_info(STAKE_DENOM, &[]),
+        transferred(MOCK_CONTRACT_ADDR, 10000),
+    )
+    .unwrap();
+    assert_eq!(referred(deps.as_ref()), (50000, 50000));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(STAKE_DENOM, &[]),
+        transferred("user_2", 15000),
     )
     .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/referred_stake_released")
+            .add_attribute("holder", "user_1")
+            .add_attribute("ustake_released", "15000")]
+    );
+    assert_eq!(referred(deps.as_ref()), (35000, 35000));
 }
 
 #[test]
-fn reconciling_underflow_second() {
+fn queuing_unbond() {
     let mut deps = setup_test();
     let state = State::default();
-    let previous_batches = vec![
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(92876),
-            uluna_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
-            est_unbond_end_time: 10000,
-        },
-        Batch {
-            id: 2,
-            reconciled: false,
-            total_shares: Uint128::new(1345),
-            uluna_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 3,
-            reconciled: false,
-            total_shares: Uint128::new(176),
-            uluna_unclaimed: Uint128::new(183), // 1.035 Token per Stake
-            est_unbond_end_time: 30000,
-        },
-        Batch {
-            id: 4,
-            reconciled: false,
-            total_shares: Uint128::new(1),
-            uluna_unclaimed: Uint128::new(1),
-            est_unbond_end_time: 30001,
-        },
-    ];
-    for previous_batch in &previous_batches {
-        state
-            .previous_batches
-            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
-            .unwrap();
-    }
-    state
-        .unlocked_coins
-        .save(
-            deps.as_mut().storage,
-            &vec![
-                Coin::new(10000, CONTRACT_DENOM),
-                Coin::new(234, "ukrw"),
-                Coin::new(345, "uusd"),
-                Coin::new(
-                    69420,
-                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
-                ),
-            ],
-        )
-        .unwrap();
-    deps.querier.set_bank_balances(&[
-        // unlocked + expected - slash
-        Coin::new(10000 + 1569 - 569, CONTRACT_DENOM),
-        Coin::new(234, "ukrw"),
-        Coin::new(345, "uusd"),
-        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
-    ]);
 
-    let result = execute(
+    // Only Stake token is accepted for unbonding requests
+    let err = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(35000),
-        mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        mock_env(),
+        mock_info("random_token", &[]),
+        ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            sender: "hacker".to_string(),
+            amount: Uint128::new(69420),
+            msg: to_json_binary(&ReceiveMsg::QueueUnbond {
+                receiver: None,
+                mint_receipt: false,
+            })
+            .unwrap(),
+        }),
     )
     .unwrap_err();
 
-    assert_eq!(result, ContractError::ReconcileTooBig(Uint128::new(1000), Uint128::new(1569)));
+    assert_eq!(err, ContractError::ExpectingStakeToken("random_token".into()));
 
-    deps.querier.set_bank_balances(&[
-        Coin::new(10000 + 1569 - 300, CONTRACT_DENOM),
-        Coin::new(234, "ukrw"),
-        Coin::new(345, "uusd"),
-        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
-    ]);
-    execute(
+    // User 1 creates an unbonding request before `est_unbond_start_time` is reached. The unbond
+    // request is saved, but not the pending batch is not submitted for unbonding
+    let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(35000),
-        mock_info("worker", &[]),
-        ExecuteMsg::Reconcile {},
+        mock_env_at_timestamp(12345), // est_unbond_start_time = 269200
+        mock_info(STAKE_DENOM, &[]),
+        ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            sender: "user_1".to_string(),
+            amount: Uint128::new(23456),
+            msg: to_json_binary(&ReceiveMsg::QueueUnbond {
+                receiver: None,
+                mint_receipt: false,
+            })
+            .unwrap(),
+        }),
     )
     .unwrap();
-}
 
-#[test]
-fn withdrawing_unbonded() {
-    let mut deps = setup_test();
-    let state = State::default();
+    assert_eq!(res.messages.len(), 0);
 
-    // We simulate a most general case:
-    // - batches 1 and 2 have finished unbonding
-    // - batch 3 have been submitted for unbonding but have not finished
-    // - batch 4 is still pending
-    let unbond_requests = vec![
+    // User 2 creates an unbonding request after `est_unbond_start_time` is reached. The unbond
+    // request is saved, and the pending is automatically submitted for unbonding
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(269201), // est_unbond_start_time = 269200
+        mock_info(STAKE_DENOM, &[]),
+        ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            sender: "user_2".to_string(),
+            amount: Uint128::new(69420),
+            msg: to_json_binary(&ReceiveMsg::QueueUnbond {
+                receiver: Some("user_3".to_string()),
+                mint_receipt: false,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_json_binary(&ExecuteMsg::SubmitBatch {}).unwrap(),
+            funds: vec![]
+        }))
+    );
+
+    // The users' unbonding requests should have been saved
+    let ubr1 = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
+        .unwrap();
+    let ubr2 = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_3")))
+        .unwrap();
+
+    assert_eq!(
+        ubr1,
         UnbondRequest {
             id: 1,
             user: Addr::unchecked("user_1"),
-            shares: Uint128::new(23456),
-        },
+            shares: Uint128::new(23456)
+        }
+    );
+    assert_eq!(
+        ubr2,
         UnbondRequest {
             id: 1,
             user: Addr::unchecked("user_3"),
-            shares: Uint128::new(69420),
-        },
-        UnbondRequest {
-            id: 2,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(34567),
-        },
-        UnbondRequest {
-            id: 3,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(45678),
-        },
-        UnbondRequest {
-            id: 4,
-            user: Addr::unchecked("user_1"),
-            shares: Uint128::new(56789),
-        },
-    ];
+            shares: Uint128::new(69420)
+        }
+    );
 
-    for unbond_request in &unbond_requests {
-        state
-            .unbond_requests
-            .save(
-                deps.as_mut().storage,
-                (unbond_request.id, &Addr::unchecked(unbond_request.user.clone())),
-                unbond_request,
-            )
-            .unwrap();
-    }
+    // Pending batch should have been updated
+    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        pending_batch,
+        PendingBatch {
+            id: 1,
+            ustake_to_burn: Uint128::new(92876), // 23,456 + 69,420
+            est_unbond_start_time: 269200
+        }
+    );
+}
 
-    let previous_batches = vec![
+fn instant_unbond_config() -> InstantUnbondConfig {
+    InstantUnbondConfig {
+        buffer_target: Uint128::new(100000),
+        min_fee: Decimal::from_str("0.005").unwrap(),
+        max_fee: Decimal::from_str("0.05").unwrap(),
+    }
+}
+
+fn enable_instant_unbond(deps: DepsMut) {
+    execute(
+        deps,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: Some(instant_unbond_config()),
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
+        },
+    )
+    .unwrap();
+}
+
+fn instant_unbond_msg(sender: &str, amount: u128, max_fee: &str) -> ExecuteMsg {
+    ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+        sender: sender.to_string(),
+        amount: Uint128::new(amount),
+        msg: to_json_binary(&ReceiveMsg::InstantUnbond {
+            max_fee: Decimal::from_str(max_fee).unwrap(),
+            receiver: None,
+        })
+        .unwrap(),
+    })
+}
+
+#[test]
+fn instant_unbonding() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 341666),
+    ]);
+    deps.querier.set_cw20_total_supply(STAKE_DENOM, 1000000);
+
+    // Instant unbonding needs to be enabled by the owner
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info(STAKE_DENOM, &[]),
+        instant_unbond_msg("user_1", 10000, "0.01"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InstantUnbondDisabled {});
+
+    // min_fee can't be greater than max_fee
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            allow_donations: None,
+            delegation_strategy: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: Some(InstantUnbondConfig {
+                buffer_target: Uint128::new(100000),
+                min_fee: Decimal::percent(2),
+                max_fee: Decimal::percent(1),
+            }),
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InstantUnbondFeeInvalid {});
+
+    enable_instant_unbond(deps.as_mut());
+    state.instant_unbond_buffer.save(deps.as_mut().storage, &Uint128::new(100000)).unwrap();
+
+    // the buffer backs the Stake token next to the delegations: (1025000 + 100000) / 1000000
+    // 10000 ustake = 11250 utoken
+    // buffer after unbond = 100000 - 11250 = 88750 -> utilization = 0.1125
+    // fee = 0.005 + (0.05 - 0.005) * 0.1125 = 0.0100625
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info(STAKE_DENOM, &[]),
+        instant_unbond_msg("user_1", 10000, "0.005"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InstantUnbondFeeTooHigh(
+            Decimal::from_str("0.0100625").unwrap(),
+            Decimal::from_str("0.005").unwrap()
+        )
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info(STAKE_DENOM, &[]),
+        instant_unbond_msg("user_1", 10000, "0.011"),
+    )
+    .unwrap();
+
+    // fee = 11250 * 0.0100625 = 113
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "user_1".to_string(),
+            amount: vec![Coin::new(11137, CONTRACT_DENOM)]
+        }))
+    );
+
+    // the hub took over the unbonding request
+    let ubr = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked(MOCK_CONTRACT_ADDR)))
+        .unwrap();
+    assert_eq!(
+        ubr,
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            shares: Uint128::new(10000)
+        }
+    );
+
+    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
+    assert_eq!(pending_batch.ustake_to_burn, Uint128::new(10000));
+
+    let buffer = state.instant_unbond_buffer.load(deps.as_ref().storage).unwrap();
+    assert_eq!(buffer, Uint128::new(88863));
+
+    // 200000 ustake = 1113863 * 200000 / 990000 = 225022 utoken, which the buffer can't cover
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info(STAKE_DENOM, &[]),
+        instant_unbond_msg("user_2", 200000, "0.05"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InstantUnbondBufferTooSmall(Uint128::new(88863)));
+
+    deps.querier.set_bank_balances(&[coin(88863, CONTRACT_DENOM)]);
+    let res: StateResponse = query_helper(deps.as_ref(), QueryMsg::State {});
+    assert_eq!(res.instant_unbond_buffer, Uint128::new(88863));
+    assert_eq!(res.instant_unbond_utilization, Decimal::from_str("0.11137").unwrap());
+
+    // the hub's pending stake is no longer part of the supply: 1113863 / 990000
+    assert_eq!(res.exchange_rate, Decimal::from_str("1.125114141414141414").unwrap());
+}
+
+#[test]
+fn reinvesting_refills_instant_unbond_buffer() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    enable_instant_unbond(deps.as_mut());
+    state.instant_unbond_buffer.save(deps.as_mut().storage, &Uint128::new(99900)).unwrap();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(234, CONTRACT_DENOM)])
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+    )
+    .unwrap();
+
+    // fee = 2, buffer refill = 100, bonded = 234 - 2 - 100 = 132
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("bob", 132).to_cosmos_msg(CONTRACT_DENOM))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(SendFee::new(Addr::unchecked("fee"), 2).to_cosmos_msg(CONTRACT_DENOM))
+    );
+
+    let buffer = state.instant_unbond_buffer.load(deps.as_ref().storage).unwrap();
+    assert_eq!(buffer, Uint128::new(100000));
+}
+
+#[test]
+fn reinvesting_bonds_instant_unbond_buffer_excess() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // instant unbond fees grew the buffer above its target
+    enable_instant_unbond(deps.as_mut());
+    state.instant_unbond_buffer.save(deps.as_mut().storage, &Uint128::new(100500)).unwrap();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(234, CONTRACT_DENOM)])
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+    )
+    .unwrap();
+
+    // fee = 2, buffer excess = 500, bonded = 234 - 2 + 500 = 732
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("bob", 732).to_cosmos_msg(CONTRACT_DENOM))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(SendFee::new(Addr::unchecked("fee"), 2).to_cosmos_msg(CONTRACT_DENOM))
+    );
+
+    let buffer = state.instant_unbond_buffer.load(deps.as_ref().storage).unwrap();
+    assert_eq!(buffer, Uint128::new(100000));
+}
+
+#[test]
+fn reconciling_refills_instant_unbond_buffer() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    enable_instant_unbond(deps.as_mut());
+    state.instant_unbond_buffer.save(deps.as_mut().storage, &Uint128::new(50000)).unwrap();
+
+    state
+        .previous_batches
+        .save(
+            deps.as_mut().storage,
+            1u64,
+            &Batch {
+                id: 1,
+                reconciled: false,
+                total_shares: Uint128::new(20000),
+                utoken_unclaimed: Uint128::new(20500),
+                est_unbond_end_time: 20000,
+            },
+        )
+        .unwrap();
+
+    for user in [MOCK_CONTRACT_ADDR, "user_1"] {
+        state
+            .unbond_requests
+            .save(
+                deps.as_mut().storage,
+                (1u64, &Addr::unchecked(user)),
+                &UnbondRequest {
+                    id: 1,
+                    user: Addr::unchecked(user),
+                    shares: Uint128::new(10000),
+                },
+            )
+            .unwrap();
+    }
+
+    // buffer + unbonded batch
+    deps.querier.set_bank_balances(&[Coin::new(70500, CONTRACT_DENOM)]);
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(30000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    // the share of the hub is moved back into the buffer
+    let buffer = state.instant_unbond_buffer.load(deps.as_ref().storage).unwrap();
+    assert_eq!(buffer, Uint128::new(60250));
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(
+        batch,
         Batch {
             id: 1,
             reconciled: true,
-            total_shares: Uint128::new(92876),
-            uluna_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            total_shares: Uint128::new(10000),
+            utoken_unclaimed: Uint128::new(10250),
+            est_unbond_end_time: 20000,
+        }
+    );
+
+    let ubr = state
+        .unbond_requests
+        .may_load(deps.as_ref().storage, (1u64, &Addr::unchecked(MOCK_CONTRACT_ADDR)))
+        .unwrap();
+    assert_eq!(ubr, None);
+}
+
+#[test]
+fn reconciling_with_limit() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: false,
+            total_shares: Uint128::new(1000),
+            utoken_unclaimed: Uint128::new(1000),
             est_unbond_end_time: 10000,
         },
         Batch {
             id: 2,
-            reconciled: true,
-            total_shares: Uint128::new(34567),
-            uluna_unclaimed: Uint128::new(35604), // 1.030 Token per Stake
+            reconciled: false,
+            total_shares: Uint128::new(2000),
+            utoken_unclaimed: Uint128::new(2000),
             est_unbond_end_time: 20000,
         },
         Batch {
             id: 3,
-            reconciled: false, // finished unbonding, but not reconciled; ignored
-            total_shares: Uint128::new(45678),
-            uluna_unclaimed: Uint128::new(47276), // 1.035 Token per Stake
-            est_unbond_end_time: 20000,
-        },
-        Batch {
-            id: 4,
-            reconciled: true,
-            total_shares: Uint128::new(56789),
-            uluna_unclaimed: Uint128::new(59060), // 1.040 Token per Stake
-            est_unbond_end_time: 30000, // reconciled, but not yet finished unbonding; ignored
+            reconciled: false,
+            total_shares: Uint128::new(3000),
+            utoken_unclaimed: Uint128::new(3000),
+            est_unbond_end_time: 40000,
         },
     ];
-
     for previous_batch in &previous_batches {
         state
             .previous_batches
@@ -1127,604 +1471,2846 @@ fn withdrawing_unbonded() {
             .unwrap();
     }
 
-    state
-        .pending_batch
-        .save(
-            deps.as_mut().storage,
-            &PendingBatch {
-                id: 4,
-                ustake_to_burn: Uint128::new(56789),
-                est_unbond_start_time: 100000,
-            },
-        )
-        .unwrap();
+    // batch 1 + batch 2 - shortfall of 150
+    deps.querier.set_bank_balances(&[Coin::new(2850, CONTRACT_DENOM)]);
 
-    // Attempt to withdraw before any batch has completed unbonding. Should error
-    let err = execute(
+    let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(5000),
-        mock_info("user_1", &[]),
-        ExecuteMsg::WithdrawUnbonded {
-            receiver: None,
+        mock_env_at_timestamp(30000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: Some(0),
         },
     )
     .unwrap_err();
+    assert_eq!(res, ContractError::CantBeZero("limit".into()));
 
-    assert_eq!(err, ContractError::CantBeZero("withdrawable amount".into()));
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(30000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: Some(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/reconciled")
+            .add_attribute("ids", "1")
+            .add_attribute("uluna_deducted", "150")
+            .add_attribute("uluna_buffer_refilled", "0")]
+    );
 
-    // Attempt to withdraw once batches 1 and 2 have finished unbonding, but 3 has not yet
-    //
-    // Withdrawable from batch 1: 95,197 * 23,456 / 92,876 = 24,042
-    // Withdrawable from batch 2: 35,604
-    // Total withdrawable: 24,042 + 35,604 = 59,646
+    // only the oldest batch is reconciled, batch 2 is expected in full as part of the balance
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert!(batch.reconciled);
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(850));
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert_eq!(batch, previous_batches[1]);
+
+    // the unclaimed funds of batch 1 are expected when reconciling batch 2
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(30000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: Some(1),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/reconciled")
+            .add_attribute("ids", "2")
+            .add_attribute("uluna_deducted", "0")
+            .add_attribute("uluna_buffer_refilled", "0")]
+    );
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert!(batch.reconciled);
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(2000));
+}
+
+#[test]
+fn tipping_keepers() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 333334),
+        Delegation::new("bob", 333333),
+        Delegation::new("charlie", 333333),
+    ]);
+    deps.querier.set_cw20_total_supply(STAKE_DENOM, 1000000);
+
+    let keeper_tips = KeeperTipConfig {
+        tip: Uint128::new(50),
+        pool_target: Uint128::new(80),
+        grace_period_s: 3600,
+        harvest_interval_s: 86400,
+    };
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: Some(keeper_tips.clone()),
+            prop_gauges: None,
+        },
+    )
+    .unwrap();
+
+    // the tip pool is refilled from the protocol fee
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(100000, CONTRACT_DENOM)])
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
+    )
+    .unwrap();
+
+    // fee = 1000, tip pool refill = 80, bonded = 100000 - 1000 = 99000
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Delegation::new("bob", 99000).to_cosmos_msg(CONTRACT_DENOM))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(SendFee::new(Addr::unchecked("fee"), 920).to_cosmos_msg(CONTRACT_DENOM))
+    );
+
+    let tip_pool = state.keeper_tip_pool.load(deps.as_ref().storage).unwrap();
+    assert_eq!(tip_pool, Uint128::new(80));
+
+    state
+        .pending_batch
+        .save(
+            deps.as_mut().storage,
+            &PendingBatch {
+                id: 2,
+                ustake_to_burn: Uint128::new(1000),
+                est_unbond_start_time: 50000,
+            },
+        )
+        .unwrap();
+    state
+        .previous_batches
+        .save(
+            deps.as_mut().storage,
+            1u64,
+            &Batch {
+                id: 1,
+                reconciled: false,
+                total_shares: Uint128::new(1000),
+                utoken_unclaimed: Uint128::new(1000),
+                est_unbond_end_time: 99000,
+            },
+        )
+        .unwrap();
+
+    // the harvest is due since 10000 + 86400, but not yet overdue
+    let res: PendingKeeperActionsResponse =
+        query_helper_env(deps.as_ref(), QueryMsg::PendingKeeperActions {}, 99999);
+    assert_eq!(
+        res,
+        PendingKeeperActionsResponse {
+            actions: vec![
+                PendingKeeperAction {
+                    action: KeeperAction::SubmitBatch,
+                    due_since: 50000,
+                    tip: Uint128::new(50),
+                },
+                PendingKeeperAction {
+                    action: KeeperAction::Reconcile,
+                    due_since: 99001,
+                    tip: Uint128::zero(),
+                },
+                PendingKeeperAction {
+                    action: KeeperAction::Harvest,
+                    due_since: 96400,
+                    tip: Uint128::zero(),
+                },
+            ],
+            keeper_tips: Some(keeper_tips),
+            tip_pool: Uint128::new(80),
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(100000),
+        mock_info("keeper", &[]),
+        ExecuteMsg::Harvest {},
+    )
+    .unwrap();
+
+    // withdraw rewards x3, check received coin, reinvest, tip
+    assert_eq!(res.messages.len(), 6);
+    assert_eq!(
+        res.messages[5],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "keeper".to_string(),
+            amount: vec![Coin::new(50, CONTRACT_DENOM)],
+        }))
+    );
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/keeper_tipped")
+            .add_attribute("keeper", "keeper")
+            .add_attribute("keeper_action", "harvest")
+            .add_attribute("uluna_tip", "50")]
+    );
+
+    // only the remaining tip pool is paid out
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(100000),
+        mock_info("keeper", &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "keeper".to_string(),
+            amount: vec![Coin::new(30, CONTRACT_DENOM)],
+        }))
+    );
+
+    let tip_pool = state.keeper_tip_pool.load(deps.as_ref().storage).unwrap();
+    assert_eq!(tip_pool, Uint128::zero());
+
+    // batches submitted by the hub itself when queuing an unbond are not tipped
+    state.keeper_tip_pool.save(deps.as_mut().storage, &Uint128::new(80)).unwrap();
+    state
+        .pending_batch
+        .save(
+            deps.as_mut().storage,
+            &PendingBatch {
+                id: 3,
+                ustake_to_burn: Uint128::new(1000),
+                est_unbond_start_time: 150000,
+            },
+        )
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(200000),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+    assert!(res.events.iter().all(|event| event.ty != "erishub/keeper_tipped"));
+
+    let tip_pool = state.keeper_tip_pool.load(deps.as_ref().storage).unwrap();
+    assert_eq!(tip_pool, Uint128::new(80));
+}
+
+#[test]
+fn submitting_batch() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // utoken bonded: 1,037,345
+    // ustake supply: 1,012,043
+    // utoken per ustake: 1.025
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 345782),
+        Delegation::new("bob", 345782),
+        Delegation::new("charlie", 345781),
+    ]);
+    deps.querier.set_cw20_total_supply(STAKE_DENOM, 1012043);
+
+    // We continue from the contract state at the end of the last test
+    let unbond_requests = vec![
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(23456),
+        },
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_3"),
+            shares: Uint128::new(69420),
+        },
+    ];
+
+    for unbond_request in &unbond_requests {
+        state
+            .unbond_requests
+            .save(
+                deps.as_mut().storage,
+                (unbond_request.id, &Addr::unchecked(unbond_request.user.clone())),
+                unbond_request,
+            )
+            .unwrap();
+    }
+
+    state
+        .pending_batch
+        .save(
+            deps.as_mut().storage,
+            &PendingBatch {
+                id: 1,
+                ustake_to_burn: Uint128::new(92876), // 23,456 + 69,420
+                est_unbond_start_time: 269200,
+            },
+        )
+        .unwrap();
+
+    // Anyone can invoke `submit_batch`. Here we continue from the previous test and assume it is
+    // invoked automatically as user 2 submits the unbonding request
     //
-    // Batch 1 should be updated:
-    // Total shares: 92,876 - 23,456 = 69,420
-    // Unclaimed utoken: 95,197 - 24,042 = 71,155
+    // ustake to burn: 23,456 + 69,420 = 92,876
+    // utoken to unbond: 1,037,345 * 92,876 / 1,012,043 = 95,197
     //
-    // Batch 2 is completely withdrawn, should be purged from storage
+    // Target: (1,037,345 - 95,197) / 3 = 314,049
+    // Remainer: 1
+    // Alice:   345,782 - (314,049 + 1) = 31,732
+    // Bob:     345,782 - (314,049 + 0) = 31,733
+    // Charlie: 345,781 - (314,049 + 0) = 31,732
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(269201),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::SubmitBatch {},
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 5);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Undelegation::new("alice", 31732).to_cosmos_msg(CONTRACT_DENOM))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(Undelegation::new("bob", 31733).to_cosmos_msg(CONTRACT_DENOM))
+    );
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(Undelegation::new("charlie", 31732).to_cosmos_msg(CONTRACT_DENOM))
+    );
+    assert_eq!(
+        res.messages[3],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: STAKE_DENOM.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Burn {
+                amount: Uint128::new(92876)
+            })
+            .unwrap(),
+            funds: vec![]
+        }))
+    );
+    assert_eq!(res.messages[4], check_received_coin(0));
+
+    // A new pending batch should have been created
+    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        pending_batch,
+        PendingBatch {
+            id: 2,
+            ustake_to_burn: Uint128::zero(),
+            est_unbond_start_time: 528401 // 269,201 + 259,200
+        }
+    );
+
+    // Previous batch should have been updated
+    let previous_batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(
+        previous_batch,
+        Batch {
+            id: 1,
+            reconciled: false,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197),
+            est_unbond_end_time: 2083601 // 269,201 + 1,814,400
+        }
+    );
+
+    // Undelegations per validator should have been tracked
+    let batch_undelegations = state.batch_undelegations.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(
+        batch_undelegations,
+        BatchUndelegations {
+            height: 12345,
+            undelegations: vec![
+                ("alice".to_string(), Uint128::new(31732)),
+                ("bob".to_string(), Uint128::new(31733)),
+                ("charlie".to_string(), Uint128::new(31732)),
+            ],
+        }
+    );
+
+    let res: StateResponse = query_helper_env(deps.as_ref(), QueryMsg::State {}, 2083600);
+    assert_eq!(
+        res,
+        StateResponse {
+            total_ustake: Uint128::from(1012043u128),
+            total_utoken: Uint128::from(1037345u128),
+            exchange_rate: Decimal::from_ratio(1037345u128, 1012043u128),
+            unlocked_coins: vec![],
+            unbonding: Uint128::from(95197u128),
+            available: Uint128::zero(),
+            tvl_utoken: Uint128::from(95197u128 + 1037345u128),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
+        },
+    );
+}
+
+#[test]
+fn reconciling() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            est_unbond_end_time: 10000,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1345),
+            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
+            est_unbond_end_time: 20000,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(1456),
+            utoken_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
+            est_unbond_end_time: 30000,
+        },
+        Batch {
+            id: 4,
+            reconciled: false,
+            total_shares: Uint128::new(1567),
+            utoken_unclaimed: Uint128::new(1629), // 1.040 Token per Stake
+            est_unbond_end_time: 40000,           // not yet finished unbonding, ignored
+        },
+    ];
+
+    for previous_batch in &previous_batches {
+        state
+            .previous_batches
+            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
+            .unwrap();
+    }
+
+    state
+        .unlocked_coins
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                Coin::new(10000, CONTRACT_DENOM),
+                Coin::new(234, "ukrw"),
+                Coin::new(345, "uusd"),
+                Coin::new(
+                    69420,
+                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
+                ),
+            ],
+        )
+        .unwrap();
+
+    deps.querier.set_bank_balances(&[
+        // batch 1 has been reconciled, but not withdrawn yet
+        Coin::new(95197 + 12345, CONTRACT_DENOM),
+        Coin::new(234, "ukrw"),
+        Coin::new(345, "uusd"),
+        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
+    ]);
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    // Expected received: batch 2 + batch 3 = 1385 + 1506 = 2891
+    // Expected unlocked: 10000 + batch 1 = 10000 + 95197 = 105197
+    // Expected: 108088
+    // Actual: 107542
+    // Shortfall: 108088 - 107542 = 546
+    //
+    // utoken per batch: 546 / 2 = 273
+    // remainder: 0
+    // batch 2: 1385 - 273 = 1112
+    // batch 3: 1506 - 273 = 1233
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 2,
+            reconciled: true,
+            total_shares: Uint128::new(1345),
+            utoken_unclaimed: Uint128::new(1112), // 1385 - 273
+            est_unbond_end_time: 20000,
+        }
+    );
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 3,
+            reconciled: true,
+            total_shares: Uint128::new(1456),
+            utoken_unclaimed: Uint128::new(1233), // 1506 - 273
+            est_unbond_end_time: 30000,
+        }
+    );
+
+    // Batches 1 and 4 should not have changed
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(batch, previous_batches[0]);
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 4u64).unwrap();
+    assert_eq!(batch, previous_batches[3]);
+}
+
+#[test]
+fn reconciling_even_when_everything_ok() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(100000),
+            utoken_unclaimed: Uint128::new(100000),
+            est_unbond_end_time: 10000,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1000),
+            utoken_unclaimed: Uint128::new(1000),
+            est_unbond_end_time: 20000,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
+            est_unbond_end_time: 30000,
+        },
+        Batch {
+            id: 4,
+            reconciled: false,
+            total_shares: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
+            est_unbond_end_time: 40000, // not yet finished unbonding, ignored
+        },
+    ];
+
+    for previous_batch in &previous_batches {
+        state
+            .previous_batches
+            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
+            .unwrap();
+    }
+
+    state
+        .unlocked_coins
+        .save(deps.as_mut().storage, &vec![Coin::new(1000, CONTRACT_DENOM)])
+        .unwrap();
+
+    // batch 1 has been reconciled, but not withdrawn yet
+    deps.querier.set_bank_balances(&[Coin::new(100000 + 3500, CONTRACT_DENOM)]);
+
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 2,
+            reconciled: true,
+            total_shares: Uint128::new(1000),
+            utoken_unclaimed: Uint128::new(1000),
+            est_unbond_end_time: 20000,
+        }
+    );
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 3u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 3,
+            reconciled: true,
+            total_shares: Uint128::new(1500),
+            utoken_unclaimed: Uint128::new(1500),
+            est_unbond_end_time: 30000,
+        }
+    );
+
+    // Batches 1 and 4 should not have changed
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(batch, previous_batches[0]);
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 4u64).unwrap();
+    assert_eq!(batch, previous_batches[3]);
+}
+
+#[test]
+fn reconciling_underflow() {
+    let mut deps = setup_test();
+    let state = State::default();
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            est_unbond_end_time: 10000,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1345),
+            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
+            est_unbond_end_time: 20000,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(1456),
+            utoken_unclaimed: Uint128::new(1506), // 1.035 Token per Stake
+            est_unbond_end_time: 30000,
+        },
+        Batch {
+            id: 4,
+            reconciled: false,
+            total_shares: Uint128::new(1),
+            utoken_unclaimed: Uint128::new(1),
+            est_unbond_end_time: 30001,
+        },
+    ];
+    for previous_batch in &previous_batches {
+        state
+            .previous_batches
+            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
+            .unwrap();
+    }
+    state
+        .unlocked_coins
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                Coin::new(10000, CONTRACT_DENOM),
+                Coin::new(234, "ukrw"),
+                Coin::new(345, "uusd"),
+                Coin::new(
+                    69420,
+                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
+                ),
+            ],
+        )
+        .unwrap();
+    deps.querier.set_bank_balances(&[
+        Coin::new(95197 + 12345, CONTRACT_DENOM),
+        Coin::new(234, "ukrw"),
+        Coin::new(345, "uusd"),
+        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
+    ]);
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn reconciling_underflow_second() {
+    let mut deps = setup_test();
+    let state = State::default();
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            est_unbond_end_time: 10000,
+        },
+        Batch {
+            id: 2,
+            reconciled: false,
+            total_shares: Uint128::new(1345),
+            utoken_unclaimed: Uint128::new(1385), // 1.030 Token per Stake
+            est_unbond_end_time: 20000,
+        },
+        Batch {
+            id: 3,
+            reconciled: false,
+            total_shares: Uint128::new(176),
+            utoken_unclaimed: Uint128::new(183), // 1.035 Token per Stake
+            est_unbond_end_time: 30000,
+        },
+        Batch {
+            id: 4,
+            reconciled: false,
+            total_shares: Uint128::new(1),
+            utoken_unclaimed: Uint128::new(1),
+            est_unbond_end_time: 30001,
+        },
+    ];
+    for previous_batch in &previous_batches {
+        state
+            .previous_batches
+            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
+            .unwrap();
+    }
+    state
+        .unlocked_coins
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                Coin::new(10000, CONTRACT_DENOM),
+                Coin::new(234, "ukrw"),
+                Coin::new(345, "uusd"),
+                Coin::new(
+                    69420,
+                    "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B",
+                ),
+            ],
+        )
+        .unwrap();
+    deps.querier.set_bank_balances(&[
+        // batch 1 + unlocked + expected - slash
+        Coin::new(95197 + 10000 + 1569 - 569, CONTRACT_DENOM),
+        Coin::new(234, "ukrw"),
+        Coin::new(345, "uusd"),
+        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
+    ]);
+
+    let result = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(result, ContractError::ReconcileTooBig(Uint128::new(1000), Uint128::new(1569)));
+
+    deps.querier.set_bank_balances(&[
+        Coin::new(95197 + 10000 + 1569 - 300, CONTRACT_DENOM),
+        Coin::new(234, "ukrw"),
+        Coin::new(345, "uusd"),
+        Coin::new(69420, "ibc/0471F1C4E7AFD3F07702BEF6DC365268D64570F7C1FDC98EA6098DD6DE59817B"),
+    ]);
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(35000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+}
+
+fn unbonding_entry(
+    creation_height: u64,
+    initial_balance: u128,
+    balance: u128,
+) -> UnbondingDelegationEntry {
+    UnbondingDelegationEntry {
+        creation_height,
+        initial_balance: Uint128::new(initial_balance),
+        balance: Uint128::new(balance),
+    }
+}
+
+#[test]
+fn reconciling_records_slashing() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    state
+        .previous_batches
+        .save(
+            deps.as_mut().storage,
+            1u64,
+            &Batch {
+                id: 1,
+                reconciled: false,
+                total_shares: Uint128::new(2000),
+                utoken_unclaimed: Uint128::new(2050),
+                est_unbond_end_time: 2083601,
+            },
+        )
+        .unwrap();
+    state
+        .batch_undelegations
+        .save(
+            deps.as_mut().storage,
+            1u64,
+            &BatchUndelegations {
+                height: 12345,
+                undelegations: vec![
+                    ("alice".to_string(), Uint128::new(1025)),
+                    ("bob".to_string(), Uint128::new(1025)),
+                ],
+            },
+        )
+        .unwrap();
+
+    // entries of other batches are ignored
+    deps.querier.set_unbonding_entries(
+        "alice",
+        vec![unbonding_entry(12345, 1025, 975), unbonding_entry(12000, 500, 400)],
+    );
+    deps.querier.set_unbonding_entries("bob", vec![unbonding_entry(12345, 1025, 1025)]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(30000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/slashed")
+            .add_attribute("validator", "alice")
+            .add_attribute("batch_id", "1")
+            .add_attribute("uluna_slashed", "50")]
+    );
+
+    // already recorded slashing is not reported twice
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(30001),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.events.len(), 0);
+
+    deps.querier.set_unbonding_entries("alice", vec![unbonding_entry(12345, 1025, 925)]);
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(40000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    let res: Vec<SlashingEvent> = query_helper(
+        deps.as_ref(),
+        QueryMsg::SlashingEvents {
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(
+        res,
+        vec![
+            SlashingEvent {
+                id: 2,
+                validator: "alice".to_string(),
+                batch_id: 1,
+                amount: Uint128::new(50),
+                time: 40000,
+            },
+            SlashingEvent {
+                id: 1,
+                validator: "alice".to_string(),
+                batch_id: 1,
+                amount: Uint128::new(50),
+                time: 30000,
+            }
+        ]
+    );
+
+    let slashed = state.slashed_validators_since(deps.as_ref().storage, 30000).unwrap();
+    assert_eq!(slashed.into_iter().collect_vec(), vec!["alice".to_string()]);
+    let slashed = state.slashed_validators_since(deps.as_ref().storage, 40000).unwrap();
+    assert!(slashed.is_empty());
+}
+
+#[test]
+fn reconciling_reports_failed_slashing_checks() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    state
+        .previous_batches
+        .save(
+            deps.as_mut().storage,
+            1u64,
+            &Batch {
+                id: 1,
+                reconciled: false,
+                total_shares: Uint128::new(1000),
+                utoken_unclaimed: Uint128::new(1025),
+                est_unbond_end_time: 2083601,
+            },
+        )
+        .unwrap();
+    state
+        .batch_undelegations
+        .save(
+            deps.as_mut().storage,
+            1u64,
+            &BatchUndelegations {
+                height: 12345,
+                undelegations: vec![("charlie".to_string(), Uint128::new(1025))],
+            },
+        )
+        .unwrap();
+
+    // no unbonding entries are available for charlie
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(30000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(res.events[0].ty, "erishub/slashing_check_failed");
+    assert_eq!(
+        res.events[0].attributes[0..2],
+        [attr("validator", "charlie"), attr("batch_id", "1")]
+    );
+}
+
+#[test]
+fn reconciling_deducts_slashing_from_slashed_batch() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    for id in [1u64, 2u64] {
+        state
+            .previous_batches
+            .save(
+                deps.as_mut().storage,
+                id,
+                &Batch {
+                    id,
+                    reconciled: false,
+                    total_shares: Uint128::new(1000),
+                    utoken_unclaimed: Uint128::new(1000),
+                    est_unbond_end_time: 20000,
+                },
+            )
+            .unwrap();
+    }
+    state.batch_slashing.save(deps.as_mut().storage, (1u64, "alice"), &Uint128::new(200)).unwrap();
+
+    // slashing of 200 + shortfall of 100
+    deps.querier.set_bank_balances(&[Coin::new(1700, CONTRACT_DENOM)]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(30000),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/reconciled")
+            .add_attribute("ids", "1,2")
+            .add_attribute("uluna_deducted", "300")
+            .add_attribute("uluna_buffer_refilled", "0")]
+    );
+
+    // the slashing is only deducted from the slashed batch, the rest is spread evenly
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(750));
+    let batch = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap();
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(950));
+}
+
+#[test]
+fn withdrawing_unbonded() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // We simulate a most general case:
+    // - batches 1 and 2 have finished unbonding
+    // - batch 3 have been submitted for unbonding but have not finished
+    // - batch 4 is still pending
+    let unbond_requests = vec![
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(23456),
+        },
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_3"),
+            shares: Uint128::new(69420),
+        },
+        UnbondRequest {
+            id: 2,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(34567),
+        },
+        UnbondRequest {
+            id: 3,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(45678),
+        },
+        UnbondRequest {
+            id: 4,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(56789),
+        },
+    ];
+
+    for unbond_request in &unbond_requests {
+        state
+            .unbond_requests
+            .save(
+                deps.as_mut().storage,
+                (unbond_request.id, &Addr::unchecked(unbond_request.user.clone())),
+                unbond_request,
+            )
+            .unwrap();
+    }
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            est_unbond_end_time: 10000,
+        },
+        Batch {
+            id: 2,
+            reconciled: true,
+            total_shares: Uint128::new(34567),
+            utoken_unclaimed: Uint128::new(35604), // 1.030 Token per Stake
+            est_unbond_end_time: 20000,
+        },
+        Batch {
+            id: 3,
+            reconciled: false, // finished unbonding, but not reconciled; ignored
+            total_shares: Uint128::new(45678),
+            utoken_unclaimed: Uint128::new(47276), // 1.035 Token per Stake
+            est_unbond_end_time: 20000,
+        },
+        Batch {
+            id: 4,
+            reconciled: true,
+            total_shares: Uint128::new(56789),
+            utoken_unclaimed: Uint128::new(59060), // 1.040 Token per Stake
+            est_unbond_end_time: 30000, // reconciled, but not yet finished unbonding; ignored
+        },
+    ];
+
+    for previous_batch in &previous_batches {
+        state
+            .previous_batches
+            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
+            .unwrap();
+    }
+
+    state
+        .pending_batch
+        .save(
+            deps.as_mut().storage,
+            &PendingBatch {
+                id: 4,
+                ustake_to_burn: Uint128::new(56789),
+                est_unbond_start_time: 100000,
+            },
+        )
+        .unwrap();
+
+    // Attempt to withdraw before any batch has completed unbonding. Should error
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(5000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            batch_ids: None,
+            limit: None,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::CantBeZero("withdrawable amount".into()));
+
+    // Attempt to withdraw once batches 1 and 2 have finished unbonding, but 3 has not yet
+    //
+    // Withdrawable from batch 1: 95,197 * 23,456 / 92,876 = 24,042
+    // Withdrawable from batch 2: 35,604
+    // Total withdrawable: 24,042 + 35,604 = 59,646
+    //
+    // Batch 1 should be updated:
+    // Total shares: 92,876 - 23,456 = 69,420
+    // Unclaimed utoken: 95,197 - 24,042 = 71,155
+    //
+    // Batch 2 is completely withdrawn, should be purged from storage
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            batch_ids: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "user_1".to_string(),
+            amount: vec![Coin::new(59646, CONTRACT_DENOM)]
+        }))
+    );
+
+    // Previous batches should have been updated
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(
+        batch,
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(69420),
+            utoken_unclaimed: Uint128::new(71155),
+            est_unbond_end_time: 10000,
+        }
+    );
+
+    let err = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::NotFound {
+            kind: "eris::hub::Batch".to_string()
+        }
+    );
+
+    // User 1's unbond requests in batches 1 and 2 should have been deleted
+    let err1 = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
+        .unwrap_err();
+    let err2 = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
+        .unwrap_err();
+
+    assert_eq!(
+        err1,
+        StdError::NotFound {
+            kind: "eris::hub::UnbondRequest".to_string()
+        }
+    );
+    assert_eq!(
+        err2,
+        StdError::NotFound {
+            kind: "eris::hub::UnbondRequest".to_string()
+        }
+    );
+
+    // User 3 attempt to withdraw; also specifying a receiver
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_3", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: Some("user_2".to_string()),
+            batch_ids: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "user_2".to_string(),
+            amount: vec![Coin::new(71155, CONTRACT_DENOM)]
+        }))
+    );
+
+    // Batch 1 and user 2's unbonding request should have been purged from storage
+    let err = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::NotFound {
+            kind: "eris::hub::Batch".to_string()
+        }
+    );
+
+    let err = state
+        .unbond_requests
+        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_3")))
+        .unwrap_err();
+
+    assert_eq!(
+        err,
+        StdError::NotFound {
+            kind: "eris::hub::UnbondRequest".to_string()
+        }
+    );
+}
+
+#[test]
+fn withdrawing_unbonded_partially() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    // Batches 1, 2 and 3 have finished unbonding, batch 4 has not
+    for (id, shares, utoken, est_unbond_end_time) in [
+        (1u64, 10000u128, 10250u128, 10000u64),
+        (2, 20000, 20600, 20000),
+        (3, 30000, 31050, 20000),
+        (4, 40000, 41600, 30000),
+    ] {
+        state
+            .unbond_requests
+            .save(
+                deps.as_mut().storage,
+                (id, &Addr::unchecked("user_1")),
+                &UnbondRequest {
+                    id,
+                    user: Addr::unchecked("user_1"),
+                    shares: Uint128::new(shares),
+                },
+            )
+            .unwrap();
+        state
+            .previous_batches
+            .save(
+                deps.as_mut().storage,
+                id,
+                &Batch {
+                    id,
+                    reconciled: true,
+                    total_shares: Uint128::new(shares),
+                    utoken_unclaimed: Uint128::new(utoken),
+                    est_unbond_end_time,
+                },
+            )
+            .unwrap();
+    }
+
+    let res: WithdrawableAmountResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::WithdrawableAmount {
+            user: "user_1".to_string(),
+        },
+        25000,
+    );
+    assert_eq!(
+        res,
+        WithdrawableAmountResponse {
+            total_utoken: Uint128::new(61900),
+            batches: vec![
+                WithdrawableBatch {
+                    id: 1,
+                    utoken: Uint128::new(10250),
+                },
+                WithdrawableBatch {
+                    id: 2,
+                    utoken: Uint128::new(20600),
+                },
+                WithdrawableBatch {
+                    id: 3,
+                    utoken: Uint128::new(31050),
+                },
+            ]
+        }
+    );
+
+    // Only withdraw batch 2. Batch 4 has not finished unbonding and is ignored
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            batch_ids: Some(vec![2, 4]),
+            limit: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "user_1".to_string(),
+            amount: vec![Coin::new(20600, CONTRACT_DENOM)]
+        }))]
+    );
+    assert!(state.previous_batches.may_load(deps.as_ref().storage, 2u64).unwrap().is_none());
+    assert!(state.previous_batches.may_load(deps.as_ref().storage, 4u64).unwrap().is_some());
+
+    // Withdraw at most one batch, starting with the oldest
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            batch_ids: None,
+            limit: Some(1),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "user_1".to_string(),
+            amount: vec![Coin::new(10250, CONTRACT_DENOM)]
+        }))]
+    );
+
+    let res: WithdrawableAmountResponse = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::WithdrawableAmount {
+            user: "user_1".to_string(),
+        },
+        25000,
+    );
+    assert_eq!(
+        res,
+        WithdrawableAmountResponse {
+            total_utoken: Uint128::new(31050),
+            batches: vec![WithdrawableBatch {
+                id: 3,
+                utoken: Uint128::new(31050),
+            }]
+        }
+    );
+
+    // Nothing withdrawable in the selected batches
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(25000),
+        mock_info("user_1", &[]),
+        ExecuteMsg::WithdrawUnbonded {
+            receiver: None,
+            batch_ids: Some(vec![4]),
+            limit: None,
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::CantBeZero("withdrawable amount".into()));
+}
+
+fn enable_unbond_receipts(deps: DepsMut) {
+    set_unbond_receipt_nft(deps, "unbond_nft").unwrap();
+}
+
+fn set_unbond_receipt_nft(deps: DepsMut, nft: &str) -> ContractResult {
+    execute(
+        deps,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: Some(nft.to_string()),
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
+        },
+    )
+}
+
+fn mint_receipt_msg(token_id: &str, owner: &str) -> SubMsg {
+    SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: "unbond_nft".to_string(),
+        msg: to_json_binary(&Cw721ExecuteMsg::Mint(MintMsg::<Extension> {
+            token_id: token_id.to_string(),
+            owner: owner.to_string(),
+            token_uri: None,
+            extension: None,
+        }))
+        .unwrap(),
+        funds: vec![],
+    }))
+}
+
+#[test]
+fn queuing_unbond_with_receipt() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let queue_unbond_msg = |sender: &str, amount: u128, receiver: Option<String>| {
+        ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&ReceiveMsg::QueueUnbond {
+                receiver,
+                mint_receipt: true,
+            })
+            .unwrap(),
+        })
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info(STAKE_DENOM, &[]),
+        queue_unbond_msg("user_1", 23456, None),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::UnbondReceiptsDisabled {});
+
+    enable_unbond_receipts(deps.as_mut());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345), // est_unbond_start_time = 269200
+        mock_info(STAKE_DENOM, &[]),
+        queue_unbond_msg("user_1", 23456, None),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages, vec![mint_receipt_msg("1", "user_1")]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(12345),
+        mock_info(STAKE_DENOM, &[]),
+        queue_unbond_msg("user_1", 10000, Some("user_2".to_string())),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages, vec![mint_receipt_msg("2", "user_2")]);
+
+    // The receipts are part of the pending batch, but not tracked as unbonding requests
+    let pending_batch = state.pending_batch.load(deps.as_ref().storage).unwrap();
+    assert_eq!(pending_batch.ustake_to_burn, Uint128::new(33456));
+
+    let res: Vec<UnbondRequestsByBatchResponseItem> = query_helper(
+        deps.as_ref(),
+        QueryMsg::UnbondRequestsByBatch {
+            id: 1,
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(res, vec![]);
+
+    let res: UnbondReceipt = query_helper(
+        deps.as_ref(),
+        QueryMsg::UnbondReceipt {
+            token_id: "2".to_string(),
+        },
+    );
+    assert_eq!(
+        res,
+        UnbondReceipt {
+            token_id: "2".to_string(),
+            id: 1,
+            shares: Uint128::new(10000),
+        }
+    );
+
+    // The collection can't be replaced while receipts minted on it are outstanding
+    let err = set_unbond_receipt_nft(deps.as_mut(), "new_unbond_nft").unwrap_err();
+    assert_eq!(err, ContractError::UnbondReceiptsOutstanding {});
+
+    set_unbond_receipt_nft(deps.as_mut(), "unbond_nft").unwrap();
+}
+
+#[test]
+fn withdrawing_unbond_receipt() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    enable_unbond_receipts(deps.as_mut());
+
+    let receipts = vec![
+        UnbondReceipt {
+            token_id: "1".to_string(),
+            id: 1,
+            shares: Uint128::new(23456),
+        },
+        UnbondReceipt {
+            token_id: "2".to_string(),
+            id: 2,
+            shares: Uint128::new(34567),
+        },
+    ];
+
+    for receipt in &receipts {
+        state.unbond_receipts.save(deps.as_mut().storage, &receipt.token_id, receipt).unwrap();
+    }
+
+    let previous_batches = vec![
+        Batch {
+            id: 1,
+            reconciled: true,
+            total_shares: Uint128::new(92876),
+            utoken_unclaimed: Uint128::new(95197), // 1.025 Token per Stake
+            est_unbond_end_time: 10000,
+        },
+        Batch {
+            id: 2,
+            reconciled: true,
+            total_shares: Uint128::new(34567),
+            utoken_unclaimed: Uint128::new(35604), // 1.030 Token per Stake
+            est_unbond_end_time: 30000,
+        },
+    ];
+
+    for previous_batch in &previous_batches {
+        state
+            .previous_batches
+            .save(deps.as_mut().storage, previous_batch.id, previous_batch)
+            .unwrap();
+    }
+
+    let withdraw_msg = |token_id: &str, receiver: Option<String>| {
+        ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: "user_1".to_string(),
+            token_id: token_id.to_string(),
+            msg: to_json_binary(&ReceiveNftMsg::WithdrawUnbonded {
+                receiver,
+            })
+            .unwrap(),
+        })
+    };
+
+    // Only receipts of the configured cw721 contract are accepted
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("random_nft", &[]),
+        withdraw_msg("1", None),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::ExpectingUnbondReceipt("random_nft".into()));
+
+    // Batch 2 has not finished unbonding
+    let err = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("unbond_nft", &[]),
+        withdraw_msg("2", None),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::UnbondReceiptNotWithdrawable("2".into()));
+
+    // Withdrawable from batch 1: 95,197 * 23,456 / 92,876 = 24,042
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("unbond_nft", &[]),
+        withdraw_msg("1", Some("user_2".to_string())),
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "user_2".to_string(),
+            amount: vec![Coin::new(24042, CONTRACT_DENOM)]
+        }))
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "unbond_nft".to_string(),
+            msg: to_json_binary(&Cw721ExecuteMsg::<Extension>::Burn {
+                token_id: "1".to_string()
+            })
+            .unwrap(),
+            funds: vec![]
+        }))
+    );
+
+    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
+    assert_eq!(batch.total_shares, Uint128::new(69420));
+    assert_eq!(batch.utoken_unclaimed, Uint128::new(71155));
+
+    let receipt = state.unbond_receipts.may_load(deps.as_ref().storage, "1").unwrap();
+    assert_eq!(receipt, None);
+}
+
+#[test]
+fn transferring_unbond_request() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let unbond_requests = vec![
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_1"),
+            shares: Uint128::new(23456),
+        },
+        UnbondRequest {
+            id: 1,
+            user: Addr::unchecked("user_2"),
+            shares: Uint128::new(69420),
+        },
+    ];
+
+    for unbond_request in &unbond_requests {
+        state
+            .unbond_requests
+            .save(deps.as_mut().storage, (unbond_request.id, &unbond_request.user), unbond_request)
+            .unwrap();
+    }
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_3", &[]),
+        ExecuteMsg::TransferUnbondRequest {
+            id: 1,
+            recipient: "user_2".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::NotFound {
+            kind: "eris::hub::UnbondRequest".to_string()
+        })
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("user_1", &[]),
+        ExecuteMsg::TransferUnbondRequest {
+            id: 1,
+            recipient: "user_2".to_string(),
+        },
+    )
+    .unwrap();
+
+    let res: Vec<UnbondRequestsByBatchResponseItem> = query_helper(
+        deps.as_ref(),
+        QueryMsg::UnbondRequestsByBatch {
+            id: 1,
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(
+        res,
+        vec![UnbondRequestsByBatchResponseItem {
+            user: "user_2".to_string(),
+            shares: Uint128::new(92876),
+        }]
+    );
+}
+
+#[test]
+fn adding_validator() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::AddValidator {
+            validator: "dave".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddValidator {
+            validator: "alice".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::ValidatorAlreadyWhitelisted("alice".into()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddValidator {
+            validator: "dave".to_string(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        validators,
+        vec![
+            String::from("alice"),
+            String::from("bob"),
+            String::from("charlie"),
+            String::from("dave")
+        ],
+    );
+}
+
+#[test]
+fn removing_validator() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 341667),
+        Delegation::new("bob", 341667),
+        Delegation::new("charlie", 341666),
+    ]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "charlie".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "dave".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::ValidatorNotWhitelisted("dave".into()));
+
+    // Target: (341667 + 341667 + 341666) / 2 = 512500
+    // Remainder: 0
+    // Alice:   512500 + 0 - 341667 = 170833
+    // Bob:     512500 + 0 - 341667 = 170833
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "charlie".to_string(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Redelegation::new("charlie", "alice", 170833).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(Redelegation::new("charlie", "bob", 170833).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+    assert_eq!(res.messages[2], check_received_coin(0));
+
+    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    assert_eq!(validators, vec![String::from("alice"), String::from("bob")],);
+}
+
+#[test]
+fn evicting_inactive_validators() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    state
+        .validators
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                "alice".to_string(),
+                "bob".to_string(),
+                "charlie".to_string(),
+                "dave".to_string(),
+            ],
+        )
+        .unwrap();
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 250000),
+        Delegation::new("bob", 250000),
+        Delegation::new("charlie", 250000),
+        Delegation::new("dave", 250000),
+    ]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::EvictInactiveValidators {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoActiveValidators {});
+
+    deps.querier.set_staking_validators(&[
+        ("alice", Decimal::percent(5)),
+        ("bob", Decimal::percent(5)),
+        ("charlie", Decimal::percent(5)),
+        ("dave", Decimal::percent(5)),
+    ]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::EvictInactiveValidators {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoInactiveValidators {});
+
+    // charlie and dave got jailed
+    deps.querier
+        .set_staking_validators(&[("alice", Decimal::percent(5)), ("bob", Decimal::percent(5))]);
+
+    // Target: 1000000 / 2 = 500000
+    // Charlie: 250000 to alice, which reaches the target
+    // Dave:    250000 to bob, as alice has already received charlie's stake
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::EvictInactiveValidators {},
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Redelegation::new("charlie", "alice", 250000).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(Redelegation::new("dave", "bob", 250000).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+    assert_eq!(res.messages[2], check_received_coin(0));
+    assert_eq!(
+        res.events,
+        vec![
+            Event::new("erishub/validator_evicted")
+                .add_attribute("validator", "charlie")
+                .add_attribute("uluna_moved", "250000"),
+            Event::new("erishub/validator_evicted")
+                .add_attribute("validator", "dave")
+                .add_attribute("uluna_moved", "250000"),
+        ]
+    );
+
+    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    assert_eq!(validators, vec![String::from("alice"), String::from("bob")]);
+}
+
+#[test]
+fn transferring_ownership() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: "jake".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: "jake".to_string(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let owner = state.owner.load(deps.as_ref().storage).unwrap();
+    assert_eq!(owner, Addr::unchecked("owner"));
+    let new_owner = state.new_owner.load(deps.as_ref().storage).unwrap();
+    assert_eq!(new_owner, Addr::unchecked("jake"));
+
+    // Check dropping ownership proposal
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pumpkin", &[]),
+        ExecuteMsg::DropOwnershipProposal {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::DropOwnershipProposal {},
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    let owner = state.owner.load(deps.as_ref().storage).unwrap();
+    assert_eq!(owner, Addr::unchecked("owner"));
+    let new_owner = state.new_owner.may_load(deps.as_ref().storage).unwrap();
+    assert_eq!(new_owner, None);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: "jake".to_string(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let owner = state.owner.load(deps.as_ref().storage).unwrap();
+    assert_eq!(owner, Addr::unchecked("owner"));
+    let new_owner = state.new_owner.load(deps.as_ref().storage).unwrap();
+    assert_eq!(new_owner, Addr::unchecked("jake"));
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pumpkin", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::UnauthorizedSenderNotNewOwner {});
+
+    let res =
+        execute(deps.as_mut(), mock_env(), mock_info("jake", &[]), ExecuteMsg::AcceptOwnership {})
+            .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let owner = state.owner.load(deps.as_ref().storage).unwrap();
+    assert_eq!(owner, Addr::unchecked("jake"));
+}
+
+//--------------------------------------------------------------------------------------------------
+// Fee Config
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn update_fee() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    let config = state.fee_config.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        config,
+        FeeConfig {
+            protocol_fee_contract: Addr::unchecked("fee"),
+            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+            fee_receivers: vec![]
+        }
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: Some(Decimal::from_ratio(11u128, 100u128)),
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: Some(Decimal::from_ratio(11u128, 100u128)),
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProtocolRewardFeeTooHigh {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: Some("fee-new".to_string()),
+            protocol_reward_fee: Some(Decimal::from_ratio(10u128, 100u128)),
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+
+    let config = state.fee_config.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        config,
+        FeeConfig {
+            protocol_fee_contract: Addr::unchecked("fee-new"),
+            protocol_reward_fee: Decimal::from_ratio(10u128, 100u128),
+            fee_receivers: vec![]
+        }
+    );
+}
+
+//--------------------------------------------------------------------------------------------------
+// Gov
+//--------------------------------------------------------------------------------------------------
+
+#[test]
+fn vote() {
+    let mut deps = setup_test();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::Vote {
+            proposal_id: 3,
+            vote: cosmwasm_std::VoteOption::Yes,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoVoteOperatorSet {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: Some("vote_operator".to_string()),
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
+        },
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::Vote {
+            proposal_id: 3,
+            vote: cosmwasm_std::VoteOption::Yes,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::UnauthorizedSenderNotVoteOperator {});
+
     let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(25000),
-        mock_info("user_1", &[]),
-        ExecuteMsg::WithdrawUnbonded {
-            receiver: None,
+        mock_env(),
+        mock_info("vote_operator", &[]),
+        ExecuteMsg::Vote {
+            proposal_id: 3,
+            vote: cosmwasm_std::VoteOption::Yes,
         },
     )
     .unwrap();
-
     assert_eq!(res.messages.len(), 1);
+
     assert_eq!(
         res.messages[0],
-        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "user_1".to_string(),
-            amount: vec![Coin::new(59646, CONTRACT_DENOM)]
+        SubMsg::new(CosmosMsg::Gov(GovMsg::Vote {
+            proposal_id: 3,
+            vote: cosmwasm_std::VoteOption::Yes
         }))
     );
+}
 
-    // Previous batches should have been updated
-    let batch = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap();
-    assert_eq!(
-        batch,
-        Batch {
-            id: 1,
-            reconciled: true,
-            total_shares: Uint128::new(69420),
-            uluna_unclaimed: Uint128::new(71155),
-            est_unbond_end_time: 10000,
-        }
-    );
-
-    let err = state.previous_batches.load(deps.as_ref().storage, 2u64).unwrap_err();
-    assert_eq!(
-        err,
-        StdError::NotFound {
-            kind: "eris::hub::Batch".to_string()
-        }
-    );
+#[test]
+fn vote_weighted() {
+    let mut deps = setup_test();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::VoteWeighted {
+            proposal_id: 3,
+            votes: vec![
+                (Decimal::from_str("0.4").unwrap(), VoteOption::Yes),
+                (Decimal::from_str("0.6").unwrap(), VoteOption::No),
+            ],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoVoteOperatorSet {});
 
-    // User 1's unbond requests in batches 1 and 2 should have been deleted
-    let err1 = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
-        .unwrap_err();
-    let err2 = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_1")))
-        .unwrap_err();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: Some("vote_operator".to_string()),
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
+        },
+    )
+    .unwrap();
 
-    assert_eq!(
-        err1,
-        StdError::NotFound {
-            kind: "eris::hub::UnbondRequest".to_string()
-        }
-    );
-    assert_eq!(
-        err2,
-        StdError::NotFound {
-            kind: "eris::hub::UnbondRequest".to_string()
-        }
-    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("jake", &[]),
+        ExecuteMsg::VoteWeighted {
+            proposal_id: 3,
+            votes: vec![
+                (Decimal::from_str("0.4").unwrap(), VoteOption::Yes),
+                (Decimal::from_str("0.6").unwrap(), VoteOption::No),
+            ],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::UnauthorizedSenderNotVoteOperator {});
 
-    // User 3 attempt to withdraw; also specifying a receiver
     let res = execute(
         deps.as_mut(),
-        mock_env_at_timestamp(25000),
-        mock_info("user_3", &[]),
-        ExecuteMsg::WithdrawUnbonded {
-            receiver: Some("user_2".to_string()),
+        mock_env(),
+        mock_info("vote_operator", &[]),
+        ExecuteMsg::VoteWeighted {
+            proposal_id: 3,
+            votes: vec![
+                (Decimal::from_str("0.1").unwrap(), VoteOption::Yes),
+                (Decimal::from_str("0.2").unwrap(), VoteOption::No),
+                (Decimal::from_str("0.3").unwrap(), VoteOption::Abstain),
+                (Decimal::from_str("0.4").unwrap(), VoteOption::NoWithVeto),
+            ],
         },
     )
     .unwrap();
-
     assert_eq!(res.messages.len(), 1);
-    assert_eq!(
-        res.messages[0],
-        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "user_2".to_string(),
-            amount: vec![Coin::new(71155, CONTRACT_DENOM)]
-        }))
-    );
-
-    // Batch 1 and user 2's unbonding request should have been purged from storage
-    let err = state.previous_batches.load(deps.as_ref().storage, 1u64).unwrap_err();
-    assert_eq!(
-        err,
-        StdError::NotFound {
-            kind: "eris::hub::Batch".to_string()
-        }
-    );
-
-    let err = state
-        .unbond_requests
-        .load(deps.as_ref().storage, (1u64, &Addr::unchecked("user_3")))
-        .unwrap_err();
 
     assert_eq!(
-        err,
-        StdError::NotFound {
-            kind: "eris::hub::UnbondRequest".to_string()
+        res.messages[0].msg,
+        MsgVoteWeighted {
+            proposal_id: 3,
+            voter: MOCK_CONTRACT_ADDR.into(),
+            options: vec![
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_YES.into(),
+                    weight: Decimal::from_str("0.1").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_NO.into(),
+                    weight: Decimal::from_str("0.2").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_ABSTAIN.into(),
+                    weight: Decimal::from_str("0.3").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_NO_WITH_VETO.into(),
+                    weight: Decimal::from_str("0.4").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+            ],
+            special_fields: SpecialFields::default()
         }
+        .to_cosmos_msg()
     );
 }
 
 #[test]
-fn adding_validator() {
+fn casting_prop_votes() {
     let mut deps = setup_test();
-    let state = State::default();
 
-    let err = execute(
+    let res = execute(
         deps.as_mut(),
         mock_env(),
         mock_info("jake", &[]),
-        ExecuteMsg::AddValidator {
-            validator: "dave".to_string(),
+        ExecuteMsg::CastPropVote {
+            proposal_id: 3,
         },
     )
     .unwrap_err();
+    assert_eq!(res, ContractError::NoPropGaugesSet {});
 
-    assert_eq!(err, ContractError::Unauthorized {});
-
-    let err = execute(
+    execute(
         deps.as_mut(),
         mock_env(),
         mock_info("owner", &[]),
-        ExecuteMsg::AddValidator {
-            validator: "alice".to_string(),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: Some("prop_gauges".to_string()),
         },
     )
-    .unwrap_err();
+    .unwrap();
 
-    assert_eq!(err, ContractError::ValidatorAlreadyWhitelisted("alice".into()));
+    deps.querier.set_prop_gauges_quorum(1000);
+    deps.querier.set_prop(
+        3,
+        PropInfo {
+            period: 1,
+            end_time_s: 20000,
+            yes_vp: Uint128::new(600),
+            no_vp: Uint128::new(300),
+            abstain_vp: Uint128::new(100),
+            nwv_vp: Uint128::zero(),
+            total_vp: Uint128::new(2000),
+            current_vote: Some(VoteOption::Yes),
+        },
+    );
+    deps.querier.set_prop(
+        4,
+        PropInfo {
+            period: 1,
+            end_time_s: 20000,
+            yes_vp: Uint128::new(50),
+            no_vp: Uint128::zero(),
+            abstain_vp: Uint128::zero(),
+            nwv_vp: Uint128::zero(),
+            total_vp: Uint128::new(2000),
+            current_vote: Some(VoteOption::Yes),
+        },
+    );
 
+    // voting in prop_gauges is still ongoing
     let res = execute(
         deps.as_mut(),
-        mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::AddValidator {
-            validator: "dave".to_string(),
+        mock_env_at_timestamp(10000),
+        mock_info("jake", &[]),
+        ExecuteMsg::CastPropVote {
+            proposal_id: 3,
         },
     )
-    .unwrap();
+    .unwrap_err();
+    assert_eq!(res, ContractError::PropVotingNotEnded(3));
 
-    assert_eq!(res.messages.len(), 0);
+    // only 2.5% of the voting power voted, below the quorum of 10%
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("jake", &[]),
+        ExecuteMsg::CastPropVote {
+            proposal_id: 4,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::PropQuorumNotReached(4));
 
-    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    // anyone can cast the vote, weighted like the votes in prop_gauges
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("jake", &[]),
+        ExecuteMsg::CastPropVote {
+            proposal_id: 3,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(
+        res.messages[0].msg,
+        MsgVoteWeighted {
+            proposal_id: 3,
+            voter: MOCK_CONTRACT_ADDR.into(),
+            options: vec![
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_YES.into(),
+                    weight: Decimal::from_str("0.6").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_NO.into(),
+                    weight: Decimal::from_str("0.3").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+                WeightedVoteOption {
+                    option: proto::VoteOption::VOTE_OPTION_ABSTAIN.into(),
+                    weight: Decimal::from_str("0.1").unwrap().numerator().to_string(),
+                    special_fields: SpecialFields::default()
+                },
+            ],
+            special_fields: SpecialFields::default()
+        }
+        .to_cosmos_msg()
+    );
     assert_eq!(
-        validators,
-        vec![
-            String::from("alice"),
-            String::from("bob"),
-            String::from("charlie"),
-            String::from("dave")
-        ],
+        res.events,
+        vec![Event::new("erishub/prop_vote_cast")
+            .add_attribute("prop", "3")
+            .add_attribute("voted_vp", "1000")
+            .add_attribute("total_vp", "2000")]
     );
 }
 
 #[test]
-fn removing_validator() {
+fn pausing_operations() {
     let mut deps = setup_test();
-    let state = State::default();
-
-    deps.querier.set_staking_delegations(&[
-        Delegation::new("alice", 341667),
-        Delegation::new("bob", 341667),
-        Delegation::new("charlie", 341666),
-    ]);
 
-    let err = execute(
+    execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("jake", &[]),
-        ExecuteMsg::RemoveValidator {
-            validator: "charlie".to_string(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            protocol_fee_contract: None,
+            protocol_reward_fee: None,
+            delegation_strategy: None,
+            allow_donations: None,
+            vote_operator: None,
+            epoch_period: None,
+            unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: Some("guardian".to_string()),
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
         },
     )
-    .unwrap_err();
-
-    assert_eq!(err, ContractError::Unauthorized {});
+    .unwrap();
 
-    let err = execute(
+    let res = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::RemoveValidator {
-            validator: "dave".to_string(),
+        mock_info("jake", &[]),
+        ExecuteMsg::SetPause {
+            operations: vec![PauseOperation::Bond],
+            paused: true,
         },
     )
     .unwrap_err();
+    assert_eq!(res, ContractError::UnauthorizedSenderNotGuardian {});
 
-    assert_eq!(err, ContractError::ValidatorNotWhitelisted("dave".into()));
-
-    // Target: (341667 + 341667 + 341666) / 2 = 512500
-    // Remainder: 0
-    // Alice:   512500 + 0 - 341667 = 170833
-    // Bob:     512500 + 0 - 341667 = 170833
     let res = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::RemoveValidator {
-            validator: "charlie".to_string(),
+        mock_info("guardian", &[]),
+        ExecuteMsg::SetPause {
+            operations: vec![PauseOperation::Bond, PauseOperation::Harvest],
+            paused: true,
         },
     )
     .unwrap();
+    assert_eq!(res.messages.len(), 0);
 
-    assert_eq!(res.messages.len(), 3);
-    assert_eq!(
-        res.messages[0],
-        SubMsg::new(Redelegation::new("charlie", "alice", 170833).to_cosmos_msg()),
-    );
-    assert_eq!(
-        res.messages[1],
-        SubMsg::new(Redelegation::new("charlie", "bob", 170833).to_cosmos_msg()),
-    );
-    assert_eq!(res.messages[2], check_received_coin(0));
-
-    let validators = state.validators.load(deps.as_ref().storage).unwrap();
-    assert_eq!(validators, vec![String::from("alice"), String::from("bob")],);
-}
-
-#[test]
-fn transferring_ownership() {
-    let mut deps = setup_test();
-    let state = State::default();
-
-    let err = execute(
+    let res = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("jake", &[]),
-        ExecuteMsg::TransferOwnership {
-            new_owner: "jake".to_string(),
+        mock_info("user_1", &[Coin::new(1000000, CONTRACT_DENOM)]),
+        ExecuteMsg::Bond {
+            receiver: None,
+            referral: None,
         },
     )
     .unwrap_err();
+    assert_eq!(res, ContractError::Paused(PauseOperation::Bond));
 
-    assert_eq!(err, ContractError::Unauthorized {});
+    let res = execute(deps.as_mut(), mock_env(), mock_info("worker", &[]), ExecuteMsg::Harvest {})
+        .unwrap_err();
+    assert_eq!(res, ContractError::Paused(PauseOperation::Harvest));
 
+    // reconciling and reinvesting are paused together with harvesting
     let res = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::TransferOwnership {
-            new_owner: "jake".to_string(),
+        mock_info("worker", &[]),
+        ExecuteMsg::Reconcile {
+            limit: None,
         },
     )
-    .unwrap();
-
-    assert_eq!(res.messages.len(), 0);
-
-    let owner = state.owner.load(deps.as_ref().storage).unwrap();
-    assert_eq!(owner, Addr::unchecked("owner"));
-    let new_owner = state.new_owner.load(deps.as_ref().storage).unwrap();
-    assert_eq!(new_owner, Addr::unchecked("jake"));
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused(PauseOperation::Harvest));
 
-    // Check dropping ownership proposal
-    let err = execute(
+    let res = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("pumpkin", &[]),
-        ExecuteMsg::DropOwnershipProposal {},
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::Callback(CallbackMsg::Reinvest {}),
     )
     .unwrap_err();
-    assert_eq!(err, ContractError::Unauthorized {});
+    assert_eq!(res, ContractError::Paused(PauseOperation::Harvest));
 
+    // only the owner can unpause
     let res = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::DropOwnershipProposal {},
+        mock_info("guardian", &[]),
+        ExecuteMsg::SetPause {
+            operations: vec![PauseOperation::Bond],
+            paused: false,
+        },
     )
-    .unwrap();
-    assert_eq!(res.messages.len(), 0);
-
-    let owner = state.owner.load(deps.as_ref().storage).unwrap();
-    assert_eq!(owner, Addr::unchecked("owner"));
-    let new_owner = state.new_owner.may_load(deps.as_ref().storage).unwrap();
-    assert_eq!(new_owner, None);
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
 
-    let res = execute(
+    execute(
         deps.as_mut(),
         mock_env(),
         mock_info("owner", &[]),
-        ExecuteMsg::TransferOwnership {
-            new_owner: "jake".to_string(),
+        ExecuteMsg::SetPause {
+            operations: vec![PauseOperation::Bond],
+            paused: false,
         },
     )
     .unwrap();
 
-    assert_eq!(res.messages.len(), 0);
+    let res: PauseStateResponse = query_helper(deps.as_ref(), QueryMsg::PauseState {});
+    assert_eq!(
+        res,
+        PauseStateResponse {
+            guardian: Some("guardian".to_string()),
+            paused: vec![PauseOperation::Harvest],
+        }
+    );
 
-    let owner = state.owner.load(deps.as_ref().storage).unwrap();
-    assert_eq!(owner, Addr::unchecked("owner"));
-    let new_owner = state.new_owner.load(deps.as_ref().storage).unwrap();
-    assert_eq!(new_owner, Addr::unchecked("jake"));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian", &[]),
+        ExecuteMsg::SetPause {
+            operations: vec![PauseOperation::Unbond, PauseOperation::Withdraw],
+            paused: true,
+        },
+    )
+    .unwrap();
 
-    let err = execute(
+    let res = execute(
         deps.as_mut(),
         mock_env(),
-        mock_info("pumpkin", &[]),
-        ExecuteMsg::AcceptOwnership {},
+        mock_info("user_1", &[]),
+        ExecuteMsg::TransferUnbondRequest {
+            id: 1,
+            recipient: "user_2".to_string(),
+        },
     )
     .unwrap_err();
+    assert_eq!(res, ContractError::Paused(PauseOperation::Unbond));
 
-    assert_eq!(err, ContractError::UnauthorizedSenderNotNewOwner {});
-
-    let res =
-        execute(deps.as_mut(), mock_env(), mock_info("jake", &[]), ExecuteMsg::AcceptOwnership {})
-            .unwrap();
-
-    assert_eq!(res.messages.len(), 0);
-
-    let owner = state.owner.load(deps.as_ref().storage).unwrap();
-    assert_eq!(owner, Addr::unchecked("jake"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("partner", &[]),
+        ExecuteMsg::ClaimReferralFees {
+            receiver: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused(PauseOperation::Withdraw));
 }
 
-//--------------------------------------------------------------------------------------------------
-// Fee Config
-//--------------------------------------------------------------------------------------------------
-
 #[test]
-fn update_fee() {
+fn timelocking_config_updates() {
     let mut deps = setup_test();
-    let state = State::default();
 
-    let config = state.fee_config.load(deps.as_ref().storage).unwrap();
-    assert_eq!(
-        config,
-        FeeConfig {
-            protocol_fee_contract: Addr::unchecked("fee"),
-            protocol_reward_fee: Decimal::from_ratio(1u128, 100u128)
-        }
-    );
+    let update_config =
+        |unbond_period: Option<u64>, guardian: Option<&str>, timelock_delay_s: Option<u64>| {
+            ExecuteMsg::UpdateConfig {
+                protocol_fee_contract: None,
+                protocol_reward_fee: None,
+                delegation_strategy: None,
+                allow_donations: None,
+                vote_operator: None,
+                epoch_period: None,
+                unbond_period,
+                instant_unbond: None,
+                validator_scoring: None,
+                unbond_receipt_nft: None,
+                fee_receivers: None,
+                referral_fee: None,
+                guardian: guardian.map(|addr| addr.to_string()),
+                timelock_delay_s,
+                keeper_tips: None,
+                prop_gauges: None,
+            }
+        };
 
-    let err = execute(
+    // without a delay, updates are applied immediately
+    execute(
         deps.as_mut(),
-        mock_env(),
-        mock_info("jake", &[]),
-        ExecuteMsg::UpdateConfig {
-            protocol_fee_contract: None,
-            protocol_reward_fee: Some(Decimal::from_ratio(11u128, 100u128)),
-            delegation_strategy: None,
-            allow_donations: None,
-            vote_operator: None,
-            epoch_period: None,
-            unbond_period: None,
-        },
+        mock_env_at_timestamp(10000),
+        mock_info("owner", &[]),
+        update_config(None, None, Some(86400)),
     )
-    .unwrap_err();
-    assert_eq!(err, ContractError::Unauthorized {});
+    .unwrap();
 
-    let err = execute(
+    let res = execute(
         deps.as_mut(),
-        mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::UpdateConfig {
-            protocol_fee_contract: None,
-            protocol_reward_fee: Some(Decimal::from_ratio(11u128, 100u128)),
-            delegation_strategy: None,
-            allow_donations: None,
-            vote_operator: None,
-            epoch_period: None,
-            unbond_period: None,
-        },
+        mock_env_at_timestamp(10000),
+        mock_info("jake", &[]),
+        update_config(Some(100), None, None),
     )
     .unwrap_err();
-    assert_eq!(err, ContractError::ProtocolRewardFeeTooHigh {});
+    assert_eq!(res, ContractError::Unauthorized {});
 
     let res = execute(
         deps.as_mut(),
-        mock_env(),
+        mock_env_at_timestamp(10000),
         mock_info("owner", &[]),
-        ExecuteMsg::UpdateConfig {
-            protocol_fee_contract: Some("fee-new".to_string()),
-            protocol_reward_fee: Some(Decimal::from_ratio(10u128, 100u128)),
-            delegation_strategy: None,
-            allow_donations: None,
-            vote_operator: None,
-            epoch_period: None,
-            unbond_period: None,
-        },
+        update_config(Some(100), None, None),
     )
     .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/action_proposed")
+            .add_attribute("id", "0")
+            .add_attribute("executable_at", "96400")]
+    );
 
-    assert_eq!(res.messages.len(), 0);
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.unbond_period, 1814400);
 
-    let config = state.fee_config.load(deps.as_ref().storage).unwrap();
+    let res: PendingActionsResponse<ExecuteMsg> = query_helper(
+        deps.as_ref(),
+        QueryMsg::PendingActions {
+            start_after: None,
+            limit: None,
+        },
+    );
     assert_eq!(
-        config,
-        FeeConfig {
-            protocol_fee_contract: Addr::unchecked("fee-new"),
-            protocol_reward_fee: Decimal::from_ratio(10u128, 100u128)
+        res,
+        PendingActionsResponse {
+            delay_s: 86400,
+            actions: vec![PendingAction {
+                id: 0,
+                action: update_config(Some(100), None, None),
+                proposed_at: 10000,
+                executable_at: 96400,
+            }],
         }
     );
-}
 
-//--------------------------------------------------------------------------------------------------
-// Gov
-//--------------------------------------------------------------------------------------------------
+    // updates without risk for stakers are not queued
+    execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("owner", &[]),
+        update_config(None, Some("guardian"), None),
+    )
+    .unwrap();
+
+    let res: PauseStateResponse = query_helper(deps.as_ref(), QueryMsg::PauseState {});
+    assert_eq!(res.guardian, Some("guardian".to_string()));
 
-#[test]
-fn vote() {
-    let mut deps = setup_test();
     let res = execute(
         deps.as_mut(),
-        mock_env(),
+        mock_env_at_timestamp(96399),
         mock_info("jake", &[]),
-        ExecuteMsg::Vote {
-            proposal_id: 3,
-            vote: cosmwasm_std::VoteOption::Yes,
+        ExecuteMsg::ExecuteTimelockedAction {
+            id: 0,
         },
     )
     .unwrap_err();
-    assert_eq!(res, ContractError::NoVoteOperatorSet {});
+    assert_eq!(res, StdError::generic_err("action 0 is timelocked until 96400").into());
 
     execute(
         deps.as_mut(),
-        mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::UpdateConfig {
-            protocol_fee_contract: None,
-            protocol_reward_fee: None,
-            delegation_strategy: None,
-            allow_donations: None,
-            vote_operator: Some("vote_operator".to_string()),
-            epoch_period: None,
-            unbond_period: None,
+        mock_env_at_timestamp(96400),
+        mock_info("jake", &[]),
+        ExecuteMsg::ExecuteTimelockedAction {
+            id: 0,
         },
     )
     .unwrap();
 
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.unbond_period, 100);
+
+    // an executed action can't be executed again
     let res = execute(
         deps.as_mut(),
-        mock_env(),
+        mock_env_at_timestamp(96400),
         mock_info("jake", &[]),
-        ExecuteMsg::Vote {
-            proposal_id: 3,
-            vote: cosmwasm_std::VoteOption::Yes,
+        ExecuteMsg::ExecuteTimelockedAction {
+            id: 0,
         },
     )
     .unwrap_err();
-    assert_eq!(res, ContractError::UnauthorizedSenderNotVoteOperator {});
+    assert_eq!(res, StdError::generic_err("pending action 0 not found").into());
 
-    let res = execute(
+    execute(
         deps.as_mut(),
-        mock_env(),
-        mock_info("vote_operator", &[]),
-        ExecuteMsg::Vote {
-            proposal_id: 3,
-            vote: cosmwasm_std::VoteOption::Yes,
-        },
+        mock_env_at_timestamp(96400),
+        mock_info("owner", &[]),
+        update_config(Some(200), None, None),
     )
     .unwrap();
-    assert_eq!(res.messages.len(), 1);
-
-    assert_eq!(
-        res.messages[0],
-        SubMsg::new(CosmosMsg::Gov(GovMsg::Vote {
-            proposal_id: 3,
-            vote: cosmwasm_std::VoteOption::Yes
-        }))
-    );
-}
 
-#[test]
-fn vote_weighted() {
-    let mut deps = setup_test();
     let res = execute(
         deps.as_mut(),
-        mock_env(),
+        mock_env_at_timestamp(96400),
         mock_info("jake", &[]),
-        ExecuteMsg::VoteWeighted {
-            proposal_id: 3,
-            votes: vec![
-                (Decimal::from_str("0.4").unwrap(), VoteOption::Yes),
-                (Decimal::from_str("0.6").unwrap(), VoteOption::No),
-            ],
+        ExecuteMsg::CancelTimelockedAction {
+            id: 1,
         },
     )
     .unwrap_err();
-    assert_eq!(res, ContractError::NoVoteOperatorSet {});
+    assert_eq!(res, ContractError::Unauthorized {});
 
     execute(
         deps.as_mut(),
-        mock_env(),
+        mock_env_at_timestamp(96400),
         mock_info("owner", &[]),
-        ExecuteMsg::UpdateConfig {
-            protocol_fee_contract: None,
-            protocol_reward_fee: None,
-            delegation_strategy: None,
-            allow_donations: None,
-            vote_operator: Some("vote_operator".to_string()),
-            epoch_period: None,
-            unbond_period: None,
+        ExecuteMsg::CancelTimelockedAction {
+            id: 1,
         },
     )
     .unwrap();
 
+    let res: PendingActionsResponse<ExecuteMsg> = query_helper(
+        deps.as_ref(),
+        QueryMsg::PendingActions {
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert_eq!(res.actions, vec![]);
+
+    // new validators have to wait for the delay as well
     let res = execute(
         deps.as_mut(),
-        mock_env(),
-        mock_info("jake", &[]),
-        ExecuteMsg::VoteWeighted {
-            proposal_id: 3,
-            votes: vec![
-                (Decimal::from_str("0.4").unwrap(), VoteOption::Yes),
-                (Decimal::from_str("0.6").unwrap(), VoteOption::No),
-            ],
+        mock_env_at_timestamp(96400),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddValidator {
+            validator: "dave".to_string(),
         },
     )
-    .unwrap_err();
-    assert_eq!(res, ContractError::UnauthorizedSenderNotVoteOperator {});
+    .unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/action_proposed")
+            .add_attribute("id", "2")
+            .add_attribute("executable_at", "182800")]
+    );
+
+    let res: ConfigResponse = query_helper(deps.as_ref(), QueryMsg::Config {});
+    assert_eq!(res.validators, vec!["alice", "bob", "charlie"]);
 
+    // so does replacing the Stake token
     let res = execute(
         deps.as_mut(),
-        mock_env(),
-        mock_info("vote_operator", &[]),
-        ExecuteMsg::VoteWeighted {
-            proposal_id: 3,
-            votes: vec![
-                (Decimal::from_str("0.1").unwrap(), VoteOption::Yes),
-                (Decimal::from_str("0.2").unwrap(), VoteOption::No),
-                (Decimal::from_str("0.3").unwrap(), VoteOption::Abstain),
-                (Decimal::from_str("0.4").unwrap(), VoteOption::NoWithVeto),
-            ],
+        mock_env_at_timestamp(96400),
+        mock_info("owner", &[]),
+        ExecuteMsg::MigrateToTokenFactory {
+            subdenom: "stake".to_string(),
         },
     )
     .unwrap();
-    assert_eq!(res.messages.len(), 1);
-
+    assert_eq!(res.messages.len(), 0);
     assert_eq!(
-        res.messages[0].msg,
-        MsgVoteWeighted {
-            proposal_id: 3,
-            voter: MOCK_CONTRACT_ADDR.into(),
-            options: vec![
-                WeightedVoteOption {
-                    option: proto::VoteOption::VOTE_OPTION_YES.into(),
-                    weight: Decimal::from_str("0.1").unwrap().numerator().to_string(),
-                    special_fields: SpecialFields::default()
-                },
-                WeightedVoteOption {
-                    option: proto::VoteOption::VOTE_OPTION_NO.into(),
-                    weight: Decimal::from_str("0.2").unwrap().numerator().to_string(),
-                    special_fields: SpecialFields::default()
-                },
-                WeightedVoteOption {
-                    option: proto::VoteOption::VOTE_OPTION_ABSTAIN.into(),
-                    weight: Decimal::from_str("0.3").unwrap().numerator().to_string(),
-                    special_fields: SpecialFields::default()
-                },
-                WeightedVoteOption {
-                    option: proto::VoteOption::VOTE_OPTION_NO_WITH_VETO.into(),
-                    weight: Decimal::from_str("0.4").unwrap().numerator().to_string(),
-                    special_fields: SpecialFields::default()
-                },
-            ],
-            special_fields: SpecialFields::default()
-        }
-        .to_cosmos_msg()
+        res.events,
+        vec![Event::new("erishub/action_proposed")
+            .add_attribute("id", "3")
+            .add_attribute("executable_at", "182800")]
+    );
+
+    // a new owner doesn't inherit the actions proposed by the previous one
+    for (sender, msg) in [
+        (
+            "owner",
+            ExecuteMsg::TransferOwnership {
+                new_owner: "jake".to_string(),
+            },
+        ),
+        ("jake", ExecuteMsg::AcceptOwnership {}),
+    ] {
+        execute(deps.as_mut(), mock_env_at_timestamp(96400), mock_info(sender, &[]), msg).unwrap();
+    }
+
+    let res: PendingActionsResponse<ExecuteMsg> = query_helper(
+        deps.as_ref(),
+        QueryMsg::PendingActions {
+            start_after: None,
+            limit: None,
+        },
     );
+    assert_eq!(res.actions, vec![]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(182800),
+        mock_info("jake", &[]),
+        ExecuteMsg::ExecuteTimelockedAction {
+            id: 2,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, StdError::generic_err("pending action 2 not found").into());
 }
 //--------------------------------------------------------------------------------------------------
 // Queries
@@ -1739,28 +4325,28 @@ fn querying_previous_batches() {
             id: 1,
             reconciled: false,
             total_shares: Uint128::new(123),
-            uluna_unclaimed: Uint128::new(678),
+            utoken_unclaimed: Uint128::new(678),
             est_unbond_end_time: 10000,
         },
         Batch {
             id: 2,
             reconciled: true,
             total_shares: Uint128::new(234),
-            uluna_unclaimed: Uint128::new(789),
+            utoken_unclaimed: Uint128::new(789),
             est_unbond_end_time: 15000,
         },
         Batch {
             id: 3,
             reconciled: false,
             total_shares: Uint128::new(345),
-            uluna_unclaimed: Uint128::new(890),
+            utoken_unclaimed: Uint128::new(890),
             est_unbond_end_time: 20000,
         },
         Batch {
             id: 4,
             reconciled: true,
             total_shares: Uint128::new(456),
-            uluna_unclaimed: Uint128::new(999),
+            utoken_unclaimed: Uint128::new(999),
             est_unbond_end_time: 25000,
         },
     ];
@@ -1968,14 +4554,14 @@ fn querying_unbond_requests_details() {
             id: 1,
             reconciled: false,
             total_shares: Uint128::new(123),
-            uluna_unclaimed: Uint128::new(678),
+            utoken_unclaimed: Uint128::new(678),
             est_unbond_end_time: 10000,
         },
         Batch {
             id: 2,
             reconciled: false,
             total_shares: Uint128::new(234),
-            uluna_unclaimed: Uint128::new(789),
+            utoken_unclaimed: Uint128::new(789),
             est_unbond_end_time: 15000,
         },
     ];
@@ -2078,7 +4664,7 @@ fn computing_redelegations_for_removal() -> StdResult<()> {
         Delegation::new("dave", 10000),
     ];
     // Suppose Dave will be removed
-    // uluna_per_validator = (13000 + 12000 + 11000 + 10000) / 3 = 15333
+    // utoken_per_validator = (13000 + 12000 + 11000 + 10000) / 3 = 15333
     // remainder = 1
     // to Alice:   15333 + 1 - 13000 = 2334
     // to Bob:     15333 + 0 - 12000 = 3333
@@ -2112,7 +4698,7 @@ fn computing_redelegations_for_rebalancing() -> StdResult<()> {
         Delegation::new("dave", 40471),
         Delegation::new("evan", 2345),
     ];
-    // uluna_per_validator = (69420 + 88888 + 1234 + 40471 + 2345) / 4 = 40471
+    // utoken_per_validator = (69420 + 88888 + 1234 + 40471 + 2345) / 4 = 40471
     // remainer = 3
     // src_delegations:
     //  - alice:   69420 - (40471 + 3) = 28946
@@ -2146,7 +4732,8 @@ fn computing_redelegations_for_rebalancing() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         expected,
     );
@@ -2180,7 +4767,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         vec![],
     );
@@ -2196,7 +4784,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         vec![Redelegation::new("unlisted", "alice", 25000)],
     );
@@ -2212,7 +4801,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         vec![
             Redelegation::new("unlisted", "alice", 25000),
@@ -2230,7 +4820,8 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
             &state,
             deps.as_ref().storage,
             &current_delegations,
-            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec()
+            current_delegations.iter().map(|a| a.validator.to_string()).collect_vec(),
+            0
         )?,
         vec![
             Redelegation::new("charlie", "alice", 27500),
@@ -2240,6 +4831,209 @@ fn computing_redelegations_for_rebalancing_complex() -> StdResult<()> {
     Ok(())
 }
 
+#[test]
+fn rebalancing_with_redelegation_locks() {
+    let mut deps = setup_test();
+
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 600),
+        Delegation::new("bob", 300),
+        Delegation::new("charlie", 300),
+    ]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000),
+        mock_info("owner", &[]),
+        ExecuteMsg::Rebalance {
+            min_redelegation: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Redelegation::new("alice", "bob", 100).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(Redelegation::new("alice", "charlie", 100).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+
+    let res: Vec<RedelegationLock> = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::RedelegationLocks {
+            start_after: None,
+            limit: None,
+        },
+        20000,
+    );
+    assert_eq!(
+        res,
+        vec![
+            RedelegationLock {
+                src: "alice".to_string(),
+                dst: "bob".to_string(),
+                redelegated_at: 10000,
+                locked_until: 10000 + 1814400,
+            },
+            RedelegationLock {
+                src: "alice".to_string(),
+                dst: "charlie".to_string(),
+                redelegated_at: 10000,
+                locked_until: 10000 + 1814400,
+            },
+        ]
+    );
+
+    // bob received a redelegation, so it can't be redelegated from within the unbonding period
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 300),
+        Delegation::new("bob", 600),
+        Delegation::new("charlie", 300),
+    ]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("owner", &[]),
+        ExecuteMsg::Rebalance {
+            min_redelegation: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(10000 + 1814400),
+        mock_info("owner", &[]),
+        ExecuteMsg::Rebalance {
+            min_redelegation: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(Redelegation::new("bob", "alice", 100).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(Redelegation::new("bob", "charlie", 100).to_cosmos_msg(CONTRACT_DENOM)),
+    );
+
+    let res: Vec<RedelegationLock> = query_helper_env(
+        deps.as_ref(),
+        QueryMsg::RedelegationLocks {
+            start_after: Some(("bob".to_string(), "alice".to_string())),
+            limit: None,
+        },
+        10000 + 1814400,
+    );
+    assert_eq!(
+        res,
+        vec![RedelegationLock {
+            src: "bob".to_string(),
+            dst: "charlie".to_string(),
+            redelegated_at: 10000 + 1814400,
+            locked_until: 10000 + 2 * 1814400,
+        }]
+    );
+
+    // matured redelegations are pruned when new ones are saved
+    let keys = State::default()
+        .redelegations
+        .keys(deps.as_ref().storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()
+        .unwrap();
+    assert_eq!(
+        keys,
+        vec![("bob".to_string(), "alice".to_string()), ("bob".to_string(), "charlie".to_string())]
+    );
+}
+
+#[test]
+fn removing_redelegation_locked_validators() {
+    let mut deps = setup_test();
+    let state = State::default();
+
+    state
+        .validators
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                "alice".to_string(),
+                "bob".to_string(),
+                "charlie".to_string(),
+                "dave".to_string(),
+            ],
+        )
+        .unwrap();
+    deps.querier.set_staking_delegations(&[
+        Delegation::new("alice", 250000),
+        Delegation::new("bob", 250000),
+        Delegation::new("charlie", 250000),
+        Delegation::new("dave", 250000),
+    ]);
+
+    // charlie and dave received redelegations that have not matured yet
+    for dst in ["charlie", "dave"] {
+        state.redelegations.save(deps.as_mut().storage, ("alice", dst), &10000).unwrap();
+    }
+
+    // their stake is left for a later rebalance instead of failing the removal
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveValidator {
+            validator: "charlie".to_string(),
+        },
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/validator_removed")
+            .add_attribute("validator", "charlie")
+            .add_attribute("uluna_deferred", "250000")]
+    );
+
+    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    assert_eq!(validators, vec!["alice", "bob", "dave"]);
+
+    // dave got jailed
+    deps.querier.set_staking_validators(&[
+        ("alice", Decimal::percent(5)),
+        ("bob", Decimal::percent(5)),
+        ("charlie", Decimal::percent(5)),
+    ]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_at_timestamp(20000),
+        mock_info("jake", &[]),
+        ExecuteMsg::EvictInactiveValidators {},
+    )
+    .unwrap();
+
+    assert_eq!(res.messages.len(), 0);
+    assert_eq!(
+        res.events,
+        vec![Event::new("erishub/validator_evicted")
+            .add_attribute("validator", "dave")
+            .add_attribute("uluna_moved", "0")
+            .add_attribute("uluna_deferred", "250000")]
+    );
+
+    let validators = state.validators.load(deps.as_ref().storage).unwrap();
+    assert_eq!(validators, vec!["alice", "bob"]);
+}
+
 //--------------------------------------------------------------------------------------------------
 // Coins
 //--------------------------------------------------------------------------------------------------
@@ -1,7 +1,7 @@
-use cosmwasm_std::{Addr, QuerierWrapper, StdResult};
+use cosmwasm_std::{Addr, QuerierWrapper, StdResult, Uint128};
 use eris::amp_gauges::{get_amp_tune_info, get_amp_validator_infos, GaugeInfoResponse as AmpGauge};
 use eris::emp_gauges::{get_emp_tune_info, get_emp_validator_infos, GaugeInfoResponse as EmpGauge};
-use eris::governance_helper::get_s_from_period;
+use eris::governance_helper::{get_period, get_s_from_period};
 use itertools::Itertools;
 
 pub trait GaugeLoader {
@@ -59,3 +59,41 @@ impl GaugeLoader for PeriodGaugeLoader {
         })
     }
 }
+
+/// This loader is used to simulate delegation strategies. Provided points replace the ones stored
+/// in the gauge contracts.
+pub struct OverrideGaugeLoader {
+    pub time: u64,
+    pub vamp_points: Option<Vec<(String, Uint128)>>,
+    pub emp_points: Option<Vec<(String, Uint128)>>,
+}
+impl GaugeLoader for OverrideGaugeLoader {
+    fn get_amp_tune_info(&self, querier: &QuerierWrapper, amp_gauges: Addr) -> StdResult<AmpGauge> {
+        match &self.vamp_points {
+            Some(vamp_points) => Ok(AmpGauge {
+                tune_ts: self.time,
+                vamp_points: vamp_points
+                    .iter()
+                    .cloned()
+                    .sorted_by(|(_, a), (_, b)| b.cmp(a)) // Sort in descending order
+                    .collect_vec(),
+            }),
+            None => get_amp_tune_info(querier, amp_gauges),
+        }
+    }
+
+    fn get_emp_tune_info(&self, querier: &QuerierWrapper, emp_gauges: Addr) -> StdResult<EmpGauge> {
+        match &self.emp_points {
+            Some(emp_points) => Ok(EmpGauge {
+                tune_ts: self.time,
+                tune_period: get_period(self.time)?,
+                emp_points: emp_points
+                    .iter()
+                    .cloned()
+                    .sorted_by(|(_, a), (_, b)| b.cmp(a)) // Sort in descending order
+                    .collect_vec(),
+            }),
+            None => get_emp_tune_info(querier, emp_gauges),
+        }
+    }
+}
@@ -1,8 +1,11 @@
 mod coins;
 pub mod gauges;
 mod keys;
+mod stake_token;
 mod staking;
 
 pub use coins::Coins;
 pub use keys::BooleanKey;
+pub(crate) use stake_token::burn_cw20_msg;
+pub use stake_token::StakeToken;
 pub use staking::{Delegation, Redelegation, SendFee, Undelegation};
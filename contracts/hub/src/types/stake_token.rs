@@ -0,0 +1,134 @@
+use cosmwasm_std::{
+    to_json_binary, Addr, Coin, CosmosMsg, QuerierWrapper, StdResult, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use eris::hub::StakeTokenType;
+
+use crate::helpers::{query_cw20_balance, query_cw20_total_supply};
+use crate::protos::token_factory::{MsgBurn, MsgMint};
+
+/// The liquid staking token minted by the hub
+pub enum StakeToken {
+    /// CW20 token contract instantiated by the hub
+    Cw20(Addr),
+    /// Token-factory denom created by the hub. `legacy` is the CW20 token the hub migrated from,
+    /// which stays part of the supply until all holders converted it.
+    TokenFactory {
+        denom: String,
+        legacy: Option<Addr>,
+    },
+}
+
+impl StakeToken {
+    /// Address of the CW20 token, or the token-factory denom
+    pub fn id(&self) -> String {
+        match self {
+            StakeToken::Cw20(addr) => addr.to_string(),
+            StakeToken::TokenFactory {
+                denom,
+                ..
+            } => denom.clone(),
+        }
+    }
+
+    pub fn token_type(&self) -> StakeTokenType {
+        match self {
+            StakeToken::Cw20(_) => StakeTokenType::Cw20,
+            StakeToken::TokenFactory {
+                ..
+            } => StakeTokenType::TokenFactory,
+        }
+    }
+
+    pub fn legacy(&self) -> Option<&Addr> {
+        match self {
+            StakeToken::Cw20(_) => None,
+            StakeToken::TokenFactory {
+                legacy,
+                ..
+            } => legacy.as_ref(),
+        }
+    }
+
+    /// Total supply, including legacy CW20 tokens that have not been converted yet
+    pub fn query_total_supply(&self, querier: &QuerierWrapper) -> StdResult<Uint128> {
+        match self {
+            StakeToken::Cw20(addr) => query_cw20_total_supply(querier, addr),
+            StakeToken::TokenFactory {
+                denom,
+                legacy,
+            } => {
+                let mut supply = querier.query_supply(denom)?.amount;
+                if let Some(legacy) = legacy {
+                    supply += query_cw20_total_supply(querier, legacy)?;
+                }
+                Ok(supply)
+            },
+        }
+    }
+
+    /// Balance of `account`, including legacy CW20 tokens that have not been converted yet
+    pub fn query_balance(&self, querier: &QuerierWrapper, account: &Addr) -> StdResult<Uint128> {
+        match self {
+            StakeToken::Cw20(addr) => query_cw20_balance(querier, addr, account),
+            StakeToken::TokenFactory {
+                denom,
+                legacy,
+            } => {
+                let mut balance = querier.query_balance(account, denom)?.amount;
+                if let Some(legacy) = legacy {
+                    balance += query_cw20_balance(querier, legacy, account)?;
+                }
+                Ok(balance)
+            },
+        }
+    }
+
+    pub fn mint_msg(&self, hub: &Addr, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+        match self {
+            StakeToken::Cw20(addr) => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            })),
+            StakeToken::TokenFactory {
+                denom,
+                ..
+            } => Ok(MsgMint {
+                sender: hub.to_string(),
+                amount: Coin::new(amount.u128(), denom),
+                mint_to_address: recipient.to_string(),
+            }
+            .to_cosmos_msg()),
+        }
+    }
+
+    /// Burns stake tokens held by the hub
+    pub fn burn_msg(&self, hub: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+        match self {
+            StakeToken::Cw20(addr) => burn_cw20_msg(addr, amount),
+            StakeToken::TokenFactory {
+                denom,
+                ..
+            } => Ok(MsgBurn {
+                sender: hub.to_string(),
+                amount: Coin::new(amount.u128(), denom),
+                burn_from_address: hub.to_string(),
+            }
+            .to_cosmos_msg()),
+        }
+    }
+}
+
+pub(crate) fn burn_cw20_msg(token: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::Burn {
+            amount,
+        })?,
+        funds: vec![],
+    }))
+}
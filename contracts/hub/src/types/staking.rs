@@ -1,7 +1,5 @@
 use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, StakingMsg};
 
-use crate::constants::CONTRACT_DENOM;
-
 #[derive(Clone)]
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 pub struct Delegation {
@@ -17,10 +15,10 @@ impl Delegation {
         }
     }
 
-    pub fn to_cosmos_msg(&self) -> CosmosMsg {
+    pub fn to_cosmos_msg(&self, denom: &str) -> CosmosMsg {
         CosmosMsg::Staking(StakingMsg::Delegate {
             validator: self.validator.clone(),
-            amount: Coin::new(self.amount, CONTRACT_DENOM),
+            amount: Coin::new(self.amount, denom),
         })
     }
 }
@@ -40,10 +38,10 @@ impl SendFee {
         }
     }
 
-    pub fn to_cosmos_msg(&self) -> CosmosMsg {
+    pub fn to_cosmos_msg(&self, denom: &str) -> CosmosMsg {
         CosmosMsg::Bank(BankMsg::Send {
             to_address: self.to_address.clone(),
-            amount: vec![Coin::new(self.amount, CONTRACT_DENOM)],
+            amount: vec![Coin::new(self.amount, denom)],
         })
     }
 }
@@ -63,10 +61,10 @@ impl Undelegation {
         }
     }
 
-    pub fn to_cosmos_msg(&self) -> CosmosMsg {
+    pub fn to_cosmos_msg(&self, denom: &str) -> CosmosMsg {
         CosmosMsg::Staking(StakingMsg::Undelegate {
             validator: self.validator.clone(),
-            amount: Coin::new(self.amount, CONTRACT_DENOM),
+            amount: Coin::new(self.amount, denom),
         })
     }
 }
@@ -88,11 +86,11 @@ impl Redelegation {
         }
     }
 
-    pub fn to_cosmos_msg(&self) -> CosmosMsg {
+    pub fn to_cosmos_msg(&self, denom: &str) -> CosmosMsg {
         CosmosMsg::Staking(StakingMsg::Redelegate {
             src_validator: self.src.clone(),
             dst_validator: self.dst.clone(),
-            amount: Coin::new(self.amount, CONTRACT_DENOM),
+            amount: Coin::new(self.amount, denom),
         })
     }
 }
@@ -1,7 +1,7 @@
 use anyhow::{Ok, Result};
 use cosmwasm_std::{attr, coin, coins, Addr, Decimal, Delegation, Event, FullDelegation, Uint128};
 use cw_multi_test::App;
-use eris::hub::{DelegationStrategy, StateResponse, WantedDelegationsResponse};
+use eris::hub::{DelegationStrategy, StakeTokenType, StateResponse, WantedDelegationsResponse};
 use eris_tests::{gov_helper::EscrowHelper, TerraAppExtension};
 use eris_tests::{mock_app, mock_app_validators, EventChecker};
 use itertools::Itertools;
@@ -26,6 +26,9 @@ fn update_configs() -> Result<()> {
             owner: "owner".to_string(),
             new_owner: None,
             stake_token: helper.base.amp_token.get_address_string(),
+            stake_token_type: StakeTokenType::Cw20,
+            legacy_stake_token: None,
+            denom: "uluna".to_string(),
             epoch_period: 259200,
             unbond_period: 1814400,
             validators: vec![
@@ -36,7 +39,8 @@ fn update_configs() -> Result<()> {
             ],
             fee_config: FeeConfig {
                 protocol_fee_contract: Addr::unchecked("fee"),
-                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128)
+                protocol_reward_fee: Decimal::from_ratio(1u128, 100u128),
+                fee_receivers: vec![]
             },
             delegation_strategy: eris::hub::DelegationStrategy::Gauges {
                 amp_gauges: helper.base.amp_gauges.get_address_string(),
@@ -45,7 +49,11 @@ fn update_configs() -> Result<()> {
                 min_delegation_bps: 100,
                 max_delegation_bps: 2500,
                 validator_count: 5,
+                slashing_penalty_bps: None,
             },
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
         }
     );
 
@@ -60,6 +68,15 @@ fn update_configs() -> Result<()> {
                 vote_operator: None,
                 epoch_period: None,
                 unbond_period: None,
+                instant_unbond: None,
+                validator_scoring: None,
+                unbond_receipt_nft: None,
+                fee_receivers: None,
+                referral_fee: None,
+                guardian: None,
+                timelock_delay_s: None,
+                keeper_tips: None,
+                prop_gauges: None,
             },
             Addr::unchecked("user"),
         )
@@ -78,6 +95,15 @@ fn update_configs() -> Result<()> {
                 vote_operator: None,
                 epoch_period: None,
                 unbond_period: None,
+                instant_unbond: None,
+                validator_scoring: None,
+                unbond_receipt_nft: None,
+                fee_receivers: None,
+                referral_fee: None,
+                guardian: None,
+                timelock_delay_s: None,
+                keeper_tips: None,
+                prop_gauges: None,
             },
         )
         .unwrap();
@@ -89,6 +115,9 @@ fn update_configs() -> Result<()> {
             owner: "owner".to_string(),
             new_owner: None,
             stake_token: helper.base.amp_token.get_address_string(),
+            stake_token_type: StakeTokenType::Cw20,
+            legacy_stake_token: None,
+            denom: "uluna".to_string(),
             epoch_period: 259200,
             unbond_period: 1814400,
             validators: vec![
@@ -99,9 +128,13 @@ fn update_configs() -> Result<()> {
             ],
             fee_config: FeeConfig {
                 protocol_fee_contract: Addr::unchecked("fee_new"),
-                protocol_reward_fee: Decimal::from_ratio(10u128, 100u128)
+                protocol_reward_fee: Decimal::from_ratio(10u128, 100u128),
+                fee_receivers: vec![]
             },
-            delegation_strategy: eris::hub::DelegationStrategy::Uniform
+            delegation_strategy: eris::hub::DelegationStrategy::Uniform,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
         }
     );
 
@@ -217,11 +250,13 @@ fn config_does_not_change_exchange_rate() -> Result<()> {
     let expected_response = StateResponse {
         exchange_rate: Decimal::from_ratio(new_luna, ustake_minted),
         total_ustake: ustake_minted,
-        total_uluna: new_luna,
+        total_utoken: new_luna,
         unlocked_coins: vec![],
         unbonding: Uint128::zero(),
         available: Uint128::zero(),
-        tvl_uluna: new_luna,
+        tvl_utoken: new_luna,
+        instant_unbond_buffer: Uint128::zero(),
+        instant_unbond_utilization: Decimal::zero(),
     };
     let state = helper.hub_query_state(router_ref)?;
     assert_eq!(state, expected_response);
@@ -254,11 +289,21 @@ fn config_does_not_change_exchange_rate() -> Result<()> {
                 min_delegation_bps: 100,
                 max_delegation_bps: 10000,
                 validator_count: 5,
+                slashing_penalty_bps: None,
             }),
             allow_donations: None,
             vote_operator: None,
             epoch_period: None,
             unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
         },
     )?;
 
@@ -350,11 +395,13 @@ fn config_does_not_change_exchange_rate_emps() -> Result<()> {
     let expected_response = StateResponse {
         exchange_rate: Decimal::from_ratio(new_luna, ustake_minted),
         total_ustake: ustake_minted,
-        total_uluna: new_luna,
+        total_utoken: new_luna,
         unlocked_coins: vec![],
         unbonding: Uint128::zero(),
         available: Uint128::zero(),
-        tvl_uluna: new_luna,
+        tvl_utoken: new_luna,
+        instant_unbond_buffer: Uint128::zero(),
+        instant_unbond_utilization: Decimal::zero(),
     };
     let state = helper.hub_query_state(router_ref)?;
     assert_eq!(state, expected_response);
@@ -387,11 +434,21 @@ fn config_does_not_change_exchange_rate_emps() -> Result<()> {
                 min_delegation_bps: 100,
                 max_delegation_bps: 10000,
                 validator_count: 5,
+                slashing_penalty_bps: None,
             }),
             allow_donations: None,
             vote_operator: None,
             epoch_period: None,
             unbond_period: None,
+            instant_unbond: None,
+            validator_scoring: None,
+            unbond_receipt_nft: None,
+            fee_receivers: None,
+            referral_fee: None,
+            guardian: None,
+            timelock_delay_s: None,
+            keeper_tips: None,
+            prop_gauges: None,
         },
     )?;
 
@@ -515,11 +572,13 @@ fn bond_and_harvest(
         StateResponse {
             exchange_rate: Decimal::one(),
             total_ustake: Uint128::new(300_000000),
-            total_uluna: Uint128::new(300_000000),
+            total_utoken: Uint128::new(300_000000),
             unlocked_coins: vec![],
             unbonding: Uint128::zero(),
             available: Uint128::zero(),
-            tvl_uluna: Uint128::new(300_000000),
+            tvl_utoken: Uint128::new(300_000000),
+            instant_unbond_buffer: Uint128::zero(),
+            instant_unbond_utilization: Decimal::zero(),
         }
     );
     let result = helper.hub_query_delegation(router_ref, "val1")?;
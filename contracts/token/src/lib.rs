@@ -1,6 +1,6 @@
 use cosmwasm_std::{
-    entry_point, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
-    Storage,
+    entry_point, to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Storage, Uint128, WasmMsg,
 };
 use cw20_base::contract::{
     execute as cw20_execute, instantiate as cw20_instantiate, query as cw20_query,
@@ -8,6 +8,7 @@ use cw20_base::contract::{
 use cw20_base::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 use cw20_base::state::{MinterData, TOKEN_INFO};
 use cw20_base::ContractError;
+use eris::hub::ExecuteMsg as HubExecuteMsg;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -25,7 +26,7 @@ pub fn instantiate(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
@@ -42,7 +43,69 @@ pub fn execute(
         _ => (),
     }
 
-    cw20_execute(deps, env, info, msg)
+    // Transfers are reported to Stake Hub, so that it stops paying referral fees for the tokens
+    let transfer = match &msg {
+        ExecuteMsg::Transfer {
+            recipient,
+            amount,
+        } => Some((info.sender.to_string(), recipient.clone(), *amount)),
+        ExecuteMsg::Send {
+            contract,
+            amount,
+            ..
+        } => Some((info.sender.to_string(), contract.clone(), *amount)),
+        ExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+        } => Some((owner.clone(), recipient.clone(), *amount)),
+        ExecuteMsg::SendFrom {
+            owner,
+            contract,
+            amount,
+            ..
+        } => Some((owner.clone(), contract.clone(), *amount)),
+        _ => None,
+    };
+
+    let res = cw20_execute(deps.branch(), env, info, msg)?;
+
+    match transfer {
+        Some((from, to, amount)) => Ok(report_transfer(deps.storage, res, from, to, amount)?),
+        None => Ok(res),
+    }
+}
+
+fn report_transfer(
+    storage: &dyn Storage,
+    res: Response,
+    from: String,
+    to: String,
+    amount: Uint128,
+) -> StdResult<Response> {
+    let minter = match TOKEN_INFO.load(storage)?.mint {
+        Some(MinterData {
+            minter,
+            ..
+        }) => minter,
+        None => return Ok(res),
+    };
+
+    // Stake Hub itself doesn't hold referred tokens, and tokens sent to it are released by the
+    // unbond they are sent for
+    if from == minter || to == minter {
+        return Ok(res);
+    }
+
+    Ok(res.add_message(WasmMsg::Execute {
+        contract_addr: minter.to_string(),
+        msg: to_json_binary(&HubExecuteMsg::StakeTransferred {
+            from,
+            to,
+            amount,
+        })?,
+        funds: vec![],
+    }))
 }
 
 fn assert_minter(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
@@ -71,7 +134,8 @@ mod tests {
     use cosmwasm_std::testing::{
         mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
     };
-    use cosmwasm_std::{OwnedDeps, Uint128};
+    use cosmwasm_std::{OwnedDeps, SubMsg};
+    use cw20::Cw20ReceiveMsg;
     use cw20_base::state::{TokenInfo, BALANCES};
 
     use super::*;
@@ -141,6 +205,60 @@ mod tests {
         assert_eq!(token_info.total_supply, Uint128::new(100));
     }
 
+    #[test]
+    fn reporting_transfers() {
+        let mut deps = setup_test();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Transfer {
+                recipient: "bob".to_string(),
+                amount: Uint128::new(40),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "stake_hub".to_string(),
+                msg: to_json_binary(&HubExecuteMsg::StakeTransferred {
+                    from: "alice".to_string(),
+                    to: "bob".to_string(),
+                    amount: Uint128::new(40),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+
+        // Sending to Stake Hub is not reported
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Send {
+                contract: "stake_hub".to_string(),
+                amount: Uint128::new(60),
+                msg: Binary::default(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(
+                Cw20ReceiveMsg {
+                    sender: "alice".to_string(),
+                    amount: Uint128::new(60),
+                    msg: Binary::default(),
+                }
+                .into_cosmos_msg("stake_hub")
+                .unwrap()
+            )]
+        );
+    }
+
     #[test]
     fn disabling_burn_from() {
         let mut deps = setup_test();
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use astroport::asset::token_asset_info;
 use cosmwasm_schema::cw_serde;
 
 use cosmwasm_std::{
@@ -9,7 +10,7 @@ use cw20::{BalanceResponse, Cw20QueryMsg, MinterResponse};
 
 use cw_multi_test::{App, ContractWrapper, Executor};
 use cw_storage_plus::Item;
-use eris::arb_vault::LsdConfig;
+use eris::arb_vault::{LsdConfig, LsdPolicy};
 
 use crate::{
     arb_contract,
@@ -176,6 +177,8 @@ impl BaseErisTestPackage {
             name: "Staking token".to_string(),
             symbol: "stake".to_string(),
             decimals: 6,
+            denom: "uluna".to_string(),
+            token_factory_subdenom: None,
             epoch_period: 259200,   // 3 * 24 * 60 * 60 = 3 days
             unbond_period: 1814400, // 21 * 24 * 60 * 60 = 21 days
             validators: vec![
@@ -353,15 +356,18 @@ impl BaseErisTestPackage {
                 (Decimal::from_ratio(20u128, 1000u128), Decimal::from_ratio(90u128, 100u128)),
                 (Decimal::from_ratio(25u128, 1000u128), Decimal::from_ratio(100u128, 100u128)),
             ]),
+            utilization_adjustment: None,
             utoken: "uluna".to_string(),
             whitelist: vec!["executor".to_string()],
             lsds: vec![LsdConfig {
                 name: "eris".into(),
                 lsd_type: eris::arb_vault::LsdType::Eris {
                     addr: hub_addr.to_string(),
-                    cw20: self.amp_token.get_address_string(),
+                    cw20_or_denom: token_asset_info(self.amp_token.get_address()),
                 },
                 disabled: false,
+                max_exposure_bps: None,
+                policy: LsdPolicy::default(),
             }],
         };
 
@@ -647,6 +653,7 @@ impl BaseErisTestPackage {
                 min_delegation_bps: 100,
                 max_delegation_bps: 2500,
                 validator_count: 5,
+                slashing_penalty_bps: None,
             })
         };
 
@@ -662,6 +669,15 @@ impl BaseErisTestPackage {
                     vote_operator: None,
                     epoch_period: None,
                     unbond_period: None,
+                    instant_unbond: None,
+                    validator_scoring: None,
+                    unbond_receipt_nft: None,
+                    fee_receivers: None,
+                    referral_fee: None,
+                    guardian: None,
+                    timelock_delay_s: None,
+                    keeper_tips: None,
+                    prop_gauges: None,
                 },
                 &[],
             )
@@ -1,14 +1,14 @@
 use std::str::FromStr;
 
 use anyhow::Result;
-use astroport::asset::native_asset;
+use astroport::asset::{native_asset, token_asset_info};
 use cosmwasm_std::{
     attr, coin, to_json_binary, Addr, Delegation, FullDelegation, StdResult, Uint128, VoteOption,
 };
 use cw20::Cw20ExecuteMsg;
 use cw_multi_test::{App, AppResponse, Executor};
 use eris::{
-    arb_vault::{LsdConfig, LsdType},
+    arb_vault::{LsdConfig, LsdPolicy, LsdType},
     emp_gauges::AddEmpInfo,
     governance_helper::WEEK,
 };
@@ -379,7 +379,12 @@ impl EscrowHelper {
             }))
             .unwrap();
 
-        self.hub_execute(router_ref, eris::hub::ExecuteMsg::Reconcile {})
+        self.hub_execute(
+            router_ref,
+            eris::hub::ExecuteMsg::Reconcile {
+                limit: None,
+            },
+        )
     }
 
     pub fn hub_remove_validator(
@@ -424,6 +429,7 @@ impl EscrowHelper {
             self.base.hub.get_address(),
             &eris::hub::ExecuteMsg::Bond {
                 receiver: None,
+                referral: None,
             },
             &[coin(amount, denom.into())],
         )
@@ -440,6 +446,15 @@ impl EscrowHelper {
                 vote_operator: None,
                 epoch_period: None,
                 unbond_period: None,
+                instant_unbond: None,
+                validator_scoring: None,
+                unbond_receipt_nft: None,
+                fee_receivers: None,
+                referral_fee: None,
+                guardian: None,
+                timelock_delay_s: None,
+                keeper_tips: None,
+                prop_gauges: None,
             },
         )
     }
@@ -761,14 +776,19 @@ impl EscrowHelper {
             router_ref,
             eris::arb_vault::ExecuteMsg::UpdateConfig {
                 utilization_method: None,
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 insert_lsd: None,
                 disable_lsd: None,
                 remove_lsd: Some("eris".to_string()),
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
     }
@@ -779,7 +799,7 @@ impl EscrowHelper {
             "stader".to_string(),
             LsdType::Stader {
                 addr: self.base.stader.get_address_string(),
-                cw20: self.base.stader_token.get_address_string(),
+                cw20_or_denom: token_asset_info(self.base.stader_token.get_address()),
             },
         )
     }
@@ -790,7 +810,7 @@ impl EscrowHelper {
             "boneLUNA".to_string(),
             LsdType::Backbone {
                 addr: self.base.steak_hub.get_address_string(),
-                cw20: self.base.steak_token.get_address_string(),
+                cw20_or_denom: token_asset_info(self.base.steak_token.get_address()),
             },
         )
     }
@@ -805,18 +825,25 @@ impl EscrowHelper {
             router_ref,
             eris::arb_vault::ExecuteMsg::UpdateConfig {
                 utilization_method: None,
+                utilization_adjustment: None,
+                remove_utilization_adjustment: None,
                 unbond_time_s: None,
                 insert_lsd: Some(LsdConfig {
                     disabled: false,
+                    max_exposure_bps: None,
+                    policy: LsdPolicy::default(),
                     name: lsd,
                     lsd_type: t,
                 }),
                 disable_lsd: None,
                 remove_lsd: None,
                 force_remove_lsd: None,
+                set_max_exposure: None,
+                set_policy: None,
                 fee_config: None,
                 set_whitelist: None,
                 remove_whitelist: None,
+                timelock_delay_s: None,
             },
         )
     }
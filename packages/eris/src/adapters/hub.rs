@@ -17,6 +17,7 @@ impl Hub {
             contract_addr: self.0.to_string(),
             msg: to_json_binary(&ExecuteMsg::Bond {
                 receiver,
+                referral: None,
             })?,
             funds: vec![coin(amount, denom)],
         }))
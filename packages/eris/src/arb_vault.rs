@@ -6,10 +6,15 @@ use cosmwasm_std::{
 };
 use cw20::Cw20ReceiveMsg;
 
-// /// The default swap slippage
-// pub const DEFAULT_SLIPPAGE: &str = "0.005";
-// /// The maximum allowed swap slippage
-// pub const MAX_ALLOWED_SLIPPAGE: &str = "0.5";
+use crate::helpers::bps::BasicPoints;
+use crate::helpers::exchange_rate::{AprResponse, ExchangeRateTwapResponse};
+use crate::helpers::timelock::PendingActionsResponse;
+use crate::lsd_adapter::LsdInterface;
+
+/// The default swap slippage
+pub const DEFAULT_SLIPPAGE: &str = "0.005";
+/// The maximum allowed swap slippage
+pub const MAX_ALLOWED_SLIPPAGE: &str = "0.5";
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -28,6 +33,9 @@ pub struct InstantiateMsg {
     pub utoken: String,
     // execution threshold
     pub utilization_method: UtilizationMethod,
+    // adjusts the utilization based on the recent usage of the vault
+    #[serde(default)]
+    pub utilization_adjustment: Option<UtilizationAdjustment>,
     // min unbond time 21+3 * 24 * 60 * 60
     pub unbond_time_s: u64,
     // config for lsds
@@ -45,64 +53,133 @@ pub struct LsdConfig<T> {
     pub disabled: bool,
     pub name: String,
     pub lsd_type: LsdType<T>,
+    // max share of the tvl (xvalue + unbonding + withdrawable) held in this LSD, no cap if not set
+    #[serde(default)]
+    pub max_exposure_bps: Option<u16>,
+    // when the permissionless crank unbonds and withdraws the LSD
+    #[serde(default)]
+    pub policy: LsdPolicy,
 }
 
 impl LsdConfig<String> {
     pub fn validate(self, api: &dyn Api) -> StdResult<LsdConfig<Addr>> {
+        if self.max_exposure_bps.unwrap_or_default() > BasicPoints::MAX {
+            return Err(StdError::generic_err("Max exposure too high"));
+        }
+
         Ok(LsdConfig {
             disabled: self.disabled,
             name: self.name,
+            max_exposure_bps: self.max_exposure_bps,
+            policy: self.policy,
             lsd_type: match self.lsd_type {
                 LsdType::Eris {
                     addr,
-                    cw20,
+                    cw20_or_denom,
                 } => LsdType::Eris {
                     addr: api.addr_validate(&addr)?,
-                    cw20: api.addr_validate(&cw20)?,
+                    cw20_or_denom: validate_asset_info(api, cw20_or_denom)?,
                 },
                 LsdType::Backbone {
                     addr,
-                    cw20,
+                    cw20_or_denom,
                 } => LsdType::Backbone {
                     addr: api.addr_validate(&addr)?,
-                    cw20: api.addr_validate(&cw20)?,
+                    cw20_or_denom: validate_asset_info(api, cw20_or_denom)?,
                 },
                 LsdType::Stader {
                     addr,
-                    cw20,
+                    cw20_or_denom,
                 } => LsdType::Stader {
                     addr: api.addr_validate(&addr)?,
-                    cw20: api.addr_validate(&cw20)?,
+                    cw20_or_denom: validate_asset_info(api, cw20_or_denom)?,
                 },
                 LsdType::Prism {
                     addr,
-                    cw20,
+                    cw20_or_denom,
                 } => LsdType::Prism {
                     addr: api.addr_validate(&addr)?,
-                    cw20: api.addr_validate(&cw20)?,
+                    cw20_or_denom: validate_asset_info(api, cw20_or_denom)?,
+                },
+                LsdType::Generic {
+                    addr,
+                    cw20_or_denom,
+                    interface,
+                } => LsdType::Generic {
+                    addr: api.addr_validate(&addr)?,
+                    cw20_or_denom: validate_asset_info(api, cw20_or_denom)?,
+                    interface,
                 },
             },
         })
     }
 }
 
+fn validate_asset_info(api: &dyn Api, asset_info: AssetInfo) -> StdResult<AssetInfo> {
+    Ok(match asset_info {
+        AssetInfo::Token {
+            contract_addr,
+        } => AssetInfo::Token {
+            contract_addr: api.addr_validate(contract_addr.as_str())?,
+        },
+        native => native,
+    })
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct LsdPolicy {
+    // no unbonding through the crank if not set
+    pub unbond: Option<UnbondPolicy>,
+    // no withdrawing through the crank if not set
+    pub withdraw: Option<WithdrawPolicy>,
+}
+
+#[cw_serde]
+pub enum UnbondPolicy {
+    // unbond the xbalance as soon as it reaches min_xbalance
+    Threshold {
+        min_xbalance: Uint128,
+    },
+    // unbond the xbalance when the next batch of the LSD is submitted within window_s.
+    // LSDs without unbonding batches are unbonded immediately.
+    BatchEpoch {
+        window_s: u64,
+    },
+}
+
+#[cw_serde]
+pub struct WithdrawPolicy {
+    // withdraw as soon as the withdrawable amount reaches min_withdrawable
+    pub min_withdrawable: Uint128,
+}
+
+// `cw20_or_denom` is the LSD token, either a CW20 token or a native (e.g. token-factory) denom.
+// Native denoms are unbonded by sending them to the Eris hub, the Backbone, Stader and Prism hubs
+// only accept their CW20 token for unbonding.
 #[cw_serde]
 pub enum LsdType<T> {
     Eris {
         addr: T,
-        cw20: T,
+        cw20_or_denom: AssetInfo,
     },
     Backbone {
         addr: T,
-        cw20: T,
+        cw20_or_denom: AssetInfo,
     },
     Stader {
         addr: T,
-        cw20: T,
+        cw20_or_denom: AssetInfo,
     },
     Prism {
         addr: T,
-        cw20: T,
+        cw20_or_denom: AssetInfo,
+    },
+    // LSD used through an external adapter contract implementing the interface
+    Generic {
+        addr: T,
+        cw20_or_denom: AssetInfo,
+        interface: LsdInterface,
     },
 }
 
@@ -125,6 +202,10 @@ impl LsdType<String> {
                 addr,
                 ..
             } => format!("prism_{0}", addr),
+            LsdType::Generic {
+                addr,
+                ..
+            } => format!("generic_{0}", addr),
         }
     }
 }
@@ -132,6 +213,35 @@ impl LsdType<String> {
 #[cw_serde]
 pub enum UtilizationMethod {
     Steps(Vec<(Decimal, Decimal)>),
+    // utilization grows linearly from min_util at min_profit to max_util at max_profit
+    Linear {
+        min_profit: Decimal,
+        max_profit: Decimal,
+        min_util: Decimal,
+        max_util: Decimal,
+    },
+    // utilization grows linearly from min_util at min_profit to kink_util at kink_profit and with
+    // a different slope to max_util at max_profit
+    Kink {
+        min_profit: Decimal,
+        kink_profit: Decimal,
+        max_profit: Decimal,
+        min_util: Decimal,
+        kink_util: Decimal,
+        max_util: Decimal,
+    },
+}
+
+#[cw_serde]
+pub struct UtilizationAdjustment {
+    // the vault is seen as idle, when no arbitrage has been executed in this period
+    pub idle_after_s: u64,
+    // increases the utilization while idle: 0.1 -> utilization * 1.1
+    pub idle_increase: Decimal,
+    // share of the locked user withdrawals compared to the tvl at which the utilization is reduced
+    pub locked_threshold: Decimal,
+    // decreases the utilization above the locked threshold: 0.2 -> utilization * 0.8
+    pub locked_decrease: Decimal,
 }
 
 #[cw_serde]
@@ -141,12 +251,21 @@ pub struct ExecuteSubMsg {
     pub funds_amount: Uint128,
 }
 
+#[cw_serde]
+pub struct ArbLeg {
+    // specify what kind of action should be executed
+    pub msg: ExecuteSubMsg,
+    // what is the result token for unbonding action
+    pub result_token: AssetInfo,
+}
+
 /// This structure describes the execute messages available in the contract.
 #[cw_serde]
 pub enum ExecuteMsg {
     // User action: Receive to queue funds for withdraw
     Receive(Cw20ReceiveMsg),
     // User action: Provide liquidity to the pool and specify who will receive the pool token.
+    // Accepts the utoken or any enabled native LSD token, LSD tokens are valued like a swap.
     Deposit {
         asset: Asset,
         receiver: Option<String>,
@@ -159,9 +278,16 @@ pub enum ExecuteMsg {
         id: u64,
     },
 
+    // User action: Sell an enabled native LSD token for utoken at its exchange rate minus a discount
+    // depending on the utilization. Offering the utoken with the LP token as ask asset provides
+    // liquidity.
     Swap {
         offer_asset: Asset,
+        ask_asset_info: Option<AssetInfo>,
+        // the return amount is checked against offer / belief_price with max_spread
         belief_price: Option<Decimal>,
+        // without a belief_price the discount is the spread, so swaps with a discount above the
+        // default of 0.5 % need a max_spread
         max_spread: Option<Decimal>,
         to: Option<String>,
     },
@@ -169,6 +295,9 @@ pub enum ExecuteMsg {
     // Admin User: Update config
     UpdateConfig {
         utilization_method: Option<UtilizationMethod>,
+        utilization_adjustment: Option<UtilizationAdjustment>,
+        // removes the utilization adjustment
+        remove_utilization_adjustment: Option<bool>,
         unbond_time_s: Option<u64>,
 
         // insert an lsd
@@ -179,11 +308,27 @@ pub enum ExecuteMsg {
         remove_lsd: Option<String>,
         // force removes a LSD (DANGER can be executed even when funds are unbonding / withdrawable)
         force_remove_lsd: Option<String>,
+        // sets the max exposure in bps of a LSD by name, None removes the cap
+        set_max_exposure: Option<(String, Option<u16>)>,
+        // sets the crank policy of a LSD by name
+        set_policy: Option<(String, LsdPolicy)>,
 
         fee_config: Option<FeeConfig<String>>,
         set_whitelist: Option<Vec<String>>,
         // opens up executions so anyone can execute.
         remove_whitelist: Option<bool>,
+
+        // delay in seconds before LSD, fee and whitelist changes can be executed. While set, such
+        // updates are queued as pending actions.
+        timelock_delay_s: Option<u64>,
+    },
+    // Anyone: Execute a pending config update whose timelock has elapsed
+    ExecuteTimelockedAction {
+        id: u64,
+    },
+    // Admin User: Remove a pending config update
+    CancelTimelockedAction {
+        id: u64,
     },
 
     // Bot: Execute arbitrage
@@ -195,6 +340,16 @@ pub enum ExecuteMsg {
         // Specify the goal profit: 0.01 -> 1 %
         wanted_profit: Decimal,
     },
+    // Bot: Execute multiple arbitrage legs with a single flashloan. The funds of all legs are
+    // taken at once and the profit is asserted over all assets of the vault.
+    ExecuteArbitrageBatch {
+        legs: Vec<ArbLeg>,
+        // Specify the minimum profit over all legs: 0.01 -> 1 %
+        min_total_profit: Decimal,
+    },
+
+    // Anyone: Unbond and withdraw all LSDs whose policy is due
+    Crank {},
 
     // Bot: Withdraw unbonded liquidity from liquid staking providers
     WithdrawFromLiquidStaking {
@@ -231,6 +386,10 @@ pub enum CallbackMsg {
         result_token: AssetInfo,
         wanted_profit: Decimal,
     },
+    AssertBatchResult {
+        result_tokens: Vec<AssetInfo>,
+        min_total_profit: Decimal,
+    },
 }
 
 impl CallbackMsg {
@@ -251,13 +410,21 @@ pub enum Cw20HookMsg {
     Unbond {
         immediate: Option<bool>,
     },
-    // /// Swap is allowed between arb[TOKEN] and allowed swap LSDs e.g. amp[TOKEN]
-    // Swap {
-    //     ask_asset_info: Option<AssetInfo>,
-    //     belief_price: Option<Decimal>,
-    //     max_spread: Option<Decimal>,
-    //     to: Option<String>,
-    // },
+    /// Withdraw liquidity immediately as a pro-rata share of the LSD tokens held by the vault.
+    /// The remaining value is paid in utoken.
+    WithdrawInKind {},
+    /// Deposit an enabled cw20 LSD token, valued like a swap of the token into the vault
+    Deposit {
+        receiver: Option<String>,
+    },
+    /// Sell an enabled cw20 LSD token for utoken e.g. amp[TOKEN] -> [TOKEN].
+    /// `max_spread` applies like for `ExecuteMsg::Swap`.
+    Swap {
+        ask_asset_info: Option<AssetInfo>,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+    },
 }
 
 /// This structure describes the query messages available in the contract.
@@ -300,9 +467,50 @@ pub enum QueryMsg {
         limit: Option<u32>,
     },
 
+    /// Time-weighted average exchange rate over the last `window_s` seconds
+    #[returns(ExchangeRateTwapResponse)]
+    ExchangeRateTwap {
+        window_s: u64,
+    },
+
+    /// APR of the exchange rate over each of the last `windows` seconds
+    #[returns(AprResponse)]
+    Apr {
+        windows: Vec<u64>,
+    },
+
+    /// Query the unbond and withdraw actions the crank would execute
+    #[returns(NextActionsResponse)]
+    NextActions {},
+
+    /// Query config updates waiting for their timelock
+    #[returns(PendingActionsResponse<ExecuteMsg>)]
+    PendingActions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Query the share of the tvl held in each LSD and its cap
+    #[returns(ExposureResponse)]
+    Exposure {},
+
     /// Returns information about a pair in an object of type [`super::asset::PairInfo`].
     #[returns(PairInfo)]
     Pair {},
+
+    /// Returns the utoken received when selling the offered LSD token
+    #[returns(SimulationResponse)]
+    Simulation {
+        offer_asset: Asset,
+        ask_asset_info: Option<AssetInfo>,
+    },
+
+    /// Returns the LSD token amount to offer for receiving the asked utoken
+    #[returns(ReverseSimulationResponse)]
+    ReverseSimulation {
+        offer_asset_info: Option<AssetInfo>,
+        ask_asset: Asset,
+    },
 }
 
 /// This structure stores the main parameters for an Astroport pair
@@ -318,6 +526,28 @@ pub struct PairInfo {
     pub pair_type: PairType,
 }
 
+/// This structure holds the parameters that are returned from a swap simulation
+#[cw_serde]
+pub struct SimulationResponse {
+    /// The amount of utoken returned by the swap
+    pub return_amount: Uint128,
+    /// The discount to the exchange rate of the LSD, kept by the vault
+    pub spread_amount: Uint128,
+    /// The protocol fee paid by the vault out of the spread
+    pub commission_amount: Uint128,
+}
+
+/// This structure holds the parameters that are returned from a reverse swap simulation
+#[cw_serde]
+pub struct ReverseSimulationResponse {
+    /// The amount of LSD tokens to offer
+    pub offer_amount: Uint128,
+    /// The discount to the exchange rate of the LSD, kept by the vault
+    pub spread_amount: Uint128,
+    /// The protocol fee paid by the vault out of the spread
+    pub commission_amount: Uint128,
+}
+
 #[cw_serde]
 pub enum PairType {
     /// XYK pair type
@@ -370,6 +600,8 @@ pub struct ConfigResponse {
 pub struct Config<T> {
     pub utoken: String,
     pub utilization_method: UtilizationMethod,
+    #[serde(default)]
+    pub utilization_adjustment: Option<UtilizationAdjustment>,
     pub unbond_time_s: u64,
     pub lp_addr: T,
     pub lsds: Vec<LsdConfig<T>>,
@@ -466,6 +698,40 @@ pub struct ClaimBalance {
     pub xfactor: Decimal,
 }
 
+#[cw_serde]
+pub struct ExposureResponse {
+    pub tvl_utoken: Uint128,
+    pub exposures: Vec<LsdExposure>,
+}
+
+#[cw_serde]
+pub struct LsdExposure {
+    pub name: String,
+    // value (utoken) held in the LSD (xvalue + unbonding + withdrawable)
+    pub value: Uint128,
+    pub exposure_bps: u16,
+    pub max_exposure_bps: Option<u16>,
+}
+
+#[cw_serde]
+pub struct NextActionsResponse {
+    pub actions: Vec<NextAction>,
+}
+
+#[cw_serde]
+pub struct NextAction {
+    pub name: String,
+    pub action: LsdAction,
+    // xtoken amount to unbond or utoken amount to withdraw
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub enum LsdAction {
+    Unbond {},
+    Withdraw {},
+}
+
 #[cw_serde]
 pub struct ExchangeRatesResponse {
     pub exchange_rates: Vec<(u64, ExchangeHistory)>,
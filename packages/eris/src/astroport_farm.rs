@@ -3,6 +3,8 @@ use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{to_json_binary, Addr, CosmosMsg, Decimal, StdResult, Uint128, WasmMsg};
 use cw20::Cw20ReceiveMsg;
 
+use crate::helpers::exchange_rate::{AprResponse, ExchangeRateTwapResponse};
+
 /// This structure describes the parameters for creating a contract.
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -163,6 +165,18 @@ pub enum QueryMsg {
         start_after: Option<u64>,
         limit: Option<u32>,
     },
+
+    /// Time-weighted average exchange rate over the last `window_s` seconds
+    #[returns(ExchangeRateTwapResponse)]
+    ExchangeRateTwap {
+        window_s: u64,
+    },
+
+    /// APR of the exchange rate over each of the last `windows` seconds
+    #[returns(AprResponse)]
+    Apr {
+        windows: Vec<u64>,
+    },
 }
 
 /// This structure holds the parameters for reward info query response
@@ -0,0 +1,168 @@
+use cosmwasm_std::{Decimal, Order, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, Map};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::constants::YEAR;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ExchangeRateTwapResponse {
+    /// Time-weighted average exchange rate over the window
+    pub twap: Decimal,
+    /// Start of the averaged period, in seconds. Later than `end_time - window_s` if the history
+    /// does not cover the full window.
+    pub start_time: u64,
+    /// End of the averaged period (the current block time), in seconds
+    pub end_time: u64,
+    /// The history does not cover the full window
+    pub partial: bool,
+    /// No exchange rate has been recorded within the window
+    pub stale: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AprResponse {
+    pub aprs: Vec<WindowApr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WindowApr {
+    /// Length of the window, in seconds
+    pub window_s: u64,
+    /// APR of the exchange rate change, annualized. `None` if less than two points in time are
+    /// available.
+    pub apr: Option<Decimal>,
+    /// The history does not cover the full window
+    pub partial: bool,
+    /// No exchange rate has been recorded within the window
+    pub stale: bool,
+}
+
+/// Loads the exchange rates needed to evaluate windows starting at `start_key`: all points from
+/// `start_key` on, and the last point before it to interpolate the rate at the window start.
+/// `start_key` is given in the unit of the history keys (e.g. seconds or days), `to_point` returns
+/// the time in seconds and the exchange rate of an entry. Points are returned oldest first.
+pub fn load_exchange_rates<T>(
+    storage: &dyn Storage,
+    history: &Map<u64, T>,
+    start_key: u64,
+    to_point: impl Fn(u64, T) -> (u64, Decimal),
+) -> StdResult<Vec<(u64, Decimal)>>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let previous = history
+        .range(storage, None, Some(Bound::exclusive(start_key)), Order::Descending)
+        .next()
+        .transpose()?;
+
+    previous
+        .into_iter()
+        .map(Ok)
+        .chain(history.range(storage, Some(Bound::inclusive(start_key)), None, Order::Ascending))
+        .map(|item| {
+            let (key, value) = item?;
+            Ok(to_point(key, value))
+        })
+        .collect()
+}
+
+/// Computes the time-weighted average exchange rate over `window_s` seconds before `now`.
+/// Between two points the rate is interpolated linearly, after the latest point the rate is
+/// considered unchanged.
+pub fn compute_twap(
+    points: &[(u64, Decimal)],
+    now: u64,
+    window_s: u64,
+) -> StdResult<ExchangeRateTwapResponse> {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Err(StdError::generic_err("no exchange rate recorded")),
+    };
+
+    let window_start = now.saturating_sub(window_s);
+    let start_time = window_start.max(first.0).min(now);
+
+    let twap = if start_time < now {
+        // the curve is integrated over every segment within [start_time, now]
+        let mut times = vec![start_time];
+        times.extend(
+            points.iter().map(|(time, _)| *time).filter(|time| *time > start_time && *time < now),
+        );
+        times.push(now);
+
+        let mut sum = Decimal::zero();
+        for segment in times.windows(2) {
+            let (from, to) = (segment[0], segment[1]);
+            let average =
+                (rate_at(points, from) + rate_at(points, to)) / Decimal::from_ratio(2u64, 1u64);
+            sum += average * Decimal::from_ratio(to - from, 1u64);
+        }
+        sum / Decimal::from_ratio(now - start_time, 1u64)
+    } else {
+        rate_at(points, now)
+    };
+
+    Ok(ExchangeRateTwapResponse {
+        twap,
+        start_time,
+        end_time: now,
+        partial: window_start < first.0,
+        stale: last.0 < window_start,
+    })
+}
+
+/// Computes the annualized APR of the exchange rate change over `window_s` seconds before `now`.
+pub fn compute_apr(points: &[(u64, Decimal)], now: u64, window_s: u64) -> WindowApr {
+    let window_start = now.saturating_sub(window_s);
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            return WindowApr {
+                window_s,
+                apr: None,
+                partial: true,
+                stale: true,
+            }
+        },
+    };
+
+    let start_time = window_start.max(first.0);
+    let end_time = now.min(last.0).max(start_time);
+    let start_rate = rate_at(points, start_time);
+    let end_rate = rate_at(points, end_time);
+
+    let apr = if end_time > start_time && !start_rate.is_zero() {
+        // if the exchange rate has been reduced (slashing), the APR is zero
+        let delta_rate = end_rate.checked_sub(start_rate).unwrap_or_default();
+        Some(delta_rate * Decimal::from_ratio(YEAR, end_time - start_time) / start_rate)
+    } else {
+        None
+    };
+
+    WindowApr {
+        window_s,
+        apr,
+        partial: window_start < first.0,
+        stale: last.0 < window_start,
+    }
+}
+
+/// Exchange rate at `time`, interpolated between the surrounding points
+fn rate_at(points: &[(u64, Decimal)], time: u64) -> Decimal {
+    let next = points.iter().position(|(t, _)| *t >= time);
+    match next {
+        Some(0) => points[0].1,
+        None => points[points.len() - 1].1,
+        Some(i) => {
+            let (t0, r0) = points[i - 1];
+            let (t1, r1) = points[i];
+            let progress = Decimal::from_ratio(time - t0, t1 - t0);
+            if r1 >= r0 {
+                r0 + (r1 - r0) * progress
+            } else {
+                r0 - (r0 - r1) * progress
+            }
+        },
+    }
+}
@@ -1,2 +1,4 @@
 pub mod bps;
+pub mod exchange_rate;
 pub mod slope;
+pub mod timelock;
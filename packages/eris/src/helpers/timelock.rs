@@ -0,0 +1,159 @@
+use cosmwasm_std::{Order, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, Item, Map};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::constants::DAY;
+
+/// Longest delay that can be configured between proposing and executing an action
+pub const MAX_TIMELOCK_DELAY_S: u64 = 30 * DAY;
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingAction<T> {
+    pub id: u64,
+    /// The message proposed by the owner
+    pub action: T,
+    /// Time the action has been proposed, in seconds
+    pub proposed_at: u64,
+    /// Time from which anyone can execute the action, in seconds
+    pub executable_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingActionsResponse<T> {
+    /// Delay between proposing and executing an action, in seconds
+    pub delay_s: u64,
+    pub actions: Vec<PendingAction<T>>,
+}
+
+/// Queue of owner actions that can only be executed once a delay has elapsed. Proposing and
+/// cancelling is up to the contract owner, executing an action that is due is open to anyone.
+pub struct Timelock<'a, T> {
+    delay_s: Item<'a, u64>,
+    next_id: Item<'a, u64>,
+    actions: Map<'a, u64, PendingAction<T>>,
+}
+
+impl<'a, T> Timelock<'a, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub fn new(delay_key: &'a str, next_id_key: &'a str, actions_key: &'a str) -> Self {
+        Self {
+            delay_s: Item::new(delay_key),
+            next_id: Item::new(next_id_key),
+            actions: Map::new(actions_key),
+        }
+    }
+
+    /// Configured delay in seconds. Without a delay, actions are not queued.
+    pub fn delay(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.delay_s.may_load(storage)?.unwrap_or_default())
+    }
+
+    pub fn set_delay(&self, storage: &mut dyn Storage, delay_s: u64) -> StdResult<()> {
+        if delay_s > MAX_TIMELOCK_DELAY_S {
+            return Err(StdError::generic_err(format!(
+                "timelock delay can be at most {} seconds",
+                MAX_TIMELOCK_DELAY_S
+            )));
+        }
+        self.delay_s.save(storage, &delay_s)
+    }
+
+    /// Queues an action that can be executed after the configured delay
+    pub fn propose(
+        &self,
+        storage: &mut dyn Storage,
+        now: u64,
+        action: T,
+    ) -> StdResult<PendingAction<T>> {
+        let id = self.next_id.may_load(storage)?.unwrap_or_default();
+        let pending = PendingAction {
+            id,
+            action,
+            proposed_at: now,
+            executable_at: now + self.delay(storage)?,
+        };
+
+        self.actions.save(storage, id, &pending)?;
+        self.next_id.save(storage, &(id + 1))?;
+
+        Ok(pending)
+    }
+
+    /// Removes an action from the queue
+    pub fn cancel(&self, storage: &mut dyn Storage, id: u64) -> StdResult<PendingAction<T>> {
+        let pending = self.load(storage, id)?;
+        self.actions.remove(storage, id);
+        Ok(pending)
+    }
+
+    /// Removes all actions from the queue, e.g. as they were proposed by a previous owner.
+    /// Returns the ids of the removed actions.
+    pub fn clear(&self, storage: &mut dyn Storage) -> StdResult<Vec<u64>> {
+        let ids = self
+            .actions
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for id in &ids {
+            self.actions.remove(storage, *id);
+        }
+
+        Ok(ids)
+    }
+
+    /// Removes an action from the queue to execute it, if its delay has elapsed
+    pub fn take_executable(
+        &self,
+        storage: &mut dyn Storage,
+        now: u64,
+        id: u64,
+    ) -> StdResult<PendingAction<T>> {
+        let pending = self.load(storage, id)?;
+        if now < pending.executable_at {
+            return Err(StdError::generic_err(format!(
+                "action {} is timelocked until {}",
+                id, pending.executable_at
+            )));
+        }
+
+        self.actions.remove(storage, id);
+        Ok(pending)
+    }
+
+    pub fn query_pending(
+        &self,
+        storage: &dyn Storage,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<PendingActionsResponse<T>> {
+        let start = start_after.map(Bound::exclusive);
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let actions = self
+            .actions
+            .range(storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (_, pending) = item?;
+                Ok(pending)
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(PendingActionsResponse {
+            delay_s: self.delay(storage)?,
+            actions,
+        })
+    }
+
+    fn load(&self, storage: &dyn Storage, id: u64) -> StdResult<PendingAction<T>> {
+        self.actions
+            .may_load(storage, id)?
+            .ok_or_else(|| StdError::generic_err(format!("pending action {} not found", id)))
+    }
+}
@@ -1,8 +1,11 @@
+use std::fmt;
+
 use cosmwasm_std::{
     to_json_binary, Addr, Api, Coin, CosmosMsg, Decimal, Empty, QuerierWrapper, StdResult,
     Timestamp, Uint128, VoteOption, WasmMsg,
 };
 use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -27,6 +30,9 @@ pub enum DelegationStrategy<T = String> {
         max_delegation_bps: u16,
         /// count of validators that should receive delegations
         validator_count: u8,
+        /// score reduction of validators that caused a slashing event within the last 90 days.
+        /// Ignored with validator scoring, which applies its own slashing penalty.
+        slashing_penalty_bps: Option<u16>,
     },
 }
 
@@ -41,6 +47,7 @@ impl DelegationStrategy<String> {
                 min_delegation_bps,
                 validator_count,
                 max_delegation_bps,
+                slashing_penalty_bps,
             } => DelegationStrategy::Gauges {
                 amp_gauges: api.addr_validate(&amp_gauges)?,
                 emp_gauges: addr_opt_validate(api, &emp_gauges)?,
@@ -48,6 +55,7 @@ impl DelegationStrategy<String> {
                 min_delegation_bps,
                 validator_count,
                 max_delegation_bps,
+                slashing_penalty_bps,
             },
         };
         Ok(result)
@@ -66,6 +74,13 @@ pub struct InstantiateMsg {
     pub symbol: String,
     /// Number of decimals of the liquid staking token
     pub decimals: u8,
+    /// Denom of the native token that is staked, `uluna` if not provided
+    #[serde(default = "default_denom")]
+    pub denom: String,
+    /// If set, the liquid staking token is created as the token-factory denom
+    /// `factory/{hub}/{subdenom}` instead of a CW20 token. `cw20_code_id`, `name`, `symbol` and
+    /// `decimals` are ignored in that case.
+    pub token_factory_subdenom: Option<String>,
     /// How often the unbonding queue is to be executed, in seconds
     pub epoch_period: u64,
     /// The staking module's unbonding time, in seconds
@@ -83,22 +98,69 @@ pub struct InstantiateMsg {
     pub vote_operator: Option<String>,
 }
 
+fn default_denom() -> String {
+    "uluna".to_string()
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     /// Implements the Cw20 receiver interface
     Receive(Cw20ReceiveMsg),
-    /// Bond specified amount of Luna
+    /// Bond specified amount of native tokens. The bond is attributed to the `referral` partner,
+    /// which can't be the receiver. A holder referring itself through another account is not
+    /// detected.
     Bond {
         receiver: Option<String>,
+        referral: Option<String>,
     },
-    /// Donates specified amount of Luna to pool
+    /// Donates specified amount of native tokens to pool
     Donate {},
-    /// Withdraw Luna that have finished unbonding in previous batches
+    /// Submit an unbonding request for the token-factory stake tokens sent along; see
+    /// `ReceiveMsg::QueueUnbond`
+    QueueUnbond {
+        receiver: Option<String>,
+        #[serde(default)]
+        mint_receipt: bool,
+    },
+    /// Unbond the token-factory stake tokens sent along against the instant unbond buffer; see
+    /// `ReceiveMsg::InstantUnbond`
+    InstantUnbond {
+        max_fee: Decimal,
+        receiver: Option<String>,
+    },
+    /// Withdraw native tokens that have finished unbonding in previous batches. Only the batches in
+    /// `batch_ids` are withdrawn if provided, and at most `limit` batches per call.
     WithdrawUnbonded {
         receiver: Option<String>,
+        batch_ids: Option<Vec<u64>>,
+        limit: Option<u32>,
     },
-    /// Add a validator to the whitelist; callable by the owner
+    /// Implements the Cw721 receiver interface for unbond receipts
+    ReceiveNft(Cw721ReceiveMsg),
+    /// Claim the referral fees accrued by the sender as a partner
+    ClaimReferralFees {
+        receiver: Option<String>,
+    },
+    /// Stop paying referral fees for Stake tokens that `holders` were minted through a referral,
+    /// but no longer hold. Can be called by anyone, e.g. for CW20 Stake tokens that don't report
+    /// their transfers.
+    SyncReferredStake {
+        holders: Vec<String>,
+    },
+    /// Reported by the CW20 Stake token when `from` transfers `amount` to `to`, so that referral
+    /// fees are no longer paid for them. Only accepted from the Stake token.
+    StakeTransferred {
+        from: String,
+        to: String,
+        amount: Uint128,
+    },
+    /// Move the sender's unbonding request in batch `id` to `recipient`
+    TransferUnbondRequest {
+        id: u64,
+        recipient: String,
+    },
+    /// Add a validator to the whitelist; callable by the owner and subject to the timelock
     AddValidator {
         validator: String,
     },
@@ -106,6 +168,9 @@ pub enum ExecuteMsg {
     RemoveValidator {
         validator: String,
     },
+    /// Remove all whitelisted validators that are no longer bonded (e.g. jailed or tombstoned) and
+    /// redelegate their stake to the remaining validators; callable by anyone
+    EvictInactiveValidators {},
     /// Transfer ownership to another account; will not take effect unless the new owner accepts
     TransferOwnership {
         new_owner: String,
@@ -114,17 +179,26 @@ pub enum ExecuteMsg {
     DropOwnershipProposal {},
     /// Accept an ownership transfer
     AcceptOwnership {},
-    /// Claim staking rewards, swap all for Luna, and restake
+    /// Create a token-factory denom that replaces the CW20 stake token; callable by the owner and
+    /// subject to the timelock. Holders of the CW20 token convert it through
+    /// `ReceiveMsg::ConvertStakeToken`
+    MigrateToTokenFactory {
+        subdenom: String,
+    },
+    /// Claim staking rewards, swap all for the native token, and restake
     Harvest {},
 
     TuneDelegations {},
-    /// Use redelegations to balance the amounts of Luna delegated to validators
+    /// Use redelegations to balance the amounts of native tokens delegated to validators
     Rebalance {
         min_redelegation: Option<Uint128>,
     },
 
-    /// Update Luna amounts in unbonding batches to reflect any slashing or rounding errors
-    Reconcile {},
+    /// Update native token amounts in unbonding batches to reflect any slashing or rounding errors
+    Reconcile {
+        /// Reconcile at most `limit` batches that finished unbonding, oldest first
+        limit: Option<u32>,
+    },
     /// Submit the current pending batch of unbonding requests to be unbonded
     SubmitBatch {},
     /// Vote on a proposal (only allowed by the vote_operator)
@@ -137,6 +211,11 @@ pub enum ExecuteMsg {
         proposal_id: u64,
         votes: Vec<(Decimal, VoteOption)>,
     },
+    /// Cast the hub's vote on a proposal as decided by the vAMP holders in prop_gauges. Can be
+    /// called by anyone once the vote in prop_gauges has ended and reached its quorum.
+    CastPropVote {
+        proposal_id: u64,
+    },
 
     /// Callbacks; can only be invoked by the contract itself
     Callback(CallbackMsg),
@@ -158,6 +237,40 @@ pub enum ExecuteMsg {
         epoch_period: Option<u64>,
         /// The staking module's unbonding time, in seconds
         unbond_period: Option<u64>,
+        /// Enables instant unbonding through the liquidity buffer
+        instant_unbond: Option<InstantUnbondConfig>,
+        /// Enables validator performance scoring when tuning gauge delegations
+        validator_scoring: Option<ValidatorScoringConfig>,
+        /// Cw721 contract minting unbond receipts, the hub needs to be its minter. Timelocked and
+        /// can't be changed while receipts are outstanding.
+        unbond_receipt_nft: Option<String>,
+        /// Receivers splitting the protocol reward fee
+        fee_receivers: Option<Vec<FeeReceiver<String>>>,
+        /// Cut of the protocol reward fee generated by referred stake that is paid to the partner
+        referral_fee: Option<Decimal>, // "1 is 100%, 0.05 is 5%"
+        /// Account that can pause operations in case of an incident
+        guardian: Option<String>,
+        /// Delay before changes to fees, delegation strategy, validators, votes or unbonding can be
+        /// executed, in seconds. While set, such updates are queued as pending actions.
+        timelock_delay_s: Option<u64>,
+        /// Tips paid from the protocol reward fee to whoever executes an overdue keeper action
+        keeper_tips: Option<KeeperTipConfig>,
+        /// prop_gauges contract whose results can be cast as the hub's governance votes
+        prop_gauges: Option<String>,
+    },
+    /// Executes a pending action whose timelock has elapsed; can be called by anyone
+    ExecuteTimelockedAction {
+        id: u64,
+    },
+    /// Removes a pending action; can only be called by owner
+    CancelTimelockedAction {
+        id: u64,
+    },
+    /// Pause or unpause classes of operations. The guardian and the owner can pause, only the
+    /// owner can unpause.
+    SetPause {
+        operations: Vec<PauseOperation>,
+        paused: bool,
     },
 
     Claim {
@@ -171,22 +284,104 @@ pub enum ClaimType {
     Default(String),
 }
 
+/// Hooks called by the token-factory module before the Stake token denom is sent
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Releases the referred stake of `from`, the same as `ExecuteMsg::StakeTransferred`
+    TrackBeforeSend {
+        from: String,
+        to: String,
+        amount: Coin,
+    },
+    /// Transfers of the Stake token are never blocked
+    BlockBeforeSend {
+        from: String,
+        to: String,
+        amount: Coin,
+    },
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReceiveMsg {
     /// Submit an unbonding request to the current unbonding queue; automatically invokes `unbond`
     /// if `epoch_time` has elapsed since when the last unbonding queue was executed.
+    /// With `mint_receipt`, the request is represented by a transferable cw721 unbond receipt
+    /// minted to the receiver instead.
     QueueUnbond {
         receiver: Option<String>,
+        #[serde(default)]
+        mint_receipt: bool,
+    },
+    /// Unbond immediately against the instant unbond buffer. The hub takes over the unbonding
+    /// claim and the sender receives the native token value minus a utilization based fee.
+    InstantUnbond {
+        /// Maximum fee the sender accepts, fails otherwise
+        max_fee: Decimal,
+        receiver: Option<String>,
+    },
+    /// Burn the legacy CW20 stake token and receive the same amount of the token-factory denom.
+    /// Only available after the hub migrated to token-factory mode.
+    ConvertStakeToken {
+        receiver: Option<String>,
     },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveNftMsg {
+    /// Withdraw the native tokens of an unbond receipt whose batch has finished unbonding. The
+    /// receipt is burned.
+    WithdrawUnbonded {
+        receiver: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseOperation {
+    /// Bonding and donating
+    Bond,
+    /// Queuing unbonds, instant unbonds, submitting batches, transferring unbond requests and
+    /// converting the legacy Stake token
+    Unbond,
+    /// Harvesting and reinvesting rewards, and reconciling batches
+    Harvest,
+    /// Redelegating between validators and tuning delegations
+    Rebalance,
+    /// Withdrawing unbonded tokens and claiming referral fees
+    Withdraw,
+}
+
+impl fmt::Display for PauseOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PauseOperation::Bond => "bond",
+            PauseOperation::Unbond => "unbond",
+            PauseOperation::Harvest => "harvest",
+            PauseOperation::Rebalance => "rebalance",
+            PauseOperation::Withdraw => "withdraw",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StakeTokenType {
+    /// CW20 token contract instantiated by the hub
+    Cw20,
+    /// Token-factory denom created by the hub
+    TokenFactory,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum CallbackMsg {
     /// Swap Terra stablecoins held by the contract to Luna
     // Swap {},
-    /// Following the swaps, stake the Luna acquired to the whitelisted validators
+    /// Following the swaps, stake the native tokens acquired to the whitelisted validators
     Reinvest {},
 
     CheckReceivedCoin {
@@ -218,6 +413,15 @@ pub enum QueryMsg {
         /// by default uses the next period to look into the future.
         period: Option<u64>,
     },
+    /// Previews the delegations of a proposed delegation strategy and the redelegations a rebalance
+    /// would execute with it. Response: `SimulateDelegationStrategyResponse`
+    SimulateDelegationStrategy {
+        strategy: DelegationStrategy,
+        /// Amount to distribute, by default the currently delegated amount
+        total_uluna: Option<Uint128>,
+        /// Gauge points used instead of the ones of the gauge contracts
+        overrides: Option<GaugeOverrides>,
+    },
     /// The current batch on unbonding requests pending submission. Response: `PendingBatch`
     PendingBatch {},
     /// Query an individual batch that has previously been submitted for unbonding but have not yet
@@ -254,6 +458,53 @@ pub enum QueryMsg {
         start_after: Option<u64>,
         limit: Option<u32>,
     },
+    /// Enumerate all recorded slashing events, newest first. Response: `Vec<SlashingEvent>`
+    SlashingEvents {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// The validator scores computed during the last tune or rebalance. Response:
+    /// `Vec<(String, Decimal)>`
+    ValidatorScores {},
+    /// The guardian and the paused operations. Response: `PauseStateResponse`
+    PauseState {},
+    /// The unbonding claim represented by an unbond receipt. Response: `UnbondReceipt`
+    UnbondReceipt {
+        token_id: String,
+    },
+    /// The keeper actions that can be executed now. Response: `PendingKeeperActionsResponse`
+    PendingKeeperActions {},
+    /// Enumerate config updates waiting for their timelock.
+    /// Response: `PendingActionsResponse<ExecuteMsg>`
+    PendingActions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Time-weighted average exchange rate over the last `window_s` seconds.
+    /// Response: `ExchangeRateTwapResponse`
+    ExchangeRateTwap {
+        window_s: u64,
+    },
+    /// APR of the exchange rate over each of the last `windows` seconds. Response: `AprResponse`
+    Apr {
+        windows: Vec<u64>,
+    },
+    /// Enumerate the stats of all referral partners. Response: `Vec<ReferralResponseItem>`
+    Referrals {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// The native tokens a user can withdraw from batches that finished unbonding.
+    /// Response: `WithdrawableAmountResponse`
+    WithdrawableAmount {
+        user: String,
+    },
+    /// Enumerate the redelegations that are still within the unbonding period. Their destination
+    /// can't be redelegated from until then. Response: `Vec<RedelegationLock>`
+    RedelegationLocks {
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -262,8 +513,14 @@ pub struct ConfigResponse {
     pub owner: String,
     /// Pending ownership transfer, awaiting acceptance by the new owner
     pub new_owner: Option<String>,
-    /// Address of the Stake token
+    /// Address of the Stake token, or its denom in token-factory mode
     pub stake_token: String,
+    /// Whether the Stake token is a CW20 token or a token-factory denom
+    pub stake_token_type: StakeTokenType,
+    /// CW20 Stake token that can still be converted after migrating to token-factory mode
+    pub legacy_stake_token: Option<String>,
+    /// Denom of the native token that is staked
+    pub denom: String,
     /// How often the unbonding queue is to be executed, in seconds
     pub epoch_period: u64,
     /// The staking module's unbonding time, in seconds
@@ -276,24 +533,41 @@ pub struct ConfigResponse {
 
     /// Defines how delegations are spread out
     pub delegation_strategy: DelegationStrategy<String>,
+
+    /// Configuration of the instant unbond buffer, if enabled
+    pub instant_unbond: Option<InstantUnbondConfig>,
+
+    /// Configuration of the validator scoring, if enabled
+    pub validator_scoring: Option<ValidatorScoringConfig>,
+
+    /// Cw721 contract minting unbond receipts, if enabled
+    pub unbond_receipt_nft: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct StateResponse {
     /// Total supply to the Stake token
     pub total_ustake: Uint128,
-    /// Total amount of uluna staked (bonded)
-    pub total_uluna: Uint128,
-    /// The exchange rate between ustake and uluna, in terms of uluna per ustake
+    /// Total amount of utoken staked (bonded), also accepted as `total_uluna`
+    #[serde(alias = "total_uluna")]
+    pub total_utoken: Uint128,
+    /// The exchange rate between ustake and utoken, in terms of utoken per ustake. Besides the
+    /// bonded utoken it includes the instant unbond buffer, which is funded by the holders.
     pub exchange_rate: Decimal,
     /// Staking rewards currently held by the contract that are ready to be reinvested
     pub unlocked_coins: Vec<Coin>,
-    // Amount of uluna currently unbonding
+    // Amount of utoken currently unbonding
     pub unbonding: Uint128,
-    // Amount of uluna currently available as balance of the contract
+    // Amount of utoken currently available as balance of the contract
     pub available: Uint128,
-    // Total amount of uluna within the contract (bonded + unbonding + available)
-    pub tvl_uluna: Uint128,
+    // Total amount of utoken within the contract (bonded + unbonding + available), also accepted
+    // as `tvl_uluna`
+    #[serde(alias = "tvl_uluna")]
+    pub tvl_utoken: Uint128,
+    // Amount of utoken currently held back in the instant unbond buffer
+    pub instant_unbond_buffer: Uint128,
+    // Utilization of the instant unbond buffer compared to its target (0 = full, 1 = empty)
+    pub instant_unbond_utilization: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -302,6 +576,29 @@ pub struct WantedDelegationsResponse {
     pub delegations: Vec<(String, Uint128)>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct GaugeOverrides {
+    /// vAMP points per validator, replacing the ones of the amp gauges
+    pub vamp_points: Option<Vec<(String, Uint128)>>,
+    /// eMP points per validator, replacing the ones of the emp gauges
+    pub emp_points: Option<Vec<(String, Uint128)>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RedelegationItem {
+    pub src: String,
+    pub dst: String,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateDelegationStrategyResponse {
+    /// Wanted delegations of the strategy for the simulated amount
+    pub delegations: Vec<(String, Uint128)>,
+    /// Redelegations a rebalance would execute to move the current delegations to the strategy
+    pub redelegations: Vec<RedelegationItem>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct WantedDelegationsShare {
     pub tune_time: u64,
@@ -355,6 +652,71 @@ pub struct FeeConfig {
     pub protocol_fee_contract: Addr,
     /// Fees that are being applied during reinvest of staking rewards
     pub protocol_reward_fee: Decimal, // "1 is 100%, 0.05 is 5%"
+    /// Receivers of a share of the protocol fee. The rest of the fee is sent to
+    /// `protocol_fee_contract`.
+    #[serde(default)]
+    pub fee_receivers: Vec<FeeReceiver>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeReceiver<T = Addr> {
+    /// Address receiving the fee
+    pub address: T,
+    /// Share of the protocol fee, in basis points
+    pub share_bps: u16,
+    /// How the fee is paid out
+    pub payout: FeePayout,
+}
+
+impl FeeReceiver<String> {
+    pub fn validate(self, api: &dyn Api) -> StdResult<FeeReceiver<Addr>> {
+        Ok(FeeReceiver {
+            address: api.addr_validate(&self.address)?,
+            share_bps: self.share_bps,
+            payout: self.payout,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeePayout {
+    /// The fee is sent as `utoken`
+    Native,
+    /// The fee is bonded and the receiver gets Stake tokens minted at the current exchange rate
+    StakeToken,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct InstantUnbondConfig {
+    /// Amount of utoken the buffer is refilled to from harvests
+    pub buffer_target: Uint128,
+    /// Fee applied to instant unbonds while the buffer is full
+    pub min_fee: Decimal, // "1 is 100%, 0.05 is 5%"
+    /// Fee applied to instant unbonds that empty the buffer
+    pub max_fee: Decimal, // "1 is 100%, 0.05 is 5%"
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct KeeperTipConfig {
+    /// Amount of utoken paid to whoever executes an overdue action
+    pub tip: Uint128,
+    /// Amount of utoken the tip pool is refilled to from the protocol reward fee
+    pub pool_target: Uint128,
+    /// Time after which a due action is considered overdue, in seconds
+    pub grace_period_s: u64,
+    /// Expected time between two harvests, in seconds
+    pub harvest_interval_s: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ValidatorScoringConfig {
+    /// Validators charging a higher commission do not receive any delegations
+    pub max_commission: Decimal, // "1 is 100%, 0.05 is 5%"
+    /// Score reduction per slashing event within the lookback period
+    pub slashing_penalty_bps: u16,
+    /// Time in seconds for which slashing events reduce the score of a validator
+    pub slashing_lookback: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -365,8 +727,10 @@ pub struct Batch {
     pub reconciled: bool,
     /// Total amount of shares remaining this batch. Each `ustake` burned = 1 share
     pub total_shares: Uint128,
-    /// Amount of `uluna` in this batch that have not been claimed
-    pub uluna_unclaimed: Uint128,
+    /// Amount of `utoken` in this batch that have not been claimed. Batches stored with
+    /// `uluna_unclaimed` are still loaded
+    #[serde(alias = "uluna_unclaimed")]
+    pub utoken_unclaimed: Uint128,
     /// Estimated time when this batch will finish unbonding
     pub est_unbond_end_time: u64,
 }
@@ -381,6 +745,16 @@ pub struct UnbondRequest {
     pub shares: Uint128,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct UnbondReceipt {
+    /// Token ID of the receipt in the cw721 contract
+    pub token_id: String,
+    /// ID of the batch
+    pub id: u64,
+    /// The receipt's share in the batch
+    pub shares: Uint128,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct UnbondRequestsByBatchResponseItem {
     /// The user's address
@@ -432,6 +806,109 @@ pub struct UnbondRequestsByUserResponseItemDetails {
     pub pending: Option<PendingBatch>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default)]
+pub struct ReferralStats {
+    /// Total amount of `utoken` bonded through the partner
+    pub utoken_bonded: Uint128,
+    /// Stake tokens minted through the partner that are still held by their receivers, which earn
+    /// the partner a cut of the reward fee
+    pub ustake_referred: Uint128,
+    /// Referral fee per referred Stake token when the stats were last updated
+    pub fee_index: Decimal,
+    /// Referral fees in `utoken` the partner can claim
+    pub utoken_claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReferralResponseItem {
+    pub partner: String,
+    pub stats: ReferralStats,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeeperAction {
+    SubmitBatch,
+    Reconcile,
+    Harvest,
+}
+
+impl fmt::Display for KeeperAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            KeeperAction::SubmitBatch => "submit_batch",
+            KeeperAction::Reconcile => "reconcile",
+            KeeperAction::Harvest => "harvest",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingKeeperAction {
+    pub action: KeeperAction,
+    /// Time from which the action can be executed, in seconds
+    pub due_since: u64,
+    /// Tip paid to whoever executes the action now
+    pub tip: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingKeeperActionsResponse {
+    pub actions: Vec<PendingKeeperAction>,
+    pub keeper_tips: Option<KeeperTipConfig>,
+    /// Amount of utoken available for tips
+    pub tip_pool: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PauseStateResponse {
+    /// Account that can pause operations
+    pub guardian: Option<String>,
+    /// Operations that are currently paused
+    pub paused: Vec<PauseOperation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WithdrawableBatch {
+    /// ID of the batch
+    pub id: u64,
+    /// Amount of `utoken` the user can withdraw from the batch
+    pub utoken: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WithdrawableAmountResponse {
+    /// Total amount of `utoken` the user can withdraw
+    pub total_utoken: Uint128,
+    /// Withdrawable amount per batch
+    pub batches: Vec<WithdrawableBatch>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RedelegationLock {
+    pub src: String,
+    pub dst: String,
+    /// Time of the last redelegation from `src` to `dst`, in seconds
+    pub redelegated_at: u64,
+    /// Time from which `dst` can be redelegated from again, in seconds
+    pub locked_until: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SlashingEvent {
+    /// ID of the slashing event
+    pub id: u64,
+    /// The validator whose unbonding entries were slashed
+    pub validator: String,
+    /// ID of the batch that lost the amount
+    pub batch_id: u64,
+    /// Amount of `utoken` that has been slashed
+    pub amount: Uint128,
+    /// Time when the slashing was detected, in seconds
+    pub time: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct ExchangeRatesResponse {
     pub exchange_rates: Vec<(u64, Decimal)>,
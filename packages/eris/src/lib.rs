@@ -12,6 +12,7 @@ pub mod governance_helper;
 pub mod helper;
 pub mod helpers;
 pub mod hub;
+pub mod lsd_adapter;
 pub mod pair_proxy;
 pub mod prop_gauges;
 pub mod voting_escrow;
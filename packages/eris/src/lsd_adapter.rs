@@ -0,0 +1,46 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Decimal, Uint128};
+
+/// Interface implemented by an external adapter contract, allowing the arb-vault to use an LSD
+/// through `LsdType::Generic` without a migration of the vault.
+#[cw_serde]
+pub enum LsdInterface {
+    /// The adapter contract implements `ExecuteMsg`, `ReceiveMsg` and `QueryMsg` of this module.
+    /// Unbonding and withdrawing is tracked per sender of the messages.
+    V1,
+}
+
+/// Execute messages an adapter contract implementing `LsdInterface::V1` needs to support
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Unbonds the native LSD tokens sent along with the message for the sender
+    Unbond {},
+    /// Sends all underlying tokens of the sender that finished unbonding to the sender
+    Withdraw {},
+}
+
+/// Cw20 hook messages an adapter contract implementing `LsdInterface::V1` needs to support
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Unbonds the received cw20 LSD tokens for the sender of the tokens
+    Unbond {},
+}
+
+/// Queries an adapter contract implementing `LsdInterface::V1` needs to support
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Underlying tokens of the user that are still unbonding
+    #[returns(Uint128)]
+    Unbonding {
+        user: String,
+    },
+    /// Underlying tokens of the user that finished unbonding and can be withdrawn
+    #[returns(Uint128)]
+    Withdrawable {
+        user: String,
+    },
+    /// Underlying tokens per LSD token
+    #[returns(Decimal)]
+    ExchangeRate {},
+}